@@ -17,6 +17,9 @@ import { generateThumbnail } from "../../services/pdf/pdfExtractor";
 import { saveThumbnail } from "../../services/tauri/commands";
 import { useReceiverNameHistoryStore } from "../../hooks/useReceiverNameHistoryStore";
 
+/** これ未満の信頼度の項目は手動確認を促すためハイライトする */
+const LOW_CONFIDENCE_THRESHOLD = 0.7;
+
 interface ReceiptTableRowProps {
   receipt: ReceiptData;
   yearMonth: string;
@@ -98,10 +101,26 @@ export function ReceiptTableRow({
   const hasErrors = receipt.issues?.some((i) => i.severity === "error");
   const hasWarnings = receipt.issues?.some((i) => i.severity === "warning");
 
+  // 信頼度が低い項目（手動で確定した項目は除く）
+  function isLowConfidence(field: "merchant" | "date" | "amount"): boolean {
+    const confidence = receipt.confidence?.[field];
+    return (
+      confidence != null &&
+      confidence < LOW_CONFIDENCE_THRESHOLD &&
+      !receipt.manuallyEdited?.includes(field)
+    );
+  }
+
+  function lowConfidenceClass(field: "merchant" | "date" | "amount"): string {
+    return isLowConfidence(field) ? "bg-orange-100 rounded" : "";
+  }
+
   // Field update handler
   function handleFieldChange(field: keyof ReceiptData, newValue: string): void {
     if (field === "amount") {
-      onUpdate({ amount: newValue ? parseFloat(newValue) : undefined });
+      // 集計用の最小単位（amountMinor）は更新時に金額・通貨から計算し直す
+      const amount = parseFloat(newValue);
+      onUpdate({ amount: Number.isFinite(amount) ? amount : undefined });
     } else {
       onUpdate({ [field]: newValue || undefined });
     }
@@ -189,7 +208,7 @@ export function ReceiptTableRow({
           type="date"
           placeholder="-"
           onChange={(v) => handleFieldChange("date", v)}
-          className="hover:bg-yellow-50"
+          className={`hover:bg-yellow-50 ${lowConfidenceClass("date")}`}
         />
       </td>
 
@@ -208,7 +227,7 @@ export function ReceiptTableRow({
             type="text"
             placeholder="-"
             onChange={(v) => handleFieldChange("merchant", v)}
-            className="hover:bg-yellow-50"
+            className={`hover:bg-yellow-50 ${lowConfidenceClass("merchant")}`}
           />
         )}
       </td>
@@ -245,7 +264,7 @@ export function ReceiptTableRow({
           type="number"
           placeholder="-"
           onChange={(v) => handleFieldChange("amount", v)}
-          className="text-right hover:bg-yellow-50"
+          className={`text-right hover:bg-yellow-50 ${lowConfidenceClass("amount")}`}
         />
       </td>
 
@@ -435,7 +435,8 @@ export function AccountCategoryRulesSettings() {
         </h3>
         <p className="text-xs text-gray-500">
           店舗名に正規表現パターンがマッチした場合、勘定科目を自動設定します。
-          上位のルールが優先されます。
+          複数のルールがマッチした場合は、店舗名全体との一致 → 正規表現 →
+          部分一致の順に優先し、同じ強さなら上位のルールが優先されます。
         </p>
       </div>
 
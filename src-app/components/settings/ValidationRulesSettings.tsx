@@ -14,6 +14,7 @@ import {
   FiCopy,
   FiSettings,
   FiEdit3,
+  FiArchive,
 } from "react-icons/fi";
 import { useValidationRulesStore } from "../../hooks/useValidationRulesStore";
 import type { ValidationRule } from "../../types/validationRule";
@@ -58,6 +59,11 @@ const ruleTypeConfig: Record<
     color: "text-purple-600",
     bgColor: "bg-purple-50",
   },
+  "denchou-required-fields": {
+    icon: <FiArchive className="w-4 h-4" />,
+    color: "text-rose-600",
+    bgColor: "bg-rose-50",
+  },
 };
 
 interface RuleItemProps {
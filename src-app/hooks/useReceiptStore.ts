@@ -12,6 +12,7 @@ import type {
   ApplicationMonth,
   SortConfig,
   SortField,
+  ValidationIssue,
 } from "../types/receipt";
 import { getCurrentYearMonth } from "../types/receipt";
 import {
@@ -19,8 +20,11 @@ import {
   ensureMonthDirectory,
   copyFileToMonth,
   saveThumbnail,
-  getAccountCategoryRules,
   getValidationRules,
+  bulkUpdateReceipts as invokeBulkUpdateReceipts,
+  type BulkUpdateResult,
+  type ReceiptFilter,
+  type ReceiptPatch,
 } from "../services/tauri/commands";
 import {
   readFileAsBase64,
@@ -29,6 +33,7 @@ import {
 } from "../services/pdf/pdfExtractor";
 import { validateAllReceipts } from "../services/validation";
 import { normalizeOcrResultData } from "../services/ocrResult";
+import { toAmountMinor } from "../services/money";
 import { setBreadcrumb } from "../services/errorLog";
 import type { ValidationRule } from "../types/validationRule";
 import { mergeWithDefaultRules } from "../types/validationRule";
@@ -37,8 +42,6 @@ import {
   loadApplicationMonthReceipts,
   saveApplicationMonth,
 } from "../services/persistence";
-import { matchAccountCategory } from "../services/accountCategoryMatcher";
-import type { AccountCategoryRule } from "../types/accountCategoryRule";
 
 function debounce<Args extends unknown[]>(
   fn: (...args: Args) => void,
@@ -51,6 +54,56 @@ function debounce<Args extends unknown[]>(
   };
 }
 
+/** 手動で確定できる項目と、その項目の変更で古くなる検証結果の対象 */
+const MANUAL_EDIT_ISSUE_FIELDS: Record<string, ValidationIssue["field"][]> = {
+  merchant: ["merchant", "duplicate"],
+  date: ["date", "duplicate"],
+  amount: ["amount", "duplicate"],
+  receiverName: ["receiverName"],
+  accountCategory: ["note"],
+  note: ["note"],
+};
+
+/** 編集を適用し、手動確定済みとして記録して古い検証結果を取り除く */
+function applyManualEdit(
+  receipt: ReceiptData,
+  changes: Partial<ReceiptData>,
+): ReceiptData {
+  // 金額・通貨を変えたら集計用の最小単位も合わせる
+  const updates: Partial<ReceiptData> =
+    "amount" in changes || "currency" in changes
+      ? {
+          ...changes,
+          amountMinor: toAmountMinor(
+            "amount" in changes ? changes.amount : receipt.amount,
+            "currency" in changes ? changes.currency : receipt.currency,
+          ),
+        }
+      : changes;
+
+  const edited = Object.keys(updates).filter(
+    (key) =>
+      key in MANUAL_EDIT_ISSUE_FIELDS &&
+      updates[key as keyof ReceiptData] !== receipt[key as keyof ReceiptData],
+  );
+  if (edited.length === 0) return { ...receipt, ...updates };
+
+  const staleFields = new Set(
+    edited.flatMap((key) => MANUAL_EDIT_ISSUE_FIELDS[key]),
+  );
+  const issues = receipt.issues?.filter(
+    (issue) => !staleFields.has(issue.field),
+  );
+  return {
+    ...receipt,
+    ...updates,
+    manuallyEdited: [
+      ...new Set([...(receipt.manuallyEdited ?? []), ...edited]),
+    ],
+    issues: issues && issues.length > 0 ? issues : undefined,
+  };
+}
+
 const debouncedSave = debounce((month: ApplicationMonth) => {
   saveApplicationMonth(month).catch((error) => {
     console.error("Failed to auto-save application month:", error);
@@ -89,6 +142,11 @@ export interface ReceiptStoreActions {
   addReceipts: (filePaths: string[]) => Promise<void>;
   removeReceipt: (id: string) => void;
   updateReceipt: (id: string, updates: Partial<ReceiptData>) => void;
+  bulkUpdateReceipts: (
+    filter: ReceiptFilter,
+    patch: ReceiptPatch,
+    manualEdits?: "preserve" | "overwrite",
+  ) => Promise<BulkUpdateResult>;
   // OCR操作
   startOcr: () => Promise<void>;
   validateReceipts: () => Promise<{ warningCount: number; errorCount: number }>;
@@ -340,7 +398,7 @@ export function useReceiptStore(): UseReceiptStoreReturn {
             ? {
                 ...m,
                 receipts: m.receipts.map((r) =>
-                  r.id === id ? { ...r, ...updates } : r,
+                  r.id === id ? applyManualEdit(r, updates) : r,
                 ),
               }
             : m,
@@ -370,17 +428,6 @@ export function useReceiptStore(): UseReceiptStoreReturn {
     setIsProcessing(true);
     setBreadcrumb(`OCR batch (${pendingReceipts.length} receipts)`);
 
-    // 勘定科目ルールを読み込み
-    let accountCategoryRules: AccountCategoryRule[] = [];
-    try {
-      const rulesSettings = await getAccountCategoryRules();
-      if (rulesSettings?.rules) {
-        accountCategoryRules = rulesSettings.rules;
-      }
-    } catch (error) {
-      console.warn("Failed to load account category rules:", error);
-    }
-
     // すべてのpendingレシートをprocessingに更新
     setMonths((prev) =>
       prev.map((m) =>
@@ -418,14 +465,9 @@ export function useReceiptStore(): UseReceiptStoreReturn {
                         if (r.id !== targetReceipt.id) return r;
 
                         if (result.success && result.data) {
-                          // 勘定科目の自動推測（既存値がなければマッチング）
-                          let accountCategory = r.accountCategory;
-                          if (!accountCategory && result.data.merchant) {
-                            accountCategory = matchAccountCategory(
-                              result.data.merchant,
-                              accountCategoryRules,
-                            );
-                          }
+                          // 勘定科目の自動推測（既存値がなければバックエンドが勘定科目ルールで推定した値）
+                          const accountCategory =
+                            r.accountCategory || result.data.category;
                           return {
                             ...r,
                             status: "success" as const,
@@ -554,6 +596,40 @@ export function useReceiptStore(): UseReceiptStoreReturn {
     });
   }, []);
 
+  /**
+   * 現在の申請月に部分更新を一括適用
+   * 編集中の内容を保存してからバックエンドで更新し、Excelに取り込んだ結果を読み直す
+   */
+  const bulkUpdateReceipts = useCallback(
+    async (
+      filter: ReceiptFilter,
+      patch: ReceiptPatch,
+      manualEdits: "preserve" | "overwrite" = "preserve",
+    ): Promise<BulkUpdateResult> => {
+      const month = getCurrentMonth(months, currentMonthId);
+      if (!month) return { matched: 0, updated: 0 };
+
+      await saveApplicationMonth(month);
+      const result = await invokeBulkUpdateReceipts(
+        month.yearMonth,
+        filter,
+        patch,
+        manualEdits,
+      );
+      if (result.updated > 0 && month.directoryPath) {
+        const receipts = await loadApplicationMonthReceipts(
+          month.yearMonth,
+          month.directoryPath,
+        );
+        setMonths((prev) =>
+          prev.map((m) => (m.id === month.id ? { ...m, receipts } : m)),
+        );
+      }
+      return result;
+    },
+    [months, currentMonthId],
+  );
+
   /** 手動バリデーション実行 */
   const validateReceipts = useCallback(async (): Promise<{
     warningCount: number;
@@ -640,6 +716,7 @@ export function useReceiptStore(): UseReceiptStoreReturn {
     addReceipts,
     removeReceipt,
     updateReceipt,
+    bulkUpdateReceipts,
     startOcr,
     validateReceipts,
     clearCurrentMonth,
@@ -5,7 +5,12 @@
 
 import { useState, useEffect, useCallback } from "react";
 import type { OcrSettings, OcrProvider } from "../types/receipt";
-import { getOcrSettings, saveOcrSettings } from "../services/tauri/commands";
+import { listen } from "@tauri-apps/api/event";
+import {
+  getOcrSettings,
+  saveOcrSettings,
+  type OcrSettingsReloadedEvent,
+} from "../services/tauri/commands";
 
 /**
  * 環境変数からデフォルト設定を取得
@@ -14,20 +19,24 @@ import { getOcrSettings, saveOcrSettings } from "../services/tauri/commands";
 function getDefaultSettings(): OcrSettings {
   const provider = import.meta.env.VITE_OCR_PROVIDER;
   return {
-    provider: (provider === "veryfi"
-      ? "veryfi"
+    activeProvider: (provider === "veryfi" ||
+    provider === "tesseract" ||
+    provider === "textract"
+      ? provider
       : "googledocumentai") as OcrProvider,
-    // Google Document AI
-    projectId: import.meta.env.VITE_GOOGLE_PROJECT_ID || undefined,
-    location: import.meta.env.VITE_GOOGLE_LOCATION || undefined,
-    processorId: import.meta.env.VITE_GOOGLE_PROCESSOR_ID || undefined,
-    serviceAccountJson:
-      import.meta.env.VITE_GOOGLE_SERVICE_ACCOUNT_JSON || undefined,
-    // Veryfi
-    veryfiClientId: import.meta.env.VITE_VERYFI_CLIENT_ID || undefined,
-    veryfiClientSecret: import.meta.env.VITE_VERYFI_CLIENT_SECRET || undefined,
-    veryfiUsername: import.meta.env.VITE_VERYFI_USERNAME || undefined,
-    veryfiApiKey: import.meta.env.VITE_VERYFI_API_KEY || undefined,
+    google: {
+      projectId: import.meta.env.VITE_GOOGLE_PROJECT_ID || undefined,
+      location: import.meta.env.VITE_GOOGLE_LOCATION || undefined,
+      processorId: import.meta.env.VITE_GOOGLE_PROCESSOR_ID || undefined,
+      serviceAccountJson:
+        import.meta.env.VITE_GOOGLE_SERVICE_ACCOUNT_JSON || undefined,
+    },
+    veryfi: {
+      clientId: import.meta.env.VITE_VERYFI_CLIENT_ID || undefined,
+      clientSecret: import.meta.env.VITE_VERYFI_CLIENT_SECRET || undefined,
+      username: import.meta.env.VITE_VERYFI_USERNAME || undefined,
+      apiKey: import.meta.env.VITE_VERYFI_API_KEY || undefined,
+    },
   };
 }
 
@@ -37,16 +46,31 @@ function getDefaultSettings(): OcrSettings {
  */
 function mergeWithDefaults(saved: OcrSettings): OcrSettings {
   const defaults = getDefaultSettings();
+  const google = saved.google ?? {};
+  const veryfi = saved.veryfi ?? {};
   return {
-    provider: saved.provider ?? defaults.provider,
-    projectId: saved.projectId ?? defaults.projectId,
-    location: saved.location ?? defaults.location,
-    processorId: saved.processorId ?? defaults.processorId,
-    serviceAccountJson: saved.serviceAccountJson ?? defaults.serviceAccountJson,
-    veryfiClientId: saved.veryfiClientId ?? defaults.veryfiClientId,
-    veryfiClientSecret: saved.veryfiClientSecret ?? defaults.veryfiClientSecret,
-    veryfiUsername: saved.veryfiUsername ?? defaults.veryfiUsername,
-    veryfiApiKey: saved.veryfiApiKey ?? defaults.veryfiApiKey,
+    activeProvider: saved.activeProvider ?? defaults.activeProvider,
+    google: {
+      projectId: google.projectId ?? defaults.google?.projectId,
+      location: google.location ?? defaults.google?.location,
+      processorId: google.processorId ?? defaults.google?.processorId,
+      processorVersion: google.processorVersion,
+      serviceAccountJson:
+        google.serviceAccountJson ?? defaults.google?.serviceAccountJson,
+      useAdc: google.useAdc,
+      entityMapping: google.entityMapping,
+    },
+    veryfi: {
+      clientId: veryfi.clientId ?? defaults.veryfi?.clientId,
+      clientSecret: veryfi.clientSecret ?? defaults.veryfi?.clientSecret,
+      username: veryfi.username ?? defaults.veryfi?.username,
+      apiKey: veryfi.apiKey ?? defaults.veryfi?.apiKey,
+    },
+    textract: saved.textract,
+    tesseract: saved.tesseract,
+    maxConcurrent: saved.maxConcurrent,
+    timezone: saved.timezone,
+    profilePath: saved.profilePath,
   };
 }
 
@@ -100,6 +124,19 @@ export function useSettingsStore(): UseSettingsStoreReturn {
     loadSettings();
   }, [loadSettings]);
 
+  /** 共有プロファイルの変更が反映されたら読み直す */
+  useEffect(() => {
+    const unlistenPromise = listen<OcrSettingsReloadedEvent>(
+      "ocr-settings-reloaded",
+      () => {
+        loadSettings();
+      },
+    );
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, [loadSettings]);
+
   return {
     settings,
     isLoading,
@@ -1,41 +1,8 @@
 /**
  * 勘定科目マッチングサービス
- * 店舗名から勘定科目を正規表現でマッチングする
+ * ルールの適用はバックエンド（classify.rs）で行い、ここではパターンの検証のみ行う
  */
 
-import type { AccountCategoryRule } from "../types/accountCategoryRule";
-
-/**
- * 店舗名から勘定科目をマッチングする
- * リスト上位のルールを優先（最初にマッチしたルールを適用）
- */
-export function matchAccountCategory(
-  merchant: string | undefined,
-  rules: AccountCategoryRule[],
-): string | undefined {
-  if (!merchant) {
-    return undefined;
-  }
-
-  for (const rule of rules) {
-    if (!rule.enabled) {
-      continue;
-    }
-
-    try {
-      const regex = new RegExp(rule.pattern, rule.flags);
-      if (regex.test(merchant)) {
-        return rule.accountCategory;
-      }
-    } catch {
-      // 無効な正規表現はスキップ
-      continue;
-    }
-  }
-
-  return undefined;
-}
-
 /**
  * 正規表現パターンを検証する
  */
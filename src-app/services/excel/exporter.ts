@@ -7,7 +7,12 @@ import ExcelJS from "exceljs";
 import { writeFile, readFile, exists } from "@tauri-apps/plugin-fs";
 import { openPath } from "@tauri-apps/plugin-opener";
 import { nanoid } from "nanoid";
-import type { ReceiptData, ValidationIssue } from "../../types/receipt";
+import type {
+  ReceiptData,
+  ReviewStatus,
+  ValidationIssue,
+} from "../../types/receipt";
+import { REVIEW_STATUS_LABELS } from "../../types/receipt";
 import {
   ExcelColumnLabel,
   getColumnIndex,
@@ -99,6 +104,28 @@ function parseIssuesText(issuesText: string): ValidationIssue[] {
   return issues;
 }
 
+/**
+ * カンマ区切りのセルを配列にする（空なら undefined）
+ */
+function parseListCell(value: unknown): string[] | undefined {
+  const items = String(value ?? "")
+    .split(",")
+    .map((item) => item.trim())
+    .filter((item) => item !== "");
+  return items.length > 0 ? items : undefined;
+}
+
+/**
+ * レビュー状態の表示名を値に戻す（空や不明な表記は undefined）
+ */
+function parseReviewStatusCell(value: unknown): ReviewStatus | undefined {
+  const label = String(value ?? "").trim();
+  const entry = Object.entries(REVIEW_STATUS_LABELS).find(
+    ([, statusLabel]) => statusLabel === label,
+  );
+  return entry ? (entry[0] as ReviewStatus) : undefined;
+}
+
 /**
  * ExcelファイルからReceiptData配列を読み込む
  * @param yearMonth 年月 (YYYYMM形式)
@@ -224,10 +251,18 @@ export async function loadReceiptsFromExcel(
     const issuesText = issuesValue ? String(issuesValue) : "";
     const issues = parseIssuesText(issuesText);
 
-    // 新フォーマットのみ: receiverName, accountCategory, note を読み込む
+    // 新フォーマットのみ: receiverName, accountCategory, note などを読み込む
+    // （後から追加した列が無いファイルでは空のセルとして読まれ undefined になる）
     let receiverName: string | undefined;
     let accountCategory: string | undefined;
     let note: string | undefined;
+    let tags: string[] | undefined;
+    let manuallyEdited: string[] | undefined;
+    let reviewStatus: ReviewStatus | undefined;
+    let documentNumber: string | undefined;
+    let branch: string | undefined;
+    let sourceProvider: string | undefined;
+    let modelVersion: string | undefined;
     if (isNewFormat) {
       const receiverNameValue = row.getCell(
         getColumnIndex(ExcelColumnLabel.ReceiverName),
@@ -243,6 +278,35 @@ export async function loadReceiptsFromExcel(
         ? String(accountCategoryValue)
         : undefined;
       note = noteValue ? String(noteValue) : undefined;
+      tags = parseListCell(
+        row.getCell(getColumnIndex(ExcelColumnLabel.Tags)).value,
+      );
+      manuallyEdited = parseListCell(
+        row.getCell(getColumnIndex(ExcelColumnLabel.ManuallyEdited)).value,
+      );
+      reviewStatus = parseReviewStatusCell(
+        row.getCell(getColumnIndex(ExcelColumnLabel.ReviewStatus)).value,
+      );
+      const documentNumberValue = row.getCell(
+        getColumnIndex(ExcelColumnLabel.DocumentNumber),
+      ).value;
+      documentNumber = documentNumberValue
+        ? String(documentNumberValue)
+        : undefined;
+      const branchValue = row.getCell(
+        getColumnIndex(ExcelColumnLabel.Branch),
+      ).value;
+      branch = branchValue ? String(branchValue) : undefined;
+      const sourceProviderValue = row.getCell(
+        getColumnIndex(ExcelColumnLabel.SourceProvider),
+      ).value;
+      sourceProvider = sourceProviderValue
+        ? String(sourceProviderValue)
+        : undefined;
+      const modelVersionValue = row.getCell(
+        getColumnIndex(ExcelColumnLabel.ModelVersion),
+      ).value;
+      modelVersion = modelVersionValue ? String(modelVersionValue) : undefined;
     }
 
     const hasOcrData =
@@ -260,6 +324,13 @@ export async function loadReceiptsFromExcel(
       receiverName,
       accountCategory,
       note,
+      tags,
+      manuallyEdited,
+      reviewStatus,
+      documentNumber,
+      branch,
+      sourceProvider,
+      modelVersion,
       issues: issues.length > 0 ? issues : undefined,
       status,
     });
@@ -362,6 +433,16 @@ async function generateSummaryExcel(
       [ExcelColumnLabel.AccountCategory]: receipt.accountCategory ?? "",
       [ExcelColumnLabel.Note]: receipt.note ?? "",
       [ExcelColumnLabel.ValidationIssues]: issuesText,
+      [ExcelColumnLabel.Tags]: receipt.tags?.join(", ") ?? "",
+      [ExcelColumnLabel.ManuallyEdited]:
+        receipt.manuallyEdited?.join(", ") ?? "",
+      [ExcelColumnLabel.ReviewStatus]: receipt.reviewStatus
+        ? REVIEW_STATUS_LABELS[receipt.reviewStatus]
+        : "",
+      [ExcelColumnLabel.DocumentNumber]: receipt.documentNumber ?? "",
+      [ExcelColumnLabel.Branch]: receipt.branch ?? "",
+      [ExcelColumnLabel.SourceProvider]: receipt.sourceProvider ?? "",
+      [ExcelColumnLabel.ModelVersion]: receipt.modelVersion ?? "",
     });
 
     // 通貨コードがJPY以外の場合は赤字で太字にする
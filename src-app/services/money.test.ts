@@ -0,0 +1,21 @@
+import { describe, it, expect } from "vitest";
+import { toAmountMinor } from "./money";
+
+describe("toAmountMinor", () => {
+  it("converts amounts with the currency's minor unit", () => {
+    expect(toAmountMinor(1200, "JPY")).toBe(1200);
+    expect(toAmountMinor(1200, undefined)).toBe(1200);
+    expect(toAmountMinor(12.5, "usd")).toBe(1250);
+    expect(toAmountMinor(1.2345, "KWD")).toBe(1235);
+  });
+
+  it("rounds half away from zero like the backend", () => {
+    expect(toAmountMinor(0.125, "EUR")).toBe(13);
+    expect(toAmountMinor(-0.5, "JPY")).toBe(-1);
+  });
+
+  it("returns undefined without a numeric amount", () => {
+    expect(toAmountMinor(undefined, "JPY")).toBeUndefined();
+    expect(toAmountMinor(Number.NaN, "JPY")).toBeUndefined();
+  });
+});
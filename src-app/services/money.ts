@@ -0,0 +1,56 @@
+/**
+ * 金額の最小単位の換算
+ * バックエンド（money.rs）と同じ規則で、集計用の整数（amountMinor）を求める
+ */
+
+/** 通貨が無い場合に使う通貨 */
+const DEFAULT_CURRENCY = "JPY";
+
+/** 小数桁が 0 の通貨 */
+const ZERO_DECIMAL_CURRENCIES = [
+  "JPY",
+  "KRW",
+  "VND",
+  "CLP",
+  "ISK",
+  "PYG",
+  "UGX",
+  "XAF",
+  "XOF",
+];
+
+/** 小数桁が 3 の通貨 */
+const THREE_DECIMAL_CURRENCIES = [
+  "BHD",
+  "IQD",
+  "JOD",
+  "KWD",
+  "LYD",
+  "OMR",
+  "TND",
+];
+
+/**
+ * 通貨の最小単位の桁数（ISO 4217 の minor unit）
+ * 一覧に無い通貨は 2 桁とみなす
+ */
+export function minorUnitExponent(currency: string): number {
+  const code = currency.toUpperCase();
+  if (ZERO_DECIMAL_CURRENCIES.includes(code)) return 0;
+  if (THREE_DECIMAL_CURRENCIES.includes(code)) return 3;
+  return 2;
+}
+
+/**
+ * 金額を最小単位の整数に変換する（0 から遠い方に四捨五入）
+ * 金額が無い・数値でない場合は undefined
+ */
+export function toAmountMinor(
+  amount: number | undefined,
+  currency: string | undefined,
+): number | undefined {
+  if (amount === undefined || !Number.isFinite(amount)) return undefined;
+
+  const scaled = amount * 10 ** minorUnitExponent(currency || DEFAULT_CURRENCY);
+  return Math.sign(scaled) * Math.round(Math.abs(scaled));
+}
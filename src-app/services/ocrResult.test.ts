@@ -74,4 +74,37 @@ describe("normalizeOcrResultData", () => {
     expect(result.currency).toBeUndefined();
     expect(result.receiverName).toBeUndefined();
   });
+
+  it("carries the source provider and model version for later comparison", () => {
+    const result = normalizeOcrResultData(
+      asOcrData({
+        file: "receipt.jpg",
+        sourceProvider: "googledocumentai",
+        modelVersion: null,
+      }),
+    );
+
+    expect(result.sourceProvider).toBe("googledocumentai");
+    expect(result.modelVersion).toBeUndefined();
+  });
+
+  it("carries per-field confidence so low-confidence fields can be highlighted", () => {
+    const input = asOcrData({
+      file: "receipt.jpg",
+      merchant: "ローソン",
+      confidence: { merchant: 0.42, date: 0.98, amount: null },
+    });
+
+    const result = normalizeOcrResultData(input);
+
+    expect(result.confidence).toEqual({
+      merchant: 0.42,
+      date: 0.98,
+      amount: null,
+    });
+    expect(
+      normalizeOcrResultData(asOcrData({ file: "a.jpg", confidence: null }))
+        .confidence,
+    ).toBeUndefined();
+  });
 });
@@ -7,7 +7,20 @@ import type { OcrResult, ReceiptData } from "../types/receipt";
 /** OCR結果データのうちレシートへマージ可能なフィールドのみ */
 export type NormalizedOcrData = Pick<
   ReceiptData,
-  "merchant" | "date" | "amount" | "currency" | "receiverName"
+  | "merchant"
+  | "branch"
+  | "date"
+  | "time"
+  | "amount"
+  | "amountMinor"
+  | "taxAmount"
+  | "taxRate"
+  | "currency"
+  | "receiverName"
+  | "documentNumber"
+  | "sourceProvider"
+  | "modelVersion"
+  | "confidence"
 >;
 
 /**
@@ -20,9 +33,18 @@ export function normalizeOcrResultData(
 ): NormalizedOcrData {
   return {
     merchant: data.merchant ?? undefined,
+    branch: data.branch ?? undefined,
     date: data.date ?? undefined,
+    time: data.time ?? undefined,
     amount: data.amount ?? undefined,
+    amountMinor: data.amountMinor ?? undefined,
+    taxAmount: data.taxAmount ?? undefined,
+    taxRate: data.taxRate ?? undefined,
     currency: data.currency ?? undefined,
     receiverName: data.receiverName ?? undefined,
+    documentNumber: data.documentNumber ?? undefined,
+    sourceProvider: data.sourceProvider ?? undefined,
+    modelVersion: data.modelVersion ?? undefined,
+    confidence: data.confidence ?? undefined,
   };
 }
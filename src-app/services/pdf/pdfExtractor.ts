@@ -47,6 +47,11 @@ export function getMimeType(filePath: string): string {
     case "jpg":
     case "jpeg":
       return "image/jpeg";
+    case "tif":
+    case "tiff":
+      return "image/tiff";
+    case "bmp":
+      return "image/bmp";
     default:
       return "application/octet-stream";
   }
@@ -9,9 +9,14 @@ import {
   listMonthDirectories,
   listFilesInDirectory,
   ensureMonthDirectory,
-  readThumbnail,
+  readThumbnails,
+  readMonthSummary,
+  saveMonthSummary,
+  type MonthSummary,
+  type SummaryConflict,
 } from "./tauri/commands";
 import { loadReceiptsFromExcel, saveReceiptsToExcel } from "./excel/exporter";
+import { logError } from "./errorLog";
 
 /**
  * ディレクトリをスキャンして既存の申請月を読み込む（遅延読み込み）
@@ -33,6 +38,79 @@ export async function loadApplicationMonths(): Promise<ApplicationMonth[]> {
   return months;
 }
 
+/**
+ * Excelの列に保存している項目（サマリーJSONの値で補わない）
+ * 後から追加した列（レビュー状態・証憑番号など）は古いファイルに無いため含めず、空ならサマリーの値で補う
+ */
+const EXCEL_FIELDS = new Set<string>([
+  "id",
+  "file",
+  "filePath",
+  "date",
+  "merchant",
+  "receiverName",
+  "amount",
+  "currency",
+  "accountCategory",
+  "note",
+  "tags",
+  "manuallyEdited",
+  "issues",
+  "status",
+]);
+
+/**
+ * Excelに列の無い項目（時刻・税額・信頼度など）をサマリーJSONの同じファイルのエントリから補う
+ * Excelとサマリーは同時に保存するため、列の無い項目はサマリーの値が最新
+ */
+function fillFromSummary(
+  receipts: ReceiptData[],
+  summary: MonthSummary | null,
+): ReceiptData[] {
+  if (!summary) return receipts;
+  const byFile = new Map(summary.receipts.map((r) => [r.file, r]));
+  return receipts.map((receipt) => {
+    const saved = byFile.get(receipt.file);
+    if (!saved) return receipt;
+    const filled: Record<string, unknown> = { ...receipt };
+    for (const [key, value] of Object.entries(saved)) {
+      if (!EXCEL_FIELDS.has(key) && filled[key] === undefined) {
+        filled[key] = value;
+      }
+    }
+    return filled as unknown as ReceiptData;
+  });
+}
+
+/**
+ * サマリーJSONのエントリをレシートに上書きする（JSONにだけあるエントリは末尾に追加）
+ * バックエンドだけで更新した内容（終了時の書き出し・一括更新など）をExcelに取り込むのに使う
+ */
+function applySummaryEntries(
+  receipts: ReceiptData[],
+  summary: MonthSummary,
+  directoryPath: string,
+): ReceiptData[] {
+  const byFile = new Map(summary.receipts.map((r) => [r.file, r]));
+  const merged = receipts.map((receipt) => {
+    const saved = byFile.get(receipt.file);
+    return saved
+      ? { ...receipt, ...saved, id: receipt.id, filePath: receipt.filePath }
+      : receipt;
+  });
+  const known = new Set(receipts.map((r) => r.file));
+  for (const saved of summary.receipts) {
+    if (!known.has(saved.file)) {
+      merged.push({
+        ...saved,
+        id: nanoid(6),
+        filePath: `${directoryPath}/${saved.file}`,
+      });
+    }
+  }
+  return merged;
+}
+
 /**
  * 申請月のレシートを読み込む（遅延読み込み用）
  */
@@ -40,66 +118,101 @@ export async function loadApplicationMonthReceipts(
   yearMonth: string,
   directoryPath: string,
 ): Promise<ReceiptData[]> {
+  // 保存時の競合解決のベースとしてサマリーを読み込んでおく（Excelに無い項目の補完にも使う）
+  const summary = await readMonthSummary(yearMonth).catch((error) => {
+    console.warn("Failed to read month summary:", error);
+    return null;
+  });
+
   // まずExcelから読み込みを試みる（directoryPathを渡して壊れたパスを復元）
   const excelReceipts = await loadReceiptsFromExcel(yearMonth, directoryPath);
 
+  let receipts: ReceiptData[];
   if (excelReceipts && excelReceipts.length > 0) {
-    // Excelからデータが読み込めた場合、サムネイルを読み込む
-    const receiptsWithThumbnails = await Promise.all(
-      excelReceipts.map(async (receipt) => {
-        const thumbnailDataUrl = await loadThumbnail(yearMonth, receipt.file);
-        return { ...receipt, thumbnailDataUrl };
-      }),
-    );
-    return receiptsWithThumbnails;
+    receipts = fillFromSummary(excelReceipts, summary);
+  } else {
+    // Excelがない場合はディレクトリをスキャンしてpendingレシートを作成
+    const files = await listFilesInDirectory(directoryPath);
+    receipts = files.map((file) => ({
+      id: nanoid(6),
+      file: file.name,
+      filePath: file.path,
+      status: "pending" as const,
+    }));
   }
 
-  // Excelがない場合はディレクトリをスキャンしてpendingレシートを作成
-  const files = await listFilesInDirectory(directoryPath);
+  // バックエンドだけが更新した内容があれば、Excelに取り込んで保存する
+  if (summary?.pendingExcelSync) {
+    receipts = applySummaryEntries(receipts, summary, directoryPath);
+    try {
+      await saveReceiptsToExcel(receipts, yearMonth);
+      await saveMonthSummary(
+        yearMonth,
+        receipts.map((receipt) => ({ ...receipt, thumbnailDataUrl: undefined })),
+      );
+    } catch (error) {
+      await logError(error, { context: "summary excel sync" });
+    }
+  }
 
-  const receipts: ReceiptData[] = await Promise.all(
-    files.map(async (file) => {
-      const thumbnailDataUrl = await loadThumbnail(yearMonth, file.name);
-      return {
-        id: nanoid(6),
-        file: file.name,
-        filePath: file.path,
-        status: "pending" as const,
-        thumbnailDataUrl,
-      };
-    }),
+  // サムネイルをまとめて読み込む
+  const thumbnails = await loadThumbnails(
+    yearMonth,
+    receipts.map((receipt) => receipt.file),
   );
-
-  return receipts;
+  return receipts.map((receipt) => ({
+    ...receipt,
+    thumbnailDataUrl: thumbnails[receipt.file] ?? undefined,
+  }));
 }
 
 /**
- * サムネイルを読み込む
+ * サムネイルをまとめて読み込む（失敗時は空として扱う）
  */
-async function loadThumbnail(
+async function loadThumbnails(
   yearMonth: string,
-  fileName: string,
-): Promise<string | undefined> {
+  fileNames: string[],
+): Promise<Record<string, string | null>> {
   try {
-    const thumbnail = await readThumbnail(yearMonth, fileName);
-    return thumbnail ?? undefined;
+    return await readThumbnails(yearMonth, fileNames);
   } catch (error) {
-    console.warn("Failed to read thumbnail:", error);
-    return undefined;
+    console.warn("Failed to read thumbnails:", error);
+    return {};
   }
 }
 
 /**
  * 申請月のデータをExcelとして保存
+ * サマリーJSONは他のデバイスの更新とマージして保存し、競合した項目を返す
  */
 export async function saveApplicationMonth(
   month: ApplicationMonth,
-): Promise<void> {
+): Promise<SummaryConflict[]> {
   // ディレクトリを確保
   await ensureMonthDirectory(month.yearMonth);
 
   // Excelに保存
   await saveReceiptsToExcel(month.receipts, month.yearMonth);
+
+  // 解析結果をサマリーJSONにも保存（サムネイルは別ファイルのため含めない）
+  const { conflicts } = await saveMonthSummary(
+    month.yearMonth,
+    month.receipts.map((receipt) => ({
+      ...receipt,
+      thumbnailDataUrl: undefined,
+    })),
+  );
+  if (conflicts.length > 0) {
+    // 領収書の内容は記録せず、件数と項目名だけをエラーログに残す
+    const fields = [...new Set(conflicts.map((c) => c.field ?? "entry"))];
+    await logError(
+      new Error(
+        `Summary conflicts resolved automatically: ${conflicts.length} (${fields.join(", ")})`,
+      ),
+      { context: "summary merge" },
+    );
+  }
+  return conflicts;
 }
 
 /**
@@ -7,7 +7,11 @@ import { invoke } from "@tauri-apps/api/core";
 import type {
   OcrSettings,
   OcrResult,
+  ReceiptData,
   DirectoryValidation,
+  AppError,
+  ReviewStatus,
+  ValidationIssue,
 } from "../../types/receipt";
 
 /** OCR設定を取得 */
@@ -20,9 +24,26 @@ export async function saveOcrSettings(settings: OcrSettings): Promise<void> {
   return invoke<void>("save_ocr_settings", { settings });
 }
 
-/** プロバイダー接続テスト */
-export async function testProviderConnection(): Promise<void> {
-  return invoke<void>("test_provider_connection");
+/** ロケーション推定の結果 */
+export interface LocationSuggestion {
+  location: string;
+  source: "locale" | "timezone";
+}
+
+/**
+ * OSのロケール・タイムゾーンから Document AI のロケーションを推定
+ * 初期値の提案のみで設定には保存されない
+ */
+export async function suggestOcrLocation(): Promise<LocationSuggestion> {
+  return invoke<LocationSuggestion>("suggest_ocr_location");
+}
+
+/**
+ * プロバイダー接続テスト
+ * 直近の成功結果はRust側で短時間キャッシュされる。`force` で強制的に再テストする
+ */
+export async function testProviderConnection(force = false): Promise<void> {
+  return invoke<void>("test_provider_connection", { force });
 }
 
 /** OCRリクエスト */
@@ -32,24 +53,396 @@ export interface OcrRequest {
   mimeType: string;
 }
 
-/** 単一ファイルのOCR処理 */
+/** プレフライト検証の結果 */
+export interface PreflightReport {
+  exists: boolean;
+  size: number | null;
+  /** 内容から判定したMIMEタイプ */
+  mimeType: string | null;
+  /** ページ数（PDFのみ） */
+  pageCount: number | null;
+  exceedsLimit: boolean;
+  /** OCRに回せない理由 */
+  issues: string[];
+  ok: boolean;
+}
+
+/** OCR前にファイルを検証（Base64 で送る前に問題を検出する） */
+export async function preflightOcr(filePath: string): Promise<PreflightReport> {
+  return invoke<PreflightReport>("preflight_ocr", { filePath });
+}
+
+/** 画像の手ブレ・ボケの判定結果 */
+export interface QualityReport {
+  sharp: boolean;
+  /** 鮮鋭度のスコア（大きいほど鮮明） */
+  score: number;
+  /** 不鮮明な場合の再撮影の案内 */
+  suggestion: string | null;
+}
+
+/** レシート画像の手ブレ・ボケを判定する（PDF は対象外） */
+export async function assessImageQuality(
+  fileContent: string,
+  mimeType: string,
+): Promise<QualityReport> {
+  return invoke<QualityReport>("assess_image_quality", { fileContent, mimeType });
+}
+
+/**
+ * HEIC・HEIF 画像（Base64）を JPEG（Base64）に変換（libheif の heif-convert が必要）
+ *
+ * OCR では自動で変換されるため、プレビュー表示などに使う。
+ */
+export async function convertHeicToJpeg(fileContent: string): Promise<string> {
+  return invoke<string>("convert_heic_to_jpeg", { fileContent });
+}
+
+/** OCRリクエストごとの検証結果 */
+export interface RequestValidation {
+  index: number;
+  filePath: string;
+  /** 正規化したMIMEタイプ（OCRに回せない形式なら null） */
+  mimeType: string | null;
+  size: number | null;
+  /** ページ数（PDFのみ） */
+  pageCount: number | null;
+  /** 同じ内容・設定のOCR結果がキャッシュにあるか */
+  cacheHit: boolean;
+  exceedsLimit: boolean;
+  issues: string[];
+  ok: boolean;
+}
+
+/** バッチ投入前にOCRリクエストを一括検証（validIndices のものだけ投入できる） */
+export async function validateOcrRequests(
+  requests: OcrRequest[],
+): Promise<{ results: RequestValidation[]; validIndices: number[] }> {
+  return invoke<{ results: RequestValidation[]; validIndices: number[] }>(
+    "validate_ocr_requests",
+    { requests },
+  );
+}
+
+/** エラーを日本語/英語のメッセージにする（`locale` 省略時はOSのロケール） */
+export async function localizeError(
+  error: AppError,
+  locale?: string,
+): Promise<string> {
+  return invoke<string>("localize_error", { error, locale });
+}
+
+/** LLM 抽出プロンプトのプレビュー */
+export interface LlmPromptPreview {
+  prompt: string;
+  /** テンプレートの問題点（必須項目の指示がないなど） */
+  warnings: string[];
+}
+
+/** LLM 抽出プロンプトのテンプレートを展開し、問題点を確認する */
+export async function previewLlmPrompt(
+  template?: string,
+  fileName?: string,
+): Promise<LlmPromptPreview> {
+  return invoke<LlmPromptPreview>("preview_llm_prompt", { template, fileName });
+}
+
+/** ページ数を取得（PDFはメタデータから、画像は常に 1。壊れたPDFはエラー） */
+export async function getPdfPageCount(filePath: string): Promise<number> {
+  return invoke<number>("get_pdf_page_count", { filePath });
+}
+
+/** 単一ファイルのOCR処理（`collectTimings` でフェーズ別の所要時間を含める） */
 export async function ocrReceipt(
   filePath: string,
   fileContent: string,
   mimeType: string,
+  collectTimings = false,
 ): Promise<OcrResult> {
   return invoke<OcrResult>("ocr_receipt", {
     filePath,
     fileContent,
     mimeType,
+    collectTimings,
   });
 }
 
+/**
+ * プロバイダーの切り替えイベント（`provider-failover`）
+ * バッチでは同じ切り替え元・先の組をまとめて完了時に通知する
+ */
+export interface ProviderFailover {
+  from: string; // 不調だったプロバイダー
+  to: string;
+  reason: string; // 最後に発生した理由
+  count: number;
+  files: string[];
+}
+
+/**
+ * 壊れた設定から復旧したときのイベント（`settings-recovered`）
+ * 元の内容は backupPath に `.corrupt` として退避される
+ */
+export interface SettingsRecoveredEvent {
+  key: string | null; // ストアのファイルごと退避した場合は null
+  reason: string;
+  backupPath: string | null;
+  recoveredFields: string[]; // 救済できた項目
+  droppedFields: string[]; // 既定値に戻した項目
+}
+
+/** 共有プロファイルの変更を設定に反映したときのイベント（`ocr-settings-reloaded`） */
+export interface OcrSettingsReloadedEvent {
+  path: string; // 読み込んだプロファイル JSON
+}
+
+/** バッチの完了イベント（`batch-completed`）。処理時間の分布と遅いファイルを含む */
+export interface BatchSummary {
+  total: number;
+  succeeded: number;
+  failed: number;
+  skipped: number;
+  elapsedMs: number; // バッチ全体の所要時間
+  p50Ms: number | null;
+  p90Ms: number | null;
+  maxMs: number | null;
+  slowestFiles: [string, number][]; // [ファイル名, ミリ秒] を遅い順に
+  issues: ValidationIssue[]; // 成功した結果の検証で見つかった問題（autoValidate 指定時のみ）
+}
+
+/** バッチOCRのオプション */
+export interface BatchOcrOptions {
+  /** 結果をCSV文字列（UTF-8 BOM付き）としても返す */
+  returnCsv?: boolean;
+  /** 指定すると途中経過を保存し、`resumeBatch` で再開できるようにする */
+  batchId?: string;
+  /** 各結果にフェーズ別の所要時間（`timing`）を含める */
+  collectTimings?: boolean;
+  /** 完了時に成功/失敗件数と所要時間をデスクトップ通知する */
+  notifyOnComplete?: boolean;
+  /** 1ファイルあたりの制限時間（秒、実行枠の待ちは含めない）。超えたファイルは失敗になる */
+  fileTimeoutSecs?: number;
+  /** バッチ全体の制限時間（秒）。超えたら未完了のファイルを「デッドライン超過」でスキップする */
+  batchDeadlineSecs?: number;
+  /** 画像の手ブレ・ボケを判定し、不鮮明なら warnings と `needsRetake` を付ける */
+  checkImageQuality?: boolean;
+  /** 完了時に成功した結果を検証し、問題を `batch-completed` の `issues` に含める */
+  autoValidate?: boolean;
+}
+
+/** バッチOCRの応答 */
+export interface BatchOcrResponse {
+  results: OcrResult[];
+  csvContent: string | null;
+}
+
 /** バッチOCR処理 */
 export async function batchOcrReceipts(
   requests: OcrRequest[],
-): Promise<OcrResult[]> {
-  return invoke<OcrResult[]>("batch_ocr_receipts", { requests });
+  options?: BatchOcrOptions,
+): Promise<BatchOcrResponse> {
+  return invoke<BatchOcrResponse>("batch_ocr_receipts", { requests, options });
+}
+
+/**
+ * 失敗したファイルだけを再OCR（succeededPaths のファイルは除外）
+ * 3回続けて失敗したファイルは送らずに retryLimitReached でスキップする。結果は除外後のリクエスト順
+ */
+export async function retryFailedOcr(
+  requests: OcrRequest[],
+  succeededPaths: string[],
+  options?: BatchOcrOptions,
+): Promise<BatchOcrResponse> {
+  return invoke<BatchOcrResponse>("retry_failed_ocr", {
+    requests,
+    succeededPaths,
+    options,
+  });
+}
+
+/** 実行中のバッチOCRを中止（処理中のファイルも即座に打ち切り、スキップ扱いにする）。中止したバッチ数を返す */
+export async function cancelBatchOcr(): Promise<number> {
+  return invoke<number>("cancel_batch_ocr");
+}
+
+/** 中断されたまま残っているバッチの概要 */
+export interface PendingBatchInfo {
+  batchId: string;
+  total: number;
+  processed: number;
+  updatedAt: string;
+}
+
+/** 中断されたバッチを列挙 */
+export async function listPendingBatches(): Promise<PendingBatchInfo[]> {
+  return invoke<PendingBatchInfo[]>("list_pending_batches");
+}
+
+/** 中断されたバッチを再開（処理済みの結果は再利用し、未処理分のみ再実行） */
+export async function resumeBatch(batchId: string): Promise<BatchOcrResponse> {
+  return invoke<BatchOcrResponse>("resume_batch", { batchId });
+}
+
+/**
+ * レシートを指定フィールドのみに投影
+ * `confidence.amount` のようなドット記法でネストしたフィールドも指定できる。不明なフィールドは無視される
+ */
+export async function projectReceipts(
+  receipts: ReceiptData[],
+  fields: string[],
+): Promise<Record<string, unknown>[]> {
+  return invoke<Record<string, unknown>[]>("project_receipts", {
+    receipts,
+    fields,
+  });
+}
+
+/** 通貨ごとの合計 */
+export interface CurrencyTotal {
+  currency: string;
+  /** 合計（最小単位の整数） */
+  amountMinor: number;
+  /** 合計（表示用） */
+  amount: number;
+  count: number;
+}
+
+/** レシートの金額を通貨ごとに合計（丸め誤差の出ない整数計算） */
+export async function sumReceiptAmounts(
+  receipts: ReceiptData[],
+): Promise<CurrencyTotal[]> {
+  return invoke<CurrencyTotal[]>("sum_receipt_amounts", { receipts });
+}
+
+/** 勘定科目の推定結果 */
+export interface CategoryMatch {
+  category: string;
+  /** 確信度（0〜1） */
+  confidence: number;
+  kind: "exact" | "regex" | "contains";
+  ruleId: string;
+}
+
+/** 店舗名から勘定科目を推定（保存済みの勘定科目ルールを使用） */
+export async function classifyAccount(
+  merchant: string,
+): Promise<CategoryMatch | null> {
+  return invoke<CategoryMatch | null>("classify_account", { merchant });
+}
+
+/** 仕訳との照合の許容範囲 */
+export interface LedgerMatchOptions {
+  /** 日付のずれの許容日数（既定 1） */
+  dateToleranceDays?: number;
+  /** 金額の差の許容額（既定 0 = 完全一致） */
+  amountTolerance?: number;
+}
+
+/** 計上済みの候補（仕訳 CSV の1行） */
+export interface LedgerCandidate {
+  /** CSV の行番号（見出しが 1 行目） */
+  line: number;
+  date: string;
+  amount: number;
+  counterparty: string | null;
+  /** 取引先が店舗名と一致したか（どちらかが空なら true） */
+  counterpartyMatched: boolean;
+}
+
+/** レシートごとの照合結果 */
+export interface LedgerCheckResult {
+  index: number;
+  alreadyPosted: boolean;
+  candidates: LedgerCandidate[];
+}
+
+/** OCRしたレシートを会計ソフトの仕訳 CSV と照合し、計上済みの候補を返す */
+export async function checkAgainstLedger(
+  receipts: ReceiptData[],
+  ledgerCsv: string,
+  options?: LedgerMatchOptions,
+): Promise<LedgerCheckResult[]> {
+  return invoke<LedgerCheckResult[]>("check_against_ledger", {
+    receipts,
+    ledgerCsv,
+    options,
+  });
+}
+
+/** 重複の判定ルール（日付・店舗名・金額 / 時刻・金額で日付が前後1日以内。時刻・日付の無いレシートは後者の対象外） */
+export type DuplicateRule = "dateMerchantAmount" | "timeAmount";
+
+/** 重複候補のグループ */
+export interface DuplicateGroup {
+  /** レシートの位置（2件以上） */
+  indices: number[];
+  matchedRule: DuplicateRule;
+}
+
+/** いずれかのルールに一致する重複候補のレシートをグループにして返す（未指定なら日付・店舗名・金額のみ） */
+export async function detectDuplicateReceipts(
+  receipts: ReceiptData[],
+  rules?: DuplicateRule[],
+): Promise<DuplicateGroup[]> {
+  return invoke<DuplicateGroup[]>("detect_duplicate_receipts", {
+    receipts,
+    rules,
+  });
+}
+
+/** 店舗名から業種を推定（内蔵辞書と保存済みのユーザー辞書を使用） */
+export async function inferMerchantCategory(
+  merchant: string,
+): Promise<string | null> {
+  return invoke<string | null>("infer_merchant_category", { merchant });
+}
+
+/** 業種ユーザー辞書のエントリ */
+export interface MerchantCategoryEntry {
+  keyword: string;
+  category: string;
+}
+
+/** 業種ユーザー辞書 */
+export interface MerchantCategorySettings {
+  entries: MerchantCategoryEntry[];
+}
+
+/** 業種ユーザー辞書を取得 */
+export async function getMerchantCategoryDictionary(): Promise<MerchantCategorySettings> {
+  return invoke<MerchantCategorySettings>("get_merchant_category_dictionary");
+}
+
+/** 業種ユーザー辞書を保存 */
+export async function saveMerchantCategoryDictionary(
+  settings: MerchantCategorySettings,
+): Promise<void> {
+  return invoke<void>("save_merchant_category_dictionary", { settings });
+}
+
+/** フィールドの差分 */
+export interface FieldDiff {
+  field: string;
+  /** equivalent: 表記は違うが実質同一（金額が許容誤差内・正規化後の文字列が一致） */
+  kind: "added" | "removed" | "changed" | "equivalent";
+  old: unknown;
+  new: unknown;
+}
+
+/**
+ * 2つのOCR結果をフィールドごとに比較（差分のあるフィールドのみ）
+ * `amountTolerance` 省略時は通貨の最小単位の半分を許容する
+ */
+export async function diffReceipts(
+  oldData: NonNullable<OcrResult["data"]>,
+  newData: NonNullable<OcrResult["data"]>,
+  amountTolerance?: number,
+): Promise<FieldDiff[]> {
+  return invoke<FieldDiff[]>("diff_receipts", {
+    old: oldData,
+    new: newData,
+    amountTolerance,
+  });
 }
 
 /** デフォルトのルートディレクトリを取得 */
@@ -91,11 +484,246 @@ export interface MonthDirectoryInfo {
   yearMonth: string;
   path: string;
   hasExcel: boolean;
+  /** 未OCRのファイル数（`includeCounts` 指定時のみ。Excelサマリーが読めない月は null） */
+  unprocessedCount: number | null;
 }
 
 /** ルートディレクトリ以下の年月ディレクトリ一覧を取得 */
-export async function listMonthDirectories(): Promise<MonthDirectoryInfo[]> {
-  return invoke<MonthDirectoryInfo[]>("list_month_directories");
+export async function listMonthDirectories(
+  includeCounts = false,
+): Promise<MonthDirectoryInfo[]> {
+  return invoke<MonthDirectoryInfo[]>("list_month_directories", {
+    includeCounts,
+  });
+}
+
+/** 月別サマリー（`YYYYMM-summary.json`） */
+export interface MonthSummary {
+  yearMonth: string;
+  updatedAt: string;
+  receipts: Omit<ReceiptData, "thumbnailDataUrl">[];
+  /** バックエンドだけが更新し、Excelサマリーに未反映の変更がある（次の読み込みで取り込む） */
+  pendingExcelSync?: boolean;
+}
+
+/** 読み込み時に不正な日付・金額として落とした項目 */
+export interface DroppedField {
+  file: string;
+  field: string;
+  /** 元の値（JSON 表現） */
+  value: string;
+  reason: string;
+}
+
+/** 月別サマリーを読み込み（不正な日付・金額は落として `droppedFields` に記録される） */
+export async function readMonthSummary(
+  yearMonth: string,
+): Promise<(MonthSummary & { droppedFields: DroppedField[] }) | null> {
+  return invoke<(MonthSummary & { droppedFields: DroppedField[] }) | null>(
+    "read_month_summary",
+    { yearMonth },
+  );
+}
+
+/** 月別サマリーの集計用Excel（`{yearMonth}-summary-export.xlsx`）を書き出し、そのパスを返す（既存の書き出しは上書き） */
+export async function generateMonthSummary(
+  receipts: ReceiptData[],
+  yearMonth: string,
+): Promise<string> {
+  return invoke<string>("generate_month_summary", { receipts, yearMonth });
+}
+
+/** 月別サマリーを会計ソフト取り込み用のCSV（`{yearMonth}-summary.csv`、UTF-8 BOM付き）に書き出し、そのパスを返す */
+export async function exportMonthCsv(
+  receipts: ReceiptData[],
+  yearMonth: string,
+): Promise<string> {
+  return invoke<string>("export_month_csv", { receipts, yearMonth });
+}
+
+/**
+ * freee会計の取引明細CSV（`{yearMonth}-freee.csv`）を書き出す（判定できない勘定科目は「未分類」）
+ * 円以外の通貨のレシートは書き出さず `skipped` に返す
+ */
+export async function exportFreeeCsv(
+  receipts: ReceiptData[],
+  yearMonth: string,
+): Promise<CsvExportResponse> {
+  return invoke<CsvExportResponse>("export_freee_csv", { receipts, yearMonth });
+}
+
+/** マネーフォワード向けCSVの出力オプション */
+export interface MoneyForwardExportOptions {
+  /** 貸方勘定科目（未指定なら「未払金」） */
+  creditAccount?: string;
+  /** 文字コード（未指定なら UTF-8 BOM付き） */
+  encoding?: "utf8" | "shiftJis";
+}
+
+/** エクスポートから除外した行 */
+export interface SkippedRow {
+  file: string;
+  reason: string;
+}
+
+/** 書き出したCSVのパスと、除外した行（理由付き） */
+export interface CsvExportResponse {
+  path: string;
+  skipped: SkippedRow[];
+}
+
+/** マネーフォワード クラウド会計の仕訳インポートCSV（`{yearMonth}-moneyforward.csv`）を書き出す */
+export async function exportMoneyforwardCsv(
+  receipts: ReceiptData[],
+  yearMonth: string,
+  options?: MoneyForwardExportOptions,
+): Promise<CsvExportResponse> {
+  return invoke<CsvExportResponse>("export_moneyforward_csv", {
+    receipts,
+    yearMonth,
+    options,
+  });
+}
+
+/** 他のデバイスの更新と競合したサマリーの項目 */
+export interface SummaryConflict {
+  file: string;
+  /** 項目名（一方が削除し他方が更新したエントリは null） */
+  field: string | null;
+  ours: unknown;
+  theirs: unknown;
+  /** 自動で採用した側（更新日時が新しい方） */
+  resolved: "ours" | "theirs";
+  theirDeviceId: string | null;
+}
+
+/**
+ * 月別サマリーを保存
+ * 保存直前にディスク上の内容と 3-way マージし、競合した項目を返す
+ * （読み込み時点の内容は readMonthSummary で読み込んだものをベースにする）
+ */
+export async function saveMonthSummary(
+  yearMonth: string,
+  receipts: ReceiptData[],
+): Promise<{ conflicts: SummaryConflict[] }> {
+  return invoke<{ conflicts: SummaryConflict[] }>("save_month_summary", {
+    yearMonth,
+    receipts,
+  });
+}
+
+/** 電子帳簿保存法の検索用インデックス（月ディレクトリの index.json） */
+export interface DenchouIndex {
+  yearMonth: string;
+  generatedAt: string;
+  entries: {
+    file: string;
+    /** 取引年月日（YYYY-MM-DD） */
+    transactionDate: string;
+    /** 取引金額 */
+    amount: number;
+    currency?: string;
+    /** 取引先 */
+    counterparty: string;
+  }[];
+  /** 検索項目が欠けていてインデックスに含めなかった証憑 */
+  incomplete: {
+    file: string;
+    missingFields: ("date" | "amount" | "merchant")[];
+  }[];
+}
+
+/** 月別サマリーから電子帳簿保存法の検索用インデックスを生成 */
+export async function generateDenchouIndex(
+  yearMonth: string,
+): Promise<DenchouIndex> {
+  return invoke<DenchouIndex>("generate_denchou_index", { yearMonth });
+}
+
+/** 連番の抜け（`firstMissing`〜`lastMissing` がスキャン漏れの候補） */
+export interface NumberGap {
+  prefix: string;
+  after: string;
+  before: string;
+  firstMissing: string;
+  lastMissing: string;
+  missingCount: number;
+}
+
+/** 証憑番号の連番チェックの結果 */
+export interface NumberGapReport {
+  gaps: NumberGap[];
+  /** 複数のレシートに付いていた番号 */
+  duplicates: { documentNumber: string; files: string[] }[];
+  /** 数字で終わらず連番として扱えなかった番号 */
+  unparsed: { file: string; documentNumber: string }[];
+}
+
+/** 月別サマリーの証憑番号から抜け番（スキャン漏れ）と重複を検出 */
+export async function detectNumberGaps(
+  yearMonth: string,
+): Promise<NumberGapReport> {
+  return invoke<NumberGapReport>("detect_number_gaps", { yearMonth });
+}
+
+/** 一括更新の対象を選ぶ条件（指定した条件をすべて満たすレシートが対象） */
+export interface ReceiptFilter {
+  /** 店舗名の完全一致（前後の空白と大文字・小文字を無視） */
+  merchant?: string;
+  /** 店舗名の部分一致（大文字・小文字を無視） */
+  merchantContains?: string;
+  status?: ReceiptData["status"];
+  files?: string[];
+}
+
+/** 一括更新で適用する部分更新 */
+export interface ReceiptPatch {
+  merchant?: string;
+  date?: string; // YYYY-MM-DD（不正な値は無視される）
+  amount?: number;
+  receiverName?: string;
+  accountCategory?: string;
+  note?: string;
+  addTags?: string[];
+  removeTags?: string[];
+}
+
+/** 一括更新の結果 */
+export interface BulkUpdateResult {
+  matched: number;
+  updated: number;
+}
+
+/**
+ * 月別サマリーのうち条件に合致するレシートに部分更新を一括適用
+ * `manualEdits` が "overwrite" の場合は手動確定済みの項目も上書きする
+ * 更新した項目への検証結果（issues）は手動値基準で見直される
+ * 更新はサマリーJSONにだけ書かれるため、呼び出し後に申請月を読み直してExcelに取り込むこと
+ */
+export async function bulkUpdateReceipts(
+  yearMonth: string,
+  filter: ReceiptFilter,
+  patch: ReceiptPatch,
+  manualEdits: "preserve" | "overwrite" = "preserve",
+): Promise<BulkUpdateResult> {
+  return invoke<BulkUpdateResult>("bulk_update_receipts", {
+    yearMonth,
+    filter,
+    patch,
+    manualEdits,
+  });
+}
+
+/**
+ * レシートのレビュー状態を変更（許可しない遷移はエラー）
+ * 変更はサマリーJSONにだけ書かれ、次に申請月を読み込んだときにExcelへ取り込まれる
+ */
+export async function setReviewStatus(
+  yearMonth: string,
+  file: string,
+  status: ReviewStatus,
+): Promise<void> {
+  return invoke<void>("set_review_status", { yearMonth, file, status });
 }
 
 /** ファイル情報 */
@@ -107,11 +735,81 @@ export interface FileInfo {
   size: number;
 }
 
-/** ディレクトリ内のファイル一覧を取得 */
+/**
+ * ディレクトリ内のファイル一覧を取得
+ * `reviewStatus` を指定するとサマリー上のレビュー状態が一致するファイルだけを返す
+ */
 export async function listFilesInDirectory(
   directoryPath: string,
+  reviewStatus?: ReviewStatus,
 ): Promise<FileInfo[]> {
-  return invoke<FileInfo[]>("list_files_in_directory", { directoryPath });
+  return invoke<FileInfo[]>("list_files_in_directory", {
+    directoryPath,
+    reviewStatus,
+  });
+}
+
+/** 月ごとのインデックス */
+export interface MonthIndex {
+  yearMonth: string;
+  path: string;
+  hasExcel: boolean;
+  hasSummary: boolean;
+  files: FileInfo[];
+  /** 未OCRのファイル数（Excelサマリーが読めない月は null） */
+  unprocessedCount: number | null;
+}
+
+/** ルートディレクトリ全体のインデックス */
+export interface RootIndex {
+  rootDirectory: string;
+  indexedAt: string;
+  months: MonthIndex[];
+  totalFiles: number;
+  totalUnprocessed: number;
+}
+
+/** インデックス作成の進捗イベント（`index-progress`） */
+export interface IndexProgressEvent {
+  current: number;
+  total: number;
+  yearMonth: string;
+}
+
+/**
+ * ルートディレクトリ以下の全月ディレクトリを走査してインデックスを作成
+ * 進捗は `index-progress` イベントで通知される
+ */
+export async function indexRootDirectory(): Promise<RootIndex> {
+  return invoke<RootIndex>("index_root_directory");
+}
+
+/** キャッシュ済みのインデックスを取得（無ければ null） */
+export async function getRootIndex(): Promise<RootIndex | null> {
+  return invoke<RootIndex | null>("get_root_index");
+}
+
+/** 動画から取り出したフレーム（OCR入力用） */
+export interface VideoFrame {
+  fileContent: string; // JPEG（Base64）
+  mimeType: string;
+  timestamp: number; // 実際に採用した時刻（秒）
+}
+
+/**
+ * 動画の指定時刻（秒）のフレームを JPEG として取り出す（ffmpeg が必要、mp4/mov の H.264/HEVC のみ）
+ * `searchWindowSecs` を指定すると前後その秒数から最も鮮明なフレームを選ぶ
+ */
+export async function extractFrame(
+  videoPath: string,
+  timestamp: number,
+  searchWindowSecs?: number,
+): Promise<VideoFrame> {
+  return invoke<VideoFrame>("extract_frame", {
+    videoPath,
+    timestamp,
+    searchWindowSecs,
+  });
 }
 
 /** ファイルコピー結果 */
@@ -121,14 +819,91 @@ export interface CopyFileResult {
   fileName: string;
 }
 
-/** ファイルを月別ディレクトリにコピー */
+/**
+ * ファイルを月別ディレクトリにコピー
+ * `normalizeOrientation` を指定すると画像は EXIF の向きを適用して正立させて保存する
+ */
 export async function copyFileToMonth(
   sourcePath: string,
   yearMonth: string,
+  normalizeOrientation = false,
 ): Promise<CopyFileResult> {
   return invoke<CopyFileResult>("copy_file_to_month", {
     sourcePath,
     yearMonth,
+    normalizeOrientation,
+  });
+}
+
+/** 取り込みパイプラインのステップ（実行順） */
+export type PipelineStep =
+  | "ocr"
+  | "resolveMonth"
+  | "copy"
+  | "summary"
+  | "thumbnail";
+
+/** `processAndFile` のオプション */
+export interface ProcessAndFileOptions {
+  normalizeOrientation?: boolean;
+  fallbackYearMonth?: string; // 日付を読み取れなかったときの保存先（YYYYMM）
+  yearMonth?: string; // 保存先の月（YYYYMM）を固定する（日付によらずこの月に保存）
+  thumbnailSize?: number; // サムネイルの長辺（px、既定 256）
+}
+
+/** 取り込みパイプラインの結果（失敗時は failedStep で止まった場所を示す） */
+export interface PipelineResult {
+  ocr: OcrResult | null;
+  yearMonth: string | null;
+  destinationPath: string | null;
+  summaryUpdated: boolean;
+  thumbnailPath: string | null; // 画像以外は null
+  completedSteps: PipelineStep[];
+  failedStep: PipelineStep | null;
+  error: string | null;
+}
+
+/**
+ * スキャンしたファイルを OCR し、レシート日付の月へコピーしてサマリー・サムネイルまで作る
+ */
+export async function processAndFile(
+  sourcePath: string,
+  options?: ProcessAndFileOptions,
+): Promise<PipelineResult> {
+  return invoke<PipelineResult>("process_and_file", { sourcePath, options });
+}
+
+/**
+ * https のURLの画像・PDFをダウンロードし、`yearMonth` の月別ディレクトリへ取り込む
+ *
+ * ダウンロードの失敗（拒否・サイズ超過・非対応の形式など）は例外として返る。
+ */
+export async function ocrFromUrl(
+  url: string,
+  yearMonth: string,
+  options?: Omit<ProcessAndFileOptions, "yearMonth">,
+): Promise<PipelineResult> {
+  return invoke<PipelineResult>("ocr_from_url", { url, yearMonth, options });
+}
+
+/** 一覧に即時表示するプレースホルダ（本サムネイルの生成後に差し替える） */
+export interface Placeholder {
+  blurhash: string;
+  /** 元画像の幅・高さ（EXIF の向きを適用後） */
+  width: number;
+  height: number;
+  /** 長辺 32px の PNG（DataURL） */
+  dataUrl: string;
+}
+
+/** プレースホルダを生成（画像以外は null） */
+export async function generatePlaceholder(
+  fileContent: string,
+  mimeType: string,
+): Promise<Placeholder | null> {
+  return invoke<Placeholder | null>("generate_placeholder", {
+    fileContent,
+    mimeType,
   });
 }
 
@@ -141,6 +916,15 @@ export async function saveThumbnail(
   return invoke<string>("save_thumbnail", { yearMonth, fileName, dataUrl });
 }
 
+/** 月別ディレクトリの元画像からサムネイルを生成して保存（長辺は maxSize、省略時 256px。PDF は未対応） */
+export async function generateThumbnail(
+  yearMonth: string,
+  fileName: string,
+  maxSize?: number,
+): Promise<string> {
+  return invoke<string>("generate_thumbnail", { yearMonth, fileName, maxSize });
+}
+
 /** サムネイルを読み込み */
 export async function readThumbnail(
   yearMonth: string,
@@ -149,6 +933,17 @@ export async function readThumbnail(
   return invoke<string | null>("read_thumbnail", { yearMonth, fileName });
 }
 
+/** 複数のサムネイルを一括で読み込み（ファイル名 → DataURL） */
+export async function readThumbnails(
+  yearMonth: string,
+  fileNames: string[],
+): Promise<Record<string, string | null>> {
+  return invoke<Record<string, string | null>>("read_thumbnails", {
+    yearMonth,
+    fileNames,
+  });
+}
+
 /**
  * ディレクトリをゴミ箱に移動
  */
@@ -247,6 +247,30 @@ function validateEntertainmentNoteRequired(
   return null;
 }
 
+/** 電子帳簿保存法の検索項目（取引年月日・取引金額・取引先）の必須チェック */
+function validateDenchouRequiredFields(
+  receipt: ReceiptData,
+  rule: ValidationRule | undefined,
+): ValidationIssue[] {
+  if (!rule?.enabled) return [];
+
+  const missing: { field: ValidationIssue["field"]; label: string }[] = [];
+  if (!receipt.date) missing.push({ field: "date", label: "取引年月日" });
+  if (receipt.amount === undefined) {
+    missing.push({ field: "amount", label: "取引金額" });
+  }
+  if (!receipt.merchant?.trim()) {
+    missing.push({ field: "merchant", label: "取引先" });
+  }
+
+  return missing.map(({ field, label }) => ({
+    field,
+    type: "missing-field",
+    severity: rule.severity,
+    message: `電子帳簿保存法の検索項目「${label}」が入力されていません`,
+  }));
+}
+
 /** 単一レシートのバリデーション */
 export function validateReceipt(
   receipt: ReceiptData,
@@ -318,6 +342,10 @@ export function validateReceipt(
   );
   if (entertainmentNoteIssue) issues.push(entertainmentNoteIssue);
 
+  // 電子帳簿保存法の検索項目チェック
+  const denchouRule = findRule(effectiveRules, "denchou-required-fields");
+  issues.push(...validateDenchouRequiredFields(receipt, denchouRule));
+
   return issues;
 }
 
@@ -16,12 +16,21 @@ export enum ExcelColumnLabel {
   AccountCategory = "AccountCategory",
   Note = "Note",
   ValidationIssues = "validationIssues",
+  Tags = "tags",
+  ManuallyEdited = "manuallyEdited",
+  ReviewStatus = "reviewStatus",
+  DocumentNumber = "documentNumber",
+  Branch = "branch",
+  SourceProvider = "sourceProvider",
+  ModelVersion = "modelVersion",
 }
 
 /** カラムのメタデータ */
 export interface ExcelColumnMeta {
   header: string;
   width: number;
+  /** アプリが読み書きするための列（Excel上では非表示にする） */
+  hidden?: boolean;
 }
 
 /** 各カラムのメタデータ定義 */
@@ -40,6 +49,17 @@ export const ExcelColumns: Record<ExcelColumnLabel, ExcelColumnMeta> = {
   [ExcelColumnLabel.AccountCategory]: { header: "勘定科目", width: 15 },
   [ExcelColumnLabel.Note]: { header: "備考", width: 25 },
   [ExcelColumnLabel.ValidationIssues]: { header: "検証結果", width: 40 },
+  [ExcelColumnLabel.Tags]: { header: "タグ", width: 20 },
+  [ExcelColumnLabel.ManuallyEdited]: {
+    header: "手動確定項目",
+    width: 20,
+    hidden: true,
+  },
+  [ExcelColumnLabel.ReviewStatus]: { header: "レビュー状態", width: 14 },
+  [ExcelColumnLabel.DocumentNumber]: { header: "証憑番号", width: 16 },
+  [ExcelColumnLabel.Branch]: { header: "支店", width: 18 },
+  [ExcelColumnLabel.SourceProvider]: { header: "OCRエンジン", width: 18 },
+  [ExcelColumnLabel.ModelVersion]: { header: "モデルバージョン", width: 24 },
 };
 
 /** カラムの順序（この配列の順序がExcelの列順序を決定する） */
@@ -55,6 +75,14 @@ export const ExcelColumnOrder: ExcelColumnLabel[] = [
   ExcelColumnLabel.AccountCategory,
   ExcelColumnLabel.Note,
   ExcelColumnLabel.ValidationIssues,
+  // 以降の列は後から追加したもの（既存のファイルとの互換のため末尾に足していく）
+  ExcelColumnLabel.Tags,
+  ExcelColumnLabel.ManuallyEdited,
+  ExcelColumnLabel.ReviewStatus,
+  ExcelColumnLabel.DocumentNumber,
+  ExcelColumnLabel.Branch,
+  ExcelColumnLabel.SourceProvider,
+  ExcelColumnLabel.ModelVersion,
 ];
 
 /**
@@ -76,10 +104,12 @@ export function generateSheetColumns(): {
   header: string;
   key: string;
   width: number;
+  hidden: boolean;
 }[] {
   return ExcelColumnOrder.map((label) => ({
     header: ExcelColumns[label].header,
     key: label,
     width: ExcelColumns[label].width,
+    hidden: ExcelColumns[label].hidden ?? false,
   }));
 }
@@ -4,30 +4,62 @@
 
 /** バリデーションイシュー */
 export interface ValidationIssue {
-  field: "date" | "amount" | "merchant" | "file" | "duplicate" | "note";
+  field:
+    | "date"
+    | "amount"
+    | "merchant"
+    | "file"
+    | "duplicate"
+    | "note"
+    | "receiverName";
   type:
     | "format"
     | "range"
     | "outlier"
     | "duplicate-file"
     | "duplicate-data"
-    | "missing-field";
+    | "missing-field"
+    | "receiver-mismatch"; // 登録済みの宛名と一致しない（バッチの検証のみ）
   severity: "warning" | "error";
   message: string;
+  file?: string; // 対象のファイル名（バッチの検証結果のみ）
 }
 
+/** 経費レビューの状態（承認済みは下書きに戻せない） */
+export type ReviewStatus = "draft" | "needsReview" | "approved" | "rejected";
+
+/** レビュー状態の表示名（Excelの列にもこの表記で保存する） */
+export const REVIEW_STATUS_LABELS: Record<ReviewStatus, string> = {
+  draft: "下書き",
+  needsReview: "レビュー待ち",
+  approved: "承認済み",
+  rejected: "差し戻し",
+};
+
 /** レシートデータ */
 export interface ReceiptData {
   id: string;
   file: string;
   filePath: string;
-  merchant?: string;
+  merchant?: string; // 支店名を分けられた場合はチェーン名
+  branch?: string; // 店舗名の末尾の支店名（"渋谷店" など。店舗名はそのまま残す）
   date?: string; // YYYY-MM-DD
+  time?: string; // HH:MM:SS（二重スキャンの検出に使う）
   amount?: number;
+  amountMinor?: number; // 通貨の最小単位の整数（集計用）
+  taxAmount?: number; // 消費税額（複数税率の場合は合計税額）
+  taxRate?: number; // 消費税率（0.1 = 10%）
   currency?: string; // "JPY", "USD" など
   receiverName?: string;
+  documentNumber?: string; // 証憑番号（領収書番号など。スキャン漏れの検知に使う）
+  sourceProvider?: string; // 読み取りに使ったOCRプロバイダー名
+  modelVersion?: string; // 読み取りに使ったモデル（プロセッサ）のバージョン
+  confidence?: ReceiptConfidence; // 主要項目の読み取りの信頼度（0.0〜1.0、OCR時のみ）
   accountCategory?: string;
   note?: string;
+  tags?: string[];
+  manuallyEdited?: string[]; // 手動で確定した項目名（"receiverName" など）
+  reviewStatus?: ReviewStatus; // 未設定は "draft"
   issues?: ValidationIssue[];
   status: "pending" | "processing" | "success" | "error";
   errorMessage?: string;
@@ -75,21 +107,119 @@ export function getCurrentYearMonth(): string {
 }
 
 /** OCRプロバイダ */
-export type OcrProvider = "googledocumentai" | "veryfi";
+export type OcrProvider =
+  | "googledocumentai"
+  | "veryfi"
+  | "tesseract"
+  | "textract";
 
-/** OCR設定 */
-export interface OcrSettings {
-  provider?: OcrProvider;
-  // Google Document AI
+/** Google Document AI の設定 */
+export interface GoogleSettings {
   projectId?: string;
   location?: string;
   processorId?: string;
+  processorVersion?: string; // 未指定ならプロセッサの既定バージョン
   serviceAccountJson?: string;
-  // Veryfi
-  veryfiClientId?: string;
-  veryfiClientSecret?: string;
-  veryfiUsername?: string;
-  veryfiApiKey?: string;
+  /** サービスアカウントJSONの代わりに ADC（Application Default Credentials）を使う */
+  useAdc?: boolean;
+  /** 項目（merchant・date・amount など）→ 探すエンティティ名。無い項目は既定の候補で探す */
+  entityMapping?: Partial<Record<EntityMappingField, string[]>>;
+}
+
+/** エンティティのマッピングで指定できる項目 */
+export type EntityMappingField =
+  | "merchant"
+  | "date"
+  | "time"
+  | "amount"
+  | "currency"
+  | "taxAmount"
+  | "netAmount"
+  | "taxRate"
+  | "receiverName"
+  | "documentNumber";
+
+/** Veryfi の設定 */
+export interface VeryfiSettings {
+  clientId?: string;
+  clientSecret?: string;
+  username?: string;
+  apiKey?: string;
+}
+
+/** AWS Textract（AnalyzeExpense）の設定 */
+export interface TextractSettings {
+  region?: string;
+  accessKeyId?: string;
+  secretAccessKey?: string;
+}
+
+/** Tesseract（ローカル）の設定 */
+export interface TesseractSettings {
+  lang?: string; // "jpn"・"jpn+eng" など、未指定なら "jpn+eng"
+}
+
+/** OCR設定（プロバイダー固有の項目はプロバイダーごとのサブ設定に持つ） */
+export interface OcrSettings {
+  activeProvider?: OcrProvider; // 未指定なら Google Document AI
+  google?: GoogleSettings;
+  veryfi?: VeryfiSettings;
+  textract?: TextractSettings;
+  tesseract?: TesseractSettings;
+  // 計上日を決めるタイムゾーン（"+09:00" 形式、未指定なら OS のタイムゾーン）
+  timezone?: string;
+  // 共有プロファイル（OCR設定の JSON。変更を監視して即反映する）
+  profilePath?: string;
+  // エスカレーション
+  escalationChain?: string[];
+  escalationMinCompleteness?: number;
+  // バッチ処理の同時実行数（既定 4、1〜10 に丸める）
+  maxConcurrent?: number;
+  // プロバイダー別のタイムアウト・リトライ・同時実行数（未指定はグローバル値）
+  providerOverrides?: Record<string, ProviderTuning>;
+  // バッチ完了時の Webhook（署名は X-Torifune-Signature: sha256=<hex>）
+  webhookUrl?: string;
+  webhookSecret?: string;
+  // LLM 系プロバイダーの抽出プロンプト（{fields}・{file_name} を埋め込める。未指定なら既定）
+  llmPromptTemplate?: string;
+  // OCR前に縮小する画像の長辺の上限（px、未指定なら 3500、0 で縮小しない）。PDFは対象外
+  maxImageDimension?: number;
+  // 抽出結果に定義順で当てる正規表現の整形ルール（保存時に検証）
+  postprocessRules?: PostprocessRule[];
+  // 一覧に画像として追加する拡張子（"tiff"・"bmp" など）。BMP・TIFF は PNG に変換してOCR
+  extraImageExtensions?: string[];
+  // 信頼度の低い項目だけ別プロバイダーで読み直す（閾値の既定は 0.7）
+  refineFields?: ("merchant" | "date" | "amount")[];
+  refineMinConfidence?: number;
+  refineProvider?: string;
+}
+
+/** 明細行（読み取れなかった項目は null） */
+export interface LineItem {
+  description: string | null;
+  amount: number | null;
+  quantity: number | null;
+}
+
+/** 主要項目の読み取りの信頼度（プロバイダーが返さない項目は null） */
+export interface ReceiptConfidence {
+  merchant: number | null;
+  date: number | null;
+  amount: number | null;
+}
+
+/** OCR結果の整形ルール（field は merchant・date・currency・receiverName） */
+export interface PostprocessRule {
+  field: "merchant" | "date" | "currency" | "receiverName";
+  pattern: string;
+  replacement: string; // $1 などでキャプチャを参照できる
+}
+
+/** プロバイダー別のチューニング */
+export interface ProviderTuning {
+  timeoutSecs?: number;
+  maxRetries?: number;
+  maxConcurrent?: number;
 }
 
 /** OCR結果 */
@@ -98,12 +228,56 @@ export interface OcrResult {
   data?: {
     file: string;
     merchant?: string;
+    branch?: string; // 店舗名の末尾の支店名
     date?: string;
+    time?: string; // HH:MM:SS
     amount?: number;
+    amountMinor?: number; // 通貨の最小単位の整数（集計用）
+    taxAmount?: number; // 消費税額（複数税率の場合は合計税額）
+    taxRate?: number; // 明示が無ければ合計と税額から推定（0.1・0.08）
     currency?: string;
     receiverName?: string;
+    documentNumber?: string; // 証憑番号（領収書番号など）
+    lineItems?: LineItem[]; // 明細行（明細を返さないプロバイダーでは空）
+    category?: string; // 勘定科目ルールから推定した勘定科目
+    categoryConfidence?: number; // 推定の確信度（0〜1）
+    merchantCategory?: string; // 店舗名から推定した業種（コンビニ・カフェ・交通など）
+    sourceProvider?: string; // 読み取りに使ったOCRプロバイダー名
+    modelVersion?: string; // 読み取りに使ったモデル（プロセッサ）のバージョン
+    manuallyEdited?: string[]; // 手動確定済みの項目（値はサマリーの手動値を引き継ぐ）
+    reviewStatus?: ReviewStatus;
+    detectedLanguage?: string; // OCR全文から推定した言語（"ja", "en" など）
+    confidence?: ReceiptConfidence; // 主要項目の読み取りの信頼度（0.0〜1.0）
   };
-  error?: string;
+  error?: string; // 日本語のメッセージ
+  errorDetail?: AppError; // 識別子とパラメータ（localizeError で翻訳する）
+  retryable?: boolean; // 一時的な失敗で、再試行すれば成功しうるか（再試行ボタンの表示に使う）
+  providerName?: string; // 結果を返したプロバイダー（フォールバック時は切り替え先）
+  timing?: OcrTiming;
+  elapsedMs?: number; // 1ファイルの処理時間（バッチのみ、実行枠の待ちは含めない）
+  warnings?: string[]; // 画像を縮小した など
+  attempt?: number; // 連続何回目の試行か（バッチのみ、成功すると数え直す）
+  needsRetake?: boolean; // 画像が不鮮明で再撮影したほうがよいか（checkImageQuality 指定時のみ）
+}
+
+/** 識別できるエラー（`code` で種類を判別） */
+export type AppError =
+  | { code: "quotaExceeded"; retryAfterSecs?: number }
+  | { code: "alreadyInFlight" }
+  | { code: "cancelled" }
+  | { code: "deadlineExceeded" }
+  | { code: "fileTimeout"; limitSecs: number }
+  | { code: "fileReadFailed"; detail: string }
+  | { code: "retryLimitReached"; attempts: number }
+  | { code: "notProcessed" };
+
+/** OCRのフェーズ別所要時間（ミリ秒） */
+export interface OcrTiming {
+  tokenMs: number;
+  preprocessMs: number;
+  apiMs: number;
+  parseMs: number;
+  totalMs: number;
 }
 
 /** OCR進捗イベント */
@@ -112,6 +286,8 @@ export interface OcrProgressEvent {
   total: number;
   fileName: string;
   result?: OcrResult;
+  runningTotal: Record<string, number>; // 成功した金額の通貨別の累計（通貨不明は "UNKNOWN"）
+  cancelled: boolean; // バッチが中止された（以降の未処理分はスキップされる）
 }
 
 /** ディレクトリ検証結果 */
@@ -11,6 +11,7 @@ export type ValidationRuleType =
   | "duplicate-file"
   | "duplicate-data"
   | "entertainment-note-required"
+  | "denchou-required-fields"
   | "custom";
 
 /** バリデーションルール */
@@ -109,6 +110,16 @@ export const DEFAULT_VALIDATION_RULES: Omit<
     params: {},
     isBuiltIn: true,
   },
+  {
+    type: "denchou-required-fields",
+    name: "電子帳簿保存法の検索項目",
+    description:
+      "電子帳簿保存法の検索要件に必要な取引年月日・取引金額・取引先が揃っていることを確認します",
+    enabled: false,
+    severity: "error",
+    params: {},
+    isBuiltIn: true,
+  },
 ];
 
 /** 新規ルールを作成するためのヘルパー */
@@ -0,0 +1,13 @@
+import { describe, it, expect } from "vitest";
+import { isMerchantSimilar } from "./stringSimilarity";
+import cases from "./merchantSimilarity.cases.json";
+
+/**
+ * バックエンドのバッチ検証（validation.rs）と同じ判定になるよう、
+ * 両方のテストで同じケースを使う
+ */
+describe("isMerchantSimilar", () => {
+  it.each(cases)("$a / $b → $similar", ({ a, b, similar }) => {
+    expect(isMerchantSimilar(a, b)).toBe(similar);
+  });
+});
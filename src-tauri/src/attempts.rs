@@ -0,0 +1,61 @@
+//! ファイルごとのOCRの連続失敗回数
+//!
+//! 失敗分だけの再OCR（`retry_failed_ocr`）で、何度やっても読めないファイルを自動で
+//! 再試行し続けないよう、連続で失敗した回数をセッション中だけ覚えておく。成功すれば数え直す。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 自動で再試行する連続失敗回数の上限
+pub const MAX_AUTO_RETRY_ATTEMPTS: u32 = 3;
+
+/// ファイルパス → 連続失敗回数
+#[derive(Default)]
+pub struct OcrAttempts {
+    failures: Mutex<HashMap<String, u32>>,
+}
+
+impl OcrAttempts {
+    /// OCRの結果を記録し、今回が連続何回目の試行だったかを返す
+    pub fn record(&self, file_path: &str, success: bool) -> u32 {
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        let attempt = failures.get(file_path).copied().unwrap_or(0) + 1;
+        if success {
+            failures.remove(file_path);
+        } else {
+            failures.insert(file_path.to_string(), attempt);
+        }
+        attempt
+    }
+
+    /// 連続失敗回数が上限に達したファイルの失敗回数（達していなければ `None`）
+    pub fn exhausted(&self, file_path: &str) -> Option<u32> {
+        self.failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(file_path)
+            .copied()
+            .filter(|failures| *failures >= MAX_AUTO_RETRY_ATTEMPTS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_failures_exhaust_retries_until_success() {
+        let attempts = OcrAttempts::default();
+        assert_eq!(attempts.record("a.jpg", false), 1);
+        assert_eq!(attempts.record("a.jpg", false), 2);
+        assert_eq!(attempts.exhausted("a.jpg"), None);
+        assert_eq!(attempts.record("a.jpg", false), 3);
+        assert_eq!(attempts.exhausted("a.jpg"), Some(3));
+
+        // 成功すれば数え直す
+        assert_eq!(attempts.record("a.jpg", true), 4);
+        assert_eq!(attempts.exhausted("a.jpg"), None);
+        assert_eq!(attempts.record("a.jpg", false), 1);
+        assert_eq!(attempts.exhausted("b.jpg"), None);
+    }
+}
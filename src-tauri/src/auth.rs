@@ -2,9 +2,14 @@
 //!
 //! OAuth認証のためのトークン管理とブラウザ連携を提供する。
 
+use crate::store_keys;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
-use tauri_plugin_store::StoreExt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
 
 /// 認証トークン構造体
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,12 +33,10 @@ pub struct UserInfo {
 /// 認証トークンを取得
 #[tauri::command]
 pub async fn get_auth_tokens(app_handle: AppHandle) -> Result<Option<AuthTokens>, String> {
-    let store = app_handle
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    let store = store_keys::open_store(&app_handle)?;
 
     let tokens = store
-        .get("auth_tokens")
+        .get(store_keys::AUTH_TOKENS)
         .and_then(|v| serde_json::from_value(v).ok());
 
     Ok(tokens)
@@ -42,34 +45,27 @@ pub async fn get_auth_tokens(app_handle: AppHandle) -> Result<Option<AuthTokens>
 /// 認証トークンを保存
 #[tauri::command]
 pub async fn save_auth_tokens(app_handle: AppHandle, tokens: AuthTokens) -> Result<(), String> {
-    let store = app_handle
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
-
-    store.set(
-        "auth_tokens",
+    store_keys::set_value(
+        &app_handle,
+        store_keys::AUTH_TOKENS,
         serde_json::to_value(&tokens).map_err(|e| e.to_string())?,
-    );
-
-    store
-        .save()
-        .map_err(|e| format!("トークンの保存に失敗しました: {}", e))?;
-
-    Ok(())
+    )
 }
 
 /// 認証トークンをクリア
 #[tauri::command]
 pub async fn clear_auth_tokens(app_handle: AppHandle) -> Result<(), String> {
-    let store = app_handle
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
-
-    store.delete("auth_tokens");
+    store_keys::with_store_transaction(&app_handle, |store| {
+        store.delete(store_keys::AUTH_TOKENS);
+        Ok(())
+    })
+    .map_err(|e| format!("トークンの削除に失敗しました: {}", e))?;
 
-    store
-        .save()
-        .map_err(|e| format!("トークンの削除に失敗しました: {}", e))?;
+    // ログアウトしたユーザーのプロフィール画像は残さない
+    if let Ok((image_path, meta_path)) = picture_cache_paths(&app_handle) {
+        let _ = fs::remove_file(image_path);
+        let _ = fs::remove_file(meta_path);
+    }
 
     Ok(())
 }
@@ -79,3 +75,224 @@ pub async fn clear_auth_tokens(app_handle: AppHandle) -> Result<(), String> {
 pub async fn open_oauth_url(url: String) -> Result<(), String> {
     open::that(&url).map_err(|e| format!("ブラウザを開けませんでした: {}", e))
 }
+
+/// プロフィール画像のキャッシュ情報
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedPicture {
+    /// 取得元URL
+    url: String,
+    mime_type: String,
+}
+
+/// プロフィール画像のダウンロードのタイムアウト
+const PICTURE_DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// プロフィール画像のキャッシュ（画像本体, キャッシュ情報）のパス
+fn picture_cache_paths(app_handle: &AppHandle) -> Result<(PathBuf, PathBuf), String> {
+    let cache_dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("キャッシュディレクトリを取得できませんでした: {}", e))?;
+
+    Ok((
+        cache_dir.join("user_picture"),
+        cache_dir.join("user_picture.json"),
+    ))
+}
+
+/// キャッシュ済みの画像を DataURL で読み込む（指定URLのキャッシュが無ければ `None`）
+fn read_cached_picture(app_handle: &AppHandle, url: &str) -> Option<String> {
+    let (image_path, meta_path) = picture_cache_paths(app_handle).ok()?;
+
+    let meta: CachedPicture = serde_json::from_slice(&fs::read(meta_path).ok()?).ok()?;
+    if meta.url != url {
+        return None;
+    }
+
+    let image_data = fs::read(image_path).ok()?;
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    Some(format!(
+        "data:{};base64,{}",
+        meta.mime_type,
+        STANDARD.encode(&image_data)
+    ))
+}
+
+/// プロフィール画像をダウンロードしてアプリのキャッシュディレクトリに保存
+///
+/// 同じURLの画像がキャッシュ済みなら再取得しない。
+#[tauri::command]
+pub async fn cache_user_picture(app_handle: AppHandle, url: String) -> Result<(), String> {
+    if read_cached_picture(&app_handle, &url).is_some() {
+        return Ok(());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(PICTURE_DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("プロフィール画像の取得に失敗しました: {}", e))?;
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_string());
+
+    let image_data = response
+        .bytes()
+        .await
+        .map_err(|e| format!("プロフィール画像の取得に失敗しました: {}", e))?;
+
+    // Content-Type が画像でなければ内容から判定する
+    let mime_type = content_type
+        .filter(|t| t.starts_with("image/"))
+        .or_else(|| crate::preflight::sniff_mime_type(&image_data).map(String::from))
+        .ok_or_else(|| "プロフィール画像の形式を判定できませんでした".to_string())?;
+
+    let (image_path, meta_path) = picture_cache_paths(&app_handle)?;
+    if let Some(parent) = image_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("キャッシュディレクトリの作成に失敗しました: {}", e))?;
+    }
+
+    fs::write(&image_path, &image_data)
+        .map_err(|e| format!("プロフィール画像の保存に失敗しました: {}", e))?;
+
+    let meta = CachedPicture { url, mime_type };
+    fs::write(
+        &meta_path,
+        serde_json::to_vec(&meta).map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| format!("プロフィール画像の保存に失敗しました: {}", e))?;
+
+    Ok(())
+}
+
+/// ログインユーザーのプロフィール画像を取得
+///
+/// キャッシュがあれば DataURL を返す。URLが変わっていれば再取得し、取得に失敗した
+/// 場合は元のURLを返す。ログインしていないか画像が無い場合は `None`。
+#[tauri::command]
+pub async fn get_user_picture(app_handle: AppHandle) -> Result<Option<String>, String> {
+    let Some(url) = get_auth_tokens(app_handle.clone())
+        .await?
+        .and_then(|tokens| tokens.user)
+        .and_then(|user| user.picture)
+    else {
+        return Ok(None);
+    };
+
+    if let Some(data_url) = read_cached_picture(&app_handle, &url) {
+        return Ok(Some(data_url));
+    }
+
+    if cache_user_picture(app_handle.clone(), url.clone())
+        .await
+        .is_ok()
+    {
+        if let Some(data_url) = read_cached_picture(&app_handle, &url) {
+            return Ok(Some(data_url));
+        }
+    }
+
+    Ok(Some(url))
+}
+
+/// URLスキームの形式（RFC 3986: 英字始まり、英数字・`+`・`-`・`.`）
+static SCHEME_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z][a-z0-9+.-]*$").unwrap());
+
+/// スキーム名を検証する（`://` 付きで渡されても受け付ける）
+fn normalize_scheme(scheme: &str) -> Result<String, String> {
+    let scheme = scheme.trim().trim_end_matches("://").to_ascii_lowercase();
+
+    if !SCHEME_PATTERN.is_match(&scheme) {
+        return Err(format!(
+            "スキームの形式が正しくありません（英字始まり、英数字・+・-・.）: {}",
+            scheme
+        ));
+    }
+
+    Ok(scheme)
+}
+
+/// スキームがこのアプリに登録済みかどうか
+fn scheme_registered(app_handle: &AppHandle, scheme: &str) -> Result<bool, String> {
+    app_handle
+        .deep_link()
+        .is_registered(scheme)
+        .map_err(|e| format!("スキームの登録状況を確認できませんでした: {}", e))
+}
+
+/// 未登録の場合のみスキームを登録する（登録した場合は `true`）
+///
+/// 登録済みのスキームを再登録すると OS 側の関連付けが書き換わるため避ける。
+pub fn ensure_scheme_registered(app_handle: &AppHandle, scheme: &str) -> Result<bool, String> {
+    if scheme_registered(app_handle, scheme)? {
+        return Ok(false);
+    }
+
+    app_handle
+        .deep_link()
+        .register(scheme)
+        .map_err(|e| format!("スキームの登録に失敗しました: {}", e))?;
+
+    Ok(true)
+}
+
+/// 設定に保存されたディープリンクスキームを取得（未設定なら `None`）
+pub fn configured_deep_link_scheme(app_handle: &AppHandle) -> Option<String> {
+    let store = store_keys::open_store(app_handle).ok()?;
+
+    store
+        .get(store_keys::DEEP_LINK_SCHEME)
+        .and_then(|v| v.as_str().map(String::from))
+}
+
+/// ディープリンクスキームを登録し、設定に保存する
+///
+/// 開発用と本番用で別のスキームを使い分けるためのもの。既に登録済みなら何もしない。
+#[tauri::command]
+pub async fn register_deep_link_scheme(
+    app_handle: AppHandle,
+    scheme: String,
+) -> Result<(), String> {
+    let scheme = normalize_scheme(&scheme)?;
+
+    ensure_scheme_registered(&app_handle, &scheme)?;
+
+    store_keys::set_value(&app_handle, store_keys::DEEP_LINK_SCHEME, scheme.into())
+}
+
+/// スキームがこのアプリに登録済みか確認
+#[tauri::command]
+pub async fn is_scheme_registered(app_handle: AppHandle, scheme: String) -> Result<bool, String> {
+    let scheme = normalize_scheme(&scheme)?;
+    scheme_registered(&app_handle, &scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_scheme_accepts_rfc3986_names_only() {
+        assert_eq!(normalize_scheme("Torifune-Dev://").unwrap(), "torifune-dev");
+        assert_eq!(
+            normalize_scheme("app.torifune+v2").unwrap(),
+            "app.torifune+v2"
+        );
+        assert!(normalize_scheme("1torifune").is_err());
+        assert!(normalize_scheme("tori fune").is_err());
+        assert!(normalize_scheme("").is_err());
+    }
+}
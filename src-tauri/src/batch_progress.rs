@@ -0,0 +1,213 @@
+//! バッチOCRの途中経過の永続化
+//!
+//! `batch_id` を指定したバッチは、処理済みの結果をストアへ保存する（保存は間隔を空けてまとめ、
+//! バッチの最後に必ず保存する）。アプリが途中で終了しても、再起動後に未処理分だけを再実行して
+//! 続きから再開できる。スキップしたファイルも未処理とみなし、すべて処理し終えるまで進捗を残す。
+//! ファイル内容（Base64）は保存せず、再開時にファイルパスから読み直す。
+
+use crate::commands::BatchOcrOptions;
+use crate::providers::OcrResult;
+use crate::store_keys;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// 途中経過を保存する最短の間隔（1件ごとにストア全体を書き直さない）
+const SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 再開に必要なリクエスト情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRequestRecord {
+    pub file_path: String,
+    pub mime_type: String,
+}
+
+/// バッチの進捗状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    pub batch_id: String,
+    /// リクエスト順のファイル情報
+    pub requests: Vec<BatchRequestRecord>,
+    /// 処理済みリクエストの index → 結果
+    pub results: BTreeMap<usize, OcrResult>,
+    /// 開始時のオプション（再開時も同じオプションで処理する）
+    #[serde(default)]
+    pub options: BatchOcrOptions,
+    /// 最終更新日時（RFC 3339）
+    pub updated_at: String,
+    /// 最後にストアへ保存した時刻（保存の間引きに使う）
+    #[serde(skip)]
+    last_saved: Option<Instant>,
+}
+
+impl BatchProgress {
+    pub fn new(batch_id: &str, requests: Vec<BatchRequestRecord>) -> Self {
+        Self {
+            batch_id: batch_id.to_string(),
+            requests,
+            results: BTreeMap::new(),
+            options: BatchOcrOptions::default(),
+            updated_at: chrono::Local::now().to_rfc3339(),
+            last_saved: None,
+        }
+    }
+
+    /// 結果を記録する
+    ///
+    /// 処理中スキップは処理済みとみなさない（再開時に再実行する）。
+    pub fn record(&mut self, index: usize, result: &OcrResult) {
+        if result.skipped {
+            return;
+        }
+        self.results.insert(index, result.clone());
+        self.updated_at = chrono::Local::now().to_rfc3339();
+    }
+
+    /// 未処理のリクエストの index
+    pub fn pending_indices(&self) -> Vec<usize> {
+        (0..self.requests.len())
+            .filter(|index| !self.results.contains_key(index))
+            .collect()
+    }
+
+    /// すべてのリクエストを処理し終えたか（スキップが残っていれば未完了）
+    pub fn is_complete(&self) -> bool {
+        self.results.len() == self.requests.len()
+    }
+
+    /// 前回の保存から `SAVE_INTERVAL` 経っていれば保存する時刻として記録し `true` を返す
+    fn save_due(&mut self, now: Instant) -> bool {
+        let due = self
+            .last_saved
+            .is_none_or(|last| now.duration_since(last) >= SAVE_INTERVAL);
+        if due {
+            self.last_saved = Some(now);
+        }
+        due
+    }
+}
+
+/// 未完了バッチの概要（フロントエンドへ返す形）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingBatchInfo {
+    pub batch_id: String,
+    pub total: usize,
+    pub processed: usize,
+    pub updated_at: String,
+}
+
+impl From<&BatchProgress> for PendingBatchInfo {
+    fn from(progress: &BatchProgress) -> Self {
+        Self {
+            batch_id: progress.batch_id.clone(),
+            total: progress.requests.len(),
+            processed: progress.results.len(),
+            updated_at: progress.updated_at.clone(),
+        }
+    }
+}
+
+fn store_key(batch_id: &str) -> String {
+    format!("{}{}", store_keys::BATCH_PROGRESS_PREFIX, batch_id)
+}
+
+/// 進捗状態を読み込む（存在しない場合は `None`）
+pub fn load(app: &AppHandle, batch_id: &str) -> Result<Option<BatchProgress>, String> {
+    let store = store_keys::open_store(app)?;
+
+    Ok(store
+        .get(store_key(batch_id))
+        .and_then(|v| serde_json::from_value(v).ok()))
+}
+
+/// 進捗状態を保存する
+pub fn save(app: &AppHandle, progress: &BatchProgress) -> Result<(), String> {
+    store_keys::set_value(
+        app,
+        &store_key(&progress.batch_id),
+        serde_json::to_value(progress).map_err(|e| e.to_string())?,
+    )
+}
+
+/// 前回の保存から一定時間経っていれば進捗状態を保存する（バッチの途中用）
+pub fn save_throttled(app: &AppHandle, progress: &mut BatchProgress) -> Result<(), String> {
+    if progress.save_due(Instant::now()) {
+        save(app, progress)?;
+    }
+    Ok(())
+}
+
+/// 進捗状態を削除する（バッチ完了時）
+pub fn remove(app: &AppHandle, batch_id: &str) -> Result<(), String> {
+    store_keys::with_store_transaction(app, |store| {
+        store.delete(store_key(batch_id));
+        Ok(())
+    })
+}
+
+/// 未完了のバッチをすべて列挙する
+pub fn list(app: &AppHandle) -> Result<Vec<PendingBatchInfo>, String> {
+    let store = store_keys::open_store(app)?;
+
+    let mut batches: Vec<PendingBatchInfo> = store
+        .entries()
+        .into_iter()
+        .filter(|(key, _)| key.starts_with(store_keys::BATCH_PROGRESS_PREFIX))
+        .filter_map(|(_, value)| serde_json::from_value::<BatchProgress>(value).ok())
+        .map(|progress| PendingBatchInfo::from(&progress))
+        .collect();
+
+    batches.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn request(file_path: &str) -> BatchRequestRecord {
+        BatchRequestRecord {
+            file_path: file_path.to_string(),
+            mime_type: "image/jpeg".to_string(),
+        }
+    }
+
+    #[test]
+    fn pending_indices_exclude_recorded_results_but_not_skips() {
+        let mut progress = BatchProgress::new("b1", vec![request("a"), request("b"), request("c")]);
+
+        progress.record(0, &OcrResult::failure("失敗".to_string()));
+        progress.record(2, &OcrResult::skipped(AppError::AlreadyInFlight));
+
+        assert_eq!(progress.pending_indices(), vec![1, 2]);
+
+        progress.options.check_image_quality = true;
+        progress.options.file_timeout_secs = Some(30);
+
+        // 永続化して読み戻しても処理済みの index とオプションが保たれる
+        let restored: BatchProgress =
+            serde_json::from_value(serde_json::to_value(&progress).unwrap()).unwrap();
+        assert_eq!(restored.pending_indices(), vec![1, 2]);
+        assert!(restored.options.check_image_quality);
+        assert_eq!(restored.options.file_timeout_secs, Some(30));
+        assert!(!restored.is_complete());
+    }
+
+    #[test]
+    fn saves_are_throttled_until_the_interval_passes() {
+        let mut progress = BatchProgress::new("b1", vec![request("a")]);
+        let now = Instant::now();
+
+        assert!(progress.save_due(now));
+        assert!(!progress.save_due(now + Duration::from_millis(500)));
+        assert!(progress.save_due(now + SAVE_INTERVAL));
+
+        progress.record(0, &OcrResult::failure("失敗".to_string()));
+        assert!(progress.is_complete());
+    }
+}
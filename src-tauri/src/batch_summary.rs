@@ -0,0 +1,113 @@
+//! バッチOCRの完了サマリー
+//!
+//! 各ファイルの処理時間（`OcrResult.elapsed_ms`）から分布（p50/p90/最大）を求め、
+//! 全体を遅くしているファイルを特定できるよう遅い順に上位を挙げる。
+
+use crate::providers::OcrResult;
+use crate::summary::ValidationIssue;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// `slowest_files` に載せる件数
+const SLOWEST_FILES_LIMIT: usize = 5;
+
+/// `batch-completed` イベントの内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// バッチ全体の所要時間（ミリ秒）
+    pub elapsed_ms: u64,
+    /// 1ファイルの処理時間の中央値（処理したファイルが無ければ `None`）
+    pub p50_ms: Option<u64>,
+    /// 1ファイルの処理時間の90パーセンタイル
+    pub p90_ms: Option<u64>,
+    /// 1ファイルの処理時間の最大値
+    pub max_ms: Option<u64>,
+    /// 処理時間の長いファイル（ファイル名, ミリ秒）を遅い順に
+    pub slowest_files: Vec<(String, u64)>,
+    /// 成功した結果の検証で見つかった問題（`auto_validate` 指定時のみ）
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// 昇順に並んだ値のパーセンタイル（最近順位法）
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+impl BatchSummary {
+    /// リクエスト順のファイル名と結果から集計する（処理時間の無い結果は分布に含めない）
+    pub fn from_results(file_names: &[String], results: &[OcrResult], elapsed: Duration) -> Self {
+        let skipped = results.iter().filter(|r| r.skipped).count();
+        let succeeded = results.iter().filter(|r| r.success).count();
+
+        let mut timed: Vec<(String, u64)> = file_names
+            .iter()
+            .zip(results)
+            .filter_map(|(file_name, result)| Some((file_name.clone(), result.elapsed_ms?)))
+            .collect();
+        let mut sorted: Vec<u64> = timed.iter().map(|(_, ms)| *ms).collect();
+        sorted.sort_unstable();
+
+        timed.sort_by_key(|(_, ms)| Reverse(*ms));
+        timed.truncate(SLOWEST_FILES_LIMIT);
+
+        Self {
+            total: results.len(),
+            succeeded,
+            failed: results.len() - succeeded - skipped,
+            skipped,
+            elapsed_ms: elapsed.as_millis() as u64,
+            p50_ms: percentile(&sorted, 50.0),
+            p90_ms: percentile(&sorted, 90.0),
+            max_ms: sorted.last().copied(),
+            slowest_files: timed,
+            issues: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::providers::ReceiptData;
+
+    #[test]
+    fn summary_computes_percentiles_and_slowest_files() {
+        let mut file_names = Vec::new();
+        let mut results = Vec::new();
+        for i in 1..=10u64 {
+            file_names.push(format!("{}.jpg", i));
+            let mut result = OcrResult::success(ReceiptData::new(format!("{}.jpg", i)));
+            result.elapsed_ms = Some(i * 100);
+            results.push(result);
+        }
+        file_names.push("skip.jpg".to_string());
+        results.push(OcrResult::skipped(AppError::AlreadyInFlight));
+
+        let summary =
+            BatchSummary::from_results(&file_names, &results, Duration::from_millis(2500));
+        assert_eq!(summary.total, 11);
+        assert_eq!(
+            (summary.succeeded, summary.failed, summary.skipped),
+            (10, 0, 1)
+        );
+        assert_eq!(summary.p50_ms, Some(500));
+        assert_eq!(summary.p90_ms, Some(900));
+        assert_eq!(summary.max_ms, Some(1000));
+        assert_eq!(summary.slowest_files.len(), SLOWEST_FILES_LIMIT);
+        assert_eq!(summary.slowest_files[0], ("10.jpg".to_string(), 1000));
+
+        let empty = BatchSummary::from_results(&[], &[], Duration::ZERO);
+        assert_eq!(empty.p50_ms, None);
+    }
+}
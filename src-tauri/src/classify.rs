@@ -0,0 +1,196 @@
+//! 勘定科目の自動分類
+//!
+//! 勘定科目ルール（フロントエンドで編集し `account_category_rules` に保存）を店舗名に
+//! 当て、マッチの強さから確信度を付けて勘定科目を推定する。OCR結果の勘定科目の自動設定・
+//! 会計ソフト向け書き出しのどちらもここで判定し、フロントエンドはその結果（`category`）を使う。
+
+use crate::providers::ReceiptData;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// 勘定科目マッチングルール
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCategoryRule {
+    #[serde(default)]
+    pub id: String,
+    /// 正規表現パターン
+    pub pattern: String,
+    /// 正規表現フラグ（"i" など）
+    #[serde(default)]
+    pub flags: String,
+    /// マッチ時に設定する勘定科目
+    pub account_category: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 勘定科目ルール設定
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCategoryRulesSettings {
+    #[serde(default)]
+    pub rules: Vec<AccountCategoryRule>,
+}
+
+/// マッチの種類（強い順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchKind {
+    /// 文字列として店舗名全体と一致
+    Exact,
+    /// 正規表現として部分一致
+    Regex,
+    /// 文字列として部分一致
+    Contains,
+}
+
+impl MatchKind {
+    /// 種類ごとの確信度の範囲（下限, 上限）
+    ///
+    /// 範囲が重ならないため、種類の強さが一致範囲の長さより常に優先される。
+    fn confidence_range(self) -> (f32, f32) {
+        match self {
+            MatchKind::Exact => (1.0, 1.0),
+            MatchKind::Regex => (0.7, 0.9),
+            MatchKind::Contains => (0.4, 0.65),
+        }
+    }
+}
+
+/// 分類結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryMatch {
+    pub category: String,
+    /// 確信度（0.0〜1.0）
+    pub confidence: f32,
+    pub kind: MatchKind,
+    pub rule_id: String,
+}
+
+/// ルールのパターンをフラグ付きでコンパイルする（無効なパターンは `None`）
+fn compile(rule: &AccountCategoryRule) -> Option<Regex> {
+    RegexBuilder::new(&rule.pattern)
+        .case_insensitive(rule.flags.contains('i'))
+        .multi_line(rule.flags.contains('m'))
+        .dot_matches_new_line(rule.flags.contains('s'))
+        .build()
+        .ok()
+}
+
+/// 1ルールを店舗名に当てて確信度を計算する
+fn score_rule(merchant: &str, rule: &AccountCategoryRule) -> Option<CategoryMatch> {
+    if !rule.enabled || rule.pattern.trim().is_empty() {
+        return None;
+    }
+
+    let found = compile(rule)?.find(merchant)?;
+
+    // メタ文字を含まないパターンは文字列として扱う
+    let is_literal = regex::escape(&rule.pattern) == rule.pattern;
+    let is_whole = found.start() == 0 && found.end() == merchant.len();
+    let kind = match (is_literal, is_whole) {
+        (true, true) => MatchKind::Exact,
+        (true, false) => MatchKind::Contains,
+        (false, _) => MatchKind::Regex,
+    };
+
+    // 店舗名のうち一致した部分が長いほど確からしい
+    let coverage = found.as_str().chars().count() as f32 / merchant.chars().count() as f32;
+    let (low, high) = kind.confidence_range();
+
+    Some(CategoryMatch {
+        category: rule.account_category.clone(),
+        confidence: low + (high - low) * coverage,
+        kind,
+        rule_id: rule.id.clone(),
+    })
+}
+
+/// 店舗名から勘定科目を推定する
+///
+/// 複数のルールがマッチした場合は確信度が最も高いものを採用し、同点なら先のルールを採用する。
+pub fn classify_account(merchant: &str, rules: &[AccountCategoryRule]) -> Option<CategoryMatch> {
+    let merchant = merchant.trim();
+    if merchant.is_empty() {
+        return None;
+    }
+
+    rules
+        .iter()
+        .filter_map(|rule| score_rule(merchant, rule))
+        .fold(None, |best: Option<CategoryMatch>, candidate| match best {
+            Some(best) if best.confidence >= candidate.confidence => Some(best),
+            _ => Some(candidate),
+        })
+}
+
+/// レシートの店舗名から勘定科目と確信度を設定する
+pub fn apply_classification(data: &mut ReceiptData, rules: &[AccountCategoryRule]) {
+    let Some(matched) = data
+        .merchant
+        .as_deref()
+        .and_then(|merchant| classify_account(merchant, rules))
+    else {
+        return;
+    };
+
+    data.category = Some(matched.category);
+    data.category_confidence = Some(matched.confidence);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, pattern: &str, category: &str) -> AccountCategoryRule {
+        AccountCategoryRule {
+            id: id.to_string(),
+            pattern: pattern.to_string(),
+            flags: "i".to_string(),
+            account_category: category.to_string(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn classify_account_prefers_exact_then_regex_then_contains() {
+        let rules = vec![
+            rule("contains", "交通", "旅費交通費"),
+            rule("regex", "^日本.*タクシー", "旅費交通費（タクシー）"),
+            rule("exact", "日本交通タクシー", "タクシー代"),
+        ];
+
+        let matched = classify_account("日本交通タクシー", &rules).unwrap();
+        assert_eq!(matched.rule_id, "exact");
+        assert_eq!(matched.confidence, 1.0);
+
+        let matched = classify_account("日本交通タクシー 渋谷営業所", &rules).unwrap();
+        assert_eq!(matched.kind, MatchKind::Regex);
+
+        let matched = classify_account("東急交通", &rules).unwrap();
+        assert_eq!(matched.kind, MatchKind::Contains);
+        assert!(matched.confidence < 0.7);
+    }
+
+    #[test]
+    fn classify_account_keeps_first_rule_on_tie_and_skips_invalid() {
+        let mut disabled = rule("disabled", "ローソン", "会議費");
+        disabled.enabled = false;
+        let rules = vec![
+            disabled,
+            rule("invalid", "(", "雑費"),
+            rule("first", "ローソン", "消耗品費"),
+            rule("second", "ローソン", "福利厚生費"),
+        ];
+
+        let matched = classify_account("ローソン 新宿店", &rules).unwrap();
+        assert_eq!(matched.rule_id, "first");
+        assert_eq!(classify_account("セブンイレブン", &rules), None);
+    }
+}
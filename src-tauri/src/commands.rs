@@ -2,17 +2,69 @@
 //!
 //! フロントエンドから呼び出されるTauriコマンドを定義する。
 
-use crate::providers::{OcrProgressEvent, OcrProviderRegistry, OcrResult, OcrSettings};
-use futures::future::join_all;
+use crate::attempts::OcrAttempts;
+use crate::batch_progress::{self, BatchProgress, BatchRequestRecord, PendingBatchInfo};
+use crate::batch_summary::BatchSummary;
+use crate::classify::{
+    apply_classification, AccountCategoryRule, AccountCategoryRulesSettings, CategoryMatch,
+};
+use crate::denchou::DenchouIndex;
+use crate::diff::FieldDiff;
+use crate::document_number::NumberGapReport;
+use crate::duplicates::{DuplicateGroup, DuplicateRule};
+use crate::error::{AppError, Locale};
+use crate::image_quality::QualityReport;
+use crate::inflight::InFlightFiles;
+use crate::ledger::{LedgerCheckResult, LedgerMatchOptions};
+use crate::merchant_branch::apply_branch_split;
+use crate::merchant_category::{
+    apply_merchant_category, MerchantCategoryEntry, MerchantCategorySettings,
+};
+use crate::money::CurrencyTotal;
+use crate::ocr_cache::{MemoryOcrCache, OcrCacheKey};
+use crate::pipeline::{resolve_target_month, PipelineResult, PipelineStep, ProcessAndFileOptions};
+use crate::placeholder::Placeholder;
+use crate::postprocess::apply_postprocess;
+use crate::preflight::{PreflightReport, RequestValidation};
+use crate::profile_watch::ProfileWatcher;
+use crate::providers::escalation::{
+    detect_failovers, extract_with_escalation, merge_failovers, Adoption,
+};
+use crate::providers::googledocumentai::{GoogleDocumentAiProvider, LocationSource};
+use crate::providers::refine::refine_low_confidence_fields;
+use crate::providers::timing::OcrTiming;
+use crate::providers::tuning::{FileClock, ProviderLimits};
+use crate::providers::{
+    OcrChain, OcrProgressEvent, OcrProvider, OcrProviderRegistry, OcrResult, OcrSettings,
+    ReceiptData,
+};
+use crate::root_index::{
+    build_index, count_unprocessed_in_month, find_month_directories, list_receipt_files,
+    receipt_file_kind, FileInfo, RootIndex, RootIndexCache,
+};
+use crate::settings_recovery::SettingsRecoveredEvent;
+use crate::shutdown::{BatchCancellation, BatchShutdown, CancelToken};
+use crate::store_keys;
+use crate::summary::{
+    BulkUpdateResult, DroppedField, ManualEditMode, MonthSummary, ReceiptFilter, ReceiptPatch,
+    ReviewStatus, SummaryReceipt,
+};
+use crate::summary_merge::{SummaryBaseCache, SummaryConflict};
+use crate::validation::{merge_with_default_rules, ValidationRule, ValidationRulesSettings};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager, State};
-use tauri_plugin_store::StoreExt;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::Mutex;
 
 /// ディレクトリ検証結果
 #[derive(Serialize, Deserialize)]
@@ -24,53 +76,174 @@ pub struct DirectoryValidation {
 }
 
 /// OCR設定を取得
+///
+/// 保存された値が設定の型に合わない場合は、元の値を退避して読める項目だけで復旧し、
+/// `settings-recovered` イベントで知らせる。
 #[tauri::command]
 pub async fn get_ocr_settings(app: AppHandle) -> Result<OcrSettings, String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    let store = store_keys::open_store(&app)?;
 
-    let settings = store
-        .get("ocr_settings")
-        .and_then(|v| serde_json::from_value(v).ok())
-        .unwrap_or_default();
+    let Some(mut value) = store.get(store_keys::OCR_SETTINGS) else {
+        return Ok(OcrSettings::default());
+    };
+    // 旧形式の設定は現在の形式に書き換えて保存し直す（保存できなくても移行後の値で読む）
+    if crate::settings_migration::migrate_ocr_settings(&mut value) {
+        let _ = store_keys::set_value(&app, store_keys::OCR_SETTINGS, value.clone());
+    }
+    let reason = match serde_json::from_value(value.clone()) {
+        Ok(settings) => return Ok(settings),
+        Err(e) => format!("OCR設定を読み込めませんでした: {}", e),
+    };
 
-    Ok(settings)
+    let backup_path =
+        crate::settings_recovery::backup_value(&app, store_keys::OCR_SETTINGS, &value)
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned());
+    let salvaged = crate::settings_recovery::salvage::<OcrSettings>(&value);
+    if let Ok(recovered) = crate::settings_migration::to_store_value(&salvaged.value) {
+        store_keys::set_value(&app, store_keys::OCR_SETTINGS, recovered)?;
+    }
+    crate::settings_recovery::notify(
+        &app,
+        SettingsRecoveredEvent {
+            key: Some(store_keys::OCR_SETTINGS.to_string()),
+            reason,
+            backup_path,
+            recovered_fields: salvaged.recovered_fields,
+            dropped_fields: salvaged.dropped_fields,
+        },
+    );
+
+    Ok(salvaged.value)
+}
+
+/// ロケーションの推定結果
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationSuggestion {
+    /// 推奨する Document AI のロケーション
+    pub location: String,
+    /// 推定の根拠
+    pub source: LocationSource,
+}
+
+/// OSのロケール・タイムゾーンから Document AI のロケーションを推定
+///
+/// 初期値の提案のみで設定には保存しない（ユーザーが上書きできる）。
+#[tauri::command]
+pub async fn suggest_ocr_location() -> Result<LocationSuggestion, String> {
+    let locale = tauri_plugin_os::locale();
+    let utc_offset_secs = chrono::Local::now().offset().local_minus_utc();
+
+    let (location, source) =
+        GoogleDocumentAiProvider::suggest_location(locale.as_deref(), utc_offset_secs);
+
+    Ok(LocationSuggestion {
+        location: location.to_string(),
+        source,
+    })
 }
 
 /// OCR設定を保存
 #[tauri::command]
-pub async fn save_ocr_settings(app: AppHandle, settings: OcrSettings) -> Result<(), String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+pub async fn save_ocr_settings(
+    app: AppHandle,
+    test_cache: State<'_, ConnectionTestCache>,
+    profile_watcher: State<'_, ProfileWatcher>,
+    settings: OcrSettings,
+) -> Result<(), String> {
+    // 形式の誤りは保存前に弾く（実行時の HTTP 404 を防ぐ）
+    settings.validate()?;
 
-    store.set(
-        "ocr_settings",
-        serde_json::to_value(&settings).map_err(|e| e.to_string())?,
-    );
+    // 設定が変わるので接続テストの結果は使えなくなる
+    test_cache.invalidate();
 
-    store
-        .save()
-        .map_err(|e| format!("設定の保存に失敗しました: {}", e))?;
+    let value = crate::settings_migration::to_store_value(&settings)?;
+    store_keys::with_store_transaction(&app, |store| {
+        store.set(store_keys::OCR_SETTINGS, value);
+        Ok(())
+    })?;
 
-    Ok(())
+    // 共有プロファイルのパスが変わっていれば監視し直す
+    profile_watcher.watch(&app, settings.profile_path.as_deref())
+}
+
+/// 接続テストの成功結果を再利用する期間
+const CONNECTION_TEST_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// 直近の接続テスト成功結果のキャッシュ
+///
+/// 設定画面での連打によるトークン取得の多発を防ぐ。
+/// 設定内容のフィンガープリントを併せて持ち、設定が変わったら無効とする。
+#[derive(Default)]
+pub struct ConnectionTestCache {
+    last_success: std::sync::Mutex<Option<(u64, Instant)>>,
+}
+
+impl ConnectionTestCache {
+    /// 設定内容のフィンガープリント（機密値を保持しないようハッシュ化する）
+    fn fingerprint(settings: &OcrSettings) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(settings)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// 同じ設定での成功結果がTTL内に残っているか
+    fn is_fresh(&self, settings: &OcrSettings) -> bool {
+        let fingerprint = Self::fingerprint(settings);
+        self.last_success
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|(cached, at)| {
+                cached == fingerprint && at.elapsed() < CONNECTION_TEST_CACHE_TTL
+            })
+    }
+
+    fn record_success(&self, settings: &OcrSettings) {
+        *self.last_success.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((Self::fingerprint(settings), Instant::now()));
+    }
+
+    pub fn invalidate(&self) {
+        *self.last_success.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
 }
 
 /// プロバイダー接続テスト
+///
+/// 直近30秒以内に同じ設定で成功していれば実通信せずに成功を返す。
+/// `force` が true の場合はキャッシュを無視して再テストする。
 #[tauri::command]
 pub async fn test_provider_connection(
     app: AppHandle,
     registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    test_cache: State<'_, ConnectionTestCache>,
+    force: Option<bool>,
 ) -> Result<(), String> {
     let settings = get_ocr_settings(app).await?;
+
+    if !force.unwrap_or(false) && test_cache.is_fresh(&settings) {
+        return Ok(());
+    }
+
     let registry = registry.lock().await;
 
     let provider = registry
-        .get_default_provider()
+        .get_active_provider(&settings)
         .ok_or("OCRプロバイダーが見つかりません")?;
 
-    provider.test_connection(&settings).await
+    match provider.test_connection(&settings).await {
+        Ok(()) => {
+            test_cache.record_success(&settings);
+            Ok(())
+        }
+        Err(e) => {
+            test_cache.invalidate();
+            Err(e)
+        }
+    }
 }
 
 /// 単一ファイルのOCR処理
@@ -78,34 +251,534 @@ pub async fn test_provider_connection(
 pub async fn ocr_receipt(
     app: AppHandle,
     registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    in_flight: State<'_, Arc<InFlightFiles>>,
     file_path: String,
     file_content: String,
     mime_type: String,
+    collect_timings: Option<bool>,
 ) -> Result<OcrResult, String> {
+    // 同じファイルが処理中なら二重処理しない（ガードのドロップで解放される）
+    let Some(_in_flight_guard) = in_flight.try_acquire(&file_path) else {
+        return Ok(OcrResult::skipped(AppError::AlreadyInFlight));
+    };
+
     let settings = get_ocr_settings(app.clone()).await?;
-    let registry = registry.lock().await;
+    let (chain, refiner) = {
+        let registry = registry.lock().await;
+        (
+            registry.resolve_chain(&settings),
+            registry.resolve_refiner(&settings),
+        )
+    };
 
-    let provider = registry
-        .get_default_provider()
-        .ok_or("OCRプロバイダーが見つかりません")?;
+    if chain.providers.is_empty() {
+        return Err("OCRプロバイダーが見つかりません".to_string());
+    }
+
+    let mut result = extract_to_result(
+        &app,
+        &chain,
+        refiner.as_deref(),
+        &settings,
+        None,
+        collect_timings.unwrap_or(false),
+        &file_path,
+        &file_content,
+        &mime_type,
+        "single-file OCR",
+    )
+    .await;
+
+    if let Some(data) = result.data.as_mut() {
+        let summaries = crate::summary::load_summaries_for([file_path.as_str()]);
+        crate::summary::preserve_manual_edits(data, &file_path, &summaries);
+        apply_classification(data, &load_account_category_rules(&app));
+        apply_merchant_category(data, &load_merchant_category_entries(&app));
+    }
+
+    emit_failovers(
+        &app,
+        &[file_name_of(&file_path)],
+        std::slice::from_ref(&result),
+    );
+
+    Ok(result)
+}
+
+/// アプリの終了で止めたバッチの処理済みの結果をサマリーへ書き出す（失敗はエラーログに残す）
+fn flush_results_on_shutdown(app: &AppHandle, file_paths: &[String], results: &[OcrResult]) {
+    if let Err(e) = crate::summary::flush_results(file_paths, results) {
+        let _ = crate::errorlog::write_log_entry(
+            app,
+            "rust-shutdown",
+            &format!("終了時の結果の書き出しに失敗しました: {}", e),
+            None,
+            None,
+            None,
+        );
+    }
+}
+
+/// バッチの完了を処理時間の分布とともに `batch-completed` イベントで通知する
+///
+/// `auto_validate` なら成功した結果を検証して `issues` に含める。ルールを読めないなど
+/// 検証できなかった場合はエラーログに残し、`issues` を空のまま通知する。
+fn emit_batch_completed(
+    app: &AppHandle,
+    file_names: &[String],
+    results: &[OcrResult],
+    elapsed: Duration,
+    auto_validate: bool,
+) {
+    let mut summary = BatchSummary::from_results(file_names, results, elapsed);
+    if auto_validate {
+        match load_validation_rules(app) {
+            Ok(rules) => {
+                let succeeded: Vec<ReceiptData> = results
+                    .iter()
+                    .filter(|result| result.success)
+                    .filter_map(|result| result.data.clone())
+                    .collect();
+                summary.issues = crate::validation::validate_receipts(
+                    &succeeded,
+                    &rules,
+                    &load_registered_receiver_names(app),
+                );
+            }
+            Err(e) => {
+                let _ = crate::errorlog::write_log_entry(
+                    app,
+                    "rust-validation",
+                    &format!("バッチ結果の検証に失敗しました: {}", e),
+                    None,
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+    let _ = app.emit("batch-completed", summary);
+}
 
-    match provider
-        .extract_receipt(&file_path, &file_content, &mime_type, &settings)
+/// 保存済みのバリデーションルールを読み込む（未設定なら組み込みルールの既定値）
+fn load_validation_rules(app: &AppHandle) -> Result<Vec<ValidationRule>, String> {
+    let store = store_keys::open_store(app)?;
+    let rules = match store.get(store_keys::VALIDATION_RULES) {
+        Some(value) if !value.is_null() => {
+            serde_json::from_value::<ValidationRulesSettings>(value)
+                .map_err(|e| format!("バリデーションルールの形式が正しくありません: {}", e))?
+                .rules
+        }
+        _ => Vec::new(),
+    };
+    Ok(merge_with_default_rules(rules))
+}
+
+/// 宛名設定の登録済み宛名（未設定・読めない場合は空）
+fn load_registered_receiver_names(app: &AppHandle) -> Vec<String> {
+    store_keys::open_store(app)
+        .ok()
+        .and_then(|store| store.get(store_keys::RECEIVER_NAME_HISTORY))
+        .and_then(|value| {
+            serde_json::from_value::<Vec<String>>(value.get("registeredNames")?.clone()).ok()
+        })
+        .unwrap_or_default()
+}
+
+/// プロバイダーの切り替えを `provider-failover` イベントで通知する
+///
+/// バッチでは同じ切り替え元・先の組をまとめて1件ずつ通知する。
+fn emit_failovers(app: &AppHandle, file_names: &[String], results: &[OcrResult]) {
+    let failovers = file_names
+        .iter()
+        .zip(results)
+        .flat_map(|(file_name, result)| detect_failovers(file_name, &result.escalation_steps))
+        .collect();
+
+    for failover in merge_failovers(failovers) {
+        let _ = app.emit("provider-failover", failover);
+    }
+}
+
+/// 保存済みの勘定科目ルールを読み込む（未設定・読み込み失敗時は空）
+fn load_account_category_rules(app: &AppHandle) -> Vec<AccountCategoryRule> {
+    store_keys::open_store(app)
+        .ok()
+        .and_then(|store| store.get(store_keys::ACCOUNT_CATEGORY_RULES))
+        .and_then(|v| serde_json::from_value::<AccountCategoryRulesSettings>(v).ok())
+        .map(|settings| settings.rules)
+        .unwrap_or_default()
+}
+
+/// 保存済みの業種ユーザー辞書を読み込む（未設定・読み込み失敗時は空）
+fn load_merchant_category_entries(app: &AppHandle) -> Vec<MerchantCategoryEntry> {
+    store_keys::open_store(app)
+        .ok()
+        .and_then(|store| store.get(store_keys::MERCHANT_CATEGORY_DICTIONARY))
+        .and_then(|v| serde_json::from_value::<MerchantCategorySettings>(v).ok())
+        .map(|settings| settings.entries)
+        .unwrap_or_default()
+}
+
+/// 店舗名から業種を推定（内蔵辞書と保存済みのユーザー辞書を使用）
+#[tauri::command]
+pub async fn infer_merchant_category(
+    app: AppHandle,
+    merchant: String,
+) -> Result<Option<String>, String> {
+    Ok(crate::merchant_category::infer_merchant_category(
+        &merchant,
+        &load_merchant_category_entries(&app),
+    ))
+}
+
+/// 店舗名から勘定科目を推定（保存済みの勘定科目ルールを使用）
+#[tauri::command]
+pub async fn classify_account(
+    app: AppHandle,
+    merchant: String,
+) -> Result<Option<CategoryMatch>, String> {
+    Ok(crate::classify::classify_account(
+        &merchant,
+        &load_account_category_rules(&app),
+    ))
+}
+
+/// OCRしたレシートを会計ソフトの仕訳 CSV と照合し、計上済みの候補を返す
+///
+/// 日付・金額が許容範囲内（既定は日付±1日・金額完全一致）で取引先も近い仕訳があれば
+/// `already_posted` になる。結果はリクエストのレシート順。
+#[tauri::command]
+pub async fn check_against_ledger(
+    receipts: Vec<ReceiptData>,
+    ledger_csv: String,
+    options: Option<LedgerMatchOptions>,
+) -> Result<Vec<LedgerCheckResult>, String> {
+    let ledger = crate::ledger::parse_ledger_csv(&ledger_csv)?;
+    Ok(crate::ledger::check_against_ledger(
+        &receipts,
+        &ledger,
+        &options.unwrap_or_default(),
+    ))
+}
+
+/// 重複候補のレシートを、リクエストでの位置のグループとヒットしたルールにして返す
+///
+/// `rules` のいずれかに一致すれば重複候補とする（未指定なら日付・店舗名・金額のみ）。
+/// 店舗名は全角半角・空白などの表記ゆれを吸収し、金額は1円（通貨の最小単位）単位で比較する。
+#[tauri::command]
+pub async fn detect_duplicate_receipts(
+    receipts: Vec<ReceiptData>,
+    rules: Option<Vec<DuplicateRule>>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let rules = rules.unwrap_or_else(|| vec![DuplicateRule::DateMerchantAmount]);
+    Ok(crate::duplicates::find_duplicate_receipts(
+        &receipts, &rules,
+    ))
+}
+
+/// Base64 の画像を長辺 `max_dimension` に収まるよう縮小し、BMP・TIFF は PNG に変換する
+/// （縮小・変換が不要、または対象外なら `None`）
+async fn downscale_for_ocr(
+    file_content: &str,
+    max_dimension: Option<u32>,
+) -> Option<(String, crate::downscale::Downscaled)> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let file_content = file_content.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = STANDARD.decode(file_content).ok()?;
+        let downscaled = crate::downscale::downscale_to_fit(&bytes, max_dimension).ok()??;
+        Some((STANDARD.encode(&downscaled.content), downscaled))
+    })
+    .await
+    .ok()?
+}
+
+/// Base64 の HEIC 画像を JPEG（Base64）に変換する
+async fn convert_heic_base64(file_content: &str) -> Result<String, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let file_content = file_content.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = STANDARD
+            .decode(file_content)
+            .map_err(|e| format!("Base64のデコードに失敗しました: {}", e))?;
+        crate::heic::convert_heic_to_jpeg(&bytes).map(|jpeg| STANDARD.encode(jpeg))
+    })
+    .await
+    .map_err(|e| format!("HEIC 画像の変換に失敗しました: {}", e))?
+}
+
+/// HEIC・HEIF 画像（Base64）を JPEG（Base64）に変換する
+///
+/// libheif の `heif-convert` が PATH 上に必要。OCR では自動で変換するため、
+/// プレビューなど画像として表示したい場合に使う。
+#[tauri::command]
+pub async fn convert_heic_to_jpeg(file_content: String) -> Result<String, String> {
+    convert_heic_base64(&file_content).await
+}
+
+/// Base64 の画像の鮮鋭度を判定する（画像として読めなければ `None`）
+async fn assess_quality_for_ocr(file_content: &str) -> Option<QualityReport> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let file_content = file_content.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = STANDARD.decode(file_content).ok()?;
+        crate::image_quality::assess_image_quality(&bytes).ok()?
+    })
+    .await
+    .ok()?
+}
+
+/// レシート画像の手ブレ・ボケを判定する
+///
+/// 鮮鋭度のスコアが閾値未満なら `sharp: false` と再撮影の案内を返す。PDF は判定できない。
+#[tauri::command]
+pub async fn assess_image_quality(
+    file_content: String,
+    mime_type: String,
+) -> Result<QualityReport, String> {
+    let mime_type = crate::mime::normalize_base64_mime_type(&mime_type, &file_content)?;
+    if mime_type == "application/pdf" {
+        return Err("PDFは画質を判定できません".to_string());
+    }
+    assess_quality_for_ocr(&file_content)
         .await
-    {
-        Ok(data) => Ok(OcrResult::success(data)),
+        .ok_or_else(|| "画像を読み込めないため画質を判定できませんでした".to_string())
+}
+
+/// OCRの前段（キャッシュの確認とプロバイダーに送る形への変換）の結果
+enum PreparedOcr {
+    /// 読み取り済みの抽出結果（整形ルールの適用前）
+    Cached(Box<ReceiptData>),
+    /// プロバイダーに送れる形に整えたファイル
+    Ready {
+        cache_key: OcrCacheKey,
+        /// 正規化した MIME タイプ
+        mime_type: String,
+        /// HEIC から変換した JPEG（Base64。HEIC 以外は `None`）
+        converted: Option<String>,
+        /// 縮小・PNG 変換した内容（不要なら元の内容のまま送る）
+        downscaled: Option<Box<(String, crate::downscale::Downscaled)>>,
+    },
+    /// 送れない形式
+    Unsupported(String),
+}
+
+/// OCRの前段: キャッシュを確認し、無ければプロバイダーに送る形に整える
+///
+/// プロバイダーを呼ばないので、バッチでは API 呼び出しと重ねて先読みできる。
+async fn prepare_ocr(
+    app: &AppHandle,
+    chain: &[Arc<dyn OcrProvider>],
+    settings: &OcrSettings,
+    file_content: &str,
+    mime_type: &str,
+) -> PreparedOcr {
+    // 同じファイル・同じプロバイダーと設定で読み取り済みならキャッシュを返す
+    // （セッション中に読んだものはメモリから、それ以外はディスクから）
+    let provider_names: Vec<&str> = chain.iter().map(|provider| provider.name()).collect();
+    let cache_key = OcrCacheKey::new(file_content, &provider_names, settings);
+    let memory_cache = app.state::<MemoryOcrCache>();
+    let cached = memory_cache.get(&cache_key).or_else(|| {
+        let cache_dir = crate::ocr_cache::cache_dir(app).ok()?;
+        let data = crate::ocr_cache::read(&cache_dir, &cache_key)?;
+        memory_cache.put(cache_key.clone(), data.clone());
+        Some(data)
+    });
+    if let Some(data) = cached {
+        return PreparedOcr::Cached(Box::new(data));
+    }
+
+    // HEIC はプロバイダーが受け付けないことがあるため JPEG にしてから送る
+    let converted = if crate::mime::is_heic_base64(mime_type, file_content) {
+        match convert_heic_base64(file_content).await {
+            Ok(jpeg) => Some(jpeg),
+            Err(e) => return PreparedOcr::Unsupported(e),
+        }
+    } else {
+        None
+    };
+    let file_content = converted.as_deref().unwrap_or(file_content);
+
+    // MIME の表記ゆれを揃え、対応していない形式はプロバイダーに送る前に止める
+    let mime_type = match crate::mime::normalize_base64_mime_type(mime_type, file_content) {
+        Ok(normalized) => normalized,
+        Err(e) => return PreparedOcr::Unsupported(e),
+    };
+
+    // 上限を超える画像は縮小し、BMP・TIFF は PNG にしてから送る（できなければ元の画像のまま）
+    let downscaled = downscale_for_ocr(file_content, settings.max_image_dimension())
+        .await
+        .map(Box::new);
+    PreparedOcr::Ready {
+        cache_key,
+        mime_type,
+        converted,
+        downscaled,
+    }
+}
+
+/// 設定されたプロバイダー（エスカレーションチェーン）で1ファイルを抽出する
+///
+/// `refiner` があれば信頼度の低い項目をそのプロバイダーで読み直す。
+/// 失敗はエラーログに `log_context` 付きで記録する。`collect_timings` が有効なら
+/// フェーズ別の所要時間を結果に含める。
+#[allow(clippy::too_many_arguments)]
+async fn extract_to_result(
+    app: &AppHandle,
+    chain: &OcrChain,
+    refiner: Option<&dyn OcrProvider>,
+    settings: &OcrSettings,
+    limits: Option<&ProviderLimits>,
+    collect_timings: bool,
+    file_path: &str,
+    file_content: &str,
+    mime_type: &str,
+    log_context: &str,
+) -> OcrResult {
+    let prepared = prepare_ocr(app, &chain.providers, settings, file_content, mime_type).await;
+    extract_prepared(
+        app,
+        chain,
+        refiner,
+        settings,
+        limits,
+        None,
+        collect_timings,
+        file_path,
+        file_content,
+        prepared,
+        log_context,
+    )
+    .await
+}
+
+/// 前段で整えたファイルをプロバイダーで抽出する（`extract_to_result` の後段）
+///
+/// `clock` があれば最初のプロバイダーの実行枠を確保した時点を記録する。
+#[allow(clippy::too_many_arguments)]
+async fn extract_prepared(
+    app: &AppHandle,
+    chain: &OcrChain,
+    refiner: Option<&dyn OcrProvider>,
+    settings: &OcrSettings,
+    limits: Option<&ProviderLimits>,
+    clock: Option<&FileClock>,
+    collect_timings: bool,
+    file_path: &str,
+    file_content: &str,
+    prepared: PreparedOcr,
+    log_context: &str,
+) -> OcrResult {
+    let started = collect_timings.then(Instant::now);
+    let mut timing = collect_timings.then(OcrTiming::default);
+
+    let (cache_key, normalized_mime, converted, downscaled) = match prepared {
+        PreparedOcr::Cached(mut data) => {
+            // 同じ内容の別ファイルの結果でもファイル名は今回のものにする
+            data.file = file_name_of(file_path);
+            apply_postprocess(&mut data, &settings.postprocess_rules);
+            apply_branch_split(&mut data);
+            let provider_name = data.source_provider.clone();
+            return OcrResult {
+                provider_name,
+                ..OcrResult::success(*data)
+            };
+        }
+        PreparedOcr::Unsupported(e) => return OcrResult::failure(e),
+        PreparedOcr::Ready {
+            cache_key,
+            mime_type,
+            converted,
+            downscaled,
+        } => (cache_key, mime_type, converted, downscaled),
+    };
+    let file_content = converted.as_deref().unwrap_or(file_content);
+    let memory_cache = app.state::<MemoryOcrCache>();
+    let cache_dir = crate::ocr_cache::cache_dir(app).ok();
+    let (file_content, mime_type) = match downscaled.as_deref() {
+        Some((content, downscaled)) => (content.as_str(), downscaled.mime_type),
+        None => (file_content, normalized_mime.as_str()),
+    };
+
+    let outcome = extract_with_escalation(
+        &chain.providers,
+        file_path,
+        file_content,
+        mime_type,
+        settings,
+        chain.adoption,
+        limits,
+        clock,
+        timing.as_mut(),
+    )
+    .await;
+
+    if outcome.stopped {
+        return OcrResult::skipped(AppError::Cancelled);
+    }
+
+    let mut refine_warning = None;
+    let mut result = match outcome.result {
+        Ok(mut data) => {
+            if let Some(refiner) = refiner {
+                refine_warning = refine_low_confidence_fields(
+                    &mut data,
+                    refiner,
+                    file_path,
+                    file_content,
+                    mime_type,
+                    settings,
+                    limits,
+                )
+                .await;
+            }
+            if let Some(dir) = &cache_dir {
+                let _ = crate::ocr_cache::write(dir, &cache_key, &data);
+            }
+            memory_cache.put(cache_key.clone(), data.clone());
+            apply_postprocess(&mut data, &settings.postprocess_rules);
+            apply_branch_split(&mut data);
+            OcrResult::success(data)
+        }
         Err(e) => {
             let _ = crate::errorlog::write_log_entry(
-                &app,
+                app,
                 "rust-ocr",
-                &e,
+                &e.message,
                 None,
                 None,
-                Some("single-file OCR"),
+                Some(log_context),
             );
-            Ok(OcrResult::failure(e))
+            OcrResult::provider_failure(e)
         }
+    };
+
+    if converted.is_some() {
+        result
+            .warnings
+            .push("HEIC 画像をJPEGに変換してOCRしました".to_string());
+    }
+    if let Some((_, downscaled)) = downscaled.as_deref() {
+        result.warnings.push(downscaled.warning());
     }
+    result.warnings.extend(refine_warning);
+
+    result.provider_name = outcome.provider_name;
+    // どのプロバイダーを経て採用されたかを残す（チェーン設定時・フォールバック時のみ）
+    if chain.adoption == Adoption::Completeness || chain.providers.len() > 1 {
+        result.escalation_steps = outcome.steps;
+    }
+
+    if let (Some(mut timing), Some(started)) = (timing, started) {
+        timing.total_ms = started.elapsed().as_millis() as u64;
+        result.timing = Some(timing);
+    }
+
+    result
 }
 
 /// OCR処理リクエスト
@@ -117,78 +790,594 @@ pub struct OcrRequest {
     pub mime_type: String,
 }
 
-/// 並列処理の最大同時実行数
-const MAX_CONCURRENT_OCR: usize = 4;
+/// バッチOCRのオプション（`batch_id` 指定時は進捗と一緒に保存し、再開時に使う）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BatchOcrOptions {
+    /// 結果をCSV文字列としても返すか
+    pub return_csv: bool,
+    /// 指定すると途中経過をストアに保存し、`resume_batch` で再開できるようにする
+    pub batch_id: Option<String>,
+    /// 各結果にフェーズ別の所要時間を含めるか
+    pub collect_timings: bool,
+    /// 完了時にデスクトップ通知を出すか
+    pub notify_on_complete: bool,
+    /// 1ファイルあたりの制限時間（秒）。最初の実行枠を確保してから数え、リトライ・エスカレーションを含む
+    pub file_timeout_secs: Option<u64>,
+    /// バッチ全体の制限時間（秒）。超えたら未完了のファイルを打ち切ってスキップ扱いにする
+    pub batch_deadline_secs: Option<u64>,
+    /// 画像の手ブレ・ボケを判定し、不鮮明なら warnings と `needs_retake` を付ける
+    pub check_image_quality: bool,
+    /// 完了時に成功した結果を検証し、問題を `batch-completed` の `issues` に含める
+    pub auto_validate: bool,
+}
+
+/// バッチOCRの応答
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOcrResponse {
+    /// リクエスト順の処理結果
+    pub results: Vec<OcrResult>,
+    /// 結果のCSV（UTF-8 BOM付き、`return_csv` 指定時のみ）
+    pub csv_content: Option<String>,
+}
+
+/// パスからファイル名部分を取り出す（取れない場合はパスそのもの）
+fn file_name_of(file_path: &str) -> String {
+    Path::new(file_path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(file_path)
+        .to_string()
+}
 
 /// バッチOCR処理（並列実行）
 #[tauri::command]
 pub async fn batch_ocr_receipts(
     app: AppHandle,
     registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    in_flight: State<'_, Arc<InFlightFiles>>,
+    shutdown: State<'_, Arc<BatchShutdown>>,
+    cancellation: State<'_, BatchCancellation>,
     requests: Vec<OcrRequest>,
-) -> Result<Vec<OcrResult>, String> {
-    let settings = Arc::new(get_ocr_settings(app.clone()).await?);
-    let registry_guard = registry.lock().await;
+    options: Option<BatchOcrOptions>,
+) -> Result<BatchOcrResponse, String> {
+    let _running = shutdown.register();
+    let cancelled = cancellation.register();
+    let options = options.unwrap_or_default();
+    let started_at = Instant::now();
+    let file_names: Vec<String> = requests
+        .iter()
+        .map(|request| file_name_of(&request.file_path))
+        .collect();
+    let file_paths: Vec<String> = requests
+        .iter()
+        .map(|request| request.file_path.clone())
+        .collect();
+
+    let progress = match &options.batch_id {
+        Some(batch_id) => {
+            let records = requests
+                .iter()
+                .map(|request| BatchRequestRecord {
+                    file_path: request.file_path.clone(),
+                    mime_type: request.mime_type.clone(),
+                })
+                .collect();
+            let mut progress = BatchProgress::new(batch_id, records);
+            progress.options = options.clone();
+            batch_progress::save(&app, &progress)?;
+            Some(Arc::new(Mutex::new(progress)))
+        }
+        None => None,
+    };
+
+    let pending = requests.into_iter().enumerate().collect();
+    let mut indexed_results = run_batch(
+        &app,
+        &registry,
+        &in_flight,
+        &shutdown,
+        &cancelled,
+        &options,
+        pending,
+        &file_names,
+        0,
+        progress,
+    )
+    .await?;
+
+    // 元のインデックス順にソート
+    indexed_results.sort_by_key(|(index, _)| *index);
+
+    // 結果のみを抽出
+    let results: Vec<OcrResult> = indexed_results
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect();
+
+    // アプリの終了で止めた場合は結果をサマリーへ書き出し、進捗は再開用に残す
+    if shutdown.is_shutting_down() {
+        flush_results_on_shutdown(&app, &file_paths, &results);
+        return Ok(batch_response(&file_names, results, options.return_csv));
+    }
+
+    // 中止・デッドラインなどでスキップしたファイルがあれば `resume_batch` で続けられるよう進捗を残す
+    if let Some(batch_id) = options
+        .batch_id
+        .as_ref()
+        .filter(|_| results.iter().all(|result| !result.skipped))
+    {
+        batch_progress::remove(&app, batch_id)?;
+    }
 
-    let provider = Arc::new(
-        registry_guard
-            .get_default_provider()
-            .ok_or("OCRプロバイダーが見つかりません")?
-            .clone(),
+    if options.notify_on_complete {
+        crate::notify::notify_batch_complete(&app, &results, started_at.elapsed());
+    }
+    emit_batch_completed(
+        &app,
+        &file_names,
+        &results,
+        started_at.elapsed(),
+        options.auto_validate,
     );
+    emit_failovers(&app, &file_names, &results);
+    crate::webhook::spawn_batch_results(&app, &results);
 
-    drop(registry_guard);
+    Ok(batch_response(&file_names, results, options.return_csv))
+}
 
-    let total = requests.len();
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_OCR));
-    let completed_count = Arc::new(AtomicUsize::new(0));
+/// 失敗したファイルだけを再OCRする
+///
+/// `succeeded_paths` に含まれるファイル（成功済み）は除いて `batch_ocr_receipts` と同じ並列処理で
+/// 実行する。連続で [`crate::attempts::MAX_AUTO_RETRY_ATTEMPTS`] 回失敗したファイルは送らずに
+/// スキップ（`RetryLimitReached`）とする。結果は除外後のリクエスト順。
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn retry_failed_ocr(
+    app: AppHandle,
+    registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    in_flight: State<'_, Arc<InFlightFiles>>,
+    shutdown: State<'_, Arc<BatchShutdown>>,
+    cancellation: State<'_, BatchCancellation>,
+    attempts: State<'_, OcrAttempts>,
+    requests: Vec<OcrRequest>,
+    succeeded_paths: Vec<String>,
+    options: Option<BatchOcrOptions>,
+) -> Result<BatchOcrResponse, String> {
+    let succeeded: HashSet<&str> = succeeded_paths.iter().map(String::as_str).collect();
+    let requests: Vec<OcrRequest> = requests
+        .into_iter()
+        .filter(|request| !succeeded.contains(request.file_path.as_str()))
+        .collect();
+    let file_names: Vec<String> = requests
+        .iter()
+        .map(|request| file_name_of(&request.file_path))
+        .collect();
 
-    // 各リクエストを並列タスクとして生成
-    let tasks: Vec<_> = requests
+    let (exhausted, retryable): (Vec<_>, Vec<_>) = requests
         .into_iter()
         .enumerate()
+        .map(|(index, request)| (index, attempts.exhausted(&request.file_path), request))
+        .partition(|(_, exhausted, _)| exhausted.is_some());
+    let (retry_indices, retry_requests): (Vec<usize>, Vec<OcrRequest>) = retryable
+        .into_iter()
+        .map(|(index, _, request)| (index, request))
+        .unzip();
+
+    let mut options = options.unwrap_or_default();
+    let return_csv = std::mem::take(&mut options.return_csv);
+    let response = batch_ocr_receipts(
+        app,
+        registry,
+        in_flight,
+        shutdown,
+        cancellation,
+        retry_requests,
+        Some(options),
+    )
+    .await?;
+
+    // 上限に達して送らなかったファイルを元の位置に戻す
+    let mut results: Vec<Option<OcrResult>> = vec![None; file_names.len()];
+    for (index, result) in retry_indices.into_iter().zip(response.results) {
+        results[index] = Some(result);
+    }
+    for (index, attempts, _) in exhausted {
+        results[index] = Some(OcrResult::skipped(AppError::RetryLimitReached {
+            attempts: attempts.unwrap_or_default(),
+        }));
+    }
+    let results = results.into_iter().flatten().collect();
+
+    Ok(batch_response(&file_names, results, return_csv))
+}
+
+/// 途中で中断されたバッチを再開する
+///
+/// 処理済みの結果は保存済みのものを再利用し、未処理のファイルだけを
+/// パスから読み直して再実行する。
+#[tauri::command]
+pub async fn resume_batch(
+    app: AppHandle,
+    registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    in_flight: State<'_, Arc<InFlightFiles>>,
+    shutdown: State<'_, Arc<BatchShutdown>>,
+    cancellation: State<'_, BatchCancellation>,
+    batch_id: String,
+) -> Result<BatchOcrResponse, String> {
+    let _running = shutdown.register();
+    let cancelled = cancellation.register();
+    let started_at = Instant::now();
+    let mut progress = batch_progress::load(&app, &batch_id)?
+        .ok_or_else(|| format!("バッチが見つかりません: {}", batch_id))?;
+
+    let file_names: Vec<String> = progress
+        .requests
+        .iter()
+        .map(|request| file_name_of(&request.file_path))
+        .collect();
+
+    // 未処理分のファイルを読み直す（読めないものはその場で失敗として記録）
+    let mut pending = Vec::new();
+    for index in progress.pending_indices() {
+        let record = progress.requests[index].clone();
+        match fs::read(&record.file_path) {
+            Ok(bytes) => {
+                use base64::{engine::general_purpose::STANDARD, Engine};
+                pending.push((
+                    index,
+                    OcrRequest {
+                        file_path: record.file_path,
+                        file_content: STANDARD.encode(&bytes),
+                        mime_type: record.mime_type,
+                    },
+                ));
+            }
+            Err(e) => {
+                let result = OcrResult::failure_with(AppError::FileReadFailed {
+                    detail: e.to_string(),
+                });
+                progress.record(index, &result);
+            }
+        }
+    }
+
+    let total = progress.requests.len();
+    let already_completed = total - pending.len();
+    // 開始時のオプション（画質判定・制限時間・自動検証など）で続ける
+    let options = BatchOcrOptions {
+        batch_id: Some(batch_id.clone()),
+        ..progress.options.clone()
+    };
+    let progress = Arc::new(Mutex::new(progress));
+    let _ = batch_progress::save(&app, &*progress.lock().await);
+
+    let fresh: HashMap<usize, OcrResult> = run_batch(
+        &app,
+        &registry,
+        &in_flight,
+        &shutdown,
+        &cancelled,
+        &options,
+        pending,
+        &file_names,
+        already_completed,
+        Some(Arc::clone(&progress)),
+    )
+    .await?
+    .into_iter()
+    .collect();
+
+    // 保存済みの結果と今回の結果（スキップを含む）を合わせてリクエスト順に並べる
+    let progress = progress.lock().await;
+    let results: Vec<OcrResult> = (0..total)
+        .map(|index| {
+            progress
+                .results
+                .get(&index)
+                .or_else(|| fresh.get(&index))
+                .cloned()
+                .unwrap_or_else(|| OcrResult::skipped(AppError::NotProcessed))
+        })
+        .collect();
+
+    if shutdown.is_shutting_down() {
+        let file_paths: Vec<String> = progress
+            .requests
+            .iter()
+            .map(|request| request.file_path.clone())
+            .collect();
+        flush_results_on_shutdown(&app, &file_paths, &results);
+        return Ok(batch_response(&file_names, results, options.return_csv));
+    }
+
+    // スキップしたファイルが残っていれば、もう一度再開できるよう進捗を残す
+    if progress.is_complete() {
+        batch_progress::remove(&app, &batch_id)?;
+    }
+    if options.notify_on_complete {
+        crate::notify::notify_batch_complete(&app, &results, started_at.elapsed());
+    }
+    emit_batch_completed(
+        &app,
+        &file_names,
+        &results,
+        started_at.elapsed(),
+        options.auto_validate,
+    );
+    emit_failovers(&app, &file_names, &results);
+    crate::webhook::spawn_batch_results(&app, &results);
+
+    Ok(batch_response(&file_names, results, options.return_csv))
+}
+
+/// 実行中のバッチOCRを中止する
+///
+/// 処理中のファイルはプロバイダーの応答を待たずに打ち切り、未着手のファイルとともにスキップする。
+/// 中止したバッチ数を返す。
+#[tauri::command]
+pub fn cancel_batch_ocr(cancellation: State<'_, BatchCancellation>) -> usize {
+    cancellation.cancel_all()
+}
+
+/// 中断されたまま残っているバッチを列挙する
+#[tauri::command]
+pub async fn list_pending_batches(app: AppHandle) -> Result<Vec<PendingBatchInfo>, String> {
+    batch_progress::list(&app)
+}
+
+/// バッチの応答を組み立てる
+fn batch_response(
+    file_names: &[String],
+    results: Vec<OcrResult>,
+    return_csv: bool,
+) -> BatchOcrResponse {
+    let csv_content = return_csv.then(|| {
+        crate::export::batch_results_csv(file_names.iter().map(String::as_str).zip(results.iter()))
+    });
+
+    BatchOcrResponse {
+        results,
+        csv_content,
+    }
+}
+
+/// バッチの前段（キャッシュ確認・縮小）の同時実行数の上限（CPU 数がこれより少なければ CPU 数）
+const MAX_PREPARE_CONCURRENCY: usize = 4;
+
+/// バッチの各リクエストを並列に処理する
+///
+/// `pending` は（元の index, リクエスト）の組で、結果は完了順に返す（並べ替えは呼び出し側）。
+/// 前段でファイルを整え、後段でプロバイダーを呼ぶパイプラインで、縮小の待ちを API の待ちと重ねる。
+/// `progress` を渡すと1件完了するごとに
+/// 結果を記録し、間隔を空けてストアへ保存する（最後に必ず保存する）。進捗イベントの `current` は
+/// `already_completed` 件が処理済みの状態から数える。
+/// 中止されると未着手・処理中のファイルはスキップ（`Cancelled`）になる。
+#[allow(clippy::too_many_arguments)]
+async fn run_batch(
+    app: &AppHandle,
+    registry: &Mutex<OcrProviderRegistry>,
+    in_flight: &Arc<InFlightFiles>,
+    shutdown: &BatchShutdown,
+    cancelled: &Arc<CancelToken>,
+    options: &BatchOcrOptions,
+    pending: Vec<(usize, OcrRequest)>,
+    file_names: &[String],
+    already_completed: usize,
+    progress: Option<Arc<Mutex<BatchProgress>>>,
+) -> Result<Vec<(usize, OcrResult)>, String> {
+    let settings = Arc::new(get_ocr_settings(app.clone()).await?);
+    let (chain, refiner) = {
+        let registry = registry.lock().await;
+        (
+            Arc::new(registry.resolve_chain(&settings)),
+            registry.resolve_refiner(&settings),
+        )
+    };
+
+    if chain.providers.is_empty() {
+        return Err("OCRプロバイダーが見つかりません".to_string());
+    }
+
+    let total = file_names.len();
+    let collect_timings = options.collect_timings;
+    let category_rules = Arc::new(load_account_category_rules(app));
+    let merchant_entries = Arc::new(load_merchant_category_entries(app));
+    // 手動確定済みの値を引き継ぐため、対象の月のサマリーを先に読んでおく
+    let summaries = Arc::new(crate::summary::load_summaries_for(
+        pending
+            .iter()
+            .map(|(_, request)| request.file_path.as_str()),
+    ));
+    // 同時実行数はプロバイダーごとに制限する
+    // アプリの終了・ユーザーの中止時は実行枠を待っているファイルに着手させない
+    // 再抽出のプロバイダーも同時実行数の制限に含める
+    let limited: Vec<_> = chain
+        .providers
+        .iter()
+        .cloned()
+        .chain(refiner.clone())
+        .collect();
+    let limits = Arc::new(
+        ProviderLimits::new(&limited, &settings)
+            .with_stop_flag(shutdown.stop_flag())
+            .with_stop_flag(cancelled.flag()),
+    );
+    let completed_count = Arc::new(AtomicUsize::new(already_completed));
+    let file_timeout = options.file_timeout_secs.map(Duration::from_secs);
+    let deadline = options
+        .batch_deadline_secs
+        .map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs));
+
+    // 成功した金額の通貨別の累計（再開時は処理済みの結果から数え直す）
+    let mut running_total = HashMap::new();
+    if let Some(progress) = &progress {
+        for result in progress.lock().await.results.values() {
+            crate::money::add_to_running_total(&mut running_total, result);
+        }
+    }
+    let running_total = Arc::new(std::sync::Mutex::new(running_total));
+
+    // 前段（キャッシュ確認・縮小）と後段（プロバイダー呼び出し）を別の並列度で流す。
+    // 前段は後段が引き取った分だけ先へ進むので、整えたファイルが溜まり続けることはない
+    let prepare_concurrency = std::thread::available_parallelism()
+        .map_or(1, usize::from)
+        .clamp(1, MAX_PREPARE_CONCURRENCY);
+    let prepared = futures::stream::iter(pending)
         .map(|(index, request)| {
             let app = app.clone();
-            let provider = Arc::clone(&provider);
+            let chain = Arc::clone(&chain);
             let settings = Arc::clone(&settings);
-            let semaphore = Arc::clone(&semaphore);
-            let completed_count = Arc::clone(&completed_count);
+            let in_flight = Arc::clone(in_flight);
+            let cancelled = Arc::clone(cancelled);
+            let check_image_quality = options.check_image_quality;
 
             async move {
-                let file_name = std::path::Path::new(&request.file_path)
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or(&request.file_path)
-                    .to_string();
-
-                // セマフォでガード（4並列に制限）
-                let _permit = semaphore.acquire().await.unwrap();
-
-                let result = match provider
-                    .extract_receipt(
-                        &request.file_path,
-                        &request.file_content,
-                        &request.mime_type,
-                        &settings,
-                    )
-                    .await
-                {
-                    Ok(data) => OcrResult::success(data),
-                    Err(e) => {
-                        let _ = crate::errorlog::write_log_entry(
+                // 中止後に順番が来たファイルは読み込みも始めない
+                // 同じファイルが処理中（他のバッチ・単発、またはバッチ内の重複）ならスキップ
+                let in_flight_guard = if cancelled.is_cancelled() {
+                    Err(AppError::Cancelled)
+                } else {
+                    in_flight
+                        .try_acquire(&request.file_path)
+                        .ok_or(AppError::AlreadyInFlight)
+                };
+                let prepared = match in_flight_guard {
+                    Err(reason) => Err(reason),
+                    Ok(in_flight_guard) => {
+                        let prepared = prepare_ocr(
                             &app,
-                            "rust-ocr",
-                            &e,
-                            None,
-                            None,
-                            Some(&format!("batch OCR ({}/{})", index + 1, total)),
-                        );
-                        OcrResult::failure(e)
+                            &chain.providers,
+                            &settings,
+                            &request.file_content,
+                            &request.mime_type,
+                        )
+                        .await;
+                        // 送れない形式は判定しても意味がない
+                        let quality = match &prepared {
+                            PreparedOcr::Unsupported(_) => None,
+                            _ if check_image_quality => {
+                                assess_quality_for_ocr(&request.file_content).await
+                            }
+                            _ => None,
+                        };
+                        Ok((in_flight_guard, prepared, quality))
                     }
                 };
+                (index, request, prepared)
+            }
+        })
+        .buffer_unordered(prepare_concurrency);
+
+    // 実行枠を超えて Future を先に作らないよう、先頭プロバイダーの枠の数だけ並べて完了順に回収する
+    let max_concurrent = limits.primary_capacity();
+    let results = prepared
+        .map(|(index, request, prepared)| {
+            let app = app.clone();
+            let chain = Arc::clone(&chain);
+            let refiner = refiner.clone();
+            let settings = Arc::clone(&settings);
+            let limits = Arc::clone(&limits);
+            let category_rules = Arc::clone(&category_rules);
+            let merchant_entries = Arc::clone(&merchant_entries);
+            let summaries = Arc::clone(&summaries);
+            let completed_count = Arc::clone(&completed_count);
+            let running_total = Arc::clone(&running_total);
+            let progress = progress.clone();
+            let cancelled = Arc::clone(cancelled);
+
+            let file_name = file_names[index].clone();
+
+            async move {
+                // 制限時間と処理時間は実行枠を確保してから数える（枠の待ちは含めない）
+                let clock = FileClock::default();
+                let stage_started = tokio::time::Instant::now();
+                let mut result = match prepared {
+                    Err(reason) => OcrResult::skipped(reason),
+                    Ok((_in_flight_guard, prepared, quality)) => {
+                        let log_context = format!("batch OCR ({}/{})", index + 1, total);
+                        let extraction = async {
+                            let extraction = extract_prepared(
+                                &app,
+                                &chain,
+                                refiner.as_deref(),
+                                &settings,
+                                Some(&limits),
+                                Some(&clock),
+                                collect_timings,
+                                &request.file_path,
+                                &request.file_content,
+                                prepared,
+                                &log_context,
+                            );
+                            match file_timeout {
+                                Some(limit) => tokio::select! {
+                                    result = extraction => result,
+                                    _ = clock.expired(limit) => {
+                                        OcrResult::failure_with(AppError::FileTimeout {
+                                            limit_secs: limit.as_secs(),
+                                        })
+                                    }
+                                },
+                                None => extraction.await,
+                            }
+                        };
+
+                        // デッドラインを過ぎたら、待機中・処理中を問わず打ち切る
+                        let extraction = async {
+                            match deadline {
+                                Some(deadline) => tokio::time::timeout_at(deadline, extraction)
+                                    .await
+                                    .unwrap_or_else(|_| {
+                                        OcrResult::skipped(AppError::DeadlineExceeded)
+                                    }),
+                                None => extraction.await,
+                            }
+                        };
+
+                        // 中止されたら応答を待たずに打ち切る（Future ごとドロップするので
+                        // 送信中の HTTP 接続と実行枠もその場で手放す）
+                        let mut result = tokio::select! {
+                            result = extraction => result,
+                            _ = cancelled.cancelled() => OcrResult::skipped(AppError::Cancelled),
+                        };
+                        // 不鮮明な画像も処理は続け、再撮影を促す
+                        if let Some(quality) = quality.filter(|quality| !quality.sharp) {
+                            result.warnings.push(quality.warning());
+                            result.needs_retake = true;
+                        }
+                        result
+                    }
+                };
+                if !result.skipped {
+                    // キャッシュから返した場合など実行枠を使わなかったときは後段に入ってから数える
+                    let started = clock.started().unwrap_or(stage_started);
+                    result.elapsed_ms = Some(started.elapsed().as_millis() as u64);
+                    result.attempt = app
+                        .state::<OcrAttempts>()
+                        .record(&request.file_path, result.success);
+                }
+                if let Some(data) = result.data.as_mut() {
+                    crate::summary::preserve_manual_edits(data, &request.file_path, &summaries);
+                    apply_classification(data, &category_rules);
+                    apply_merchant_category(data, &merchant_entries);
+                }
+
+                // 途中経過を記録し、間隔を空けて保存する（保存に失敗してもバッチは継続する）
+                if let Some(progress) = &progress {
+                    let mut progress = progress.lock().await;
+                    progress.record(index, &result);
+                    let _ = batch_progress::save_throttled(&app, &mut progress);
+                }
 
                 // 完了数をインクリメント
                 let completed = completed_count.fetch_add(1, Ordering::SeqCst) + 1;
+                let running_total = {
+                    let mut running_total = running_total.lock().unwrap_or_else(|e| e.into_inner());
+                    crate::money::add_to_running_total(&mut running_total, &result);
+                    running_total.clone()
+                };
 
                 // 進捗イベントを発火（処理完了）
                 let _ = app.emit(
@@ -198,27 +1387,179 @@ pub async fn batch_ocr_receipts(
                         total,
                         file_name,
                         result: Some(result.clone()),
+                        running_total,
+                        cancelled: cancelled.is_cancelled(),
                     },
                 );
 
                 (index, result)
             }
         })
-        .collect();
+        .buffer_unordered(max_concurrent)
+        .collect::<Vec<_>>()
+        .await;
 
-    // 全タスクを並列実行
-    let mut indexed_results = join_all(tasks).await;
+    // 間引いた分も含めて最後の状態を保存する
+    if let Some(progress) = &progress {
+        let _ = batch_progress::save(app, &*progress.lock().await);
+    }
 
-    // 元のインデックス順にソート
-    indexed_results.sort_by_key(|(index, _)| *index);
+    Ok(results)
+}
 
-    // 結果のみを抽出
-    let results = indexed_results
-        .into_iter()
-        .map(|(_, result)| result)
+/// OCRリクエストのプレフライト検証
+///
+/// ファイルの存在・サイズ・形式（内容から判定）・PDFのページ数を確認し、
+/// Base64 で送る前にOCRに回せるかを返す。
+#[tauri::command]
+pub async fn preflight_ocr(file_path: String) -> Result<PreflightReport, String> {
+    crate::preflight::preflight(Path::new(&file_path))
+        .map_err(|e| format!("ファイルの検証に失敗しました: {}", e))
+}
+
+/// OCRリクエストの一括検証の結果
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrRequestsValidation {
+    pub results: Vec<RequestValidation>,
+    /// OCRに回せるリクエストの位置（問題のあるものを除いて投入するのに使う）
+    pub valid_indices: Vec<usize>,
+}
+
+/// バッチ投入前にOCRリクエストを一括検証する
+///
+/// 各リクエストの形式（MIME）・サイズ・PDFのページ数・上限超過と、キャッシュに
+/// 結果があるかを返す。プロバイダーは呼ばない。
+#[tauri::command]
+pub async fn validate_ocr_requests(
+    app: AppHandle,
+    registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    memory_cache: State<'_, MemoryOcrCache>,
+    requests: Vec<OcrRequest>,
+) -> Result<OcrRequestsValidation, String> {
+    let settings = get_ocr_settings(app.clone()).await?;
+    let chain = registry.lock().await.resolve_chain(&settings);
+    let provider_names: Vec<&str> = chain
+        .providers
+        .iter()
+        .map(|provider| provider.name())
         .collect();
+    let cache_dir = crate::ocr_cache::cache_dir(&app).ok();
 
-    Ok(results)
+    let results: Vec<RequestValidation> = requests
+        .iter()
+        .enumerate()
+        .map(|(index, request)| {
+            let mut validation = crate::preflight::validate_request(
+                index,
+                &request.file_path,
+                &request.file_content,
+                &request.mime_type,
+            );
+            let key = OcrCacheKey::new(&request.file_content, &provider_names, &settings);
+            validation.cache_hit = memory_cache.contains(&key)
+                || cache_dir
+                    .as_deref()
+                    .is_some_and(|dir| crate::ocr_cache::contains(dir, &key));
+            validation
+        })
+        .collect();
+
+    let valid_indices = results
+        .iter()
+        .filter(|validation| validation.ok)
+        .map(|validation| validation.index)
+        .collect();
+
+    Ok(OcrRequestsValidation {
+        results,
+        valid_indices,
+    })
+}
+
+/// エラーをロケールに応じたメッセージにする（`locale` 省略時はOSのロケール）
+#[tauri::command]
+pub async fn localize_error(error: AppError, locale: Option<String>) -> Result<String, String> {
+    let locale = locale
+        .or_else(tauri_plugin_os::locale)
+        .map(|locale| Locale::parse(&locale))
+        .unwrap_or_default();
+    Ok(crate::error::localize_error(&error, locale))
+}
+
+/// LLM 抽出プロンプトのプレビュー
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmPromptPreview {
+    /// プレースホルダを埋め込んだプロンプト
+    pub prompt: String,
+    /// テンプレートの問題点（必須項目の指示がないなど）
+    pub warnings: Vec<String>,
+}
+
+/// LLM 抽出プロンプトのテンプレートを展開し、問題点を確認する（`template` 省略時は既定のプロンプト）
+#[tauri::command]
+pub async fn preview_llm_prompt(
+    template: Option<String>,
+    file_name: Option<String>,
+) -> Result<LlmPromptPreview, String> {
+    let template = template.as_deref();
+    Ok(LlmPromptPreview {
+        prompt: crate::providers::prompt::render_prompt(
+            template,
+            file_name.as_deref().unwrap_or("receipt.jpg"),
+        ),
+        warnings: crate::providers::prompt::check_prompt_template(template),
+    })
+}
+
+/// ファイルのページ数を取得（PDFはメタデータから読み、画像は常に 1）
+///
+/// ページ課金のプロバイダーのコスト見積もりに使う。壊れたPDFはエラー。
+#[tauri::command]
+pub async fn get_pdf_page_count(file_path: String) -> Result<u32, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::preflight::page_count(Path::new(&file_path))
+    })
+    .await
+    .map_err(|e| format!("ページ数の取得に失敗しました: {}", e))?
+}
+
+/// レシートを指定フィールドのみに投影
+///
+/// ドット記法でネストしたフィールドも指定できる。不明なフィールド名は無視する。
+#[tauri::command]
+pub async fn project_receipts(
+    receipts: Vec<ReceiptData>,
+    fields: Vec<String>,
+) -> Result<Vec<Value>, String> {
+    receipts
+        .iter()
+        .map(|receipt| {
+            serde_json::to_value(receipt)
+                .map(|value| crate::export::project_fields(&value, &fields))
+                .map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+/// レシートの金額を通貨ごとに合計（最小単位の整数で計算）
+#[tauri::command]
+pub async fn sum_receipt_amounts(receipts: Vec<ReceiptData>) -> Result<Vec<CurrencyTotal>, String> {
+    Ok(crate::money::sum_by_currency(&receipts))
+}
+
+/// 2つのOCR結果をフィールドごとに比較（差分のあるフィールドのみ返す）
+///
+/// 金額は `amount_tolerance`（省略時は通貨の最小単位の半分）以内なら、文字列は正規化後に
+/// 一致すれば実質同一（`equivalent`）とする。
+#[tauri::command]
+pub async fn diff_receipts(
+    old: ReceiptData,
+    new: ReceiptData,
+    amount_tolerance: Option<f64>,
+) -> Result<Vec<FieldDiff>, String> {
+    Ok(crate::diff::diff_receipts(&old, &new, amount_tolerance))
 }
 
 /// デフォルトのルートディレクトリを取得
@@ -239,11 +1580,9 @@ pub async fn get_default_root_directory(app: AppHandle) -> Result<String, String
 /// ルートディレクトリを取得（保存済みの値またはデフォルト）
 #[tauri::command]
 pub async fn get_root_directory(app: AppHandle) -> Result<String, String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    let store = store_keys::open_store(&app)?;
 
-    if let Some(value) = store.get("root_directory") {
+    if let Some(value) = store.get(store_keys::ROOT_DIRECTORY) {
         if let Some(path) = value.as_str() {
             return Ok(path.to_string());
         }
@@ -256,17 +1595,11 @@ pub async fn get_root_directory(app: AppHandle) -> Result<String, String> {
 /// ルートディレクトリを保存
 #[tauri::command]
 pub async fn save_root_directory(app: AppHandle, path: String) -> Result<(), String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
-
-    store.set("root_directory", serde_json::Value::String(path));
-
-    store
-        .save()
-        .map_err(|e| format!("設定の保存に失敗しました: {}", e))?;
-
-    Ok(())
+    store_keys::set_value(
+        &app,
+        store_keys::ROOT_DIRECTORY,
+        serde_json::Value::String(path),
+    )
 }
 
 /// 月別ディレクトリを作成（{root}/YYYY/MM/）
@@ -353,11 +1686,28 @@ pub struct MonthDirectoryInfo {
     pub year_month: String,
     pub path: String,
     pub has_excel: bool,
+    /// 未OCRのファイル数（`include_counts` 指定時のみ。Excelサマリーが読めない月は `None`）
+    pub unprocessed_count: Option<usize>,
+}
+
+/// 設定で追加した画像の拡張子（設定を読めなければ追加なし）
+async fn load_extra_image_extensions(app: &AppHandle) -> Vec<String> {
+    get_ocr_settings(app.clone())
+        .await
+        .map(|settings| settings.extra_image_extensions)
+        .unwrap_or_default()
 }
 
 /// ルートディレクトリ以下の年月ディレクトリ一覧を取得
+///
+/// `include_counts` を指定すると、各月のファイルとExcelサマリーを突き合わせて未OCR件数も数える。
 #[tauri::command]
-pub async fn list_month_directories(app: AppHandle) -> Result<Vec<MonthDirectoryInfo>, String> {
+pub async fn list_month_directories(
+    app: AppHandle,
+    include_counts: Option<bool>,
+) -> Result<Vec<MonthDirectoryInfo>, String> {
+    let include_counts = include_counts.unwrap_or(false);
+    let extensions = load_extra_image_extensions(&app).await;
     let root_directory = get_root_directory(app).await?;
     let root_path = PathBuf::from(&root_directory);
 
@@ -365,143 +1715,152 @@ pub async fn list_month_directories(app: AppHandle) -> Result<Vec<MonthDirectory
         return Ok(Vec::new());
     }
 
-    let mut results = Vec::new();
-
-    // 年ディレクトリを走査
-    let year_entries = fs::read_dir(&root_path)
+    let directories = find_month_directories(&root_path)
         .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
 
-    for year_entry in year_entries.flatten() {
-        let year_path = year_entry.path();
-        if !year_path.is_dir() {
-            continue;
-        }
-
-        let year_name = year_path.file_name().and_then(|s| s.to_str()).unwrap_or("");
-
-        // 4桁の数字（年）かチェック
-        if year_name.len() != 4 || year_name.parse::<u32>().is_err() {
-            continue;
-        }
-
-        // 月ディレクトリを走査
-        let month_entries = match fs::read_dir(&year_path) {
-            Ok(entries) => entries,
-            Err(_) => continue,
-        };
-
-        for month_entry in month_entries.flatten() {
-            let month_path = month_entry.path();
-            if !month_path.is_dir() {
-                continue;
-            }
-
-            let month_name = month_path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("");
-
-            // 2桁の数字（月）かチェック
-            if month_name.len() != 2 {
-                continue;
-            }
-            let month_num = match month_name.parse::<u32>() {
-                Ok(n) if (1..=12).contains(&n) => n,
-                _ => continue,
+    let results = directories
+        .into_iter()
+        .map(|dir| {
+            let has_excel = dir.has_excel();
+            let unprocessed_count = if include_counts {
+                count_unprocessed_files(&dir.path, &dir.year_month, &extensions)
+            } else {
+                None
             };
 
-            let year_month = format!("{}{:02}", year_name, month_num);
-            let excel_path = month_path.join(format!("{}-summary.xlsx", year_month));
-
-            results.push(MonthDirectoryInfo {
-                year: year_name.to_string(),
-                month: month_name.to_string(),
-                year_month,
-                path: month_path.to_str().unwrap_or("").to_string(),
-                has_excel: excel_path.exists(),
-            });
-        }
-    }
-
-    // 降順ソート（新しい年月が先）
-    results.sort_by(|a, b| b.year_month.cmp(&a.year_month));
+            MonthDirectoryInfo {
+                year: dir.year,
+                month: dir.month,
+                path: dir.path.to_str().unwrap_or("").to_string(),
+                year_month: dir.year_month,
+                has_excel,
+                unprocessed_count,
+            }
+        })
+        .collect();
 
     Ok(results)
 }
 
-/// ディレクトリ内のファイル一覧を取得
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FileInfo {
-    pub name: String,
-    pub path: String,
-    pub is_image: bool,
-    pub is_pdf: bool,
-    pub size: u64,
+/// 月ディレクトリ内の未OCRファイル数を数える（Excelサマリーが読めなければ `None`）
+fn count_unprocessed_files(
+    month_path: &Path,
+    year_month: &str,
+    extra_image_extensions: &[String],
+) -> Option<usize> {
+    let files = list_receipt_files(month_path, extra_image_extensions).ok()?;
+    count_unprocessed_in_month(&files, month_path, year_month)
 }
 
+/// ディレクトリ内のファイル一覧を取得
+///
+/// `review_status` を指定すると、月別サマリー上のレビュー状態が一致するファイルだけを返す
+/// （サマリーに無いファイルは下書き扱い）。
 #[tauri::command]
-pub async fn list_files_in_directory(directory_path: String) -> Result<Vec<FileInfo>, String> {
+pub async fn list_files_in_directory(
+    app: AppHandle,
+    directory_path: String,
+    review_status: Option<ReviewStatus>,
+) -> Result<Vec<FileInfo>, String> {
     let path = PathBuf::from(&directory_path);
 
     if !path.exists() || !path.is_dir() {
         return Ok(Vec::new());
     }
 
-    let entries =
-        fs::read_dir(&path).map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
-
-    let mut files = Vec::new();
-
-    for entry in entries.flatten() {
-        let file_path = entry.path();
-        if !file_path.is_file() {
-            continue;
-        }
-
-        let file_name = file_path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_string();
+    let files = list_receipt_files(&path, &load_extra_image_extensions(&app).await)
+        .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+    let Some(review_status) = review_status else {
+        return Ok(files);
+    };
 
-        // サマリーファイルはスキップ
-        if file_name.ends_with("-summary.json") || file_name.ends_with("-summary.xlsx") {
-            continue;
-        }
+    let summary = match crate::summary::year_month_of_directory(&path) {
+        Some(year_month) => crate::summary::read_summary(&path, &year_month)
+            .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?,
+        None => None,
+    };
+    let status_of = |name: &str| {
+        summary
+            .as_ref()
+            .and_then(|s| s.receipts.iter().find(|r| r.file == name))
+            .map(|r| r.review_status)
+            .unwrap_or_default()
+    };
 
-        let extension = file_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_lowercase();
+    Ok(files
+        .into_iter()
+        .filter(|file| status_of(&file.name) == review_status)
+        .collect())
+}
 
-        let is_image = matches!(
-            extension.as_str(),
-            "jpg" | "jpeg" | "png" | "gif" | "webp" | "heic" | "heif"
-        );
-        let is_pdf = extension == "pdf";
+/// ルートディレクトリ以下の全月ディレクトリを走査してインデックスを作成する
+///
+/// 走査はブロッキングスレッドで行い、1か月ごとに `index-progress` イベントを送る。
+/// 結果はキャッシュし、`get_root_index` で取り出せる。
+#[tauri::command]
+pub async fn index_root_directory(
+    app: AppHandle,
+    cache: State<'_, RootIndexCache>,
+) -> Result<RootIndex, String> {
+    let root_directory = get_root_directory(app.clone()).await?;
+    let extensions = load_extra_image_extensions(&app).await;
+
+    let emitter = app.clone();
+    let index = tauri::async_runtime::spawn_blocking(move || {
+        build_index(Path::new(&root_directory), &extensions, |event| {
+            let _ = emitter.emit("index-progress", event);
+        })
+    })
+    .await
+    .map_err(|e| format!("インデックスの作成に失敗しました: {}", e))?
+    .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
 
-        // 画像・PDF以外はスキップ
-        if !is_image && !is_pdf {
-            continue;
-        }
+    cache.set(index.clone());
+    Ok(index)
+}
 
-        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+/// キャッシュ済みのインデックスを取得（現在のルートディレクトリのものが無ければ `None`）
+#[tauri::command]
+pub async fn get_root_index(
+    app: AppHandle,
+    cache: State<'_, RootIndexCache>,
+) -> Result<Option<RootIndex>, String> {
+    let root_directory = get_root_directory(app).await?;
+    Ok(cache.get(&root_directory))
+}
 
-        files.push(FileInfo {
-            name: file_name,
-            path: file_path.to_str().unwrap_or("").to_string(),
-            is_image,
-            is_pdf,
-            size,
-        });
-    }
+/// 動画から取り出したフレーム（OCR入力用）
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoFrame {
+    /// JPEGデータ（Base64）
+    pub file_content: String,
+    pub mime_type: String,
+    /// 実際に採用した時刻（秒）
+    pub timestamp: f64,
+}
 
-    // ファイル名でソート
-    files.sort_by(|a, b| a.name.cmp(&b.name));
+/// 動画の指定時刻（秒）のフレームを JPEG として取り出す
+///
+/// `search_window_secs` を指定すると前後その秒数から最も鮮明なフレームを選ぶ（手ブレ対策）。
+#[tauri::command]
+pub async fn extract_frame(
+    video_path: String,
+    timestamp: f64,
+    search_window_secs: Option<f64>,
+) -> Result<VideoFrame, String> {
+    let frame = tauri::async_runtime::spawn_blocking(move || {
+        crate::video::extract_frame(Path::new(&video_path), timestamp, search_window_secs)
+    })
+    .await
+    .map_err(|e| format!("フレームの抽出に失敗しました: {}", e))??;
 
-    Ok(files)
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    Ok(VideoFrame {
+        file_content: STANDARD.encode(&frame.jpeg),
+        mime_type: "image/jpeg".to_string(),
+        timestamp: frame.timestamp,
+    })
 }
 
 /// ファイルコピー結果
@@ -513,8 +1872,35 @@ pub struct CopyFileResult {
     pub file_name: String,
 }
 
+/// 一覧に即時表示するプレースホルダ（BlurHash と超小型サムネイル）を生成
+///
+/// 本サムネイルより先に呼び、生成後に差し替える。画像以外（PDF など）は `None`。
+#[tauri::command]
+pub async fn generate_placeholder(
+    file_content: String,
+    mime_type: String,
+) -> Result<Option<Placeholder>, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    if !mime_type.starts_with("image/") {
+        return Ok(None);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let content = STANDARD
+            .decode(&file_content)
+            .map_err(|e| format!("Base64デコードに失敗しました: {}", e))?;
+        crate::placeholder::generate_placeholder(&content)
+            .map(Some)
+            .map_err(|e| format!("プレースホルダの生成に失敗しました: {}", e))
+    })
+    .await
+    .map_err(|e| format!("プレースホルダの生成に失敗しました: {}", e))?
+}
+
 /// サムネイルを保存
 /// DataURL形式のサムネイル画像を月別ディレクトリの thumbnails/ に保存
+/// ファイル名には元ファイルの内容ハッシュを含める（`{file_name}.{hash}.thumbnail.png`）
 #[tauri::command]
 pub async fn save_thumbnail(
     app: AppHandle,
@@ -541,36 +1927,36 @@ pub async fn save_thumbnail(
         return Err("月は01から12の範囲で指定してください".to_string());
     }
 
+    // 上限を超える入力はデコード前に弾く
+    let image_data = crate::thumbnail::decode_thumbnail_data_url(&data_url)?;
+
     let root_directory = get_root_directory(app).await?;
-    let thumbnails_path = PathBuf::from(&root_directory)
-        .join(year)
-        .join(month)
-        .join("thumbnails");
-
-    // thumbnailsディレクトリを作成
-    fs::create_dir_all(&thumbnails_path)
-        .map_err(|e| format!("thumbnailsディレクトリの作成に失敗しました: {}", e))?;
-
-    // DataURLからBase64部分を抽出
-    // 形式: data:image/png;base64,XXXXXX
-    let base64_data = data_url
-        .strip_prefix("data:image/png;base64,")
-        .or_else(|| data_url.strip_prefix("data:image/jpeg;base64,"))
-        .or_else(|| data_url.strip_prefix("data:image/webp;base64,"))
-        .ok_or("無効なDataURL形式です")?;
-
-    // Base64デコード
-    use base64::{engine::general_purpose::STANDARD, Engine};
-    let image_data = STANDARD
-        .decode(base64_data)
-        .map_err(|e| format!("Base64デコードに失敗しました: {}", e))?;
+    let month_path = PathBuf::from(&root_directory).join(year).join(month);
+
+    let file_path = crate::thumbnail::write_thumbnail(&month_path, &file_name, &image_data)?;
 
-    // ファイルに保存
-    let thumbnail_file_name = format!("{}.thumbnail.png", file_name);
-    let file_path = thumbnails_path.join(&thumbnail_file_name);
+    file_path
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "パスの変換に失敗しました".to_string())
+}
 
-    fs::write(&file_path, &image_data)
-        .map_err(|e| format!("サムネイルの保存に失敗しました: {}", e))?;
+/// 月別ディレクトリの元画像からサムネイルを生成し、thumbnails/ に保存
+/// 長辺は `max_size`（省略時 256px）に縮小し、保存名は `save_thumbnail` と同じ。PDF は未対応
+#[tauri::command]
+pub async fn generate_thumbnail(
+    app: AppHandle,
+    year_month: String,
+    file_name: String,
+    max_size: Option<u32>,
+) -> Result<String, String> {
+    let size = max_size.unwrap_or(crate::thumbnail::DEFAULT_THUMBNAIL_SIZE);
+    let month_path = month_directory_path(app, &year_month).await?;
+    let file_path = tauri::async_runtime::spawn_blocking(move || {
+        crate::thumbnail::generate_thumbnail(&month_path, &file_name, size)
+    })
+    .await
+    .map_err(|e| format!("サムネイルの生成に失敗しました: {}", e))??;
 
     file_path
         .to_str()
@@ -579,7 +1965,8 @@ pub async fn save_thumbnail(
 }
 
 /// サムネイルを読み込み
-/// 指定されたファイルのサムネイルをDataURL形式で返す
+/// 指定されたファイルの現在の内容に対応するサムネイルをDataURL形式で返す
+/// （古い内容のサムネイルは削除される）
 #[tauri::command]
 pub async fn read_thumbnail(
     app: AppHandle,
@@ -595,28 +1982,383 @@ pub async fn read_thumbnail(
     let month = &year_month[4..6];
 
     let root_directory = get_root_directory(app).await?;
-    let thumbnail_file_name = format!("{}.thumbnail.png", file_name);
-    let file_path = PathBuf::from(&root_directory)
-        .join(year)
-        .join(month)
-        .join("thumbnails")
-        .join(&thumbnail_file_name);
-
-    // ファイルが存在しなければNoneを返す
-    if !file_path.exists() {
-        return Ok(None);
+    let month_path = PathBuf::from(&root_directory).join(year).join(month);
+
+    crate::thumbnail::read_thumbnail_data_url(&month_path, &file_name)
+        .map_err(|e| format!("サムネイルの読み込みに失敗しました: {}", e))
+}
+
+/// 複数のサムネイルを一括で読み込み
+/// ファイル名ごとにDataURL（存在しない・読み込めない場合はNone）を返す
+///
+/// ファイル名を分けてブロッキング用のスレッドプールで並列に読む。
+#[tauri::command]
+pub async fn read_thumbnails(
+    app: AppHandle,
+    year_month: String,
+    file_names: Vec<String>,
+) -> Result<HashMap<String, Option<String>>, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+    if file_names.is_empty() {
+        return Ok(HashMap::new());
     }
 
-    // ファイルを読み込み
-    let image_data =
-        fs::read(&file_path).map_err(|e| format!("サムネイルの読み込みに失敗しました: {}", e))?;
+    let chunk_size = file_names
+        .len()
+        .div_ceil(crate::thumbnail::MAX_PARALLEL_THUMBNAIL_READS);
+    let tasks = file_names.chunks(chunk_size).map(|chunk| {
+        let month_path = month_path.clone();
+        let chunk = chunk.to_vec();
+        tauri::async_runtime::spawn_blocking(move || {
+            crate::thumbnail::read_thumbnails(&month_path, &chunk)
+        })
+    });
+
+    let mut thumbnails = HashMap::with_capacity(file_names.len());
+    for result in futures::future::join_all(tasks).await {
+        let chunk = result.map_err(|e| format!("サムネイルの読み込みに失敗しました: {}", e))?;
+        thumbnails.extend(chunk);
+    }
+    Ok(thumbnails)
+}
 
-    // Base64エンコードしてDataURL形式で返す
-    use base64::{engine::general_purpose::STANDARD, Engine};
-    let base64_data = STANDARD.encode(&image_data);
-    let data_url = format!("data:image/png;base64,{}", base64_data);
+/// 年月（YYYYMM）から月ディレクトリのパスを求める
+async fn month_directory_path(app: AppHandle, year_month: &str) -> Result<PathBuf, String> {
+    // YYYYMM形式のバリデーション
+    if year_month.len() != 6 || !year_month.chars().all(|c| c.is_ascii_digit()) {
+        return Err("年月は YYYYMM 形式で指定してください".to_string());
+    }
+
+    let year = &year_month[0..4];
+    let month = &year_month[4..6];
+
+    let root_directory = get_root_directory(app).await?;
+    Ok(PathBuf::from(&root_directory).join(year).join(month))
+}
+
+/// 月別サマリーの集計用Excel（`{YYYYMM}-summary-export.xlsx`）を月ディレクトリに書き出す
+///
+/// アプリが読み書きする `{YYYYMM}-summary.xlsx` とは別のファイルで、既存の書き出しは上書きする。
+/// 書き出したファイルのパスを返す。
+#[tauri::command]
+pub async fn generate_month_summary(
+    app: AppHandle,
+    receipts: Vec<ReceiptData>,
+    year_month: String,
+) -> Result<String, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+    let xlsx = crate::export::month_summary_xlsx(&receipts)?;
+
+    fs::create_dir_all(&month_path)
+        .map_err(|e| format!("月ディレクトリの作成に失敗しました: {}", e))?;
+    let path = month_path.join(format!("{}-summary-export.xlsx", year_month));
+    fs::write(&path, xlsx).map_err(|e| format!("Excelの書き込みに失敗しました: {}", e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// 月別サマリーをCSV（`{YYYYMM}-summary.csv`、UTF-8 BOM付き）で月ディレクトリに書き出す
+///
+/// 会計ソフトへの取り込み用。既存のファイルは上書きする。書き出したファイルのパスを返す。
+#[tauri::command]
+pub async fn export_month_csv(
+    app: AppHandle,
+    receipts: Vec<ReceiptData>,
+    year_month: String,
+) -> Result<String, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    fs::create_dir_all(&month_path)
+        .map_err(|e| format!("月ディレクトリの作成に失敗しました: {}", e))?;
+    let path = month_path.join(format!("{}-summary.csv", year_month));
+    fs::write(&path, crate::export::month_summary_csv(&receipts))
+        .map_err(|e| format!("CSVの書き込みに失敗しました: {}", e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// freee会計に取り込む取引明細CSV（`{YYYYMM}-freee.csv`）を月ディレクトリに書き出す
+///
+/// 勘定科目は保存済みの勘定科目ルールで判定し、判定できない行は「未分類」とする。
+/// 円以外の通貨のレシートは書き出さず、`skipped` に理由とともに返す。既存のファイルは上書きする。
+#[tauri::command]
+pub async fn export_freee_csv(
+    app: AppHandle,
+    receipts: Vec<ReceiptData>,
+    year_month: String,
+) -> Result<CsvExportResponse, String> {
+    let rules = load_account_category_rules(&app);
+    let month_path = month_directory_path(app, &year_month).await?;
+    let (csv, skipped) = crate::export::freee_csv(&receipts, &rules);
+
+    fs::create_dir_all(&month_path)
+        .map_err(|e| format!("月ディレクトリの作成に失敗しました: {}", e))?;
+    let path = month_path.join(format!("{}-freee.csv", year_month));
+    fs::write(&path, csv).map_err(|e| format!("CSVの書き込みに失敗しました: {}", e))?;
+    Ok(CsvExportResponse {
+        path: path.to_string_lossy().into_owned(),
+        skipped,
+    })
+}
+
+/// マネーフォワード向けCSVの出力オプション
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MoneyForwardExportOptions {
+    /// 貸方勘定科目（未指定なら「未払金」）
+    pub credit_account: Option<String>,
+    pub encoding: crate::export::CsvEncoding,
+}
+
+/// 書き出したCSVと除外した行
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvExportResponse {
+    pub path: String,
+    pub skipped: Vec<crate::export::SkippedRow>,
+}
+
+/// マネーフォワード クラウド会計の仕訳インポートCSV（`{YYYYMM}-moneyforward.csv`）を
+/// 月ディレクトリに書き出す
+///
+/// 借方は保存済みの勘定科目ルールで判定する。取引日・金額が無い、または円以外の通貨のレシートは書き出さず、
+/// `skipped` に理由とともに返す。既存のファイルは上書きする。
+#[tauri::command]
+pub async fn export_moneyforward_csv(
+    app: AppHandle,
+    receipts: Vec<ReceiptData>,
+    year_month: String,
+    options: Option<MoneyForwardExportOptions>,
+) -> Result<CsvExportResponse, String> {
+    let options = options.unwrap_or_default();
+    let rules = load_account_category_rules(&app);
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    let credit_account = options
+        .credit_account
+        .as_deref()
+        .map(str::trim)
+        .filter(|account| !account.is_empty())
+        .unwrap_or(crate::export::DEFAULT_CREDIT_ACCOUNT);
+    let (csv, skipped) = crate::export::moneyforward_csv(&receipts, &rules, credit_account);
+
+    fs::create_dir_all(&month_path)
+        .map_err(|e| format!("月ディレクトリの作成に失敗しました: {}", e))?;
+    let path = month_path.join(format!("{}-moneyforward.csv", year_month));
+    fs::write(&path, crate::export::encode_csv(&csv, options.encoding))
+        .map_err(|e| format!("CSVの書き込みに失敗しました: {}", e))?;
+    Ok(CsvExportResponse {
+        path: path.to_string_lossy().into_owned(),
+        skipped,
+    })
+}
+
+/// 読み込んだ月別サマリー
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthSummaryResponse {
+    #[serde(flatten)]
+    pub summary: MonthSummary,
+    /// 不正な日付・金額として `None` に落とした項目
+    pub dropped_fields: Vec<DroppedField>,
+}
+
+/// 月別サマリー（`{YYYYMM}-summary.json`）を読み込む（無い場合は `None`）
+///
+/// 読み込んだ内容は保存時の 3-way マージのベースとして保持する。
+#[tauri::command]
+pub async fn read_month_summary(
+    app: AppHandle,
+    bases: State<'_, SummaryBaseCache>,
+    year_month: String,
+) -> Result<Option<MonthSummaryResponse>, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    let summary = crate::summary::read_summary_checked(&month_path, &year_month)
+        .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?;
+
+    if let Some((summary, _)) = &summary {
+        bases.set(
+            crate::summary::summary_path(&month_path, &year_month),
+            summary.clone(),
+        );
+    }
+
+    Ok(
+        summary.map(|(summary, dropped_fields)| MonthSummaryResponse {
+            summary,
+            dropped_fields,
+        }),
+    )
+}
+
+/// このデバイスの識別子を取得（無ければ生成して保存する）
+fn load_device_id(app: &AppHandle) -> Result<String, String> {
+    store_keys::with_store_transaction(app, |store| {
+        if let Some(id) = store.get(store_keys::DEVICE_ID) {
+            if let Some(id) = id.as_str() {
+                return Ok(id.to_string());
+            }
+        }
+        let id = generate_device_id();
+        store.set(store_keys::DEVICE_ID, Value::String(id.clone()));
+        Ok(id)
+    })
+}
+
+/// デバイスの識別子を新しく作る
+fn generate_device_id() -> String {
+    let seed = format!(
+        "{}-{}-{:?}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME"))
+    );
+    Sha256::digest(seed.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// サマリー保存の結果
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveSummaryResult {
+    /// 他のデバイスの更新と競合した項目（自動で採用した側とともに返す）
+    pub conflicts: Vec<SummaryConflict>,
+}
 
-    Ok(Some(data_url))
+/// 月別サマリー（`{YYYYMM}-summary.json`）を保存
+///
+/// 保存直前にディスク上の内容を読み直し、読み込み時点の内容と 3-way マージしてから書き込む
+/// （他のデバイスの更新を上書きしない）。
+#[tauri::command]
+pub async fn save_month_summary(
+    app: AppHandle,
+    bases: State<'_, SummaryBaseCache>,
+    year_month: String,
+    receipts: Vec<SummaryReceipt>,
+) -> Result<SaveSummaryResult, String> {
+    let device_id = load_device_id(&app)?;
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    fs::create_dir_all(&month_path)
+        .map_err(|e| format!("ディレクトリの作成に失敗しました: {}", e))?;
+
+    let path = crate::summary::summary_path(&month_path, &year_month);
+    let disk = crate::summary::read_summary(&month_path, &year_month)
+        .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?;
+    let ours = MonthSummary {
+        year_month,
+        updated_at: String::new(),
+        receipts,
+        pending_excel_sync: false,
+    };
+    let merged = crate::summary_merge::merge_summary_with_disk(
+        bases.get(&path).as_ref(),
+        ours,
+        disk.as_ref(),
+        &device_id,
+        &chrono::Local::now().to_rfc3339(),
+    )?;
+
+    crate::summary::write_summary(&month_path, &merged.summary)
+        .map_err(|e| format!("サマリーの保存に失敗しました: {}", e))?;
+    bases.set(path, merged.summary);
+
+    Ok(SaveSummaryResult {
+        conflicts: merged.conflicts,
+    })
+}
+
+/// 月別サマリーの証憑番号から連番の抜け（スキャン漏れ）と重複を検出する
+///
+/// サマリーが無い月は空の結果を返す。
+#[tauri::command]
+pub async fn detect_number_gaps(
+    app: AppHandle,
+    year_month: String,
+) -> Result<NumberGapReport, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    let summary = crate::summary::read_summary(&month_path, &year_month)
+        .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?;
+    Ok(summary
+        .map(|summary| crate::document_number::detect_number_gaps(&summary.receipts))
+        .unwrap_or_default())
+}
+
+/// 電子帳簿保存法の検索用インデックス（`index.json`）を月ディレクトリに生成する
+///
+/// 取引年月日・取引金額・取引先が揃わない証憑は `incomplete` として返す。
+#[tauri::command]
+pub async fn generate_denchou_index(
+    app: AppHandle,
+    year_month: String,
+) -> Result<DenchouIndex, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    let summary = crate::summary::read_summary(&month_path, &year_month)
+        .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?
+        .ok_or_else(|| format!("{} のサマリーがありません", year_month))?;
+
+    let index = crate::denchou::build_index(&summary, chrono::Local::now().to_rfc3339());
+    crate::denchou::write_index(&month_path, &index)
+        .map_err(|e| format!("インデックスの保存に失敗しました: {}", e))?;
+    Ok(index)
+}
+
+/// 月別サマリーのうち条件に合致するレシートに部分更新（宛名・科目・タグ）を一括適用
+///
+/// `manual_edits` で手動確定済みの項目を上書きするかを選ぶ（既定は上書きしない）。
+#[tauri::command]
+pub async fn bulk_update_receipts(
+    app: AppHandle,
+    year_month: String,
+    filter: ReceiptFilter,
+    patch: ReceiptPatch,
+    manual_edits: Option<ManualEditMode>,
+) -> Result<BulkUpdateResult, String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    let mut summary = crate::summary::read_summary(&month_path, &year_month)
+        .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?
+        .ok_or_else(|| format!("{} のサマリーがありません", year_month))?;
+
+    let result = crate::summary::bulk_update(
+        &mut summary,
+        &filter,
+        &patch,
+        manual_edits.unwrap_or_default(),
+    );
+
+    if result.updated > 0 {
+        summary.updated_at = chrono::Local::now().to_rfc3339();
+        crate::summary::write_summary(&month_path, &summary)
+            .map_err(|e| format!("サマリーの保存に失敗しました: {}", e))?;
+    }
+
+    Ok(result)
+}
+
+/// レシートのレビュー状態を変更（承認済みを下書きに戻すなど、許可しない遷移はエラー）
+#[tauri::command]
+pub async fn set_review_status(
+    app: AppHandle,
+    year_month: String,
+    file: String,
+    status: ReviewStatus,
+) -> Result<(), String> {
+    let month_path = month_directory_path(app, &year_month).await?;
+
+    let mut summary = crate::summary::read_summary(&month_path, &year_month)
+        .map_err(|e| format!("サマリーの読み込みに失敗しました: {}", e))?
+        .ok_or_else(|| format!("{} のサマリーがありません", year_month))?;
+
+    crate::summary::set_review_status(&mut summary, &file, status)?;
+
+    summary.updated_at = chrono::Local::now().to_rfc3339();
+    crate::summary::write_summary(&month_path, &summary)
+        .map_err(|e| format!("サマリーの保存に失敗しました: {}", e))
 }
 
 /// ディレクトリをゴミ箱に移動
@@ -631,13 +2373,95 @@ pub async fn move_to_trash(path: String) -> Result<(), String> {
     trash::delete(&path_buf).map_err(|e| format!("ゴミ箱への移動に失敗しました: {}", e))
 }
 
+/// ユニーク名を生成する際の連番の上限
+const MAX_UNIQUE_NAME_COUNTER: u32 = 9999;
+
+/// `dir` 内のコピー先の候補名（元の名前 → `_1`〜`_9999` → タイムスタンプ付き）
+fn unique_name_candidates(file_name: &str) -> impl Iterator<Item = String> + '_ {
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file")
+        .to_string();
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|ext| format!(".{}", ext))
+        .unwrap_or_default();
+
+    let numbered = (1..=MAX_UNIQUE_NAME_COUNTER).map({
+        let stem = stem.clone();
+        let extension = extension.clone();
+        move |counter| format!("{}_{}{}", stem, counter, extension)
+    });
+    let timestamped = std::iter::once_with(move || {
+        let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S%3f");
+        format!("{}_{}{}", stem, timestamp, extension)
+    });
+
+    std::iter::once(file_name.to_string())
+        .chain(numbered)
+        .chain(timestamped)
+}
+
+/// `source` を `dir` 内の未使用の名前にコピーする
+///
+/// `content` を渡すと元ファイルの代わりにその内容を書き込む。
+/// 存在確認とコピーの間に並行コピーが割り込んでも上書きしないよう、コピー先は
+/// 排他作成（`create_new`）で開き、既に存在すれば次の候補に進む。
+fn copy_to_unique_path(
+    source: &Path,
+    content: Option<&[u8]>,
+    dir: &Path,
+    file_name: &str,
+) -> Result<PathBuf, String> {
+    for candidate in unique_name_candidates(file_name) {
+        let destination = dir.join(&candidate);
+        let mut output = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&destination)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("ファイルのコピーに失敗しました: {}", e)),
+        };
+
+        let copied = match content {
+            Some(content) => std::io::Write::write_all(&mut output, content),
+            None => fs::File::open(source)
+                .and_then(|mut input| std::io::copy(&mut input, &mut output))
+                .map(|_| ()),
+        };
+        if let Err(e) = copied {
+            // 書きかけのファイルを残さない
+            drop(output);
+            let _ = fs::remove_file(&destination);
+            return Err(format!("ファイルのコピーに失敗しました: {}", e));
+        }
+
+        return Ok(destination);
+    }
+
+    Err(format!(
+        "コピー先のファイル名を決められませんでした（同名ファイルが多すぎます）: {}",
+        file_name
+    ))
+}
+
 /// ファイルを月別ディレクトリにコピー
+///
+/// `normalize_orientation` を指定すると、画像（設定で追加した拡張子を含む）は EXIF の向きを
+/// 適用して正立させてから保存する（元ファイルは変更しない。正立化できない画像はそのままコピーする）。
 #[tauri::command]
 pub async fn copy_file_to_month(
     app: AppHandle,
     source_path: String,
     year_month: String,
+    normalize_orientation: Option<bool>,
 ) -> Result<CopyFileResult, String> {
+    let extensions = load_extra_image_extensions(&app).await;
     // 月別ディレクトリを確保
     let month_dir = ensure_month_directory(app, year_month).await?;
 
@@ -652,35 +2476,24 @@ pub async fn copy_file_to_month(
         .ok_or("ファイル名の取得に失敗しました")?
         .to_string();
 
-    let destination = PathBuf::from(&month_dir).join(&file_name);
-
-    // 同名ファイルが存在する場合はユニーク名を生成
-    let final_destination = if destination.exists() {
-        let stem = source
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("file");
-        let extension = source.extension().and_then(|s| s.to_str()).unwrap_or("");
-
-        let mut counter = 1;
-        loop {
-            let new_name = if extension.is_empty() {
-                format!("{}_{}", stem, counter)
-            } else {
-                format!("{}_{}.{}", stem, counter, extension)
-            };
-            let new_path = PathBuf::from(&month_dir).join(&new_name);
-            if !new_path.exists() {
-                break new_path;
-            }
-            counter += 1;
-        }
+    let normalized = if normalize_orientation.unwrap_or(false)
+        && receipt_file_kind(&file_name, &extensions).is_some_and(|(is_image, _)| is_image)
+    {
+        let content =
+            fs::read(&source).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        crate::orientation::normalize_orientation(&content)
+            .ok()
+            .flatten()
     } else {
-        destination
+        None
     };
 
-    fs::copy(&source, &final_destination)
-        .map_err(|e| format!("ファイルのコピーに失敗しました: {}", e))?;
+    let final_destination = copy_to_unique_path(
+        &source,
+        normalized.as_deref(),
+        Path::new(&month_dir),
+        &file_name,
+    )?;
 
     let final_file_name = final_destination
         .file_name()
@@ -695,14 +2508,202 @@ pub async fn copy_file_to_month(
     })
 }
 
+/// スキャンしたファイルを OCR して月別ディレクトリへ取り込む
+///
+/// OCR → レシート日付から保存先の月を決定 → `copy_file_to_month` でコピー → サマリーへ追記 →
+/// サムネイル生成（画像のみ）を順に行う。失敗したステップで止め、どこまで進んだかを返す。
+#[tauri::command]
+pub async fn process_and_file(
+    app: AppHandle,
+    registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    in_flight: State<'_, Arc<InFlightFiles>>,
+    source_path: String,
+    options: Option<ProcessAndFileOptions>,
+) -> Result<PipelineResult, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let options = options.unwrap_or_default();
+    let mut pipeline = PipelineResult::default();
+
+    // OCR
+    let Some(_in_flight_guard) = in_flight.try_acquire(&source_path) else {
+        return Ok(pipeline.fail(
+            PipelineStep::Ocr,
+            crate::error::localize_error(&AppError::AlreadyInFlight, Locale::Ja),
+        ));
+    };
+    let content = match fs::read(&source_path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Ok(pipeline.fail(
+                PipelineStep::Ocr,
+                format!("ファイルの読み込みに失敗しました: {}", e),
+            ))
+        }
+    };
+    let Some(mime_type) = crate::preflight::sniff_mime_type(&content) else {
+        return Ok(pipeline.fail(
+            PipelineStep::Ocr,
+            "ファイル形式を判定できませんでした".to_string(),
+        ));
+    };
+    let settings = get_ocr_settings(app.clone()).await?;
+    let (chain, refiner) = {
+        let registry = registry.lock().await;
+        (
+            registry.resolve_chain(&settings),
+            registry.resolve_refiner(&settings),
+        )
+    };
+    if chain.providers.is_empty() {
+        return Ok(pipeline.fail(
+            PipelineStep::Ocr,
+            "OCRプロバイダーが見つかりません".to_string(),
+        ));
+    }
+
+    let mut result = extract_to_result(
+        &app,
+        &chain,
+        refiner.as_deref(),
+        &settings,
+        None,
+        false,
+        &source_path,
+        &STANDARD.encode(&content),
+        mime_type,
+        "process-and-file",
+    )
+    .await;
+    if let Some(data) = result.data.as_mut() {
+        apply_classification(data, &load_account_category_rules(&app));
+        apply_merchant_category(data, &load_merchant_category_entries(&app));
+    }
+    let date = result.data.as_ref().and_then(|data| data.date.clone());
+    let ocr_error = (!result.success).then(|| result.error.clone().unwrap_or_default());
+    pipeline.ocr = Some(result.clone());
+    if let Some(error) = ocr_error {
+        return Ok(pipeline.fail(PipelineStep::Ocr, error));
+    }
+    pipeline.complete(PipelineStep::Ocr);
+
+    // 保存先の月
+    let Some(year_month) = options
+        .year_month
+        .clone()
+        .or_else(|| resolve_target_month(date.as_deref(), options.fallback_year_month.as_deref()))
+    else {
+        return Ok(pipeline.fail(
+            PipelineStep::ResolveMonth,
+            "日付を読み取れなかったため保存先の月を決められませんでした".to_string(),
+        ));
+    };
+    pipeline.year_month = Some(year_month.clone());
+    pipeline.complete(PipelineStep::ResolveMonth);
+
+    // 月別ディレクトリへコピー
+    let copied = match copy_file_to_month(
+        app.clone(),
+        source_path,
+        year_month,
+        Some(options.normalize_orientation),
+    )
+    .await
+    {
+        Ok(copied) => copied,
+        Err(e) => return Ok(pipeline.fail(PipelineStep::Copy, e)),
+    };
+    pipeline.destination_path = Some(copied.destination_path.clone());
+    pipeline.complete(PipelineStep::Copy);
+
+    // サマリーへ追記（コピー先のファイル名で登録する）
+    if let Some(data) = result.data.as_mut() {
+        data.file = copied.file_name.clone();
+    }
+    if let Err(e) = crate::summary::flush_results(
+        std::slice::from_ref(&copied.destination_path),
+        std::slice::from_ref(&result),
+    ) {
+        return Ok(pipeline.fail(
+            PipelineStep::Summary,
+            format!("サマリーの書き込みに失敗しました: {}", e),
+        ));
+    }
+    pipeline.summary_updated = true;
+    pipeline.complete(PipelineStep::Summary);
+
+    // サムネイル（PDF など画像以外は作らない）
+    let destination = PathBuf::from(&copied.destination_path);
+    let file_name = copied.file_name.clone();
+    let is_image = receipt_file_kind(&copied.file_name, &settings.extra_image_extensions)
+        .is_some_and(|(is_image, _)| is_image);
+    if is_image {
+        let size = options
+            .thumbnail_size
+            .unwrap_or(crate::thumbnail::DEFAULT_THUMBNAIL_SIZE);
+        let thumbnail = tauri::async_runtime::spawn_blocking(move || {
+            let month_dir = destination
+                .parent()
+                .ok_or("保存先のディレクトリが不明です")?;
+            crate::thumbnail::generate_thumbnail(month_dir, &file_name, size)
+        })
+        .await
+        .map_err(|e| format!("サムネイルの生成に失敗しました: {}", e))
+        .and_then(|thumbnail| thumbnail);
+        match thumbnail {
+            Ok(path) => pipeline.thumbnail_path = Some(path.to_string_lossy().into_owned()),
+            Err(e) => return Ok(pipeline.fail(PipelineStep::Thumbnail, e)),
+        }
+    }
+    pipeline.complete(PipelineStep::Thumbnail);
+
+    Ok(pipeline)
+}
+
+/// URL の画像・PDF をダウンロードして `year_month` の月別ディレクトリへ取り込む
+///
+/// https のURLのみ受け付け、ダウンロードしたファイルは一時ファイルにしてから `process_and_file`
+/// と同じ手順（OCR → `copy_file_to_month` でコピー → サマリー → サムネイル）で処理する。
+/// ダウンロードの失敗（拒否・サイズ超過・非対応の形式など）はエラーとして返す。
+#[tauri::command]
+pub async fn ocr_from_url(
+    app: AppHandle,
+    registry: State<'_, Arc<Mutex<OcrProviderRegistry>>>,
+    in_flight: State<'_, Arc<InFlightFiles>>,
+    url: String,
+    year_month: String,
+    options: Option<ProcessAndFileOptions>,
+) -> Result<PipelineResult, String> {
+    let url = crate::url_import::parse_download_url(&url)?;
+    let downloaded = crate::url_import::download(&url).await?;
+    let temp_path = crate::url_import::write_temp_file(&downloaded)?;
+
+    let result = process_and_file(
+        app,
+        registry,
+        in_flight,
+        temp_path.to_string_lossy().into_owned(),
+        Some(ProcessAndFileOptions {
+            year_month: Some(year_month),
+            ..options.unwrap_or_default()
+        }),
+    )
+    .await;
+
+    if let Some(dir) = temp_path.parent() {
+        let _ = fs::remove_dir_all(dir);
+    }
+    result
+}
+
 /// 勘定科目ルール設定を取得
 #[tauri::command]
 pub async fn get_account_category_rules(app: AppHandle) -> Result<Value, String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    let store = store_keys::open_store(&app)?;
 
-    let settings = store.get("account_category_rules").unwrap_or(Value::Null);
+    let settings = store
+        .get(store_keys::ACCOUNT_CATEGORY_RULES)
+        .unwrap_or(Value::Null);
 
     Ok(settings)
 }
@@ -710,27 +2711,43 @@ pub async fn get_account_category_rules(app: AppHandle) -> Result<Value, String>
 /// 勘定科目ルール設定を保存
 #[tauri::command]
 pub async fn save_account_category_rules(app: AppHandle, settings: Value) -> Result<(), String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    store_keys::set_value(&app, store_keys::ACCOUNT_CATEGORY_RULES, settings)
+}
 
-    store.set("account_category_rules", settings);
+/// 業種ユーザー辞書を取得
+#[tauri::command]
+pub async fn get_merchant_category_dictionary(
+    app: AppHandle,
+) -> Result<MerchantCategorySettings, String> {
+    let store = store_keys::open_store(&app)?;
+
+    let settings = store
+        .get(store_keys::MERCHANT_CATEGORY_DICTIONARY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
 
-    store
-        .save()
-        .map_err(|e| format!("設定の保存に失敗しました: {}", e))?;
+    Ok(settings)
+}
 
-    Ok(())
+/// 業種ユーザー辞書を保存
+#[tauri::command]
+pub async fn save_merchant_category_dictionary(
+    app: AppHandle,
+    settings: MerchantCategorySettings,
+) -> Result<(), String> {
+    let value = serde_json::to_value(&settings)
+        .map_err(|e| format!("設定のシリアライズに失敗しました: {}", e))?;
+    store_keys::set_value(&app, store_keys::MERCHANT_CATEGORY_DICTIONARY, value)
 }
 
 /// バリデーションルール設定を取得
 #[tauri::command]
 pub async fn get_validation_rules(app: AppHandle) -> Result<Value, String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    let store = store_keys::open_store(&app)?;
 
-    let rules = store.get("validation_rules").unwrap_or(Value::Null);
+    let rules = store
+        .get(store_keys::VALIDATION_RULES)
+        .unwrap_or(Value::Null);
 
     Ok(rules)
 }
@@ -738,27 +2755,17 @@ pub async fn get_validation_rules(app: AppHandle) -> Result<Value, String> {
 /// バリデーションルール設定を保存
 #[tauri::command]
 pub async fn save_validation_rules(app: AppHandle, rules: Value) -> Result<(), String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
-
-    store.set("validation_rules", rules);
-
-    store
-        .save()
-        .map_err(|e| format!("設定の保存に失敗しました: {}", e))?;
-
-    Ok(())
+    store_keys::set_value(&app, store_keys::VALIDATION_RULES, rules)
 }
 
 /// 宛名履歴を取得
 #[tauri::command]
 pub async fn get_receiver_name_history(app: AppHandle) -> Result<Value, String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
+    let store = store_keys::open_store(&app)?;
 
-    let history = store.get("receiver_name_history").unwrap_or(Value::Null);
+    let history = store
+        .get(store_keys::RECEIVER_NAME_HISTORY)
+        .unwrap_or(Value::Null);
 
     Ok(history)
 }
@@ -766,17 +2773,7 @@ pub async fn get_receiver_name_history(app: AppHandle) -> Result<Value, String>
 /// 宛名履歴を保存
 #[tauri::command]
 pub async fn save_receiver_name_history(app: AppHandle, history: Value) -> Result<(), String> {
-    let store = app
-        .store("torifune.store.json")
-        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))?;
-
-    store.set("receiver_name_history", history);
-
-    store
-        .save()
-        .map_err(|e| format!("履歴の保存に失敗しました: {}", e))?;
-
-    Ok(())
+    store_keys::set_value(&app, store_keys::RECEIVER_NAME_HISTORY, history)
 }
 
 // Logging commands
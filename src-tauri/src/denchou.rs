@@ -0,0 +1,157 @@
+//! 電子帳簿保存法の検索用インデックス
+//!
+//! 電帳法の検索要件（取引年月日・取引金額・取引先で検索できること）を満たすため、
+//! 月別サマリーから3項目の一覧を月ディレクトリの `index.json` に書き出す。
+//! 項目が欠けている証憑は `incomplete` に分けて残し、検索用の `entries` には含めない。
+
+use crate::money::Currency;
+use crate::summary::{MonthSummary, SummaryReceipt};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// インデックスのファイル名
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+/// 検索に使う1証憑
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DenchouIndexEntry {
+    pub file: String,
+    /// 取引年月日（YYYY-MM-DD）
+    pub transaction_date: String,
+    /// 取引金額
+    pub amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+    /// 取引先
+    pub counterparty: String,
+}
+
+/// 検索項目が欠けている証憑
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DenchouIncomplete {
+    pub file: String,
+    /// 欠けている項目（`date`・`amount`・`merchant`）
+    pub missing_fields: Vec<&'static str>,
+}
+
+/// 月ディレクトリの `index.json`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DenchouIndex {
+    pub year_month: String,
+    /// 生成日時（RFC 3339）
+    pub generated_at: String,
+    pub entries: Vec<DenchouIndexEntry>,
+    pub incomplete: Vec<DenchouIncomplete>,
+}
+
+/// 欠けている検索項目（取引年月日・取引金額・取引先）
+pub fn missing_search_fields(receipt: &SummaryReceipt) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if receipt.date.is_none() {
+        missing.push("date");
+    }
+    if receipt.amount.is_none() {
+        missing.push("amount");
+    }
+    if receipt
+        .merchant
+        .as_deref()
+        .is_none_or(|merchant| merchant.trim().is_empty())
+    {
+        missing.push("merchant");
+    }
+    missing
+}
+
+/// 月別サマリーからインデックスを作る（取引年月日・ファイル名順）
+pub fn build_index(summary: &MonthSummary, generated_at: String) -> DenchouIndex {
+    let mut entries = Vec::new();
+    let mut incomplete = Vec::new();
+    for receipt in &summary.receipts {
+        match (&receipt.date, receipt.amount, &receipt.merchant) {
+            (Some(date), Some(amount), Some(merchant)) if !merchant.trim().is_empty() => {
+                entries.push(DenchouIndexEntry {
+                    file: receipt.file.clone(),
+                    transaction_date: date.clone(),
+                    amount,
+                    currency: receipt.currency.clone(),
+                    counterparty: merchant.trim().to_string(),
+                });
+            }
+            _ => incomplete.push(DenchouIncomplete {
+                file: receipt.file.clone(),
+                missing_fields: missing_search_fields(receipt),
+            }),
+        }
+    }
+    entries.sort_by(|a, b| {
+        (a.transaction_date.as_str(), a.file.as_str())
+            .cmp(&(b.transaction_date.as_str(), b.file.as_str()))
+    });
+
+    DenchouIndex {
+        year_month: summary.year_month.clone(),
+        generated_at,
+        entries,
+        incomplete,
+    }
+}
+
+/// インデックスを月ディレクトリに保存する（一時ファイルに書いてから置き換える）
+pub fn write_index(month_dir: &Path, index: &DenchouIndex) -> io::Result<()> {
+    let path = month_dir.join(INDEX_FILE_NAME);
+    let temp_path = path.with_extension("json.tmp");
+
+    let content = serde_json::to_string_pretty(index).map_err(io::Error::other)?;
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(value: serde_json::Value) -> SummaryReceipt {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn index_lists_complete_receipts_by_date_and_separates_incomplete_ones() {
+        let summary = MonthSummary {
+            year_month: "202501".to_string(),
+            updated_at: String::new(),
+            pending_excel_sync: false,
+            receipts: vec![
+                receipt(serde_json::json!({
+                    "file": "b.jpg", "date": "2025-01-20", "amount": 1200, "merchant": " ローソン "
+                })),
+                receipt(serde_json::json!({
+                    "file": "a.jpg", "date": "2025-01-05", "amount": 540, "merchant": "スターバックス",
+                    "currency": "JPY"
+                })),
+                receipt(serde_json::json!({ "file": "c.jpg", "amount": 300, "merchant": "  " })),
+            ],
+        };
+
+        let index = build_index(&summary, "2025-02-01T00:00:00+09:00".to_string());
+        let files: Vec<&str> = index.entries.iter().map(|e| e.file.as_str()).collect();
+        assert_eq!(files, vec!["a.jpg", "b.jpg"]);
+        assert_eq!(index.entries[1].counterparty, "ローソン");
+        assert_eq!(
+            index.incomplete,
+            vec![DenchouIncomplete {
+                file: "c.jpg".to_string(),
+                missing_fields: vec!["date", "merchant"],
+            }]
+        );
+        assert_eq!(
+            serde_json::to_value(&index).unwrap()["entries"][0]["transactionDate"],
+            "2025-01-05"
+        );
+    }
+}
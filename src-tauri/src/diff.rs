@@ -0,0 +1,167 @@
+//! OCR結果の差分比較
+//!
+//! 再OCRの前後など2つの `ReceiptData` をフィールドごとに比較する。金額は許容誤差内、
+//! 文字列は正規化後に一致すれば「実質同一」とみなし、表記揺れを変更と区別する。
+
+use crate::money::{minor_unit_exponent, DEFAULT_CURRENCY};
+use crate::providers::ReceiptData;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use unicode_normalization::UnicodeNormalization;
+
+/// 比較しないフィールド（`amount` から導出されるもの）
+const SKIPPED_FIELDS: &[&str] = &["amountMinor"];
+
+/// 確信度など金額以外の数値の許容誤差
+const NUMBER_EPSILON: f64 = 1e-6;
+
+/// 変更の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+    /// 新しい結果にだけ値がある
+    Added,
+    /// 古い結果にだけ値がある
+    Removed,
+    /// 値が変わった
+    Changed,
+    /// 表記は違うが実質同一（金額が許容誤差内・正規化後の文字列が一致）
+    Equivalent,
+}
+
+/// 1フィールドの差分
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    pub kind: DiffKind,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// 比較用に文字列を正規化する（NFKC・前後の空白除去・連続する空白を1つに）
+fn normalize_text(text: &str) -> String {
+    text.nfkc()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 金額の既定の許容誤差（通貨の最小単位の半分）
+fn default_amount_tolerance(currency: Option<&str>) -> f64 {
+    0.5 / 10f64.powi(minor_unit_exponent(currency.unwrap_or(DEFAULT_CURRENCY)) as i32)
+}
+
+/// 値の異なる2つのフィールドが実質同一か
+fn is_equivalent(field: &str, old: &Value, new: &Value, amount_tolerance: f64) -> bool {
+    match (old, new) {
+        (Value::String(old), Value::String(new)) => normalize_text(old) == normalize_text(new),
+        (Value::Number(old), Value::Number(new)) => {
+            let (Some(old), Some(new)) = (old.as_f64(), new.as_f64()) else {
+                return false;
+            };
+            let tolerance = if field == "amount" {
+                amount_tolerance
+            } else {
+                NUMBER_EPSILON
+            };
+            (old - new).abs() <= tolerance
+        }
+        _ => false,
+    }
+}
+
+/// 2つのOCR結果をフィールドごとに比較し、差分のあるフィールドをフィールド名の順に返す
+///
+/// 空のときに書き出さないフィールドもあるため、どちらか一方にあるフィールドはすべて比べる。
+/// `amount_tolerance` を省略した場合は通貨の最小単位の半分（円なら 0.5 円）を許容する。
+pub fn diff_receipts(
+    old: &ReceiptData,
+    new: &ReceiptData,
+    amount_tolerance: Option<f64>,
+) -> Vec<FieldDiff> {
+    let amount_tolerance = amount_tolerance.unwrap_or_else(|| {
+        default_amount_tolerance(new.currency.as_deref().or(old.currency.as_deref()))
+    });
+
+    let (Ok(Value::Object(mut old)), Ok(Value::Object(mut new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    let fields: BTreeSet<String> = old.keys().chain(new.keys()).cloned().collect();
+    fields
+        .into_iter()
+        .filter(|field| !SKIPPED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old = old.remove(&field).unwrap_or(Value::Null);
+            let new = new.remove(&field).unwrap_or(Value::Null);
+            let kind = match (&old, &new) {
+                _ if old == new => return None,
+                (Value::Null, _) => DiffKind::Added,
+                (_, Value::Null) => DiffKind::Removed,
+                _ if is_equivalent(&field, &old, &new, amount_tolerance) => DiffKind::Equivalent,
+                _ => DiffKind::Changed,
+            };
+            Some(FieldDiff {
+                field,
+                kind,
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_receipts_classifies_changes() {
+        let mut old = ReceiptData::new("a.jpg".to_string());
+        old.merchant = Some("ローソン　新宿店".to_string());
+        old.amount = Some(1080.0);
+        old.date = Some("2025-01-05".to_string());
+        old.receiver_name = Some("山田".to_string());
+
+        let mut new = old.clone();
+        new.merchant = Some("ローソン 新宿店".to_string());
+        new.amount = Some(1080.3);
+        new.date = Some("2025-01-06".to_string());
+        new.receiver_name = None;
+        new.currency = crate::money::Currency::parse("JPY");
+
+        // 空のときは書き出さないフィールドも、新しい結果にだけあれば追加として数える
+        new.manually_edited = vec!["amount".to_string()];
+
+        let kinds: Vec<_> = diff_receipts(&old, &new, None)
+            .into_iter()
+            .map(|diff| (diff.field, diff.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("amount".to_string(), DiffKind::Equivalent),
+                ("currency".to_string(), DiffKind::Added),
+                ("date".to_string(), DiffKind::Changed),
+                ("manuallyEdited".to_string(), DiffKind::Added),
+                ("merchant".to_string(), DiffKind::Equivalent),
+                ("receiverName".to_string(), DiffKind::Removed),
+            ]
+        );
+
+        // 古い結果にだけあるフィールドは削除
+        let removed = diff_receipts(&new, &old, None);
+        assert!(removed
+            .iter()
+            .any(|diff| diff.field == "manuallyEdited" && diff.kind == DiffKind::Removed));
+
+        let diffs = diff_receipts(&old, &new, Some(0.1));
+        let amount = diffs.iter().find(|diff| diff.field == "amount").unwrap();
+        assert_eq!(amount.kind, DiffKind::Changed);
+    }
+}
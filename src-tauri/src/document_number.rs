@@ -0,0 +1,203 @@
+//! 証憑番号の連番チェック（スキャン漏れの検知）
+//!
+//! 領収書番号などを「プレフィックス＋数値」（`R-0012`・`No.105` など）として読み、
+//! プレフィックスごとに並べて抜け番（ギャップ）と重複を別々に報告する。
+//! 数値で終わらない番号は連番として扱えないため `unparsed` に分ける。
+
+use crate::summary::SummaryReceipt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// 連番として読んだ証憑番号
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentNumber {
+    /// 数値より前の部分（空白は取り除く）
+    pub prefix: String,
+    pub number: u64,
+    /// 数値部分の桁数（ゼロ埋めを再現するため）
+    pub width: usize,
+}
+
+impl DocumentNumber {
+    /// 末尾の数字の並びを数値として読む（数字で終わらなければ `None`）
+    pub fn parse(text: &str) -> Option<Self> {
+        let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let digits_start = text
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_ascii_digit())
+            .last()
+            .map(|(i, _)| i)?;
+        let digits = &text[digits_start..];
+
+        Some(Self {
+            prefix: text[..digits_start].to_string(),
+            number: digits.parse().ok()?,
+            width: digits.len(),
+        })
+    }
+
+    fn format(&self, number: u64) -> String {
+        format!("{}{:0width$}", self.prefix, number, width = self.width)
+    }
+}
+
+/// 連番の抜け
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NumberGap {
+    pub prefix: String,
+    /// 抜けの直前の番号
+    pub after: String,
+    /// 抜けの直後の番号
+    pub before: String,
+    /// 抜けている最初の番号
+    pub first_missing: String,
+    /// 抜けている最後の番号
+    pub last_missing: String,
+    pub missing_count: u64,
+}
+
+/// 複数のレシートに付いていた番号
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateNumber {
+    pub document_number: String,
+    pub files: Vec<String>,
+}
+
+/// 連番として読めなかった番号
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnparsedNumber {
+    pub file: String,
+    pub document_number: String,
+}
+
+/// 連番チェックの結果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NumberGapReport {
+    pub gaps: Vec<NumberGap>,
+    pub duplicates: Vec<DuplicateNumber>,
+    pub unparsed: Vec<UnparsedNumber>,
+}
+
+/// 月別サマリーのレシートの証憑番号から抜け番と重複を検出する
+///
+/// 番号の無いレシートは対象外。同じ番号は桁数が違っても（`R-012` と `R-12`）同じものとみなす。
+pub fn detect_number_gaps(receipts: &[SummaryReceipt]) -> NumberGapReport {
+    let mut report = NumberGapReport::default();
+    // プレフィックス → 番号 → (表記, ファイル)
+    let mut series: BTreeMap<String, BTreeMap<u64, (DocumentNumber, Vec<String>)>> =
+        BTreeMap::new();
+
+    for receipt in receipts {
+        let Some(text) = receipt
+            .document_number
+            .as_deref()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+        else {
+            continue;
+        };
+        let Some(parsed) = DocumentNumber::parse(text) else {
+            report.unparsed.push(UnparsedNumber {
+                file: receipt.file.clone(),
+                document_number: text.to_string(),
+            });
+            continue;
+        };
+
+        series
+            .entry(parsed.prefix.clone())
+            .or_default()
+            .entry(parsed.number)
+            .or_insert_with(|| (parsed, Vec::new()))
+            .1
+            .push(receipt.file.clone());
+    }
+
+    for (prefix, numbers) in series {
+        let mut previous: Option<&DocumentNumber> = None;
+        for (number, (parsed, files)) in &numbers {
+            if files.len() > 1 {
+                report.duplicates.push(DuplicateNumber {
+                    document_number: parsed.format(*number),
+                    files: files.clone(),
+                });
+            }
+            if let Some(previous) = previous.filter(|previous| number - previous.number > 1) {
+                report.gaps.push(NumberGap {
+                    prefix: prefix.clone(),
+                    after: previous.format(previous.number),
+                    before: parsed.format(*number),
+                    first_missing: parsed.format(previous.number + 1),
+                    last_missing: parsed.format(number - 1),
+                    missing_count: number - previous.number - 1,
+                });
+            }
+            previous = Some(parsed);
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(file: &str, document_number: Option<&str>) -> SummaryReceipt {
+        serde_json::from_value(serde_json::json!({
+            "file": file,
+            "documentNumber": document_number,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn gaps_and_duplicates_are_reported_per_prefix() {
+        assert_eq!(
+            DocumentNumber::parse("No. 0105"),
+            Some(DocumentNumber {
+                prefix: "No.".to_string(),
+                number: 105,
+                width: 4,
+            })
+        );
+        assert_eq!(DocumentNumber::parse("ABC"), None);
+
+        let report = detect_number_gaps(&[
+            receipt("a.jpg", Some("R-0001")),
+            receipt("b.jpg", Some("R-0002")),
+            receipt("c.jpg", Some("R-0005")),
+            receipt("d.jpg", Some("R-0002")),
+            receipt("e.jpg", Some("S-10")),
+            receipt("f.jpg", Some("S-11")),
+            receipt("g.jpg", Some("控え")),
+            receipt("h.jpg", None),
+        ]);
+
+        assert_eq!(
+            report.gaps,
+            vec![NumberGap {
+                prefix: "R-".to_string(),
+                after: "R-0002".to_string(),
+                before: "R-0005".to_string(),
+                first_missing: "R-0003".to_string(),
+                last_missing: "R-0004".to_string(),
+                missing_count: 2,
+            }]
+        );
+        assert_eq!(
+            report.duplicates,
+            vec![DuplicateNumber {
+                document_number: "R-0002".to_string(),
+                files: vec!["b.jpg".to_string(), "d.jpg".to_string()],
+            }]
+        );
+        assert_eq!(report.unparsed.len(), 1);
+        assert_eq!(report.unparsed[0].file, "g.jpg");
+    }
+}
@@ -0,0 +1,153 @@
+//! OCR前の画像の縮小と形式変換
+//!
+//! 高解像度のスキャン・写真は送信と処理が重く、ページ課金以外のプロバイダーではコストも増える。
+//! 長辺が `OcrSettings.max_image_dimension` を超える画像は、アスペクト比を保って縮小してから送る。
+//! スキャナが出力する BMP・TIFF（`extra_image_extensions` で一覧に追加したもの）は
+//! プロバイダーが MIME を判別できないことがあるため PNG に変換して送る。
+//! PDF はローカルでラスタライズせずそのまま送るため対象外。
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+
+/// 長辺の既定の上限（A4 を約 300dpi で読み取れる解像度。レシートの文字認識には十分）
+pub const DEFAULT_MAX_IMAGE_DIMENSION: u32 = 3500;
+
+/// 設定できる上限の最小値（これより小さいと小さな文字が読めなくなる）
+pub const MIN_MAX_IMAGE_DIMENSION: u32 = 1000;
+
+/// 縮小後の JPEG 品質
+const JPEG_QUALITY: u8 = 90;
+
+/// PNG に変換してから送る形式と表示名
+const CONVERTED_FORMATS: &[(ImageFormat, &str)] =
+    &[(ImageFormat::Bmp, "BMP"), (ImageFormat::Tiff, "TIFF")];
+
+/// 縮小・変換した画像
+#[derive(Debug, Clone)]
+pub struct Downscaled {
+    pub content: Vec<u8>,
+    pub mime_type: &'static str,
+    /// 元の幅・高さ
+    pub original: (u32, u32),
+    /// 縮小後の幅・高さ
+    pub resized: (u32, u32),
+    /// PNG に変換した場合の元の形式
+    pub converted_from: Option<&'static str>,
+}
+
+impl Downscaled {
+    /// 結果の warnings に残す文言
+    pub fn warning(&self) -> String {
+        let size = format!(
+            "{}×{} → {}×{}",
+            self.original.0, self.original.1, self.resized.0, self.resized.1
+        );
+        match (self.converted_from, self.original == self.resized) {
+            (Some(format), true) => format!("{}をPNGに変換してOCRしました", format),
+            (Some(format), false) => {
+                format!("{}をPNGに変換・縮小してOCRしました（{}）", format, size)
+            }
+            (None, _) => format!("画像を縮小してOCRしました（{}）", size),
+        }
+    }
+}
+
+/// 長辺が `max_dimension` を超える画像を縮小し、BMP・TIFF は PNG に変換する
+///
+/// 縮小は JPEG・PNG・WebP・BMP・TIFF が対象（PNG・BMP・TIFF は PNG、それ以外は JPEG で再エンコード）。
+/// `max_dimension` が `None` なら縮小しない。EXIF の向きは画素に適用してから縮小する。
+/// TIFF は先頭ページのみ。縮小も変換も不要な画像や対象外の形式は `None`。
+pub fn downscale_to_fit(
+    content: &[u8],
+    max_dimension: Option<u32>,
+) -> image::ImageResult<Option<Downscaled>> {
+    let reader = ImageReader::new(Cursor::new(content)).with_guessed_format()?;
+    let (format, converted_from) = match reader.format() {
+        Some(format @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) => (format, None),
+        Some(format) => match CONVERTED_FORMATS.iter().find(|(f, _)| *f == format) {
+            Some((_, name)) => (format, Some(*name)),
+            None => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+
+    let mut decoder = reader.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    let exceeds = max_dimension.is_some_and(|max| width.max(height) > max);
+    if !exceeds && converted_from.is_none() {
+        return Ok(None);
+    }
+
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    let original = (image.width(), image.height());
+    let image = match max_dimension {
+        Some(max) if exceeds => image.resize(max, max, FilterType::Lanczos3),
+        _ => image,
+    };
+
+    let mut output = Vec::new();
+    let mime_type = if format == ImageFormat::Png || converted_from.is_some() {
+        image.write_with_encoder(PngEncoder::new(&mut output))?;
+        "image/png"
+    } else {
+        // JPEG はアルファを持てない
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut output, JPEG_QUALITY))?;
+        "image/jpeg"
+    };
+
+    Ok(Some(Downscaled {
+        content: output,
+        mime_type,
+        original,
+        resized: (image.width(), image.height()),
+        converted_from,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn downscale_keeps_aspect_ratio_and_skips_small_images() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(400, 100))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let downscaled = downscale_to_fit(&png, Some(200)).unwrap().unwrap();
+        assert_eq!(downscaled.mime_type, "image/png");
+        assert_eq!(downscaled.resized, (200, 50));
+        assert_eq!(
+            downscaled.warning(),
+            "画像を縮小してOCRしました（400×100 → 200×50）"
+        );
+        assert_eq!(
+            image::load_from_memory(&downscaled.content)
+                .unwrap()
+                .to_rgb8()
+                .dimensions(),
+            (200, 50)
+        );
+
+        assert!(downscale_to_fit(&png, Some(400)).unwrap().is_none());
+        assert!(downscale_to_fit(&png, None).unwrap().is_none());
+        assert!(downscale_to_fit(b"%PDF-1.7", Some(200)).unwrap().is_none());
+
+        let mut tiff = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(400, 100))
+            .write_to(&mut Cursor::new(&mut tiff), ImageFormat::Tiff)
+            .unwrap();
+        let converted = downscale_to_fit(&tiff, None).unwrap().unwrap();
+        assert_eq!(converted.mime_type, "image/png");
+        assert_eq!(converted.resized, (400, 100));
+        assert_eq!(converted.warning(), "TIFFをPNGに変換してOCRしました");
+    }
+}
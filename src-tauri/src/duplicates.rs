@@ -0,0 +1,198 @@
+//! 重複レシートの検知
+//!
+//! 同じレシートの二重計上を防ぐため、日付・店舗名・金額、または時刻・金額が一致する（日付は同日か
+//! 前後1日）レシートをまとめる。店舗名は表記ゆれ（全角半角・空白・法人格）を `normalize_merchant_name` で吸収し、
+//! 金額は小数の誤差が出ないよう通貨の最小単位（円なら1円）の整数で比較する。
+
+use crate::merchant_category::normalize_merchant_name;
+use crate::money::DEFAULT_CURRENCY;
+use crate::providers::ReceiptData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 重複の判定ルール
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateRule {
+    /// 日付・店舗名・金額が一致
+    DateMerchantAmount,
+    /// 時刻・金額が一致し、日付が同日か前後1日（店舗名や日付の1日程度の読み違いも拾う）
+    ///
+    /// 時刻か日付の無いレシートは対象外。別の日の同じ時刻・同じ金額（毎日の定期券・昼食など）は
+    /// 重複とみなさない。
+    TimeAmount,
+}
+
+/// 時刻・金額のルールで同じレシートとみなす日付の差の上限（日）
+const TIME_AMOUNT_MAX_DAY_GAP: i64 = 1;
+
+/// 重複候補のグループ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    /// リクエストでのレシートの位置
+    pub indices: Vec<usize>,
+    /// ヒットしたルール
+    pub matched_rule: DuplicateRule,
+}
+
+/// 重複の判定に使うキー（通貨・最小単位の金額とルールごとの項目）
+fn duplicate_key(receipt: &ReceiptData, rule: DuplicateRule) -> Option<(String, i64, Vec<String>)> {
+    let currency = receipt
+        .currency
+        .as_deref()
+        .unwrap_or(DEFAULT_CURRENCY)
+        .to_string();
+    let amount = receipt.amount_in_minor_units()?;
+    let fields = match rule {
+        DuplicateRule::DateMerchantAmount => vec![
+            receipt.date.as_deref()?.trim().to_string(),
+            normalize_merchant_name(receipt.merchant.as_deref()?),
+        ],
+        DuplicateRule::TimeAmount => vec![receipt.time.as_deref()?.trim().to_string()],
+    };
+    if fields.iter().any(String::is_empty) {
+        return None;
+    }
+    Some((currency, amount, fields))
+}
+
+/// 1つのルールで一致するレシートの位置をグループにする（2件以上のグループのみ、先頭の位置の順）
+fn group_by_rule(receipts: &[ReceiptData], rule: DuplicateRule) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_key: HashMap<_, usize> = HashMap::new();
+
+    for (index, receipt) in receipts.iter().enumerate() {
+        let Some(key) = duplicate_key(receipt, rule) else {
+            continue;
+        };
+        match group_of_key.get(&key) {
+            Some(&group) => groups[group].push(index),
+            None => {
+                group_of_key.insert(key, groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    if rule == DuplicateRule::TimeAmount {
+        groups = groups
+            .into_iter()
+            .flat_map(|group| split_by_date(receipts, group))
+            .collect();
+        groups.sort_by_key(|group| group[0]);
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// 日付の近いレシートごとに分ける（各グループの日付の幅は `TIME_AMOUNT_MAX_DAY_GAP` 日以内）
+///
+/// 日付の古い順に、グループの最初の日付から離れすぎたら新しいグループにする。日付の無い・読めない
+/// レシートは除く。
+fn split_by_date(receipts: &[ReceiptData], group: Vec<usize>) -> Vec<Vec<usize>> {
+    let mut dated: Vec<(NaiveDate, usize)> = group
+        .into_iter()
+        .filter_map(|index| {
+            let date = receipts[index].date.as_deref()?.trim();
+            Some((NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?, index))
+        })
+        .collect();
+    dated.sort();
+
+    let mut clusters: Vec<(NaiveDate, Vec<usize>)> = Vec::new();
+    for (date, index) in dated {
+        match clusters.last_mut() {
+            Some((first, indices)) if (date - *first).num_days() <= TIME_AMOUNT_MAX_DAY_GAP => {
+                indices.push(index)
+            }
+            _ => clusters.push((date, vec![index])),
+        }
+    }
+
+    clusters
+        .into_iter()
+        .map(|(_, mut indices)| {
+            indices.sort_unstable();
+            indices
+        })
+        .collect()
+}
+
+/// 指定したルールのいずれかで一致するレシートを重複候補のグループにして返す
+///
+/// ルールごとに指定順でグループを並べる。先のルールと同じ組み合わせのグループは重ねて返さない。
+/// 判定に使う項目のどれかが無いレシートは、そのルールでは対象外。
+pub fn find_duplicate_receipts(
+    receipts: &[ReceiptData],
+    rules: &[DuplicateRule],
+) -> Vec<DuplicateGroup> {
+    let mut found: Vec<DuplicateGroup> = Vec::new();
+    for &rule in rules {
+        for indices in group_by_rule(receipts, rule) {
+            if !found.iter().any(|group| group.indices == indices) {
+                found.push(DuplicateGroup {
+                    indices,
+                    matched_rule: rule,
+                });
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipts_matching_any_rule_are_grouped() {
+        let receipt = |merchant: &str, date: &str, time: Option<&str>, amount: f64| {
+            let mut data = ReceiptData::new("a.jpg".to_string());
+            data.merchant = Some(merchant.to_string());
+            data.date = Some(date.to_string());
+            data.time = time.map(str::to_string);
+            data.amount = Some(amount);
+            data
+        };
+        let receipts = [
+            receipt("ＡＢＣ Ｍａｒｔ", "2025-01-06", None, 1080.0),
+            receipt("ローソン", "2025-01-06", Some("12:30:00"), 1080.0),
+            receipt("abc mart", "2025-01-06", None, 1080.0000001),
+            receipt("ABC Mart", "2025-01-07", None, 1080.0),
+            receipt("ローソン", "2025-01-06", Some("12:30:00"), 1080.0),
+            receipt("ロ-ソン", "2025-01-07", Some("12:30:00"), 1080.0),
+            receipt("ABCMart", "2025-01-06", Some("09:00:00"), 1081.0),
+            ReceiptData::new("b.jpg".to_string()),
+            receipt("ローソン", "2025-01-08", Some("12:30:00"), 1080.0),
+            receipt("ローソン", "2025-02-06", Some("12:30:00"), 1080.0),
+        ];
+
+        let group = |indices: &[usize], matched_rule| DuplicateGroup {
+            indices: indices.to_vec(),
+            matched_rule,
+        };
+        assert_eq!(
+            find_duplicate_receipts(&receipts, &[DuplicateRule::DateMerchantAmount]),
+            vec![
+                group(&[0, 2], DuplicateRule::DateMerchantAmount),
+                group(&[1, 4], DuplicateRule::DateMerchantAmount),
+            ]
+        );
+        // 時刻の無いレシート・2日以上離れたレシート（8: 最初の日付から2日後, 9: 翌月）は
+        // 時刻・金額のルールでは一致させない
+        assert_eq!(
+            find_duplicate_receipts(
+                &receipts,
+                &[DuplicateRule::DateMerchantAmount, DuplicateRule::TimeAmount]
+            ),
+            vec![
+                group(&[0, 2], DuplicateRule::DateMerchantAmount),
+                group(&[1, 4], DuplicateRule::DateMerchantAmount),
+                group(&[1, 4, 5], DuplicateRule::TimeAmount),
+            ]
+        );
+    }
+}
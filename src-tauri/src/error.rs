@@ -0,0 +1,278 @@
+//! アプリケーションエラー
+//!
+//! ユーザーへの案内を出し分ける必要があるエラーを識別するための型。
+//! Tauriコマンドの境界では従来どおり `String`（日本語）に変換して返す。
+//! `OcrResult` には識別子とパラメータのまま載せ、フロントのロケールに応じて
+//! [`localize_error`] で日本語/英語のメッセージに変換する。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// アプリケーションエラー
+///
+/// JSON では `{ "code": "quotaExceeded", "retryAfterSecs": 120 }` の形になる。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "code",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AppError {
+    /// OCRプロバイダーのクォータ（日次/分次）超過
+    QuotaExceeded {
+        /// クォータリセットまでの推定秒数（推定できない場合は `None`）
+        retry_after_secs: Option<u64>,
+    },
+    /// 同じファイルが処理中のため重複投入をスキップした
+    AlreadyInFlight,
+    /// バッチ全体のデッドラインで打ち切った
+    DeadlineExceeded,
+    /// 停止要求（アプリの終了など）により着手せずに中断した
+    Cancelled,
+    /// 1ファイルの制限時間内に処理が終わらなかった
+    FileTimeout { limit_secs: u64 },
+    /// ファイルの読み込みに失敗した
+    FileReadFailed { detail: String },
+    /// 連続で失敗した回数が上限に達したため自動では再試行しない
+    RetryLimitReached { attempts: u32 },
+    /// 再開したバッチで処理されずに残った（もう一度再開すれば処理する）
+    NotProcessed,
+}
+
+/// メッセージの言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Locale {
+    /// `en-US`・`en_GB.UTF-8` などのロケール文字列から判定する（英語以外は日本語）
+    pub fn parse(locale: &str) -> Self {
+        let language = locale
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        if language == "en" {
+            Locale::En
+        } else {
+            Locale::Ja
+        }
+    }
+}
+
+/// 秒数を「約N分後」のような大まかな表現にする
+fn describe_wait(secs: u64, locale: Locale) -> String {
+    let (value, unit) = if secs < 60 {
+        (secs.max(1), ("秒", "second"))
+    } else if secs < 3600 {
+        (secs.div_ceil(60), ("分", "minute"))
+    } else {
+        (secs.div_ceil(3600), ("時間", "hour"))
+    };
+    match locale {
+        Locale::Ja => format!("約{}{}後", value, unit.0),
+        Locale::En => format!(
+            "in about {} {}{}",
+            value,
+            unit.1,
+            if value == 1 { "" } else { "s" }
+        ),
+    }
+}
+
+/// エラーを指定言語のメッセージにする
+pub fn localize_error(error: &AppError, locale: Locale) -> String {
+    match (error, locale) {
+        (AppError::QuotaExceeded { retry_after_secs }, Locale::Ja) => {
+            let hint = match retry_after_secs {
+                Some(secs) => format!(
+                    "{}にリセットされる見込みです。時間をおいて再実行してください。",
+                    describe_wait(*secs, locale)
+                ),
+                None => "時間をおいて再実行してください。".to_string(),
+            };
+            format!(
+                "OCRプロバイダーの利用上限（クォータ）を超過しました。{}",
+                hint
+            )
+        }
+        (AppError::QuotaExceeded { retry_after_secs }, Locale::En) => {
+            let hint = match retry_after_secs {
+                Some(secs) => format!(
+                    "It is expected to reset {}. Please try again later.",
+                    describe_wait(*secs, locale)
+                ),
+                None => "Please try again later.".to_string(),
+            };
+            format!("The OCR provider quota has been exceeded. {}", hint)
+        }
+        (AppError::AlreadyInFlight, Locale::Ja) => "処理中です".to_string(),
+        (AppError::AlreadyInFlight, Locale::En) => "Already being processed".to_string(),
+        (AppError::DeadlineExceeded, Locale::Ja) => "デッドライン超過".to_string(),
+        (AppError::DeadlineExceeded, Locale::En) => "Batch deadline exceeded".to_string(),
+        (AppError::Cancelled, Locale::Ja) => "キャンセルされました".to_string(),
+        (AppError::Cancelled, Locale::En) => "Cancelled".to_string(),
+        (AppError::FileTimeout { limit_secs }, Locale::Ja) => {
+            format!("制限時間（{}秒）内に処理が終わりませんでした", limit_secs)
+        }
+        (AppError::FileTimeout { limit_secs }, Locale::En) => {
+            format!("Processing did not finish within {} seconds", limit_secs)
+        }
+        (AppError::FileReadFailed { detail }, Locale::Ja) => {
+            format!("ファイルの読み込みに失敗しました: {}", detail)
+        }
+        (AppError::FileReadFailed { detail }, Locale::En) => {
+            format!("Failed to read the file: {}", detail)
+        }
+        (AppError::RetryLimitReached { attempts }, Locale::Ja) => {
+            format!(
+                "{}回続けて失敗したため再試行しません（画像を確認して手動で再実行してください）",
+                attempts
+            )
+        }
+        (AppError::RetryLimitReached { attempts }, Locale::En) => {
+            format!(
+                "Not retried after {} consecutive failures (check the image and retry manually)",
+                attempts
+            )
+        }
+        (AppError::NotProcessed, Locale::Ja) => {
+            "未処理です（バッチをもう一度再開してください）".to_string()
+        }
+        (AppError::NotProcessed, Locale::En) => {
+            "Not processed yet (resume the batch again)".to_string()
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&localize_error(self, Locale::Ja))
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
+
+/// OCRプロバイダーのエラー
+///
+/// 再試行で回復しうる一時的なエラー（429・5xx・通信の失敗・タイムアウト）かを持つ。
+/// `String` からの変換は、再試行しても結果が変わらないエラー（認証・非対応形式など）とする。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderError {
+    /// エラーメッセージ（日本語）
+    pub message: String,
+    /// 再試行すれば成功しうるか
+    pub retryable: bool,
+    /// 識別できるエラー（クォータ超過など）
+    pub detail: Option<AppError>,
+}
+
+impl ProviderError {
+    /// 再試行しても結果が変わらないエラー
+    pub fn permanent(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: false,
+            detail: None,
+        }
+    }
+
+    /// 再試行すれば成功しうる一時的なエラー
+    pub fn transient(message: impl Into<String>) -> Self {
+        Self {
+            retryable: true,
+            ..Self::permanent(message)
+        }
+    }
+
+    /// HTTP ステータスで分類する（408・429・5xx は一時的）
+    pub fn from_status(status: u16, message: impl Into<String>) -> Self {
+        if status == 408 || status == 429 || (500..600).contains(&status) {
+            Self::transient(message)
+        } else {
+            Self::permanent(message)
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl From<String> for ProviderError {
+    fn from(message: String) -> Self {
+        Self::permanent(message)
+    }
+}
+
+impl From<&str> for ProviderError {
+    fn from(message: &str) -> Self {
+        Self::permanent(message)
+    }
+}
+
+/// クォータ超過はリセットまで回復しないため、すぐには再試行しない
+impl From<AppError> for ProviderError {
+    fn from(error: AppError) -> Self {
+        let retryable = matches!(error, AppError::FileTimeout { .. });
+        Self {
+            message: error.to_string(),
+            retryable,
+            detail: Some(error),
+        }
+    }
+}
+
+impl From<ProviderError> for String {
+    fn from(error: ProviderError) -> Self {
+        error.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localize_error_switches_language_by_locale() {
+        let error = AppError::QuotaExceeded {
+            retry_after_secs: Some(120),
+        };
+        assert_eq!(
+            error.to_string(),
+            "OCRプロバイダーの利用上限（クォータ）を超過しました。約2分後にリセットされる見込みです。時間をおいて再実行してください。"
+        );
+        assert_eq!(
+            localize_error(&error, Locale::parse("en_US.UTF-8")),
+            "The OCR provider quota has been exceeded. It is expected to reset in about 2 minutes. Please try again later."
+        );
+        assert_eq!(Locale::parse("ja-JP"), Locale::Ja);
+
+        let error = ProviderError::from(AppError::QuotaExceeded {
+            retry_after_secs: None,
+        });
+        assert!(!error.retryable);
+        assert!(ProviderError::from_status(503, "x").retryable);
+        assert!(!ProviderError::from_status(401, "x").retryable);
+        assert!(!ProviderError::from("非対応の形式".to_string()).retryable);
+
+        let json = serde_json::to_value(AppError::FileTimeout { limit_secs: 30 }).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "code": "fileTimeout", "limitSecs": 30 })
+        );
+    }
+}
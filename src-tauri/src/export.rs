@@ -0,0 +1,614 @@
+//! エクスポート
+//!
+//! OCR結果を外部ツール（Excel・会計ソフト）に取り込める形式へ変換する。
+
+use crate::classify::{classify_account, AccountCategoryRule};
+use crate::money::{minor_unit_exponent, sum_by_currency, DEFAULT_CURRENCY};
+use crate::providers::{OcrResult, ReceiptData};
+use chrono::NaiveDate;
+use encoding_rs::SHIFT_JIS;
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// UTF-8 BOM（Excelで開いたときの文字化けを防ぐ）
+pub const UTF8_BOM: &str = "\u{feff}";
+
+/// CSVの1セルをエスケープする
+///
+/// カンマ・ダブルクォート・改行を含む場合のみダブルクォートで囲む。
+pub fn escape_csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// セル列をCSVの1行（CRLF終端）にする
+pub fn csv_line<S: AsRef<str>>(fields: &[S]) -> String {
+    let line = fields
+        .iter()
+        .map(|field| escape_csv_field(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{}\r\n", line)
+}
+
+/// バッチOCRの結果をCSV文字列（UTF-8 BOM付き）にする
+///
+/// 失敗時は `data` が無いため、ファイル名は結果と組で受け取る。
+pub fn batch_results_csv<'a>(rows: impl IntoIterator<Item = (&'a str, &'a OcrResult)>) -> String {
+    let mut csv = String::from(UTF8_BOM);
+    csv.push_str(&csv_line(&[
+        "file",
+        "status",
+        "date",
+        "merchant",
+        "amount",
+        "currency",
+        "receiver_name",
+        "error",
+    ]));
+
+    for (file_name, result) in rows {
+        let status = if result.success {
+            "success"
+        } else if result.skipped {
+            "skipped"
+        } else {
+            "error"
+        };
+        let data = result.data.as_ref();
+
+        csv.push_str(&csv_line(&[
+            file_name.to_string(),
+            status.to_string(),
+            data.and_then(|d| d.date.clone()).unwrap_or_default(),
+            data.and_then(|d| d.merchant.clone()).unwrap_or_default(),
+            data.and_then(|d| d.amount)
+                .map(|amount| amount.to_string())
+                .unwrap_or_default(),
+            data.and_then(|d| d.currency.as_ref())
+                .map(|currency| currency.to_string())
+                .unwrap_or_default(),
+            data.and_then(|d| d.receiver_name.clone())
+                .unwrap_or_default(),
+            result.error.clone().unwrap_or_default(),
+        ]));
+    }
+
+    csv
+}
+
+/// 月別サマリーのレシートをCSV文字列（UTF-8 BOM付き）にする
+///
+/// 日付・金額などが無い項目は空セルにする。
+pub fn month_summary_csv(receipts: &[ReceiptData]) -> String {
+    let mut csv = String::from(UTF8_BOM);
+    csv.push_str(&csv_line(&[
+        "file",
+        "date",
+        "merchant",
+        "amount",
+        "currency",
+        "receiver_name",
+    ]));
+
+    for receipt in receipts {
+        csv.push_str(&csv_line(&[
+            receipt.file.clone(),
+            receipt.date.clone().unwrap_or_default(),
+            receipt.merchant.clone().unwrap_or_default(),
+            receipt
+                .amount
+                .map(|amount| amount.to_string())
+                .unwrap_or_default(),
+            receipt
+                .currency
+                .as_ref()
+                .map(|currency| currency.to_string())
+                .unwrap_or_default(),
+            receipt.receiver_name.clone().unwrap_or_default(),
+        ]));
+    }
+
+    csv
+}
+
+/// 勘定科目が判定できない行に入れる科目（取り込み後に手直しする）
+pub const UNCLASSIFIED_ACCOUNT: &str = "未分類";
+
+/// マネーフォワードの仕訳の貸方勘定科目の既定値
+pub const DEFAULT_CREDIT_ACCOUNT: &str = "未払金";
+
+/// CSVの文字コード
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CsvEncoding {
+    /// UTF-8（BOM付き）
+    #[default]
+    Utf8,
+    /// Shift_JIS（BOMなし。表せない文字は `?` にする）
+    ShiftJis,
+}
+
+/// CSV文字列を指定の文字コードのバイト列にする
+///
+/// `csv` は BOM を含まない本文とし、UTF-8 のときだけ BOM を付ける。
+pub fn encode_csv(csv: &str, encoding: CsvEncoding) -> Vec<u8> {
+    match encoding {
+        CsvEncoding::Utf8 => format!("{}{}", UTF8_BOM, csv).into_bytes(),
+        CsvEncoding::ShiftJis => {
+            let mut bytes = Vec::with_capacity(csv.len());
+            let mut buf = [0u8; 4];
+            for c in csv.chars() {
+                let (encoded, _, had_errors) = SHIFT_JIS.encode(c.encode_utf8(&mut buf));
+                if had_errors {
+                    bytes.push(b'?');
+                } else {
+                    bytes.extend_from_slice(&encoded);
+                }
+            }
+            bytes
+        }
+    }
+}
+
+/// エクスポートから除外した行
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedRow {
+    pub file: String,
+    /// 除外した理由（日本語）
+    pub reason: String,
+}
+
+/// 円以外の通貨のレシートを除外する理由（会計ソフトには円として取り込まれるため書き出さない）
+fn non_yen_reason(receipt: &ReceiptData) -> Option<String> {
+    receipt
+        .currency
+        .as_deref()
+        .filter(|currency| *currency != "JPY")
+        .map(|currency| format!("円以外の通貨のため書き出しません: {}", currency))
+}
+
+/// 会計ソフトに取り込むのに必要な取引日と金額（無い・読めない場合は除外する理由を返す）
+fn date_and_amount(receipt: &ReceiptData) -> Result<(NaiveDate, String), String> {
+    let date = match receipt.date.as_deref() {
+        None => return Err("取引日がありません".to_string()),
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("取引日を読み取れません: {}", date))?,
+    };
+    let amount = receipt
+        .amount
+        .map(|amount| amount.to_string())
+        .ok_or_else(|| "金額がありません".to_string())?;
+    Ok((date, amount))
+}
+
+/// レシートの勘定科目（設定済みのものを優先し、無ければ勘定科目ルールで判定。どちらも無ければ「未分類」）
+fn account_for(receipt: &ReceiptData, rules: &[AccountCategoryRule]) -> String {
+    receipt
+        .category
+        .clone()
+        .filter(|category| !category.trim().is_empty())
+        .or_else(|| {
+            receipt
+                .merchant
+                .as_deref()
+                .and_then(|merchant| classify_account(merchant, rules))
+                .map(|matched| matched.category)
+        })
+        .unwrap_or_else(|| UNCLASSIFIED_ACCOUNT.to_string())
+}
+
+/// freee会計の取引明細CSVにする（UTF-8 BOM付き）
+///
+/// 列は「収支」「決済日」「勘定科目」「金額」「備考」。レシートは全て支出とし、金額は税込合計、
+/// 備考は店舗名。勘定科目はレシートに設定済みのものを優先し、無ければ勘定科目ルールで判定する。
+/// 決済日が無い・読めない、金額が無い、または円以外の通貨のレシートは除外し、理由とともに返す。
+pub fn freee_csv(
+    receipts: &[ReceiptData],
+    rules: &[AccountCategoryRule],
+) -> (String, Vec<SkippedRow>) {
+    let mut csv = String::from(UTF8_BOM);
+    csv.push_str(&csv_line(&["収支", "決済日", "勘定科目", "金額", "備考"]));
+    let mut skipped = Vec::new();
+
+    for receipt in receipts {
+        let (date, amount) = match date_and_amount(receipt) {
+            Ok(row) => row,
+            Err(reason) => {
+                skipped.push(SkippedRow {
+                    file: receipt.file.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+        if let Some(reason) = non_yen_reason(receipt) {
+            skipped.push(SkippedRow {
+                file: receipt.file.clone(),
+                reason,
+            });
+            continue;
+        }
+
+        csv.push_str(&csv_line(&[
+            "支出".to_string(),
+            date.format("%Y/%m/%d").to_string(),
+            account_for(receipt, rules),
+            amount,
+            receipt.merchant.clone().unwrap_or_default(),
+        ]));
+    }
+
+    (csv, skipped)
+}
+
+/// マネーフォワード クラウド会計の仕訳インポートCSVにする（BOMなしの本文）
+///
+/// 列は「取引No」「取引日」「借方勘定科目」「借方金額」「貸方勘定科目」「貸方金額」「摘要」。
+/// 借方は勘定科目ルールで判定し、貸方は `credit_account`。金額は税込合計、摘要は店舗名。
+/// 取引日が無い・読めない、金額が無い、または円以外の通貨のレシートは除外し、理由とともに返す。
+/// 文字コードは `encode_csv` で選ぶ。
+pub fn moneyforward_csv(
+    receipts: &[ReceiptData],
+    rules: &[AccountCategoryRule],
+    credit_account: &str,
+) -> (String, Vec<SkippedRow>) {
+    let mut csv = csv_line(&[
+        "取引No",
+        "取引日",
+        "借方勘定科目",
+        "借方金額",
+        "貸方勘定科目",
+        "貸方金額",
+        "摘要",
+    ]);
+    let mut skipped = Vec::new();
+    let mut number = 0;
+
+    for receipt in receipts {
+        let (date, amount) = match date_and_amount(receipt) {
+            Ok(row) => row,
+            Err(reason) => {
+                skipped.push(SkippedRow {
+                    file: receipt.file.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+        if let Some(reason) = non_yen_reason(receipt) {
+            skipped.push(SkippedRow {
+                file: receipt.file.clone(),
+                reason,
+            });
+            continue;
+        }
+        number += 1;
+
+        csv.push_str(&csv_line(&[
+            number.to_string(),
+            date.format("%Y/%m/%d").to_string(),
+            account_for(receipt, rules),
+            amount.clone(),
+            credit_account.to_string(),
+            amount,
+            receipt.merchant.clone().unwrap_or_default(),
+        ]));
+    }
+
+    (csv, skipped)
+}
+
+/// 月別サマリーExcelの見出し
+const SUMMARY_XLSX_HEADERS: &[&str] = &["日付", "店舗", "金額", "通貨", "宛名"];
+
+/// 通貨の最小単位に合わせた金額の表示形式（`#,##0`・`#,##0.00` など）
+fn amount_format(currency: &str) -> Format {
+    let exponent = minor_unit_exponent(currency) as usize;
+    let format = if exponent == 0 {
+        "#,##0".to_string()
+    } else {
+        format!("#,##0.{}", "0".repeat(exponent))
+    };
+    Format::new().set_num_format(format)
+}
+
+/// 月別サマリーのExcel（xlsx）を作る
+///
+/// 1レシート1行で日付・店舗・金額・通貨・宛名を並べ、末尾に通貨ごとの合計行を入れる。
+/// 金額は通貨記号なしの数値セル。通貨が無いレシートは既定の通貨（JPY）として合計する。
+pub fn month_summary_xlsx(receipts: &[ReceiptData]) -> Result<Vec<u8>, String> {
+    let build = || -> Result<Vec<u8>, XlsxError> {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        let bold = Format::new().set_bold();
+
+        for (col, header) in SUMMARY_XLSX_HEADERS.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *header, &bold)?;
+        }
+        sheet.set_column_width(0, 12)?;
+        sheet.set_column_width(1, 30)?;
+        sheet.set_column_width(2, 12)?;
+        sheet.set_column_width(4, 20)?;
+
+        let mut row = 1;
+        for receipt in receipts {
+            let currency = receipt.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+            sheet.write_string(row, 0, receipt.date.as_deref().unwrap_or_default())?;
+            sheet.write_string(row, 1, receipt.merchant.as_deref().unwrap_or_default())?;
+            if let Some(amount) = receipt.amount {
+                sheet.write_number_with_format(row, 2, amount, &amount_format(currency))?;
+            }
+            sheet.write_string(row, 3, currency)?;
+            sheet.write_string(row, 4, receipt.receiver_name.as_deref().unwrap_or_default())?;
+            row += 1;
+        }
+
+        for total in sum_by_currency(receipts) {
+            let format = amount_format(&total.currency).set_bold();
+            sheet.write_string_with_format(row, 0, "合計", &bold)?;
+            sheet.write_number_with_format(row, 2, total.amount, &format)?;
+            sheet.write_string_with_format(row, 3, &total.currency, &bold)?;
+            row += 1;
+        }
+
+        workbook.save_to_buffer()
+    };
+    build().map_err(|e| format!("Excelの作成に失敗しました: {}", e))
+}
+
+/// snake_case のフィールド名をシリアライズ時の camelCase に揃える
+fn to_camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut upper_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper_next = true;
+        } else if upper_next {
+            result.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// JSONオブジェクトから指定フィールドのみを取り出す
+///
+/// フィールドはドット記法でネストを指定できる（例: `confidence.amount`）。
+/// 取り出した値は元と同じネスト構造で格納する。存在しないフィールドは無視する。
+pub fn project_fields(value: &Value, fields: &[String]) -> Value {
+    let mut projected = Map::new();
+
+    for field in fields {
+        let path: Vec<String> = field.split('.').map(to_camel_case).collect();
+
+        let Some(found) = path
+            .iter()
+            .try_fold(value, |current, key| current.get(key.as_str()))
+        else {
+            continue;
+        };
+
+        insert_at_path(&mut projected, &path, found.clone());
+    }
+
+    Value::Object(projected)
+}
+
+/// 途中の階層を作りながらパスの末端に値を置く
+fn insert_at_path(target: &mut Map<String, Value>, path: &[String], value: Value) {
+    match path {
+        [] => {}
+        [last] => {
+            target.insert(last.clone(), value);
+        }
+        [key, rest @ ..] => {
+            let entry = target
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(map) = entry {
+                insert_at_path(map, rest, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_fields_keeps_requested_paths_and_ignores_unknown() {
+        let value = serde_json::json!({
+            "file": "a.jpg",
+            "merchant": "ローソン",
+            "receiverName": "山田",
+            "confidence": { "amount": 0.9, "date": 0.5 },
+        });
+        let fields = [
+            "file",
+            "receiver_name",
+            "confidence.amount",
+            "unknown",
+            "file.x",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            project_fields(&value, &fields),
+            serde_json::json!({
+                "file": "a.jpg",
+                "receiverName": "山田",
+                "confidence": { "amount": 0.9 },
+            })
+        );
+    }
+
+    #[test]
+    fn month_summary_csv_escapes_merchants_and_leaves_missing_dates_empty() {
+        let mut receipt = ReceiptData::new("a.jpg".to_string());
+        receipt.merchant = Some("カフェ \"A,B\"".to_string());
+        receipt.amount = Some(1080.0);
+        receipt.currency = crate::money::Currency::parse("JPY");
+
+        assert_eq!(
+            month_summary_csv(&[receipt]),
+            "\u{feff}file,date,merchant,amount,currency,receiver_name\r\n\
+             a.jpg,,\"カフェ \"\"A,B\"\"\",1080,JPY,\r\n"
+        );
+    }
+
+    #[test]
+    fn freee_csv_classifies_accounts_and_formats_dates() {
+        let rules: Vec<AccountCategoryRule> = serde_json::from_value(serde_json::json!([
+            { "pattern": "ローソン", "accountCategory": "消耗品費" }
+        ]))
+        .unwrap();
+        let receipt = |merchant: &str, category: Option<&str>| {
+            let mut data = ReceiptData::new(format!("{}.jpg", merchant));
+            data.merchant = Some(merchant.to_string());
+            data.date = Some("2025-01-06".to_string());
+            data.amount = Some(1080.0);
+            data.category = category.map(str::to_string);
+            data
+        };
+
+        let mut dollars = receipt("Starbucks", None);
+        dollars.currency = crate::money::Currency::parse("USD");
+        let mut undated = receipt("ファミリーマート", None);
+        undated.date = None;
+        let mut misdated = receipt("セブン", None);
+        misdated.date = Some("R7.1.6".to_string());
+        let mut no_amount = receipt("ドトール", None);
+        no_amount.amount = None;
+
+        let (csv, skipped) = freee_csv(
+            &[
+                receipt("ローソン 渋谷店", None),
+                receipt("ローソン", Some("会議費")),
+                receipt("喫茶 A", None),
+                dollars,
+                undated,
+                misdated,
+                no_amount,
+            ],
+            &rules,
+        );
+        assert_eq!(
+            csv,
+            "\u{feff}収支,決済日,勘定科目,金額,備考\r\n\
+             支出,2025/01/06,消耗品費,1080,ローソン 渋谷店\r\n\
+             支出,2025/01/06,会議費,1080,ローソン\r\n\
+             支出,2025/01/06,未分類,1080,喫茶 A\r\n"
+        );
+        assert_eq!(
+            skipped,
+            vec![
+                SkippedRow {
+                    file: "Starbucks.jpg".to_string(),
+                    reason: "円以外の通貨のため書き出しません: USD".to_string(),
+                },
+                SkippedRow {
+                    file: "ファミリーマート.jpg".to_string(),
+                    reason: "取引日がありません".to_string(),
+                },
+                SkippedRow {
+                    file: "セブン.jpg".to_string(),
+                    reason: "取引日を読み取れません: R7.1.6".to_string(),
+                },
+                SkippedRow {
+                    file: "ドトール.jpg".to_string(),
+                    reason: "金額がありません".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn moneyforward_csv_skips_incomplete_rows_and_encodes_shift_jis() {
+        let mut dated = ReceiptData::new("a.jpg".to_string());
+        dated.merchant = Some("ローソン".to_string());
+        dated.date = Some("2025-01-06".to_string());
+        dated.amount = Some(1080.0);
+        dated.category = Some("消耗品費".to_string());
+
+        let mut no_amount = dated.clone();
+        no_amount.file = "c.jpg".to_string();
+        no_amount.amount = None;
+        let mut euros = dated.clone();
+        euros.file = "d.jpg".to_string();
+        euros.currency = crate::money::Currency::parse("EUR");
+
+        let (csv, skipped) = moneyforward_csv(
+            &[
+                ReceiptData::new("b.jpg".to_string()),
+                dated,
+                no_amount,
+                euros,
+            ],
+            &[],
+            DEFAULT_CREDIT_ACCOUNT,
+        );
+        assert_eq!(
+            csv,
+            "取引No,取引日,借方勘定科目,借方金額,貸方勘定科目,貸方金額,摘要\r\n\
+             1,2025/01/06,消耗品費,1080,未払金,1080,ローソン\r\n"
+        );
+        assert_eq!(
+            skipped,
+            vec![
+                SkippedRow {
+                    file: "b.jpg".to_string(),
+                    reason: "取引日がありません".to_string(),
+                },
+                SkippedRow {
+                    file: "c.jpg".to_string(),
+                    reason: "金額がありません".to_string(),
+                },
+                SkippedRow {
+                    file: "d.jpg".to_string(),
+                    reason: "円以外の通貨のため書き出しません: EUR".to_string(),
+                },
+            ]
+        );
+
+        assert_eq!(encode_csv("店", CsvEncoding::ShiftJis), [0x93, 0x58]);
+        assert_eq!(encode_csv("a😀", CsvEncoding::ShiftJis), b"a?");
+        assert!(encode_csv("店", CsvEncoding::Utf8).starts_with(UTF8_BOM.as_bytes()));
+    }
+
+    #[test]
+    fn month_summary_xlsx_writes_a_workbook() {
+        let mut lawson = ReceiptData::new("a.jpg".to_string());
+        lawson.merchant = Some("ローソン".to_string());
+        lawson.amount = Some(1080.0);
+        let mut cafe = ReceiptData::new("b.jpg".to_string());
+        cafe.amount = Some(4.5);
+        cafe.currency = crate::money::Currency::parse("USD");
+
+        let xlsx =
+            month_summary_xlsx(&[lawson, cafe, ReceiptData::new("c.pdf".to_string())]).unwrap();
+        // xlsx は ZIP コンテナ
+        assert!(xlsx.starts_with(b"PK"));
+        assert_eq!(amount_format("JPY"), Format::new().set_num_format("#,##0"));
+        assert_eq!(
+            amount_format("USD"),
+            Format::new().set_num_format("#,##0.00")
+        );
+    }
+
+    #[test]
+    fn escape_csv_field_quotes_only_when_needed() {
+        assert_eq!(escape_csv_field("ローソン"), "ローソン");
+        assert_eq!(escape_csv_field("A,B"), "\"A,B\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv_field("line\nbreak"), "\"line\nbreak\"");
+    }
+}
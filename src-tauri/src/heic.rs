@@ -0,0 +1,74 @@
+//! HEIC 画像の JPEG への変換
+//!
+//! iPhone で撮影した HEIC・HEIF は Document AI などが受け付けないことがあるため、
+//! OCR に送る前に JPEG にデコードする。デコードは libheif の `heif-convert`（PATH 上のもの）に
+//! 任せ、入出力の一時ファイルは変換後に必ず削除する。
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 変換後の JPEG の品質（0〜100）
+const JPEG_QUALITY: &str = "90";
+
+/// 一時ファイル名の連番（同時に変換しても衝突させない）
+static TEMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 一時ファイルのパス（拡張子で `heif-convert` の出力形式が決まる）
+fn temp_path(seq: u64, extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "torifune-heic-{}-{}.{}",
+        std::process::id(),
+        seq,
+        extension
+    ))
+}
+
+/// HEIC・HEIF 画像を JPEG に変換する（複数画像を含む場合は主画像）
+pub fn convert_heic_to_jpeg(content: &[u8]) -> Result<Vec<u8>, String> {
+    let seq = TEMP_FILE_SEQ.fetch_add(1, Ordering::Relaxed);
+    let input = temp_path(seq, "heic");
+    let output = temp_path(seq, "jpg");
+
+    let converted = std::fs::write(&input, content)
+        .map_err(|e| format!("一時ファイルの作成に失敗しました: {}", e))
+        .and_then(|()| match (input.to_str(), output.to_str()) {
+            (Some(input), Some(output)) => {
+                crate::video::run("heif-convert", &["-q", JPEG_QUALITY, input, output])
+            }
+            _ => Err("パスの変換に失敗しました".to_string()),
+        })
+        .and_then(|_| {
+            std::fs::read(&output)
+                .map_err(|e| format!("変換後のファイルの読み込みに失敗しました: {}", e))
+        });
+    let _ = std::fs::remove_file(&input);
+    let _ = std::fs::remove_file(&output);
+
+    converted.map_err(|e| format!("HEIC 画像をJPEGに変換できませんでした: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heic_brands_are_detected_for_conversion() {
+        for brand in [&b"heic"[..], b"heix", b"mif1"] {
+            let mut header = b"\0\0\0\x18ftyp".to_vec();
+            header.extend_from_slice(brand);
+            assert!(crate::mime::is_heic("", &header));
+            assert!(crate::mime::is_heic("application/octet-stream", &header));
+        }
+        assert!(crate::mime::is_heic("image/heif", b""));
+        assert!(!crate::mime::is_heic("image/jpeg", b""));
+    }
+
+    #[test]
+    fn conversion_failure_is_reported_and_cleans_up() {
+        let seq = TEMP_FILE_SEQ.load(Ordering::Relaxed);
+        let error = convert_heic_to_jpeg(b"not a heic image").unwrap_err();
+        assert!(error.starts_with("HEIC 画像をJPEGに変換できませんでした"));
+        assert!(!temp_path(seq, "heic").exists());
+        assert!(!temp_path(seq, "jpg").exists());
+    }
+}
@@ -0,0 +1,131 @@
+//! レシート画像の手ブレ・ボケの検知
+//!
+//! グレースケールにした画像のラプラシアン（4近傍）の分散を鮮鋭度のスコアとし、
+//! 閾値未満なら再撮影を促す。ボケた画像は輪郭が緩やかになり、分散が小さくなる。
+//! 解像度でスコアが変わらないよう、長辺を揃えてから計算する。PDF は対象外。
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageReader};
+use serde::Serialize;
+use std::io::Cursor;
+
+/// これ未満のスコアはボケ・手ブレとみなす
+pub const SHARPNESS_THRESHOLD: f64 = 100.0;
+
+/// スコアを計算する前に揃える長辺（これより小さい画像はそのまま）
+const ANALYSIS_DIMENSION: u32 = 1000;
+
+/// 画質の判定結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityReport {
+    /// 文字が読める程度に鮮明か
+    pub sharp: bool,
+    /// 鮮鋭度のスコア（ラプラシアンの分散。大きいほど鮮明）
+    pub score: f64,
+    /// 鮮明でない場合の対処の案内
+    pub suggestion: Option<String>,
+}
+
+impl QualityReport {
+    fn from_score(score: f64) -> Self {
+        let sharp = score >= SHARPNESS_THRESHOLD;
+        Self {
+            sharp,
+            score,
+            suggestion: (!sharp).then(|| {
+                "画像がボケているか手ブレしています。明るい場所でピントを合わせて再撮影してください"
+                    .to_string()
+            }),
+        }
+    }
+
+    /// 結果の warnings に残す文言
+    pub fn warning(&self) -> String {
+        format!(
+            "画像が不鮮明なため読み取りを誤っている可能性があります（鮮鋭度 {:.0}、基準 {:.0}）",
+            self.score, SHARPNESS_THRESHOLD
+        )
+    }
+}
+
+/// 画像の鮮鋭度を判定する（画像として読めない形式は `None`）
+///
+/// EXIF の向きは判定に影響しないため適用しない。TIFF は先頭ページのみ。
+pub fn assess_image_quality(content: &[u8]) -> image::ImageResult<Option<QualityReport>> {
+    let reader = ImageReader::new(Cursor::new(content)).with_guessed_format()?;
+    if reader.format().is_none() {
+        return Ok(None);
+    }
+    let decoder = reader.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    let image = DynamicImage::from_decoder(decoder)?;
+    let image = if width.max(height) > ANALYSIS_DIMENSION {
+        image.resize(ANALYSIS_DIMENSION, ANALYSIS_DIMENSION, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    Ok(Some(QualityReport::from_score(laplacian_variance(
+        &image.to_luma8(),
+    ))))
+}
+
+/// ラプラシアン（4近傍）の分散（端の画素は除く。3×3 未満の画像は 0）
+fn laplacian_variance(image: &GrayImage) -> f64 {
+    let (width, height) = image.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let at = |x: u32, y: u32| f64::from(image.get_pixel(x, y)[0]);
+    let mut count = 0.0;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian =
+                at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y);
+            count += 1.0;
+            sum += laplacian;
+            sum_sq += laplacian * laplacian;
+        }
+    }
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Luma};
+
+    fn png(image: GrayImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        DynamicImage::ImageLuma8(image)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn blurred_image_is_reported_as_not_sharp() {
+        let checkerboard = GrayImage::from_fn(200, 200, |x, y| {
+            Luma([if (x / 8 + y / 8) % 2 == 0 { 0 } else { 255 }])
+        });
+        let sharp = assess_image_quality(&png(checkerboard.clone()))
+            .unwrap()
+            .unwrap();
+        assert!(sharp.sharp);
+        assert_eq!(sharp.suggestion, None);
+
+        let blurred = assess_image_quality(&png(image::imageops::blur(&checkerboard, 8.0)))
+            .unwrap()
+            .unwrap();
+        assert!(!blurred.sharp);
+        assert!(blurred.score < sharp.score);
+        assert!(blurred.suggestion.is_some());
+
+        assert_eq!(assess_image_quality(b"%PDF-1.7").unwrap(), None);
+    }
+}
@@ -0,0 +1,83 @@
+//! 処理中ファイル管理
+//!
+//! 同じファイルが複数のOCR処理（単発・バッチ）に同時投入されるのを防ぐ。
+//! 二重処理はそのままAPIの二重課金につながるため、処理中のパスを記録しておく。
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// 処理中ファイルのパス集合
+#[derive(Default)]
+pub struct InFlightFiles {
+    paths: Mutex<HashSet<String>>,
+}
+
+impl InFlightFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// パス集合のロックを取得（ポイズン状態でも中身は有効なので継続する）
+    fn lock_paths(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.paths.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// ファイルを処理中として登録する
+    ///
+    /// 既に処理中の場合は `None` を返す。返されたガードがドロップされると
+    /// （パニックによる巻き戻し時も含めて）登録が解除される。
+    pub fn try_acquire(self: &Arc<Self>, file_path: &str) -> Option<InFlightGuard> {
+        if !self.lock_paths().insert(file_path.to_string()) {
+            return None;
+        }
+
+        Some(InFlightGuard {
+            files: Arc::clone(self),
+            file_path: file_path.to_string(),
+        })
+    }
+}
+
+/// 処理中登録のガード
+pub struct InFlightGuard {
+    files: Arc<InFlightFiles>,
+    file_path: String,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.files.lock_paths().remove(&self.file_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_acquire_rejects_duplicates_until_guard_is_dropped() {
+        let files = Arc::new(InFlightFiles::new());
+
+        let guard = files.try_acquire("/tmp/receipt.jpg");
+        assert!(guard.is_some());
+        assert!(files.try_acquire("/tmp/receipt.jpg").is_none());
+        assert!(files.try_acquire("/tmp/other.jpg").is_some());
+
+        drop(guard);
+        assert!(files.try_acquire("/tmp/receipt.jpg").is_some());
+    }
+
+    #[test]
+    fn guard_is_released_on_panic() {
+        let files = Arc::new(InFlightFiles::new());
+
+        let files_for_panic = Arc::clone(&files);
+        let result = std::panic::catch_unwind(move || {
+            let _guard = files_for_panic.try_acquire("/tmp/receipt.jpg");
+            panic!("OCR処理中のパニック");
+        });
+
+        assert!(result.is_err());
+        assert!(files.try_acquire("/tmp/receipt.jpg").is_some());
+    }
+}
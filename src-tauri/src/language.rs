@@ -0,0 +1,467 @@
+//! レシートの言語判定と言語別の日付・金額の解釈
+//!
+//! OCR全文（無ければ店舗名）の文字種と頻出語から言語を推定し、日付の並び順
+//! （年月日・月日年・日月年）と金額の小数点記号（`.` / `,`）を切り替える。
+//! 判定は軽量なヒューリスティックで、言語コードは ISO 639-1（`ja`・`en` など）。
+
+use chrono::{FixedOffset, TimeZone};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// ラテン文字の言語ごとの頻出語（小文字）
+const LATIN_KEYWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "total", "subtotal", "tax", "thank", "you", "receipt", "and", "change",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "und", "der", "die", "summe", "gesamt", "danke", "mwst", "quittung", "betrag",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "merci", "le", "la", "et", "tva", "reçu", "montant", "total", "ticket",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "gracias", "el", "la", "y", "iva", "recibo", "importe", "total", "ticket",
+        ],
+    ),
+];
+
+/// ラテン文字の言語ごとに特有の文字
+const LATIN_SPECIAL_CHARS: &[(&str, &[char])] = &[
+    ("de", &['ä', 'ö', 'ü', 'ß']),
+    ("fr", &['é', 'è', 'ê', 'ç', 'à', 'œ']),
+    ("es", &['ñ', '¿', '¡', 'á', 'í', 'ó', 'ú']),
+];
+
+/// 簡体字の中国語に特有の表記（漢字のみの文字列を日本語と区別する）
+const CHINESE_MARKERS: &[&str] = &["发票", "收据", "合计", "金额", "增值税", "的", "号"];
+
+/// 小数点に `,` を使う言語
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &["de", "fr", "es", "it", "pt", "nl"];
+
+/// 日付の並び順
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateOrder {
+    /// 年月日（日本語・中国語・韓国語）
+    Ymd,
+    /// 月日年（英語）
+    Mdy,
+    /// 日月年（欧州の言語・不明）
+    Dmy,
+}
+
+fn date_order(language: Option<&str>) -> DateOrder {
+    match language {
+        Some("ja" | "zh" | "ko") => DateOrder::Ymd,
+        Some("en") => DateOrder::Mdy,
+        _ => DateOrder::Dmy,
+    }
+}
+
+/// 年が先頭の日付（`2025年1月5日`・`2025/01/05`・`2025.1.5`）
+static YEAR_FIRST_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d{4})\s*[年/.\-]\s*(\d{1,2})\s*[月/.\-]\s*(\d{1,2})").unwrap());
+
+/// 数字3つの日付（`01/05/25`・`5.1.2025` など。並び順は言語で決める）
+static NUMERIC_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d{1,2})[/.\-](\d{1,2})[/.\-](\d{2}|\d{4})\b").unwrap());
+
+/// 英語の月名を含む日付（`Jan 5, 2025`・`5 January 2025`）
+static MONTH_NAME_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:(\d{1,2})\s+)?(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(?:(\d{1,2}),?\s+)?(\d{4})",
+    )
+    .unwrap()
+});
+
+/// 時刻（`14:32`・`14:32:05`・`午後2:32`・`2:32 PM`・`14時32分`）
+static TIME_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(午前|午後)?\s*(\d{1,2})\s*[:：時]\s*(\d{1,2})(?:\s*[:：分]\s*(\d{1,2}))?\s*[分秒]?\s*(am|pm|a\.m\.|p\.m\.)?",
+    )
+    .unwrap()
+});
+
+/// 時刻の直後の UTC オフセット（`Z`・`UTC`・`+09:00`・`-0500`）
+///
+/// 1 は秒まである時刻か ISO 形式の `T` 付きの時刻、2 は `HH:MM` の時刻。
+static UTC_OFFSET_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:(\d{1,2}:\d{2}:\d{2}(?:\.\d+)?|T\d{2}:\d{2})|(\d{1,2}:\d{2}))\s*(Z|UTC|GMT|([+-])(\d{2}):?(\d{2}))\s*$",
+    )
+    .unwrap()
+});
+
+/// 単独の UTC オフセット（設定のタイムゾーン `+09:00`・`-0500`）
+static OFFSET_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([+-])(\d{2}):?(\d{2})$").unwrap());
+
+/// UTC オフセットの時間の上限（`+14:00`）
+const MAX_OFFSET_HOURS: i32 = 14;
+
+/// 文字種と頻出語から言語を推定する（文字が無い場合は `None`）
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let (mut kana, mut hangul, mut han, mut latin) = (0, 0, 0, 0);
+    for c in text.chars() {
+        match c {
+            '\u{3040}'..='\u{30ff}' | '\u{ff66}'..='\u{ff9f}' => kana += 1,
+            '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}' => hangul += 1,
+            '\u{4e00}'..='\u{9fff}' => han += 1,
+            c if c.is_alphabetic() => latin += 1,
+            _ => {}
+        }
+    }
+
+    if kana > 0 {
+        return Some("ja");
+    }
+    if hangul > 0 {
+        return Some("ko");
+    }
+    if han > 0 {
+        // 漢字のみは日本語とみなし、簡体字特有の表記があれば中国語とする
+        let chinese = CHINESE_MARKERS.iter().any(|marker| text.contains(marker));
+        return Some(if chinese { "zh" } else { "ja" });
+    }
+    if latin == 0 {
+        return None;
+    }
+
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let score = |language: &str| {
+        let keywords = LATIN_KEYWORDS
+            .iter()
+            .find(|(l, _)| *l == language)
+            .map_or(0, |(_, keywords)| {
+                words.iter().filter(|w| keywords.contains(w)).count()
+            });
+        let special = LATIN_SPECIAL_CHARS
+            .iter()
+            .find(|(l, _)| *l == language)
+            .map_or(0, |(_, chars)| {
+                lower.chars().filter(|c| chars.contains(c)).count()
+            });
+        keywords + special * 2
+    };
+
+    // 手がかりが無ければ英語とみなす（同点は先に挙げた言語を優先）
+    let mut best = ("en", 0);
+    for (language, _) in LATIN_KEYWORDS {
+        let score = score(language);
+        if score > best.1 {
+            best = (language, score);
+        }
+    }
+    Some(best.0)
+}
+
+fn to_date(year: &str, month: &str, day: &str) -> Option<String> {
+    let mut year: i32 = year.parse().ok()?;
+    if year < 100 {
+        year += 2000;
+    }
+    let date = chrono::NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let name = name.to_lowercase();
+    MONTHS.iter().position(|m| *m == name).map(|i| i as u32 + 1)
+}
+
+/// OCRの日付文字列を言語の並び順に従って `YYYY-MM-DD` にする（解釈できなければ `None`）
+///
+/// 並び順どおりでは存在しない日付になる場合（英語で `25/12/2025` など）は月と日を入れ替えて解釈する。
+pub fn normalize_date(text: &str, language: Option<&str>) -> Option<String> {
+    let text = text.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+
+    if let Some(caps) = YEAR_FIRST_PATTERN.captures(text) {
+        return to_date(&caps[1], &caps[2], &caps[3]);
+    }
+
+    if let Some(caps) = NUMERIC_PATTERN.captures(text) {
+        let (a, b, c) = (&caps[1], &caps[2], &caps[3]);
+        return match date_order(language) {
+            // 2桁の年が先頭（`25/01/05`）
+            DateOrder::Ymd => to_date(a, b, c),
+            DateOrder::Mdy => to_date(c, a, b).or_else(|| to_date(c, b, a)),
+            DateOrder::Dmy => to_date(c, b, a).or_else(|| to_date(c, a, b)),
+        };
+    }
+
+    if let Some(caps) = MONTH_NAME_PATTERN.captures(text) {
+        let month = month_number(&caps[2])?.to_string();
+        let day = caps.get(1).or_else(|| caps.get(3))?.as_str();
+        return to_date(&caps[4], &month, day);
+    }
+
+    None
+}
+
+/// OCRの時刻文字列を `HH:MM:SS` にする（秒が無ければ 0 秒、解釈できなければ `None`）
+///
+/// 午前・午後や AM・PM が付いている場合は 24 時間表記に直す。
+pub fn normalize_time(text: &str) -> Option<String> {
+    let caps = TIME_PATTERN.captures(text.trim())?;
+    let mut hour: u32 = caps[2].parse().ok()?;
+    let minute: u32 = caps[3].parse().ok()?;
+    let second: u32 = caps.get(4).map_or(Some(0), |s| s.as_str().parse().ok())?;
+
+    let meridiem = caps
+        .get(1)
+        .or_else(|| caps.get(5))
+        .map(|m| m.as_str().to_lowercase());
+    match meridiem.as_deref() {
+        Some("午後" | "pm" | "p.m.") if hour < 12 => hour += 12,
+        Some("午前" | "am" | "a.m.") if hour == 12 => hour = 0,
+        _ => {}
+    }
+
+    let time = chrono::NaiveTime::from_hms_opt(hour, minute, second)?;
+    Some(time.format("%H:%M:%S").to_string())
+}
+
+/// 符号と時・分から UTC オフセットを作る（時間が `MAX_OFFSET_HOURS` を超えるものは `None`）
+fn offset_from_parts(sign: &str, hours: &str, minutes: &str) -> Option<FixedOffset> {
+    let hours = hours.parse::<i32>().ok()?;
+    let minutes = minutes.parse::<i32>().ok()?;
+    if hours > MAX_OFFSET_HOURS || minutes >= 60 {
+        return None;
+    }
+    let secs = hours * 3600 + minutes * 60;
+    FixedOffset::east_opt(if sign == "-" { -secs } else { secs })
+}
+
+/// 設定のタイムゾーンなど単独の UTC オフセット（形式が違えば `None`）
+pub fn parse_offset(text: &str) -> Option<FixedOffset> {
+    let caps = OFFSET_PATTERN.captures(text.trim())?;
+    offset_from_parts(&caps[1], &caps[2], &caps[3])
+}
+
+/// 時刻の文字列の末尾で時刻の直後に付いた UTC オフセット（付いていなければ `None`）
+///
+/// `10:00-12:30` のような時間帯と区別するため、`-` のオフセットは秒まである時刻か
+/// `T` 付きの時刻の後ろだけで受け付ける。
+pub fn parse_utc_offset(text: &str) -> Option<FixedOffset> {
+    let caps = UTC_OFFSET_PATTERN.captures(text.trim())?;
+    let Some(sign) = caps.get(4) else {
+        return FixedOffset::east_opt(0);
+    };
+    if sign.as_str() == "-" && caps.get(2).is_some() {
+        return None;
+    }
+    offset_from_parts(sign.as_str(), &caps[5], &caps[6])
+}
+
+/// 日付（YYYY-MM-DD）と時刻を `local` のタイムゾーンの日付・時刻（HH:MM:SS）にする
+///
+/// 時刻に UTC オフセットが付いていればローカルに換算し、日をまたぐ場合は日付も前後させる。
+/// 時刻が無い場合は日付をそのまま使い、オフセットの無い時刻は現地の時刻とみなす。
+pub fn normalize_date_with_tz(
+    date: &str,
+    time: Option<&str>,
+    local: FixedOffset,
+) -> (String, Option<String>) {
+    let Some(time) = time else {
+        return (date.to_string(), None);
+    };
+    let normalized = normalize_time(time);
+    let converted = normalized.as_deref().and_then(|normalized| {
+        let source = parse_utc_offset(time)?;
+        let naive = chrono::NaiveDateTime::parse_from_str(
+            &format!("{} {}", date, normalized),
+            "%Y-%m-%d %H:%M:%S",
+        )
+        .ok()?;
+        let local_time = source
+            .from_local_datetime(&naive)
+            .single()?
+            .with_timezone(&local);
+        Some((
+            local_time.format("%Y-%m-%d").to_string(),
+            Some(local_time.format("%H:%M:%S").to_string()),
+        ))
+    });
+
+    converted.unwrap_or_else(|| (date.to_string(), normalized))
+}
+
+/// OCRの金額文字列を言語の小数点記号に従って数値にする（`1.234,56` → 1234.56）
+///
+/// `.` と `,` が両方ある場合は後ろにある方を小数点とみなす。数字より前にある `-`・`△`・`▲` だけを
+/// 負の符号とし、`¥1,080-` のような末尾の `-` は金額の締めの記号として無視する。
+pub fn parse_amount(text: &str, language: Option<&str>) -> Option<f64> {
+    let negative = text
+        .chars()
+        .take_while(|c| !c.is_ascii_digit())
+        .any(|c| matches!(c, '-' | '−' | '△' | '▲'));
+    let number: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        .collect();
+    let number = number.trim_matches(|c| c == '.' || c == ',');
+    if number.is_empty() {
+        return None;
+    }
+
+    let decimal = match (number.rfind('.'), number.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+        (Some(dot), None) => {
+            // 小数点に `,` を使う言語で `.` の後ろがちょうど3桁なら桁区切り
+            let thousands = DECIMAL_COMMA_LANGUAGES.contains(&language.unwrap_or(""))
+                && number.len() - dot - 1 == 3;
+            (!thousands).then_some('.')
+        }
+        (None, Some(comma)) => {
+            let is_decimal = DECIMAL_COMMA_LANGUAGES.contains(&language.unwrap_or(""))
+                && number.len() - comma - 1 != 3;
+            is_decimal.then_some(',')
+        }
+        (None, None) => None,
+    };
+
+    let normalized: String = number
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_ascii_digit() => Some(c),
+            c if Some(c) == decimal => Some('.'),
+            _ => None,
+        })
+        .collect();
+    let amount: f64 = normalized.parse().ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_date_with_tz_shifts_date_across_midnight() {
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(
+            normalize_date_with_tz("2025-01-05", Some("23:30:00Z"), jst),
+            ("2025-01-06".to_string(), Some("08:30:00".to_string()))
+        );
+        assert_eq!(
+            normalize_date_with_tz(
+                "2025-01-05",
+                Some("01:15+09:00"),
+                FixedOffset::east_opt(0).unwrap()
+            ),
+            ("2025-01-04".to_string(), Some("16:15:00".to_string()))
+        );
+        // オフセットの無い時刻は現地の時刻、時刻が無ければ日付のまま
+        assert_eq!(
+            normalize_date_with_tz("2025-01-05", Some("23:30"), jst),
+            ("2025-01-05".to_string(), Some("23:30:00".to_string()))
+        );
+        assert_eq!(
+            normalize_date_with_tz("2025-01-05", None, jst),
+            ("2025-01-05".to_string(), None)
+        );
+        assert_eq!(parse_offset("-0500"), FixedOffset::west_opt(5 * 3600));
+        assert_eq!(parse_offset("+15:00"), None);
+    }
+
+    #[test]
+    fn parse_utc_offset_requires_a_time_and_ignores_time_ranges() {
+        let jst = FixedOffset::east_opt(9 * 3600);
+        assert_eq!(parse_utc_offset("10:00:00+09:00"), jst);
+        assert_eq!(parse_utc_offset("2025-01-05T10:00+0900"), jst);
+        assert_eq!(
+            parse_utc_offset("10:00:00-05:00"),
+            FixedOffset::west_opt(5 * 3600)
+        );
+        assert_eq!(parse_utc_offset("10:00 UTC"), FixedOffset::east_opt(0));
+        // 時間帯・時刻の無いオフセット・範囲外のオフセットは受け付けない
+        assert_eq!(parse_utc_offset("10:00-12:30"), None);
+        assert_eq!(parse_utc_offset("+09:00"), None);
+        assert_eq!(parse_utc_offset("10:00:00+15:00"), None);
+        let jst = jst.unwrap();
+        assert_eq!(
+            normalize_date_with_tz("2025-01-05", Some("10:00-12:30"), jst).0,
+            "2025-01-05"
+        );
+    }
+
+    #[test]
+    fn normalize_time_accepts_clock_and_japanese_notations() {
+        assert_eq!(normalize_time("14:32").as_deref(), Some("14:32:00"));
+        assert_eq!(
+            normalize_time("2025/01/05 09:05:07").as_deref(),
+            Some("09:05:07")
+        );
+        assert_eq!(normalize_time("14時32分").as_deref(), Some("14:32:00"));
+        assert_eq!(normalize_time("午後2:32").as_deref(), Some("14:32:00"));
+        assert_eq!(normalize_time("12:10 AM").as_deref(), Some("00:10:00"));
+        assert_eq!(normalize_time("25:00"), None);
+        assert_eq!(normalize_time("合計 1,188"), None);
+    }
+
+    #[test]
+    fn language_switches_date_order_and_decimal_separator() {
+        assert_eq!(detect_language("ローソン 東京駅前店"), Some("ja"));
+        assert_eq!(detect_language("東京駅"), Some("ja"));
+        assert_eq!(detect_language("增值税发票 合计"), Some("zh"));
+        assert_eq!(detect_language("Vielen Dank! Summe inkl. MwSt"), Some("de"));
+        assert_eq!(detect_language("Starbucks"), Some("en"));
+        assert_eq!(detect_language("1,234"), None);
+
+        assert_eq!(
+            normalize_date("2025年1月5日(日)", Some("ja")).as_deref(),
+            Some("2025-01-05")
+        );
+        assert_eq!(
+            normalize_date("01/05/2025", Some("en")).as_deref(),
+            Some("2025-01-05")
+        );
+        assert_eq!(
+            normalize_date("01/05/2025", Some("de")).as_deref(),
+            Some("2025-05-01")
+        );
+        assert_eq!(
+            normalize_date("25/12/2025", Some("en")).as_deref(),
+            Some("2025-12-25")
+        );
+        assert_eq!(
+            normalize_date("Jan 5, 2025", Some("en")).as_deref(),
+            Some("2025-01-05")
+        );
+        assert_eq!(normalize_date("合計 1,080円", Some("ja")), None);
+
+        assert_eq!(parse_amount("¥1,080", Some("ja")), Some(1080.0));
+        assert_eq!(parse_amount("$1,234.56", Some("en")), Some(1234.56));
+        assert_eq!(parse_amount("1.234,56 €", Some("de")), Some(1234.56));
+        assert_eq!(parse_amount("12,50 EUR", Some("fr")), Some(12.5));
+        assert_eq!(parse_amount("1.234 €", Some("de")), Some(1234.0));
+    }
+
+    #[test]
+    fn parse_amount_only_treats_leading_signs_as_negative() {
+        assert_eq!(parse_amount("¥1,080-", Some("ja")), Some(1080.0));
+        assert_eq!(parse_amount("-¥1,080", Some("ja")), Some(-1080.0));
+        assert_eq!(parse_amount("△500", Some("ja")), Some(-500.0));
+        assert_eq!(parse_amount("▲ 1,200", Some("ja")), Some(-1200.0));
+    }
+}
@@ -0,0 +1,250 @@
+//! 会計ソフトの仕訳との突合
+//!
+//! 会計ソフトからエクスポートした仕訳 CSV を読み込み、OCR したレシートと日付・金額・取引先を
+//! 照合して、既に計上済みの候補を返す（二重計上の防止）。列は見出しの名前で探すため、
+//! 会計ソフトごとの列の並びの違いは問わない。
+
+use crate::language::{normalize_date, parse_amount};
+use crate::merchant_category::normalize_merchant_name;
+use crate::providers::ReceiptData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// 日付の列の見出し（先にあるものを優先）
+const DATE_HEADERS: &[&str] = &["日付", "取引日", "伝票日付", "発生日", "date"];
+/// 金額の列の見出し
+const AMOUNT_HEADERS: &[&str] = &["金額", "借方金額", "税込金額", "支出金額", "amount"];
+/// 取引先の列の見出し（無くてもよい）
+const COUNTERPARTY_HEADERS: &[&str] = &["取引先", "相手先", "摘要", "counterparty", "description"];
+
+/// 照合の許容範囲
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LedgerMatchOptions {
+    /// 日付のずれの許容日数
+    pub date_tolerance_days: i64,
+    /// 金額の差の許容額（0 なら完全一致）
+    pub amount_tolerance: f64,
+}
+
+impl Default for LedgerMatchOptions {
+    fn default() -> Self {
+        Self {
+            date_tolerance_days: 1,
+            amount_tolerance: 0.0,
+        }
+    }
+}
+
+/// 仕訳 CSV の1行
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntry {
+    /// CSV の行番号（見出しを 1 行目とする）
+    pub line: usize,
+    /// 日付（YYYY-MM-DD）
+    pub date: String,
+    pub amount: f64,
+    pub counterparty: Option<String>,
+}
+
+/// 計上済みの候補
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerCandidate {
+    #[serde(flatten)]
+    pub entry: LedgerEntry,
+    /// 取引先が店舗名と一致したか（どちらかが空なら判定できないため `true`）
+    pub counterparty_matched: bool,
+}
+
+/// レシートごとの照合結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerCheckResult {
+    /// リクエストでのレシートの位置
+    pub index: usize,
+    /// 日付・金額・取引先が一致する仕訳があるか
+    pub already_posted: bool,
+    /// 日付と金額が許容範囲内の仕訳（取引先が違うものも含む）
+    pub candidates: Vec<LedgerCandidate>,
+}
+
+/// CSV を行ごとのフィールドに分ける（引用符内の区切り・改行と `""` に対応）
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// 見出しの候補のうち最初に見つかった列の位置
+fn find_column(headers: &[String], candidates: &[&str]) -> Option<usize> {
+    candidates.iter().find_map(|candidate| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(candidate))
+    })
+}
+
+/// 仕訳 CSV を読み込む（日付・金額が読めない行は飛ばす）
+pub fn parse_ledger_csv(content: &str) -> Result<Vec<LedgerEntry>, String> {
+    let rows = parse_csv(content);
+    let Some((headers, rows)) = rows.split_first() else {
+        return Err("仕訳CSVが空です".to_string());
+    };
+    let date_column = find_column(headers, DATE_HEADERS)
+        .ok_or_else(|| "仕訳CSVに日付の列が見つかりません".to_string())?;
+    let amount_column = find_column(headers, AMOUNT_HEADERS)
+        .ok_or_else(|| "仕訳CSVに金額の列が見つかりません".to_string())?;
+    let counterparty_column = find_column(headers, COUNTERPARTY_HEADERS);
+
+    Ok(rows
+        .iter()
+        .enumerate()
+        .filter_map(|(i, row)| {
+            Some(LedgerEntry {
+                line: i + 2,
+                date: normalize_date(row.get(date_column)?, Some("ja"))?,
+                amount: parse_amount(row.get(amount_column)?, Some("ja"))?,
+                counterparty: counterparty_column
+                    .and_then(|column| row.get(column))
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty()),
+            })
+        })
+        .collect())
+}
+
+/// 店舗名と取引先が近いか（正規化した名前のどちらかがもう一方を含む）
+fn counterparty_matches(merchant: Option<&str>, counterparty: Option<&str>) -> bool {
+    let merchant = merchant.map(normalize_merchant_name).unwrap_or_default();
+    let counterparty = counterparty
+        .map(normalize_merchant_name)
+        .unwrap_or_default();
+    merchant.is_empty()
+        || counterparty.is_empty()
+        || merchant.contains(&counterparty)
+        || counterparty.contains(&merchant)
+}
+
+/// レシートを仕訳と照合する
+///
+/// 日付と金額が許容範囲内の仕訳を候補とし、取引先も一致する候補があれば計上済みとする。
+/// 日付か金額が無いレシートは照合しない。
+pub fn check_against_ledger(
+    receipts: &[ReceiptData],
+    ledger: &[LedgerEntry],
+    options: &LedgerMatchOptions,
+) -> Vec<LedgerCheckResult> {
+    let parse = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok();
+
+    receipts
+        .iter()
+        .enumerate()
+        .map(|(index, receipt)| {
+            let key = receipt.date.as_deref().and_then(parse).zip(receipt.amount);
+            let candidates: Vec<LedgerCandidate> = match key {
+                None => Vec::new(),
+                Some((date, amount)) => ledger
+                    .iter()
+                    .filter(|entry| {
+                        parse(&entry.date).is_some_and(|entry_date| {
+                            (entry_date - date).num_days().abs() <= options.date_tolerance_days
+                        }) && (entry.amount - amount).abs() <= options.amount_tolerance + 1e-9
+                    })
+                    .map(|entry| LedgerCandidate {
+                        counterparty_matched: counterparty_matches(
+                            receipt.merchant.as_deref(),
+                            entry.counterparty.as_deref(),
+                        ),
+                        entry: entry.clone(),
+                    })
+                    .collect(),
+            };
+            LedgerCheckResult {
+                index,
+                already_posted: candidates.iter().any(|c| c.counterparty_matched),
+                candidates,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipts_matching_ledger_within_tolerance_are_already_posted() {
+        let csv = "\u{feff}伝票番号,日付,借方金額,摘要\r\n\
+                   1,2025/01/06,\"1,080\",ローソン 渋谷店\r\n\
+                   2,2025/01/10,5500,\"株式会社 ABC, 会議費\"\r\n\
+                   3,不明,100,\r\n";
+        let ledger = parse_ledger_csv(csv).unwrap();
+        assert_eq!(ledger.len(), 2);
+        assert_eq!(ledger[0].date, "2025-01-06");
+        assert_eq!(ledger[0].amount, 1080.0);
+        assert_eq!(
+            ledger[1].counterparty.as_deref(),
+            Some("株式会社 ABC, 会議費")
+        );
+
+        let receipt = |merchant: &str, date: &str, amount: f64| {
+            let mut data = ReceiptData::new("a.jpg".to_string());
+            data.merchant = Some(merchant.to_string());
+            data.date = Some(date.to_string());
+            data.amount = Some(amount);
+            data
+        };
+        let receipts = [
+            receipt("ローソン", "2025-01-05", 1080.0),
+            receipt("ファミリーマート", "2025-01-06", 1080.0),
+            receipt("ローソン", "2025-01-03", 1080.0),
+            receipt("ABC", "2025-01-10", 5000.0),
+        ];
+
+        let results = check_against_ledger(&receipts, &ledger, &LedgerMatchOptions::default());
+        assert!(results[0].already_posted);
+        assert_eq!(results[0].candidates[0].entry.line, 2);
+        // 日付と金額は合うが取引先が違う
+        assert!(!results[1].already_posted);
+        assert_eq!(results[1].candidates.len(), 1);
+        assert!(results[2].candidates.is_empty());
+        assert!(!results[3].already_posted);
+
+        let loose = LedgerMatchOptions {
+            date_tolerance_days: 3,
+            amount_tolerance: 500.0,
+        };
+        let results = check_against_ledger(&receipts, &ledger, &loose);
+        assert!(results[2].already_posted);
+        assert!(results[3].already_posted);
+
+        assert!(parse_ledger_csv("日付,摘要\n2025/01/06,x\n").is_err());
+    }
+}
@@ -1,11 +1,55 @@
+mod attempts;
 mod auth;
+mod batch_progress;
+mod batch_summary;
+mod classify;
 mod commands;
+mod denchou;
+mod diff;
+mod document_number;
+mod downscale;
+mod duplicates;
+mod error;
 mod errorlog;
+mod export;
+mod heic;
+mod image_quality;
+mod inflight;
+mod language;
+mod ledger;
+mod merchant_branch;
+mod merchant_category;
+mod mime;
+mod money;
+mod notify;
+mod ocr_cache;
+mod orientation;
+mod pipeline;
+mod placeholder;
+mod postprocess;
+mod preflight;
+mod profile_watch;
 mod providers;
+mod root_index;
+mod sanitize;
+mod settings_migration;
+mod settings_recovery;
+mod shutdown;
+mod store_keys;
+mod summary;
+mod summary_excel;
+mod summary_merge;
+mod thumbnail;
+mod url_import;
+mod validation;
+mod video;
+mod webhook;
 
+use inflight::InFlightFiles;
 use providers::OcrProviderRegistry;
+use shutdown::BatchShutdown;
 use std::sync::Arc;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 use tauri_plugin_deep_link::DeepLinkExt;
 use tokio::sync::Mutex;
 
@@ -13,6 +57,10 @@ use tokio::sync::Mutex;
 pub fn run() {
     // OCRプロバイダーレジストリを初期化
     let registry = Arc::new(Mutex::new(OcrProviderRegistry::new()));
+    // 処理中ファイルの集合（同一ファイルの二重OCRを防ぐ）
+    let in_flight = Arc::new(InFlightFiles::new());
+    // 実行中のバッチ（終了時に停止させて結果を書き出す）
+    let shutdown = Arc::new(BatchShutdown::new());
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -22,7 +70,17 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(registry)
+        .manage(in_flight)
+        .manage(Arc::clone(&shutdown))
+        .manage(shutdown::BatchCancellation::default())
+        .manage(commands::ConnectionTestCache::default())
+        .manage(ocr_cache::MemoryOcrCache::default())
+        .manage(attempts::OcrAttempts::default())
+        .manage(root_index::RootIndexCache::default())
+        .manage(summary_merge::SummaryBaseCache::default())
+        .manage(profile_watch::ProfileWatcher::default())
         .setup(|app| {
             // パニックフックを設置し、パニック発生時にエラーログへ記録する
             let app_handle_for_panic = app.handle().clone();
@@ -50,6 +108,17 @@ pub fn run() {
                 );
             }));
 
+            // 共有プロファイルが設定されていれば監視を始める
+            let app_handle_for_profile = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let app = app_handle_for_profile;
+                if let Ok(settings) = commands::get_ocr_settings(app.clone()).await {
+                    let _ = app
+                        .state::<profile_watch::ProfileWatcher>()
+                        .watch(&app, settings.profile_path.as_deref());
+                }
+            });
+
             // Deep link: Check if app was started via deep link
             if let Some(urls) = app.deep_link().get_current()? {
                 println!("App started via deep link: {:?}", urls);
@@ -70,20 +139,67 @@ pub fn run() {
             });
 
             // Register deep link scheme for development on Linux/Windows
+            // 設定でスキームが指定されていればそれを、なければ設定ファイルの全スキームを登録する
             #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
             {
-                let _ = app.deep_link().register_all();
+                match auth::configured_deep_link_scheme(app.handle()) {
+                    Some(scheme) => {
+                        let _ = auth::ensure_scheme_registered(app.handle(), &scheme);
+                    }
+                    None => {
+                        let _ = app.deep_link().register_all();
+                    }
+                }
             }
 
             Ok(())
         })
+        .on_window_event(move |window, event| {
+            // 実行中のバッチがあれば終了を保留し、停止と結果の書き出しを待ってから閉じる
+            // （2回目のクローズ要求や待ち時間の上限を超えた場合はそのまま閉じる）
+            let tauri::WindowEvent::CloseRequested { api, .. } = event else {
+                return;
+            };
+            if shutdown.running() == 0 || !shutdown.begin() {
+                return;
+            }
+            api.prevent_close();
+            let _ = window.emit("batch-shutdown", shutdown.running());
+
+            let shutdown = Arc::clone(&shutdown);
+            let window = window.clone();
+            tauri::async_runtime::spawn(async move {
+                shutdown
+                    .wait_idle(shutdown::GRACEFUL_SHUTDOWN_TIMEOUT)
+                    .await;
+                let _ = window.close();
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             // OCR commands
             commands::ocr_receipt,
+            commands::preflight_ocr,
+            commands::assess_image_quality,
+            commands::convert_heic_to_jpeg,
+            commands::validate_ocr_requests,
+            commands::get_pdf_page_count,
+            commands::localize_error,
+            commands::preview_llm_prompt,
             commands::batch_ocr_receipts,
+            commands::resume_batch,
+            commands::cancel_batch_ocr,
+            commands::retry_failed_ocr,
+            commands::list_pending_batches,
             commands::get_ocr_settings,
             commands::save_ocr_settings,
+            commands::suggest_ocr_location,
             commands::test_provider_connection,
+            commands::project_receipts,
+            commands::sum_receipt_amounts,
+            commands::diff_receipts,
+            commands::classify_account,
+            commands::check_against_ledger,
+            commands::detect_duplicate_receipts,
             // Directory commands
             commands::get_default_root_directory,
             commands::get_root_directory,
@@ -93,13 +209,34 @@ pub fn run() {
             commands::create_directory,
             commands::list_month_directories,
             commands::list_files_in_directory,
+            commands::index_root_directory,
+            commands::get_root_index,
+            commands::read_month_summary,
+            commands::generate_month_summary,
+            commands::export_month_csv,
+            commands::export_freee_csv,
+            commands::export_moneyforward_csv,
+            commands::save_month_summary,
+            commands::generate_denchou_index,
+            commands::detect_number_gaps,
+            commands::bulk_update_receipts,
+            commands::set_review_status,
             commands::copy_file_to_month,
+            commands::process_and_file,
+            commands::ocr_from_url,
+            commands::extract_frame,
+            commands::generate_placeholder,
             commands::save_thumbnail,
+            commands::generate_thumbnail,
             commands::read_thumbnail,
+            commands::read_thumbnails,
             commands::move_to_trash,
             // Settings commands
             commands::get_account_category_rules,
             commands::save_account_category_rules,
+            commands::infer_merchant_category,
+            commands::get_merchant_category_dictionary,
+            commands::save_merchant_category_dictionary,
             commands::get_validation_rules,
             commands::save_validation_rules,
             commands::get_receiver_name_history,
@@ -109,6 +246,10 @@ pub fn run() {
             auth::save_auth_tokens,
             auth::clear_auth_tokens,
             auth::open_oauth_url,
+            auth::cache_user_picture,
+            auth::get_user_picture,
+            auth::register_deep_link_scheme,
+            auth::is_scheme_registered,
             // Logging commands
             commands::write_error_log,
             commands::read_error_log,
@@ -0,0 +1,135 @@
+//! 店舗名からの支店名の分離
+//!
+//! `ローソン渋谷店` のような店舗名から末尾の支店名（`渋谷店`）を見つけて `branch` に記録する。
+//! 店舗名はOCRの読み取り結果のまま残し、店舗名から支店名を除いたものをチェーン名として集計に使う。
+//! 支店名は「店」「支店」「号店」などの接尾辞で終わる末尾の部分とし、空白の区切りが無ければ
+//! 末尾の漢字・数字の並びを支店名とみなす。`珈琲店`・`書店` のような業態名は支店名にしない。
+
+use crate::providers::ReceiptData;
+
+/// 支店名の接尾辞
+const BRANCH_SUFFIXES: &[&str] = &["支店", "号店", "営業所", "出張所", "店"];
+
+/// 「店」で終わるが支店名ではなく店舗名の一部になる業態名
+const BUSINESS_WORDS: &[&str] = &[
+    "珈琲店",
+    "喫茶店",
+    "書店",
+    "商店",
+    "酒店",
+    "飯店",
+    "菓子店",
+    "百貨店",
+    "専門店",
+    "料理店",
+    "売店",
+    "薬店",
+    "代理店",
+    "販売店",
+];
+
+/// 支店名の一部とみなす文字（漢字・数字と地名に使う `ヶ`・`々`・`〇`）
+fn is_branch_char(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '々' | '〇' | 'ヶ' | '0'..='9' | '０'..='９')
+}
+
+/// 接尾辞で終わり、接尾辞より長い（`店` だけではない）支店名か（業態名で終わるものは除く）
+fn is_branch(text: &str) -> bool {
+    BRANCH_SUFFIXES
+        .iter()
+        .any(|suffix| text.ends_with(suffix) && text.chars().count() > suffix.chars().count())
+        && !BUSINESS_WORDS.iter().any(|word| text.ends_with(word))
+}
+
+/// 文字列中で最後に現れる業態名の直後の位置（無ければ `None`）
+fn end_of_business_word(text: &str) -> Option<usize> {
+    BUSINESS_WORDS
+        .iter()
+        .filter_map(|word| text.rfind(word).map(|i| i + word.len()))
+        .max()
+}
+
+/// 店舗名の末尾の支店名を見つける（見つからなければ `None`）
+///
+/// 空白で区切られた最後の語が支店名ならそれを返す。区切りが無ければ末尾の漢字・数字の並びを見て、
+/// 業態名（`紀伊國屋書店新宿本店` の `書店`）があればその後ろを、無ければ並び全体を支店名の候補にする。
+/// 業態名が無く店舗名全体が漢字のもの（`丸亀製麺渋谷店`）は区切りが分からないため分けない。
+pub fn split_merchant_branch(merchant: &str) -> Option<String> {
+    let merchant = merchant.trim();
+
+    if let Some((chain, branch)) = merchant.rsplit_once(char::is_whitespace) {
+        return (!chain.trim_end().is_empty() && is_branch(branch)).then(|| branch.to_string());
+    }
+
+    let run_start = merchant
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_branch_char(*c))
+        .last()
+        .map(|(i, _)| i)?;
+    let start = match end_of_business_word(&merchant[run_start..]) {
+        Some(offset) => run_start + offset,
+        None if run_start > 0 => run_start,
+        None => return None,
+    };
+    let branch = &merchant[start..];
+    is_branch(branch).then(|| branch.to_string())
+}
+
+/// レシートの店舗名の末尾の支店名を `branch` に記録する（店舗名は変えない。設定済みなら何もしない）
+pub fn apply_branch_split(data: &mut ReceiptData) {
+    if data.branch.is_some() {
+        return;
+    }
+    data.branch = data.merchant.as_deref().and_then(split_merchant_branch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_merchant_branch_finds_branch_suffixes() {
+        let split = |merchant: &str| split_merchant_branch(merchant).unwrap_or_default();
+
+        assert_eq!(split("ローソン渋谷店"), "渋谷店");
+        assert_eq!(
+            split("STARBUCKS COFFEE 渋谷スクランブルスクエア店"),
+            "渋谷スクランブルスクエア店"
+        );
+        assert_eq!(split("セブン-イレブン新宿3丁目店"), "新宿3丁目店");
+        assert_eq!(split("みずほ銀行　渋谷支店"), "渋谷支店");
+        assert_eq!(split("ラーメン二郎 三田本店"), "三田本店");
+        // 業態名の後ろだけを支店名にする
+        assert_eq!(split("コメダ珈琲店渋谷店"), "渋谷店");
+        assert_eq!(split("紀伊國屋書店新宿本店"), "新宿本店");
+        // 分けられないもの
+        assert_eq!(split("丸亀製麺渋谷店"), "");
+        assert_eq!(split("ラーメン店"), "");
+        assert_eq!(split("Amazon.co.jp"), "");
+    }
+
+    #[test]
+    fn business_words_are_not_branches() {
+        for merchant in [
+            "コメダ珈琲店",
+            "ドトール珈琲店",
+            "星乃珈琲店",
+            "三省堂書店",
+            "ジュンク堂書店",
+            "TSUTAYA 書店",
+            "山田商店",
+        ] {
+            assert_eq!(split_merchant_branch(merchant), None, "{}", merchant);
+        }
+    }
+
+    #[test]
+    fn apply_branch_split_keeps_the_merchant() {
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.merchant = Some("ローソン渋谷店".to_string());
+        apply_branch_split(&mut data);
+        assert_eq!(data.merchant.as_deref(), Some("ローソン渋谷店"));
+        assert_eq!(data.branch.as_deref(), Some("渋谷店"));
+    }
+}
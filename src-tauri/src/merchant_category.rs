@@ -0,0 +1,300 @@
+//! 店舗名からの業種判定
+//!
+//! 店舗名を正規化し、内蔵の業種辞書（コンビニ・カフェチェーン・鉄道会社など）と
+//! ユーザー辞書（`merchant_category_dictionary` に保存）に照合して大まかな業種を推定する。
+//! 勘定科目推定の補助に使う。
+
+use crate::providers::ReceiptData;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// 内蔵の業種辞書（業種, キーワード）
+///
+/// キーワードは店舗名への部分一致で照合するため、社名・チェーン名に限る。「交通」「ホテル」や
+/// 百貨店・スーパーも営む鉄道会社の略称（「東急」など）のように別業種にも現れる語は入れない。
+const BUILTIN_DICTIONARY: &[(&str, &[&str])] = &[
+    (
+        "コンビニ",
+        &[
+            "セブンイレブン",
+            "セブン-イレブン",
+            "7-eleven",
+            "ファミリーマート",
+            "familymart",
+            "ローソン",
+            "lawson",
+            "ミニストップ",
+            "デイリーヤマザキ",
+            "セイコーマート",
+            "ニューデイズ",
+            "newdays",
+        ],
+    ),
+    (
+        "カフェ",
+        &[
+            "スターバックス",
+            "starbucks",
+            "ドトール",
+            "タリーズ",
+            "tully's",
+            "コメダ珈琲",
+            "サンマルクカフェ",
+            "エクセルシオール",
+            "プロント",
+            "上島珈琲",
+        ],
+    ),
+    (
+        "飲食",
+        &[
+            "マクドナルド",
+            "モスバーガー",
+            "ケンタッキー",
+            "吉野家",
+            "松屋フーズ",
+            "すき家",
+            "なか卯",
+            "サイゼリヤ",
+            "ガスト",
+            "ジョナサン",
+            "デニーズ",
+            "ロイヤルホスト",
+            "大戸屋",
+            "やよい軒",
+            "丸亀製麺",
+            "日高屋",
+            "餃子の王将",
+            "ココイチ",
+            "coco壱番屋",
+        ],
+    ),
+    (
+        "交通",
+        &[
+            "jr東日本",
+            "jr西日本",
+            "jr東海",
+            "jr九州",
+            "jr北海道",
+            "jr四国",
+            "東日本旅客鉄道",
+            "西日本旅客鉄道",
+            "東海旅客鉄道",
+            "東京メトロ",
+            "東京地下鉄",
+            "都営地下鉄",
+            "都営バス",
+            "東急電鉄",
+            "小田急電鉄",
+            "京王電鉄",
+            "西武鉄道",
+            "東武鉄道",
+            "京浜急行",
+            "京成電鉄",
+            "相模鉄道",
+            "阪急電鉄",
+            "阪神電気鉄道",
+            "近畿日本鉄道",
+            "南海電気鉄道",
+            "京阪電気鉄道",
+            "名古屋鉄道",
+            "西日本鉄道",
+            "suica",
+            "pasmo",
+            "icoca",
+            "全日空",
+            "日本航空",
+        ],
+    ),
+    (
+        "スーパー",
+        &[
+            "イオンモール",
+            "イオンスタイル",
+            "イトーヨーカドー",
+            "西友",
+            "ライフコーポレーション",
+            "マルエツ",
+            "成城石井",
+            "まいばすけっと",
+            "オーケーストア",
+        ],
+    ),
+    (
+        "ドラッグストア",
+        &[
+            "マツモトキヨシ",
+            "ウエルシア",
+            "ツルハ",
+            "スギ薬局",
+            "サンドラッグ",
+            "ココカラファイン",
+        ],
+    ),
+    (
+        "家電量販店",
+        &[
+            "ヨドバシカメラ",
+            "ビックカメラ",
+            "ヤマダ電機",
+            "ヤマダデンキ",
+            "ケーズデンキ",
+            "エディオン",
+            "ノジマ",
+        ],
+    ),
+    (
+        "書店",
+        &[
+            "紀伊國屋書店",
+            "紀伊国屋書店",
+            "丸善",
+            "ジュンク堂",
+            "三省堂書店",
+            "有隣堂",
+            "ツタヤ",
+            "tsutaya",
+        ],
+    ),
+    (
+        "宿泊",
+        &[
+            "東横イン",
+            "アパホテル",
+            "ルートイン",
+            "ドーミーイン",
+            "スーパーホテル",
+        ],
+    ),
+];
+
+/// 照合前に取り除く法人格の表記
+const CORPORATE_DESIGNATIONS: &[&str] = &[
+    "株式会社",
+    "有限会社",
+    "合同会社",
+    "(株)",
+    "(有)",
+    "(同)",
+    "㈱",
+    "㈲",
+];
+
+/// ユーザー辞書のエントリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerchantCategoryEntry {
+    /// 店舗名に含まれるキーワード
+    pub keyword: String,
+    /// 業種
+    pub category: String,
+}
+
+/// ユーザー辞書の設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerchantCategorySettings {
+    #[serde(default)]
+    pub entries: Vec<MerchantCategoryEntry>,
+}
+
+/// 照合用に店舗名を正規化する
+///
+/// NFKC で全角英数・半角カナを揃え、小文字化し、法人格・空白・中黒を取り除く。
+pub fn normalize_merchant_name(name: &str) -> String {
+    let mut normalized: String = name.nfkc().collect::<String>().to_lowercase();
+    for designation in CORPORATE_DESIGNATIONS {
+        normalized = normalized.replace(designation, "");
+    }
+
+    normalized
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '・' | '･'))
+        .collect()
+}
+
+/// 店舗名から業種を推定する
+///
+/// ユーザー辞書を先に照合し（先のエントリを優先）、該当が無ければ内蔵辞書のうち
+/// 最も長いキーワードが一致した業種を採用する。どれにも該当しなければ `None`。
+pub fn infer_merchant_category(
+    merchant: &str,
+    user_entries: &[MerchantCategoryEntry],
+) -> Option<String> {
+    let merchant = normalize_merchant_name(merchant);
+    if merchant.is_empty() {
+        return None;
+    }
+
+    let user_match = user_entries.iter().find(|entry| {
+        let keyword = normalize_merchant_name(&entry.keyword);
+        !keyword.is_empty() && merchant.contains(&keyword)
+    });
+    if let Some(entry) = user_match {
+        return Some(entry.category.clone());
+    }
+
+    BUILTIN_DICTIONARY
+        .iter()
+        .flat_map(|(category, keywords)| {
+            keywords
+                .iter()
+                .map(|keyword| normalize_merchant_name(keyword))
+                .filter(|keyword| merchant.contains(keyword.as_str()))
+                .map(move |keyword| (keyword.chars().count(), *category))
+        })
+        .fold(None, |best: Option<(usize, &str)>, candidate| match best {
+            Some(best) if best.0 >= candidate.0 => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|(_, category)| category.to_string())
+}
+
+/// レシートの店舗名から業種を設定する
+pub fn apply_merchant_category(data: &mut ReceiptData, user_entries: &[MerchantCategoryEntry]) {
+    data.merchant_category = data
+        .merchant
+        .as_deref()
+        .and_then(|merchant| infer_merchant_category(merchant, user_entries));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infer_merchant_category_normalizes_and_prefers_user_entries() {
+        assert_eq!(
+            normalize_merchant_name("株式会社 ﾛｰｿﾝ　ＡＢＣ店"),
+            "ローソンabc店"
+        );
+        assert_eq!(
+            infer_merchant_category("ＳＴＡＲＢＵＣＫＳ COFFEE 渋谷店", &[]),
+            Some("カフェ".to_string())
+        );
+        assert_eq!(infer_merchant_category("山田商店", &[]), None);
+        // 別業種にも現れる語では判定しない
+        assert_eq!(infer_merchant_category("東急ストア 渋谷店", &[]), None);
+        assert_eq!(infer_merchant_category("ライオン堂", &[]), None);
+        assert_eq!(infer_merchant_category("松屋銀座", &[]), None);
+        assert_eq!(infer_merchant_category("山田タクシー", &[]), None);
+        assert_eq!(
+            infer_merchant_category("ホテル椿山荘 レストラン", &[]),
+            None
+        );
+        assert_eq!(
+            infer_merchant_category("東急電鉄 渋谷駅", &[]),
+            Some("交通".to_string())
+        );
+
+        let user_entries = vec![MerchantCategoryEntry {
+            keyword: "ローソン".to_string(),
+            category: "会議用品".to_string(),
+        }];
+        assert_eq!(
+            infer_merchant_category("ローソン 新宿店", &user_entries),
+            Some("会議用品".to_string())
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! OCRに送る MIME タイプの正規化
+//!
+//! フロントエンドや OS が付ける MIME には表記ゆれ（`image/jpg` など）があり、そのまま送ると
+//! Document AI が 400 を返す。別名の表で対応形式の表記に揃え、内容の先頭バイトから判定した
+//! 形式と食い違う場合は内容を優先する。対応形式にできない場合は送る前にエラーにする。
+
+use crate::preflight::{is_supported_mime_type, sniff_mime_type};
+
+/// MIME の別名 → 対応形式の表記
+const MIME_ALIASES: &[(&str, &str)] = &[
+    ("image/jpg", "image/jpeg"),
+    ("image/pjpeg", "image/jpeg"),
+    ("image/x-png", "image/png"),
+    ("image/tif", "image/tiff"),
+    ("image/x-tiff", "image/tiff"),
+    ("image/x-bmp", "image/bmp"),
+    ("image/x-ms-bmp", "image/bmp"),
+    ("application/x-pdf", "application/pdf"),
+    ("image/heif", "image/heic"),
+];
+
+/// 判定に使う先頭の Base64 文字数（12バイト分。`sniff_mime_type` の判定に足りる長さ）
+const HEADER_BASE64_LEN: usize = 16;
+
+/// 表記ゆれを揃える（小文字化・パラメータの除去・別名の置き換え）
+fn canonical(mime_type: &str) -> String {
+    let mime_type = mime_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    MIME_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == mime_type)
+        .map_or(mime_type, |(_, canonical)| canonical.to_string())
+}
+
+/// 申告された MIME と内容の先頭バイトから、プロバイダーに送る MIME を決める
+///
+/// 内容から形式を判定できればそれを優先する。HEIC・HEIF は OCR の前に JPEG に変換するため
+/// `image/heic` として受け付け、それ以外の対応していない形式はエラー。
+pub fn normalize_mime_type(declared: &str, header: &[u8]) -> Result<String, String> {
+    let mime_type = match sniff_mime_type(header) {
+        Some(sniffed) => sniffed.to_string(),
+        None => canonical(declared),
+    };
+
+    if is_supported_mime_type(&mime_type) || mime_type == "image/heic" {
+        return Ok(mime_type);
+    }
+    Err(match mime_type.as_str() {
+        "" | "application/octet-stream" => "ファイル形式を判定できませんでした".to_string(),
+        other => format!("OCRに対応していない形式です: {}", other),
+    })
+}
+
+/// Base64 の内容の先頭（形式の判定に使う分）をデコードする
+fn decode_header(file_content: &str) -> Vec<u8> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let prefix = file_content
+        .get(..HEADER_BASE64_LEN)
+        .unwrap_or(file_content);
+    STANDARD.decode(prefix).unwrap_or_default()
+}
+
+/// Base64 の内容の先頭をデコードして [`normalize_mime_type`] を行う
+pub fn normalize_base64_mime_type(declared: &str, file_content: &str) -> Result<String, String> {
+    normalize_mime_type(declared, &decode_header(file_content))
+}
+
+/// HEIC・HEIF 画像か（内容から判定できればそれを優先する）
+pub fn is_heic(declared: &str, header: &[u8]) -> bool {
+    match sniff_mime_type(header) {
+        Some(sniffed) => sniffed == "image/heic",
+        None => canonical(declared) == "image/heic",
+    }
+}
+
+/// Base64 の内容の先頭をデコードして [`is_heic`] を判定する
+pub fn is_heic_base64(declared: &str, file_content: &str) -> bool {
+    is_heic(declared, &decode_header(file_content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mime_aliases_are_canonicalized_and_content_wins() {
+        assert_eq!(normalize_mime_type("image/jpg", b"").unwrap(), "image/jpeg");
+        assert_eq!(
+            normalize_mime_type("Image/X-PNG; charset=binary", b"").unwrap(),
+            "image/png"
+        );
+        assert_eq!(
+            normalize_mime_type("application/octet-stream", b"II*\0").unwrap(),
+            "image/tiff"
+        );
+        assert_eq!(
+            normalize_mime_type("image/png", &[0xFF, 0xD8, 0xFF, 0xE0]).unwrap(),
+            "image/jpeg"
+        );
+        assert_eq!(
+            normalize_mime_type("image/heif", b"").unwrap(),
+            "image/heic"
+        );
+        assert_eq!(
+            normalize_mime_type("image/heic", b"").unwrap(),
+            "image/heic"
+        );
+        assert!(is_heic("image/heif", b""));
+        assert!(is_heic("", b"\0\0\0\x18ftypheic"));
+        // 申告が HEIC でも内容が JPEG なら変換しない
+        assert!(!is_heic("image/heic", &[0xFF, 0xD8, 0xFF, 0xE0]));
+        assert_eq!(
+            normalize_mime_type("text/plain", b"hello").unwrap_err(),
+            "OCRに対応していない形式です: text/plain"
+        );
+
+        // "%PDF-1.7" の Base64
+        assert_eq!(
+            normalize_base64_mime_type("", "JVBERi0xLjcK").unwrap(),
+            "application/pdf"
+        );
+    }
+}
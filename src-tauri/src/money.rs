@@ -0,0 +1,295 @@
+//! 金額の整数表現
+//!
+//! 金額は通貨の最小単位（円・セントなど）の整数で保持し、集計も整数で行う。
+//! 浮動小数の `amount` は表示用の互換フィールドとして残す。
+
+use crate::providers::{OcrResult, ReceiptData};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Deref;
+
+/// 通貨が不明な場合に仮定する通貨
+pub const DEFAULT_CURRENCY: &str = "JPY";
+
+/// ISO 4217 の現行通貨コード
+const ISO_4217_CODES: &str = "AED AFN ALL AMD ANG AOA ARS AUD AWG AZN BAM BBD BDT BGN BHD BIF BMD BND \
+    BOB BRL BSD BTN BWP BYN BZD CAD CDF CHF CLP CNY COP CRC CUP CVE CZK DJF DKK DOP DZD EGP ERN ETB \
+    EUR FJD FKP GBP GEL GHS GIP GMD GNF GTQ GYD HKD HNL HTG HUF IDR ILS INR IQD IRR ISK JMD JOD JPY \
+    KES KGS KHR KMF KPW KRW KWD KYD KZT LAK LBP LKR LRD LSL LYD MAD MDL MGA MKD MMK MNT MOP MRU MUR \
+    MVR MWK MXN MYR MZN NAD NGN NIO NOK NPR NZD OMR PAB PEN PGK PHP PKR PLN PYG QAR RON RSD RUB RWF \
+    SAR SBD SCR SDG SEK SGD SHP SLE SOS SRD SSP STN SVC SYP SZL THB TJS TMT TND TOP TRY TTD TWD TZS \
+    UAH UGX USD UYU UZS VES VND VUV WST XAF XCD XCG XOF XPF YER ZAR ZMW ZWG";
+
+/// 通貨記号・通称から推定する通貨コード（比較は大文字小文字を区別しない）
+const CURRENCY_ALIASES: &[(&str, &str)] = &[
+    ("¥", "JPY"),
+    ("￥", "JPY"),
+    ("\\", "JPY"),
+    ("円", "JPY"),
+    ("yen", "JPY"),
+    ("$", "USD"),
+    ("＄", "USD"),
+    ("us$", "USD"),
+    ("ドル", "USD"),
+    ("€", "EUR"),
+    ("ユーロ", "EUR"),
+    ("£", "GBP"),
+    ("₩", "KRW"),
+    ("원", "KRW"),
+    ("元", "CNY"),
+    ("rmb", "CNY"),
+    ("hk$", "HKD"),
+    ("nt$", "TWD"),
+    ("s$", "SGD"),
+    ("a$", "AUD"),
+    ("c$", "CAD"),
+    ("₹", "INR"),
+    ("฿", "THB"),
+    ("₫", "VND"),
+];
+
+/// ISO 4217 の通貨コード（英大文字 3 文字に正規化済み）
+///
+/// 表記ゆれ（`jpy`・`￥` など）で集計が割れないよう、読み込み時に [`Currency::parse`] で正規化する。
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct Currency(String);
+
+impl Currency {
+    /// 通貨コードまたは通貨記号を正規化する（推定できなければ `None`）
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let code = text.to_ascii_uppercase();
+        if code.len() == 3 && ISO_4217_CODES.split_whitespace().any(|known| known == code) {
+            return Some(Self(code));
+        }
+
+        let lower = text.to_lowercase();
+        CURRENCY_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == lower)
+            .map(|(_, code)| Self(code.to_string()))
+    }
+}
+
+impl Deref for Currency {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Self::parse(&text).ok_or_else(|| {
+            serde::de::Error::custom(format!("ISO 4217 の通貨コードではありません: {}", text))
+        })
+    }
+}
+
+/// 通貨の最小単位の桁数（ISO 4217 の minor unit）
+///
+/// 一覧に無い通貨は 2 桁とみなす。
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    match currency.to_ascii_uppercase().as_str() {
+        "JPY" | "KRW" | "VND" | "CLP" | "ISK" | "PYG" | "UGX" | "XAF" | "XOF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// 金額を最小単位の整数に変換する（四捨五入）
+pub fn to_minor_units(amount: f64, currency: Option<&str>) -> Option<i64> {
+    let exponent = minor_unit_exponent(currency.unwrap_or(DEFAULT_CURRENCY));
+    let scaled = (amount * 10f64.powi(exponent as i32)).round();
+
+    scaled.is_finite().then_some(scaled as i64)
+}
+
+/// 最小単位の整数を表示用の金額に戻す
+pub fn from_minor_units(amount_minor: i64, currency: &str) -> f64 {
+    amount_minor as f64 / 10f64.powi(minor_unit_exponent(currency) as i32)
+}
+
+/// 通貨が取れなかった金額をまとめる累計のキー
+pub const UNKNOWN_CURRENCY: &str = "UNKNOWN";
+
+/// 成功した結果の金額を通貨別の累計に加える（失敗・スキップ・金額なしは加えない）
+pub fn add_to_running_total(totals: &mut HashMap<String, f64>, result: &OcrResult) {
+    if !result.success || result.skipped {
+        return;
+    }
+    let Some(data) = &result.data else {
+        return;
+    };
+    let Some(amount) = data.amount else {
+        return;
+    };
+    let currency = data.currency.as_deref().unwrap_or(UNKNOWN_CURRENCY);
+    *totals.entry(currency.to_string()).or_default() += amount;
+}
+
+/// 推定に使う消費税率（標準税率・軽減税率）
+const KNOWN_TAX_RATES: &[f64] = &[0.10, 0.08];
+
+/// 税込の合計と税額から消費税率を推定する（該当する税率が無ければ `None`）
+///
+/// 端数処理の差（通貨の最小単位 1 つ分未満）は許容し、複数の税率が当てはまる場合は
+/// 差が最も小さい税率を選ぶ。複数税率が混在する場合はどの税率にも合わないため `None`。
+pub fn estimate_tax_rate(total: f64, tax: f64, currency: Option<&str>) -> Option<f64> {
+    if total <= 0.0 || tax <= 0.0 || tax >= total {
+        return None;
+    }
+    let exponent = minor_unit_exponent(currency.unwrap_or(DEFAULT_CURRENCY));
+    let tolerance = 10f64.powi(-(exponent as i32));
+    KNOWN_TAX_RATES
+        .iter()
+        .map(|rate| (*rate, (total * rate / (1.0 + rate) - tax).abs()))
+        .filter(|(_, diff)| *diff < tolerance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(rate, _)| rate)
+}
+
+/// 通貨ごとの合計
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyTotal {
+    pub currency: String,
+    /// 合計（最小単位の整数）
+    pub amount_minor: i64,
+    /// 合計（表示用）
+    pub amount: f64,
+    /// 合計に含めたレシート数
+    pub count: usize,
+}
+
+/// レシートの金額を通貨ごとに整数で合計する
+///
+/// `amount_minor` が無いレシート（従来形式）は `amount` から換算する。
+/// 金額の無いレシートは含めない。
+pub fn sum_by_currency(receipts: &[ReceiptData]) -> Vec<CurrencyTotal> {
+    let mut totals: BTreeMap<String, (i64, usize)> = BTreeMap::new();
+
+    for receipt in receipts {
+        let Some(amount_minor) = receipt.amount_in_minor_units() else {
+            continue;
+        };
+        let currency = receipt
+            .currency
+            .as_deref()
+            .unwrap_or(DEFAULT_CURRENCY)
+            .to_string();
+
+        let entry = totals.entry(currency).or_default();
+        entry.0 += amount_minor;
+        entry.1 += 1;
+    }
+
+    totals
+        .into_iter()
+        .map(|(currency, (amount_minor, count))| CurrencyTotal {
+            amount: from_minor_units(amount_minor, &currency),
+            currency,
+            amount_minor,
+            count,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(amount: f64, currency: Option<&str>) -> ReceiptData {
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.amount = Some(amount);
+        data.currency = currency.and_then(Currency::parse);
+        data
+    }
+
+    #[test]
+    fn sum_by_currency_adds_minor_units_without_float_drift() {
+        let receipts: Vec<ReceiptData> = std::iter::repeat_n(receipt(0.1, Some("USD")), 10)
+            .chain([receipt(1980.0, None), receipt(20.0, Some("jpy"))])
+            .collect();
+
+        let totals = sum_by_currency(&receipts);
+
+        assert_eq!(
+            totals,
+            vec![
+                CurrencyTotal {
+                    currency: "JPY".to_string(),
+                    amount_minor: 2000,
+                    amount: 2000.0,
+                    count: 2,
+                },
+                CurrencyTotal {
+                    currency: "USD".to_string(),
+                    amount_minor: 100,
+                    amount: 1.0,
+                    count: 10,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn running_total_adds_only_successful_amounts() {
+        let mut totals = HashMap::new();
+        add_to_running_total(
+            &mut totals,
+            &OcrResult::success(receipt(1980.0, Some("JPY"))),
+        );
+        add_to_running_total(&mut totals, &OcrResult::success(receipt(20.0, Some("jpy"))));
+        add_to_running_total(&mut totals, &OcrResult::success(receipt(3.5, None)));
+        add_to_running_total(&mut totals, &OcrResult::failure("失敗".to_string()));
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals["JPY"], 2000.0);
+        assert_eq!(totals[UNKNOWN_CURRENCY], 3.5);
+    }
+
+    #[test]
+    fn estimate_tax_rate_matches_standard_and_reduced_rates() {
+        assert_eq!(estimate_tax_rate(1100.0, 100.0, None), Some(0.10));
+        assert_eq!(estimate_tax_rate(1188.0, 88.0, Some("JPY")), Some(0.08));
+        // 端数処理で 1 円未満ずれる（税込 1,079 円の 8% 分は 79.93 円）
+        assert_eq!(estimate_tax_rate(1079.0, 79.0, None), Some(0.08));
+        // 少額では 10%（4.91 円）も 1 円未満に収まるが、差の小さい 8%（4 円）を選ぶ
+        assert_eq!(estimate_tax_rate(54.0, 4.0, Some("JPY")), Some(0.08));
+        // 許容する差は通貨の最小単位に合わせる（0.50 ドルずれていれば合わない）
+        assert_eq!(estimate_tax_rate(11.0, 1.0, Some("USD")), Some(0.10));
+        assert_eq!(estimate_tax_rate(11.0, 1.5, Some("USD")), None);
+        // 8% と 10% の混在
+        assert_eq!(estimate_tax_rate(2288.0, 188.0, None), None);
+        assert_eq!(estimate_tax_rate(0.0, 0.0, None), None);
+    }
+
+    #[test]
+    fn currency_normalizes_codes_and_symbols() {
+        assert_eq!(Currency::parse(" jpy ").as_deref(), Some("JPY"));
+        assert_eq!(Currency::parse("￥").as_deref(), Some("JPY"));
+        assert_eq!(Currency::parse("円").as_deref(), Some("JPY"));
+        assert_eq!(Currency::parse("US$").as_deref(), Some("USD"));
+        assert_eq!(Currency::parse("€").as_deref(), Some("EUR"));
+        assert_eq!(Currency::parse("ABC"), None);
+        assert_eq!(Currency::parse("dollars"), None);
+
+        assert!(serde_json::from_str::<Currency>(r#""usd""#).is_ok());
+        assert!(serde_json::from_str::<Currency>(r#""XYZ""#).is_err());
+        assert_eq!(
+            serde_json::to_string(&Currency::parse("gbp").unwrap()).unwrap(),
+            r#""GBP""#
+        );
+    }
+}
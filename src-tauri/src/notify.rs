@@ -0,0 +1,72 @@
+//! バッチ完了のデスクトップ通知
+//!
+//! デスクトップの通知はクリックを受け取れないため、ウィンドウが前面にない場合は
+//! タスクバー／Dock のアイコンで注意を促し、そこからアプリを前面化できるようにする。
+
+use crate::providers::OcrResult;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, UserAttentionType};
+use tauri_plugin_notification::NotificationExt;
+
+/// 通知本文（成功・失敗・スキップ件数と所要時間）
+fn batch_summary_body(results: &[OcrResult], elapsed: Duration) -> String {
+    let skipped = results.iter().filter(|r| r.skipped).count();
+    let succeeded = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - succeeded - skipped;
+
+    let secs = elapsed.as_secs();
+    let elapsed = if secs >= 60 {
+        format!("{}分{}秒", secs / 60, secs % 60)
+    } else {
+        format!("{}秒", secs)
+    };
+
+    let mut body = format!("成功 {} 件 / 失敗 {} 件", succeeded, failed);
+    if skipped > 0 {
+        body.push_str(&format!(" / スキップ {} 件", skipped));
+    }
+    body.push_str(&format!("（所要時間 {}）", elapsed));
+    body
+}
+
+/// バッチOCRの完了を通知する（通知に失敗してもバッチの結果には影響させない）
+pub fn notify_batch_complete(app: &AppHandle, results: &[OcrResult], elapsed: Duration) {
+    let _ = app
+        .notification()
+        .builder()
+        .title("OCRが完了しました")
+        .body(batch_summary_body(results, elapsed))
+        .show();
+
+    if let Some(window) = app.get_webview_window("main") {
+        if !window.is_focused().unwrap_or(true) {
+            let _ = window.request_user_attention(Some(UserAttentionType::Informational));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::providers::ReceiptData;
+
+    #[test]
+    fn batch_summary_body_counts_results() {
+        let results = vec![
+            OcrResult::success(ReceiptData::new("a.jpg".to_string())),
+            OcrResult::success(ReceiptData::new("b.jpg".to_string())),
+            OcrResult::failure("失敗".to_string()),
+            OcrResult::skipped(AppError::AlreadyInFlight),
+        ];
+
+        assert_eq!(
+            batch_summary_body(&results, Duration::from_secs(125)),
+            "成功 2 件 / 失敗 1 件 / スキップ 1 件（所要時間 2分5秒）"
+        );
+        assert_eq!(
+            batch_summary_body(&results[..1], Duration::from_millis(900)),
+            "成功 1 件 / 失敗 0 件（所要時間 0秒）"
+        );
+    }
+}
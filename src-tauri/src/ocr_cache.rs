@@ -0,0 +1,250 @@
+//! OCR結果のキャッシュ（メモリ LRU とディスク）
+//!
+//! 同じファイルを同じ設定で読み直すときはプロバイダーを呼ばずに前回の結果を返す。
+//! セッション中に読んだ結果はメモリ（[`MemoryOcrCache`]）にも持ち、ディスクを読まずに返す。
+//! キーにはファイルのハッシュに加えてプロバイダー名・プロセッサバージョン・抽出に効く設定の
+//! ハッシュを含めるため、設定を変えると自動で再OCRされ、旧設定の結果は別のキーとして残る。
+//! 保存するのは整形ルール（`postprocess_rules`）を当てる前の抽出結果。
+
+use crate::providers::{OcrSettings, ReceiptData};
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// プロセッサバージョンを指定しないときのキーの表記
+const DEFAULT_VERSION_KEY: &str = "default";
+
+/// キャッシュの形式のバージョン（`ReceiptData` の項目や抽出処理を変えたら上げ、古い結果を使わない）
+const CACHE_SCHEMA_VERSION: u32 = 1;
+
+/// メモリに保持する結果の件数の上限（超えたら最も古く使われたものから捨てる）
+const MEMORY_CACHE_CAPACITY: usize = 512;
+
+/// 先頭 `bytes` バイトの16進表記
+fn hex_digest(data: &[u8], bytes: usize) -> String {
+    Sha256::digest(data)
+        .iter()
+        .take(bytes)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// キャッシュのキー
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OcrCacheKey {
+    /// ファイル内容のハッシュ
+    pub file_hash: String,
+    /// 使うプロバイダー名（チェーンは `+` で連結）
+    pub provider_name: String,
+    /// プロセッサバージョン（未指定なら `default`）
+    pub processor_version: String,
+    /// 抽出結果に影響する設定のハッシュ
+    pub settings_hash: String,
+}
+
+impl OcrCacheKey {
+    pub fn new(file_content: &str, provider_names: &[&str], settings: &OcrSettings) -> Self {
+        let google = settings.google.clone().unwrap_or_default();
+        let processor_version = google
+            .processor_version
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_VERSION_KEY)
+            .to_string();
+
+        // 認証情報や Webhook など結果に影響しない設定は含めない
+        let mut fingerprint = serde_json::json!([
+            CACHE_SCHEMA_VERSION,
+            google.project_id,
+            google.location,
+            google.processor_id,
+            settings.escalation_chain,
+            settings.escalation_min_completeness,
+            settings.max_image_dimension(),
+            settings.llm_prompt_template,
+            settings.refine_fields,
+            settings.refine_min_confidence,
+            settings.refine_provider,
+            settings.tesseract_lang(),
+            // 日付・時刻をローカルに換算するため、タイムゾーンが変われば結果も変わる
+            settings.local_offset().local_minus_utc(),
+        ]);
+        // マッピング未設定のキーは従来と同じにする（項目順に並べて HashMap の順序に依らせない）
+        if !google.entity_mapping.is_empty() {
+            let mapping: std::collections::BTreeMap<_, _> = google.entity_mapping.iter().collect();
+            if let Some(fingerprint) = fingerprint.as_array_mut() {
+                fingerprint.push(serde_json::json!(mapping));
+            }
+        }
+
+        Self {
+            file_hash: hex_digest(file_content.as_bytes(), 16),
+            provider_name: provider_names.join("+"),
+            processor_version,
+            settings_hash: hex_digest(fingerprint.to_string().as_bytes(), 8),
+        }
+    }
+
+    /// キャッシュファイル名（プロセッサバージョンは検証済みでパスに使える文字のみ）
+    fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{}.json",
+            self.file_hash, self.provider_name, self.processor_version, self.settings_hash
+        )
+    }
+}
+
+/// セッション内の結果のメモリキャッシュ（LRU）
+pub struct MemoryOcrCache {
+    entries: Mutex<LruCache<OcrCacheKey, ReceiptData>>,
+}
+
+impl Default for MemoryOcrCache {
+    fn default() -> Self {
+        Self::with_capacity(NonZeroUsize::new(MEMORY_CACHE_CAPACITY).unwrap())
+    }
+}
+
+impl MemoryOcrCache {
+    pub fn with_capacity(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// 保持している結果を返す（最近使ったものとして扱う）
+    pub fn get(&self, key: &OcrCacheKey) -> Option<ReceiptData> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+    }
+
+    /// 結果を保持しているか（使用順は変えない）
+    pub fn contains(&self, key: &OcrCacheKey) -> bool {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(key)
+    }
+
+    pub fn put(&self, key: OcrCacheKey, data: ReceiptData) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .put(key, data);
+    }
+}
+
+/// キャッシュディレクトリ
+pub fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("ocr"))
+        .map_err(|e| format!("キャッシュディレクトリを取得できませんでした: {}", e))
+}
+
+/// キャッシュ済みの結果を読み込む（無い・壊れている場合は `None`）
+pub fn read(dir: &Path, key: &OcrCacheKey) -> Option<ReceiptData> {
+    serde_json::from_slice(&fs::read(dir.join(key.file_name())).ok()?).ok()
+}
+
+/// キャッシュ済みの結果があるか（読み込みはしない）
+pub fn contains(dir: &Path, key: &OcrCacheKey) -> bool {
+    dir.join(key.file_name()).is_file()
+}
+
+/// 結果をキャッシュに書き込む
+pub fn write(dir: &Path, key: &OcrCacheKey, data: &ReceiptData) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("キャッシュディレクトリを作成できませんでした: {}", e))?;
+    let json = serde_json::to_vec(data)
+        .map_err(|e| format!("OCR結果をシリアライズできませんでした: {}", e))?;
+    fs::write(dir.join(key.file_name()), json)
+        .map_err(|e| format!("OCRキャッシュを書き込めませんでした: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_separates_providers_and_processor_versions() {
+        let settings = OcrSettings::default();
+        let key = OcrCacheKey::new("AAAA", &["googledocumentai"], &settings);
+        assert_eq!(
+            key,
+            OcrCacheKey::new("AAAA", &["googledocumentai"], &settings)
+        );
+        assert_eq!(key.processor_version, "default");
+        assert_ne!(key, OcrCacheKey::new("AAAA", &["veryfi"], &settings));
+        assert_ne!(
+            key,
+            OcrCacheKey::new("BBBB", &["googledocumentai"], &settings)
+        );
+
+        let versioned = OcrSettings {
+            google: Some(crate::providers::GoogleSettings {
+                processor_version: Some("stable".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let other = OcrCacheKey::new("AAAA", &["googledocumentai"], &versioned);
+        assert_eq!(other.processor_version, "stable");
+        assert_ne!(key.file_name(), other.file_name());
+
+        let tuned = OcrSettings {
+            max_image_dimension: Some(0),
+            ..Default::default()
+        };
+        assert_ne!(
+            key.settings_hash,
+            OcrCacheKey::new("AAAA", &["googledocumentai"], &tuned).settings_hash
+        );
+        let in_zone = |timezone: &str| OcrSettings {
+            timezone: Some(timezone.to_string()),
+            ..Default::default()
+        };
+        assert_ne!(
+            OcrCacheKey::new("AAAA", &["googledocumentai"], &in_zone("+09:00")).settings_hash,
+            OcrCacheKey::new("AAAA", &["googledocumentai"], &in_zone("-05:00")).settings_hash
+        );
+
+        let dir = std::env::temp_dir().join(format!("torifune-ocr-cache-{}", std::process::id()));
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.merchant = Some("ローソン".to_string());
+        // 正規化できなかった日付もOCR直後と同じ値で読み戻す
+        data.date = Some("2025年1月6日".to_string());
+        write(&dir, &key, &data).unwrap();
+        let cached = read(&dir, &key).unwrap();
+        assert_eq!(cached.merchant.as_deref(), Some("ローソン"));
+        assert_eq!(cached.date.as_deref(), Some("2025年1月6日"));
+        assert!(read(&dir, &other).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryOcrCache::with_capacity(NonZeroUsize::new(2).unwrap());
+        let settings = OcrSettings::default();
+        let key = |content: &str| OcrCacheKey::new(content, &["googledocumentai"], &settings);
+        let data = |file: &str| ReceiptData::new(file.to_string());
+
+        cache.put(key("AAAA"), data("a.jpg"));
+        cache.put(key("BBBB"), data("b.jpg"));
+        assert_eq!(cache.get(&key("AAAA")).unwrap().file, "a.jpg");
+
+        // 直前に使った a.jpg は残り、b.jpg が捨てられる
+        cache.put(key("CCCC"), data("c.jpg"));
+        assert!(cache.contains(&key("AAAA")));
+        assert!(!cache.contains(&key("BBBB")));
+        assert!(cache.contains(&key("CCCC")));
+    }
+}
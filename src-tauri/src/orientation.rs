@@ -0,0 +1,99 @@
+//! 画像の向きの正立化
+//!
+//! EXIF の Orientation を画素に適用して正立させ、Orientation を 1（補正なし）に戻して
+//! 再エンコードする。サムネイル（表示時に向きが補正される）と本体の向きを揃えるために使う。
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+
+/// 再エンコード時のJPEG品質
+const JPEG_QUALITY: u8 = 95;
+
+/// EXIF の向きを適用して正立させた画像データを返す
+///
+/// JPEG・PNG のみ対象。補正が不要な場合や対象外の形式は `None`。
+/// Orientation 以外の EXIF はそのまま引き継ぐ。
+pub fn normalize_orientation(content: &[u8]) -> image::ImageResult<Option<Vec<u8>>> {
+    let reader = ImageReader::new(Cursor::new(content)).with_guessed_format()?;
+    let format = match reader.format() {
+        Some(format @ (ImageFormat::Jpeg | ImageFormat::Png)) => format,
+        _ => return Ok(None),
+    };
+
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    if orientation == Orientation::NoTransforms {
+        return Ok(None);
+    }
+
+    let mut exif = decoder.exif_metadata()?;
+    if let Some(exif) = exif.as_mut() {
+        let _ = Orientation::remove_from_exif_chunk(exif);
+    }
+
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    let mut output = Vec::new();
+    match format {
+        ImageFormat::Jpeg => {
+            let mut encoder = JpegEncoder::new_with_quality(&mut output, JPEG_QUALITY);
+            if let Some(exif) = exif {
+                let _ = encoder.set_exif_metadata(exif);
+            }
+            // JPEG はアルファを持てない
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+        }
+        _ => {
+            let mut encoder = PngEncoder::new(&mut output);
+            if let Some(exif) = exif {
+                let _ = encoder.set_exif_metadata(exif);
+            }
+            image.write_with_encoder(encoder)?;
+        }
+    }
+
+    Ok(Some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbImage};
+
+    /// Orientation = 6（時計回りに90°回転して表示）だけを持つ EXIF
+    const EXIF_ROTATE_90: &[u8] = &[
+        b'M', b'M', 0x00, 0x2A, 0x00, 0x00, 0x00, 0x08, // TIFF ヘッダ
+        0x00, 0x01, // エントリ数
+        0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06, 0x00, 0x00, // Orientation
+        0x00, 0x00, 0x00, 0x00, // 次の IFD なし
+    ];
+
+    #[test]
+    fn normalize_orientation_rotates_pixels_and_resets_exif() {
+        let mut jpeg = Vec::new();
+        let mut encoder = JpegEncoder::new(&mut jpeg);
+        encoder.set_exif_metadata(EXIF_ROTATE_90.to_vec()).unwrap();
+        DynamicImage::ImageRgb8(RgbImage::new(4, 2))
+            .write_with_encoder(encoder)
+            .unwrap();
+
+        let normalized = normalize_orientation(&jpeg).unwrap().unwrap();
+        let mut decoder = ImageReader::new(Cursor::new(&normalized))
+            .with_guessed_format()
+            .unwrap()
+            .into_decoder()
+            .unwrap();
+        assert_eq!(decoder.orientation().unwrap(), Orientation::NoTransforms);
+        assert_eq!(
+            DynamicImage::from_decoder(decoder).unwrap().dimensions(),
+            (2, 4)
+        );
+
+        // 補正が不要なら何もしない
+        assert!(normalize_orientation(&normalized).unwrap().is_none());
+    }
+}
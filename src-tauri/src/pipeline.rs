@@ -0,0 +1,102 @@
+//! 取り込みパイプライン（OCR → 保存先の月の決定 → 月別ディレクトリへのコピー → サマリー → サムネイル）
+//!
+//! `process_and_file` コマンドで1ファイルを一括処理し、各ステップの結果を [`PipelineResult`] にまとめる。
+//! 途中で失敗した場合は以降のステップを行わず、止まったステップとエラーを返す。
+
+use crate::providers::OcrResult;
+use serde::{Deserialize, Serialize};
+
+/// パイプラインのステップ（実行順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PipelineStep {
+    Ocr,
+    ResolveMonth,
+    Copy,
+    Summary,
+    Thumbnail,
+}
+
+/// `process_and_file` のオプション
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProcessAndFileOptions {
+    /// 画像を EXIF の向きに合わせて正立させてからコピーするか
+    pub normalize_orientation: bool,
+    /// 日付を読み取れなかったときの保存先（YYYYMM）。未指定なら保存先の決定で止まる
+    pub fallback_year_month: Option<String>,
+    /// 保存先の月（YYYYMM）を固定する。指定するとレシートの日付によらずこの月に保存する
+    pub year_month: Option<String>,
+    /// サムネイルの長辺（px、未指定なら既定値）
+    pub thumbnail_size: Option<u32>,
+}
+
+/// 取り込みパイプラインの結果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineResult {
+    pub ocr: Option<OcrResult>,
+    /// 保存先の月（YYYYMM）
+    pub year_month: Option<String>,
+    /// コピー先のパス
+    pub destination_path: Option<String>,
+    /// サマリーに書き込んだか
+    pub summary_updated: bool,
+    /// 保存したサムネイルのパス（PDF など画像以外は `None`）
+    pub thumbnail_path: Option<String>,
+    /// 完了したステップ
+    pub completed_steps: Vec<PipelineStep>,
+    /// 失敗したステップ（すべて完了した場合は `None`）
+    pub failed_step: Option<PipelineStep>,
+    pub error: Option<String>,
+}
+
+impl PipelineResult {
+    pub fn complete(&mut self, step: PipelineStep) {
+        self.completed_steps.push(step);
+    }
+
+    /// `step` で止まったことを記録する
+    pub fn fail(mut self, step: PipelineStep, error: String) -> Self {
+        self.failed_step = Some(step);
+        self.error = Some(error);
+        self
+    }
+}
+
+/// レシートの日付（YYYY-MM-DD）から保存先の月（YYYYMM）を決める
+///
+/// 日付が無い・解釈できない場合は `fallback`（YYYYMM）を使う。
+pub fn resolve_target_month(date: Option<&str>, fallback: Option<&str>) -> Option<String> {
+    date.and_then(|date| chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok())
+        .map(|date| date.format("%Y%m").to_string())
+        .or_else(|| fallback.map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_month_prefers_receipt_date_and_records_failed_step() {
+        assert_eq!(
+            resolve_target_month(Some("2025-01-31"), Some("202502")).as_deref(),
+            Some("202501")
+        );
+        assert_eq!(
+            resolve_target_month(Some("2025/01/31"), Some("202502")).as_deref(),
+            Some("202502")
+        );
+        assert_eq!(resolve_target_month(None, None), None);
+
+        let mut result = PipelineResult::default();
+        result.complete(PipelineStep::Ocr);
+        let result = result.fail(PipelineStep::ResolveMonth, "日付なし".to_string());
+        assert_eq!(result.completed_steps, vec![PipelineStep::Ocr]);
+        assert_eq!(result.failed_step, Some(PipelineStep::ResolveMonth));
+        assert_eq!(
+            serde_json::to_value(&result).unwrap()["failedStep"],
+            "resolveMonth"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! サムネイルのプレースホルダ
+//!
+//! 本サムネイルの生成を待たずに一覧へ表示するため、BlurHash 文字列と長辺 32px の
+//! 超小型サムネイル（DataURL）を先に返す。本サムネイルは後から差し替える。
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::DynamicImage;
+use serde::Serialize;
+use std::f32::consts::PI;
+use std::io;
+
+/// 超小型サムネイルの長辺（ピクセル）
+const PLACEHOLDER_SIZE: u32 = 32;
+
+/// BlurHash の成分数（長辺方向, 短辺方向）
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+
+const BASE83_CHARS: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// 即時表示用のプレースホルダ
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Placeholder {
+    pub blurhash: String,
+    /// 元画像（EXIF の向きを適用後）の幅・高さ（縦横比の確保に使う）
+    pub width: u32,
+    pub height: u32,
+    /// 長辺 32px の PNG（DataURL）
+    pub data_url: String,
+}
+
+/// 画像からプレースホルダを作る
+pub fn generate_placeholder(content: &[u8]) -> image::ImageResult<Placeholder> {
+    let image = crate::thumbnail::decode_oriented(content)?;
+    let (width, height) = (image.width(), image.height());
+    let small = image.thumbnail(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE);
+
+    let mut png = io::Cursor::new(Vec::new());
+    small.write_to(&mut png, image::ImageFormat::Png)?;
+
+    Ok(Placeholder {
+        blurhash: encode_blurhash(&small),
+        width,
+        height,
+        data_url: format!(
+            "data:image/png;base64,{}",
+            STANDARD.encode(png.into_inner())
+        ),
+    })
+}
+
+/// BlurHash にエンコードする（縦長の画像は成分数も縦長にする）
+pub fn encode_blurhash(image: &DynamicImage) -> String {
+    let rgb = image.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    let (long, short) = BLURHASH_COMPONENTS;
+    let (components_x, components_y) = if height > width {
+        (short, long)
+    } else {
+        (long, short)
+    };
+
+    let linear: Vec<[f32; 3]> = rgb.pixels().map(|p| p.0.map(srgb_to_linear)).collect();
+
+    let mut factors = Vec::with_capacity((components_x * components_y) as usize);
+    for j in 0..components_y {
+        for i in 0..components_x {
+            let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut factor = [0.0f32; 3];
+            for y in 0..height {
+                let basis_y = (PI * j as f32 * y as f32 / height as f32).cos();
+                for x in 0..width {
+                    let basis = basis_y * (PI * i as f32 * x as f32 / width as f32).cos();
+                    let pixel = linear[(y * width + x) as usize];
+                    for c in 0..3 {
+                        factor[c] += basis * pixel[c];
+                    }
+                }
+            }
+            let scale = normalisation / (width * height) as f32;
+            factors.push(factor.map(|v| v * scale));
+        }
+    }
+
+    let (dc, ac) = factors.split_first().expect("成分は1つ以上");
+    let mut hash = String::new();
+    base83((components_x - 1) + (components_y - 1) * 9, 1, &mut hash);
+
+    let maximum_value = if ac.is_empty() {
+        base83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual_max = ac
+            .iter()
+            .flat_map(|f| f.iter())
+            .fold(0.0f32, |max, v| max.max(v.abs()));
+        let quantised_max = ((actual_max * 166.0 - 0.5).floor()).clamp(0.0, 82.0) as u32;
+        base83(quantised_max, 1, &mut hash);
+        (quantised_max + 1) as f32 / 166.0
+    };
+
+    let [r, g, b] = dc.map(linear_to_srgb);
+    base83((r << 16) + (g << 8) + b, 4, &mut hash);
+
+    for factor in ac {
+        let [r, g, b] = factor.map(|v| {
+            let signed = (v / maximum_value).abs().powf(0.5).copysign(v);
+            (signed * 9.0 + 9.5).floor().clamp(0.0, 18.0) as u32
+        });
+        base83(r * 19 * 19 + g * 19 + b, 2, &mut hash);
+    }
+
+    hash
+}
+
+fn base83(value: u32, length: u32, out: &mut String) {
+    for i in 1..=length {
+        let digit = (value / 83u32.pow(length - i)) % 83;
+        out.push(BASE83_CHARS[digit as usize] as char);
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u32 {
+    let v = value.clamp(0.0, 1.0);
+    if v <= 0.0031308 {
+        (v * 12.92 * 255.0 + 0.5) as u32
+    } else {
+        ((1.055 * v.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholder_has_blurhash_and_tiny_thumbnail() {
+        let white = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            40,
+            20,
+            image::Rgb([255, 255, 255]),
+        ));
+        // サイズフラグ（4x3 → 'L'）・最大値・平均色（白 → "TSUA"）・AC 成分 11 個
+        let hash = encode_blurhash(&white);
+        assert_eq!(hash.len(), 2 + 4 + 2 * 11);
+        assert!(hash.starts_with('L'));
+        assert_eq!(&hash[2..6], "TSUA");
+
+        let mut png = io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            60,
+            200,
+            image::Rgb([200, 30, 30]),
+        ))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+        let placeholder = generate_placeholder(&png.into_inner()).unwrap();
+        assert_eq!((placeholder.width, placeholder.height), (60, 200));
+        // 縦長は 3x4 成分（サイズフラグ 2 + 3 * 9 = 29 → 'T'）
+        assert!(placeholder.blurhash.starts_with('T'));
+        assert!(placeholder.data_url.starts_with("data:image/png;base64,"));
+    }
+}
@@ -0,0 +1,121 @@
+//! OCR結果の正規表現による整形
+//!
+//! `OcrSettings.postprocess_rules` に定義したルールを、抽出直後の各フィールドに定義順で当てる。
+//! 店舗名から支店名を除くなど、現場ごとの表記の揺れを設定だけで揃えるためのもの。
+
+use crate::money::Currency;
+use crate::providers::ReceiptData;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 整形できるフィールド（JSON のキー）
+pub const POSTPROCESS_FIELDS: &[&str] = &["merchant", "date", "currency", "receiverName"];
+
+/// 整形ルール
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostprocessRule {
+    /// 対象のフィールド（[`POSTPROCESS_FIELDS`] のいずれか）
+    pub field: String,
+    /// 正規表現パターン
+    pub pattern: String,
+    /// 置換後の文字列（`$1` などでキャプチャを参照できる）
+    #[serde(default)]
+    pub replacement: String,
+}
+
+impl PostprocessRule {
+    /// フィールド名と正規表現を検証する
+    pub fn validate(&self) -> Result<(), String> {
+        if !POSTPROCESS_FIELDS.contains(&self.field.as_str()) {
+            return Err(format!(
+                "整形ルールの対象フィールドが正しくありません（{}）: {}",
+                POSTPROCESS_FIELDS.join(", "),
+                self.field
+            ));
+        }
+        Regex::new(&self.pattern).map(|_| ()).map_err(|e| {
+            format!(
+                "整形ルールの正規表現が正しくありません: {}: {}",
+                self.pattern, e
+            )
+        })
+    }
+}
+
+fn field_mut<'a>(data: &'a mut ReceiptData, field: &str) -> Option<&'a mut Option<String>> {
+    match field {
+        "merchant" => Some(&mut data.merchant),
+        "date" => Some(&mut data.date),
+        "receiverName" => Some(&mut data.receiver_name),
+        _ => None,
+    }
+}
+
+fn replace(pattern: &Regex, text: &str, replacement: &str) -> Option<String> {
+    let replaced = pattern.replace_all(text, replacement);
+    let replaced = replaced.trim();
+    (!replaced.is_empty()).then(|| replaced.to_string())
+}
+
+/// ルールを定義順に当てる（置換で空になった値は未取得に戻す。不正なルールは飛ばす）
+///
+/// 通貨は置換後に通貨コードへ正規化し、推定できなければ未取得に戻す。
+pub fn apply_postprocess(data: &mut ReceiptData, rules: &[PostprocessRule]) {
+    for rule in rules {
+        let Ok(pattern) = Regex::new(&rule.pattern) else {
+            continue;
+        };
+        if rule.field == "currency" {
+            if let Some(currency) = data.currency.take() {
+                data.currency = replace(&pattern, &currency, &rule.replacement)
+                    .as_deref()
+                    .and_then(Currency::parse);
+            }
+            continue;
+        }
+        let Some(value) = field_mut(data, &rule.field) else {
+            continue;
+        };
+        if let Some(text) = value.as_deref() {
+            *value = replace(&pattern, text, &rule.replacement);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(field: &str, pattern: &str, replacement: &str) -> PostprocessRule {
+        PostprocessRule {
+            field: field.to_string(),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn rules_apply_in_order_and_reject_invalid_definitions() {
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.merchant = Some("ローソン 東京駅前店".to_string());
+        data.receiver_name = Some("様".to_string());
+
+        apply_postprocess(
+            &mut data,
+            &[
+                rule("merchant", r"\s*\S+店$", ""),
+                rule("merchant", "ローソン", "LAWSON"),
+                rule("receiverName", "^様$", ""),
+                rule("date", "(", ""),
+            ],
+        );
+        assert_eq!(data.merchant.as_deref(), Some("LAWSON"));
+        assert_eq!(data.receiver_name, None);
+        assert_eq!(data.date, None);
+
+        assert!(rule("merchant", r"(\d+)", "$1").validate().is_ok());
+        assert!(rule("merchant", "(", "").validate().is_err());
+        assert!(rule("amount", ".", "").validate().is_err());
+    }
+}
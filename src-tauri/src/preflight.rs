@@ -0,0 +1,399 @@
+//! OCRリクエストのプレフライト検証
+//!
+//! Base64 に変換して送る前に、ファイルの存在・サイズ・形式（内容から判定）・
+//! PDFのページ数を確認し、OCRに回せるかを判定する。
+
+use regex::bytes::Regex;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// 1リクエストで送れるファイルサイズの上限（Document AI のオンライン処理の上限）
+pub const MAX_FILE_SIZE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// 1リクエストで処理できるPDFのページ数の上限
+pub const MAX_PDF_PAGES: u32 = 15;
+
+/// プレフライト検証の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    pub exists: bool,
+    /// ファイルサイズ（バイト）
+    pub size: Option<u64>,
+    /// 内容から判定したMIMEタイプ（判定できない場合は `None`）
+    pub mime_type: Option<String>,
+    /// ページ数（PDFのみ。判定できない場合は `None`）
+    pub page_count: Option<u32>,
+    /// サイズまたはページ数が上限を超えているか
+    pub exceeds_limit: bool,
+    /// OCRに回せない理由
+    pub issues: Vec<String>,
+    /// OCRに回せるか
+    pub ok: bool,
+}
+
+/// 先頭バイトからMIMEタイプを判定する
+pub fn sniff_mime_type(header: &[u8]) -> Option<&'static str> {
+    match header {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => Some("image/tiff"),
+        [b'%', b'P', b'D', b'F', ..] => Some("application/pdf"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..]
+            if [b"heic", b"heix", b"hevc", b"mif1", b"msf1"]
+                .iter()
+                .any(|b| brand.starts_with(*b)) =>
+        {
+            Some("image/heic")
+        }
+        _ => None,
+    }
+}
+
+/// OCRプロバイダーに送れる形式か
+pub fn is_supported_mime_type(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "image/jpeg"
+            | "image/png"
+            | "image/gif"
+            | "image/bmp"
+            | "image/tiff"
+            | "image/webp"
+            | "application/pdf"
+    )
+}
+
+/// ページオブジェクト（`/Type /Page`、`/Pages` は除く）
+static PDF_PAGE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/Type\s*/Page(?:[^s]|$)").unwrap());
+
+/// ページツリーのページ数（`/Count N`）
+static PDF_COUNT_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/Count\s+(\d+)").unwrap());
+
+/// PDFのページ数を簡易的に数える
+///
+/// ページオブジェクトを数え、圧縮オブジェクトストリームで数えられない場合は
+/// ページツリーの `/Count` の最大値を使う。どちらも無ければ `None`。
+pub fn pdf_page_count_hint(content: &[u8]) -> Option<u32> {
+    let pages = PDF_PAGE_PATTERN.find_iter(content).count() as u32;
+    if pages > 0 {
+        return Some(pages);
+    }
+
+    PDF_COUNT_PATTERN
+        .captures_iter(content)
+        .filter_map(|caps| std::str::from_utf8(&caps[1]).ok()?.parse::<u32>().ok())
+        .max()
+}
+
+/// 末尾の `%%EOF` を探す範囲（バイト）
+const PDF_EOF_SEARCH_BYTES: usize = 1024;
+
+/// 間接参照 `/{key} N G R` の最後のもの（増分更新では後ろが最新）
+fn last_reference(content: &[u8], key: &str) -> Option<(u32, u32)> {
+    let pattern = Regex::new(&format!(r"/{}\s+(\d+)\s+(\d+)\s+R", key)).ok()?;
+    let caps = pattern.captures_iter(content).last()?;
+    let number = std::str::from_utf8(&caps[1]).ok()?.parse().ok()?;
+    let generation = std::str::from_utf8(&caps[2]).ok()?.parse().ok()?;
+    Some((number, generation))
+}
+
+/// 間接オブジェクト `N G obj ... endobj` の中身の最後のもの
+fn last_object(content: &[u8], (number, generation): (u32, u32)) -> Option<&[u8]> {
+    let pattern = Regex::new(&format!(
+        r"(?s-u)(?:^|[^0-9]){}\s+{}\s+obj\b(.*?)endobj",
+        number, generation
+    ))
+    .ok()?;
+    let caps = pattern.captures_iter(content).last()?;
+    caps.get(1).map(|body| body.as_bytes())
+}
+
+/// トレーラーから `/Root` → `/Pages` → `/Count` をたどってページ数を得る
+fn pdf_page_count_from_catalog(content: &[u8]) -> Option<u32> {
+    let catalog = last_object(content, last_reference(content, "Root")?)?;
+    let pages = last_object(content, last_reference(catalog, "Pages")?)?;
+    let caps = PDF_COUNT_PATTERN.captures(pages)?;
+    std::str::from_utf8(&caps[1]).ok()?.parse().ok()
+}
+
+/// PDFのページ数を取得する（ページはレンダリングせず、ページツリーのメタデータから読む）
+///
+/// カタログが圧縮オブジェクトストリームにあってたどれない場合は
+/// [`pdf_page_count_hint`] で数える。PDFとして壊れている場合はエラー。
+pub fn pdf_page_count(content: &[u8]) -> Result<u32, String> {
+    if !content.starts_with(b"%PDF-") {
+        return Err("PDFではありません（ヘッダがありません）".to_string());
+    }
+
+    let tail = &content[content.len().saturating_sub(PDF_EOF_SEARCH_BYTES)..];
+    if !tail.windows(5).any(|window| window == b"%%EOF") {
+        return Err("PDFが壊れているか途中で切れています（%%EOF がありません）".to_string());
+    }
+
+    match pdf_page_count_from_catalog(content).or_else(|| pdf_page_count_hint(content)) {
+        Some(0) => Err("PDFにページがありません".to_string()),
+        Some(pages) => Ok(pages),
+        None => Err("PDFのページツリーを読み取れませんでした".to_string()),
+    }
+}
+
+/// ファイルのページ数を取得する（画像は常に 1）
+pub fn page_count(path: &Path) -> Result<u32, String> {
+    let mut header = Vec::with_capacity(16);
+    fs::File::open(path)
+        .and_then(|file| file.take(16).read_to_end(&mut header))
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+
+    match sniff_mime_type(&header) {
+        Some("application/pdf") => {
+            let content =
+                fs::read(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+            pdf_page_count(&content)
+        }
+        Some(_) => Ok(1),
+        None => Err(format!(
+            "対応していないファイル形式です: {}",
+            path.display()
+        )),
+    }
+}
+
+/// ファイルをプレフライト検証する
+pub fn preflight(path: &Path) -> io::Result<PreflightReport> {
+    let mut report = PreflightReport {
+        exists: false,
+        size: None,
+        mime_type: None,
+        page_count: None,
+        exceeds_limit: false,
+        issues: Vec::new(),
+        ok: false,
+    };
+
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            report.issues.push("ファイルが存在しません".to_string());
+            return Ok(report);
+        }
+        Err(e) => return Err(e),
+    };
+    report.exists = true;
+
+    if !metadata.is_file() {
+        report.issues.push("ファイルではありません".to_string());
+        return Ok(report);
+    }
+
+    let size = metadata.len();
+    report.size = Some(size);
+    if size == 0 {
+        report.issues.push("ファイルが空です".to_string());
+    } else if size > MAX_FILE_SIZE_BYTES {
+        report.exceeds_limit = true;
+        report.issues.push(format!(
+            "ファイルサイズが上限（{}MB）を超えています",
+            MAX_FILE_SIZE_BYTES / 1024 / 1024
+        ));
+    }
+
+    let mut header = Vec::with_capacity(16);
+    fs::File::open(path)?.take(16).read_to_end(&mut header)?;
+    let mime_type = sniff_mime_type(&header);
+    report.mime_type = mime_type.map(String::from);
+
+    match mime_type {
+        None => report
+            .issues
+            .push("対応していないファイル形式です".to_string()),
+        Some(mime_type) if !is_supported_mime_type(mime_type) => report
+            .issues
+            .push(format!("OCRに対応していない形式です: {}", mime_type)),
+        Some("application/pdf") => {
+            report.page_count = pdf_page_count(&fs::read(path)?).ok();
+            if report.page_count.is_some_and(|pages| pages > MAX_PDF_PAGES) {
+                report.exceeds_limit = true;
+                report.issues.push(format!(
+                    "ページ数が上限（{}ページ）を超えています",
+                    MAX_PDF_PAGES
+                ));
+            }
+        }
+        Some(_) => {}
+    }
+
+    report.ok = report.issues.is_empty();
+    Ok(report)
+}
+
+/// Base64 で受け取ったOCRリクエストの検証結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestValidation {
+    /// リクエストの位置
+    pub index: usize,
+    pub file_path: String,
+    /// 正規化したMIMEタイプ（OCRに回せない形式なら `None`）
+    pub mime_type: Option<String>,
+    /// デコード後のサイズ（バイト）
+    pub size: Option<u64>,
+    /// ページ数（PDFのみ。判定できない場合は `None`）
+    pub page_count: Option<u32>,
+    /// 同じ内容・設定のOCR結果がキャッシュにあるか
+    pub cache_hit: bool,
+    /// サイズまたはページ数が上限を超えているか
+    pub exceeds_limit: bool,
+    /// OCRに回せない理由
+    pub issues: Vec<String>,
+    pub ok: bool,
+}
+
+/// Base64 の内容と申告された MIME からリクエストを検証する（`cache_hit` は呼び出し側で設定する）
+pub fn validate_request(
+    index: usize,
+    file_path: &str,
+    file_content: &str,
+    mime_type: &str,
+) -> RequestValidation {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let mut validation = RequestValidation {
+        index,
+        file_path: file_path.to_string(),
+        mime_type: None,
+        size: None,
+        page_count: None,
+        cache_hit: false,
+        exceeds_limit: false,
+        issues: Vec::new(),
+        ok: false,
+    };
+
+    let content = match STANDARD.decode(file_content) {
+        Ok(content) => content,
+        Err(e) => {
+            validation
+                .issues
+                .push(format!("Base64デコードに失敗しました: {}", e));
+            return validation;
+        }
+    };
+
+    let size = content.len() as u64;
+    validation.size = Some(size);
+    if size == 0 {
+        validation.issues.push("ファイルが空です".to_string());
+        return validation;
+    }
+    if size > MAX_FILE_SIZE_BYTES {
+        validation.exceeds_limit = true;
+        validation.issues.push(format!(
+            "ファイルサイズが上限（{}MB）を超えています",
+            MAX_FILE_SIZE_BYTES / 1024 / 1024
+        ));
+    }
+
+    match crate::mime::normalize_mime_type(mime_type, &content) {
+        Ok(normalized) => {
+            if normalized == "application/pdf" {
+                match pdf_page_count(&content) {
+                    Ok(pages) => {
+                        validation.page_count = Some(pages);
+                        if pages > MAX_PDF_PAGES {
+                            validation.exceeds_limit = true;
+                            validation.issues.push(format!(
+                                "ページ数が上限（{}ページ）を超えています",
+                                MAX_PDF_PAGES
+                            ));
+                        }
+                    }
+                    Err(e) => validation.issues.push(e),
+                }
+            }
+            validation.mime_type = Some(normalized);
+        }
+        Err(e) => validation.issues.push(e),
+    }
+
+    validation.ok = validation.issues.is_empty();
+    validation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_mime_type_detects_by_magic_bytes() {
+        assert_eq!(
+            sniff_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some("image/jpeg")
+        );
+        assert_eq!(sniff_mime_type(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff_mime_type(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_mime_type(b"\0\0\0\x18ftypheic"), Some("image/heic"));
+        assert_eq!(sniff_mime_type(b"\0\0\0\x18ftypisom"), None);
+        assert_eq!(sniff_mime_type(b"hello"), None);
+    }
+
+    #[test]
+    fn pdf_page_count_hint_counts_pages_or_falls_back_to_count() {
+        let plain = b"<< /Type /Pages /Count 2 >> << /Type /Page >> << /Type/Page/Parent 1 0 R >>";
+        assert_eq!(pdf_page_count_hint(plain), Some(2));
+
+        let compressed = b"<< /Type /Pages /Kids [...] /Count 3 >> << /Count 1 >>";
+        assert_eq!(pdf_page_count_hint(compressed), Some(3));
+
+        assert_eq!(pdf_page_count_hint(b"%PDF-1.7 binary"), None);
+    }
+
+    #[test]
+    fn pdf_page_count_follows_catalog_and_rejects_broken_files() {
+        // 増分更新で 2 ページ → 3 ページになった PDF（後ろのオブジェクトが最新）
+        let pdf = b"%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >> endobj\n\
+            trailer << /Root 1 0 R >>\n%%EOF\n\
+            12 0 obj << /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [12 0 R] /Count 3 >> endobj\n\
+            trailer << /Root 1 0 R /Prev 9 >>\n%%EOF\n";
+        assert_eq!(pdf_page_count(pdf), Ok(3));
+
+        assert!(pdf_page_count(b"%PDF-1.4\n1 0 obj << /Type /Catalog").is_err());
+        assert!(pdf_page_count(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn validate_request_reports_mime_and_page_issues() {
+        // "%PDF-1.4 ... /Count 20 ... %%EOF"
+        let pdf = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            b"%PDF-1.4\n<< /Type /Pages /Count 20 >>\n%%EOF\n",
+        );
+        let validation = validate_request(0, "a.pdf", &pdf, "application/x-pdf");
+        assert_eq!(validation.mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(validation.page_count, Some(20));
+        assert!(validation.exceeds_limit);
+        assert!(!validation.ok);
+
+        let jpeg = validate_request(1, "b.jpg", "/9j/4AAQ", "image/jpg");
+        assert_eq!(jpeg.mime_type.as_deref(), Some("image/jpeg"));
+        assert!(jpeg.ok);
+
+        // HEIC は OCR の前に JPEG に変換するため通す
+        let heic = validate_request(4, "e.heic", "AAAAGGZ0eXBoZWljAAAAAA==", "image/heif");
+        assert_eq!(heic.mime_type.as_deref(), Some("image/heic"));
+        assert!(heic.ok);
+
+        assert!(!validate_request(2, "c.txt", "aGVsbG8=", "text/plain").ok);
+        assert!(!validate_request(3, "d.jpg", "***", "image/jpeg").ok);
+    }
+}
@@ -0,0 +1,201 @@
+//! 共有プロファイルの監視
+//!
+//! チームの共有フォルダに置いた OCR 設定の JSON（`OcrSettings.profile_path`）を監視し、
+//! 変更されたらストアの設定に重ねて保存し、`ocr-settings-reloaded` を emit する。
+//! JSON として読めない・形式の誤りがある変更は無視し、現行の設定を維持する。
+
+use crate::providers::OcrSettings;
+use crate::{settings_migration, store_keys};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 反映したことを知らせるイベント名
+pub const SETTINGS_RELOADED_EVENT: &str = "ocr-settings-reloaded";
+
+/// `ocr-settings-reloaded` イベントの内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrSettingsReloadedEvent {
+    /// 読み込んだプロファイルのパス
+    pub path: String,
+}
+
+/// 監視中のプロファイル（差し替えるとドロップで前の監視が止まる）
+#[derive(Default)]
+pub struct ProfileWatcher {
+    current: Mutex<Option<(PathBuf, RecommendedWatcher)>>,
+}
+
+impl ProfileWatcher {
+    /// 監視するプロファイルを切り替える（未指定なら監視をやめる。同じパスなら何もしない）
+    ///
+    /// エディタの保存は置き換え（rename）になることがあるため、親ディレクトリを監視して
+    /// ファイル名で絞り込む。監視を始めた時点の内容も一度反映する（監視していない間の変更を取りこぼさない）。
+    pub fn watch(&self, app: &AppHandle, path: Option<&str>) -> Result<(), String> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let path = path
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from);
+        if current.as_ref().map(|(watched, _)| watched) == path.as_ref() {
+            return Ok(());
+        }
+        *current = None;
+
+        let Some(path) = path else {
+            return Ok(());
+        };
+        let file_name = path
+            .file_name()
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| format!("プロファイルのパスが正しくありません: {}", path.display()))?;
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+
+        let watched_app = app.clone();
+        let target = path.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                let changed = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == Some(file_name.as_os_str()));
+                if changed {
+                    on_profile_changed(&watched_app, &target);
+                }
+            })
+            .map_err(|e| format!("プロファイルの監視を開始できませんでした: {}", e))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("プロファイルの監視を開始できませんでした: {}", e))?;
+
+        *current = Some((path.clone(), watcher));
+        drop(current);
+
+        on_profile_changed(app, &path);
+        Ok(())
+    }
+}
+
+/// プロファイルを現行の設定に重ねる（プロファイルに無い項目・監視するパスは現行のまま）
+///
+/// どちらも旧形式なら現在の形式に移行してから重ね、プロバイダーのサブ設定は項目ごとに重ねる。
+pub fn apply_profile(content: &str, current: &Value) -> Result<OcrSettings, String> {
+    let mut profile: Value = serde_json::from_str(content)
+        .map_err(|e| format!("プロファイルを JSON として読み込めませんでした: {}", e))?;
+    settings_migration::migrate_ocr_settings(&mut profile);
+    let Value::Object(profile) = profile else {
+        return Err("プロファイルが JSON オブジェクトではありません".to_string());
+    };
+
+    let mut current = current.clone();
+    settings_migration::migrate_ocr_settings(&mut current);
+    let mut merged = current.as_object().cloned().unwrap_or_default();
+    for (key, value) in profile {
+        if key == "profilePath" {
+            continue;
+        }
+        match (merged.get_mut(&key), value) {
+            (Some(Value::Object(section)), Value::Object(overlay)) => section.extend(overlay),
+            (_, value) => {
+                merged.insert(key, value);
+            }
+        }
+    }
+    let settings: OcrSettings = serde_json::from_value(Value::Object(merged))
+        .map_err(|e| format!("プロファイルを設定として読み込めませんでした: {}", e))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// プロファイルを読み直してストアに保存する（内容が変わらなければ `false`）
+fn reload(app: &AppHandle, path: &Path) -> Result<bool, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("プロファイルの読み込みに失敗しました: {}", e))?;
+    store_keys::with_store_transaction(app, |store| {
+        let current = store
+            .get(store_keys::OCR_SETTINGS)
+            .unwrap_or(Value::Object(Default::default()));
+
+        let settings = apply_profile(&content, &current)?;
+        let value = settings_migration::to_store_value(&settings)?;
+        if value == current {
+            return Ok(false);
+        }
+        store.set(store_keys::OCR_SETTINGS, value);
+        Ok(true)
+    })
+}
+
+/// 変更を反映してイベントを送る（反映できない変更はエラーログに残して無視する）
+fn on_profile_changed(app: &AppHandle, path: &Path) {
+    match reload(app, path) {
+        Ok(true) => {
+            // 設定が変わるので接続テストの結果は使えなくなる
+            app.state::<crate::commands::ConnectionTestCache>()
+                .invalidate();
+            let _ = app.emit(
+                SETTINGS_RELOADED_EVENT,
+                OcrSettingsReloadedEvent {
+                    path: path.to_string_lossy().into_owned(),
+                },
+            );
+        }
+        Ok(false) => {}
+        Err(e) => {
+            let _ = crate::errorlog::write_log_entry(
+                app,
+                "rust-profile",
+                &e,
+                None,
+                None,
+                Some(&path.to_string_lossy()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_profile_overlays_valid_json_and_rejects_invalid() {
+        let current = serde_json::json!({
+            "google": { "projectId": "my-project", "serviceAccountJson": "{}" },
+            "profilePath": "/shared/profile.json"
+        });
+
+        // 旧形式（フラット）のプロファイルもサブ設定に重ねる
+        for profile in [
+            r#"{ "projectId": "team-project", "escalationChain": ["veryfi"], "profilePath": "/x" }"#,
+            r#"{ "google": { "projectId": "team-project" }, "escalationChain": ["veryfi"] }"#,
+        ] {
+            let settings = apply_profile(profile, &current).unwrap();
+            let google = settings.google.as_ref().unwrap();
+            assert_eq!(google.project_id.as_deref(), Some("team-project"));
+            assert_eq!(google.service_account_json.as_deref(), Some("{}"));
+            assert_eq!(settings.escalation_chain, vec!["veryfi".to_string()]);
+            assert_eq!(
+                settings.profile_path.as_deref(),
+                Some("/shared/profile.json")
+            );
+        }
+
+        assert!(apply_profile(r#"{ "projectId": "#, &current).is_err());
+        assert!(apply_profile("[]", &current).is_err());
+        assert!(apply_profile(r#"{ "location": "us.evil.com/" }"#, &current).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! プロバイダーのエスカレーション
+//!
+//! 安価なプロバイダーから順に試し、抽出結果の充足率が閾値に満たない場合だけ
+//! 次の（高精度な）プロバイダーに上げる。
+
+use super::timing::OcrTiming;
+use super::tuning::{extract_with_tuning, FileClock, ProviderLimits};
+use super::{OcrProvider, OcrSettings, ReceiptData};
+use crate::error::{AppError, ProviderError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 充足率の既定の閾値（主要項目がすべて取れていること）
+pub const DEFAULT_MIN_COMPLETENESS: f64 = 1.0;
+
+/// チェーンのどの結果を採用するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adoption {
+    /// 充足率が閾値（`escalation_min_completeness`）を満たした結果（エスカレーション）
+    Completeness,
+    /// 最初に成功した結果（失敗時だけ次のプロバイダーに切り替えるフォールバック）
+    FirstSuccess,
+}
+
+/// エスカレーションの各段の記録
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EscalationStep {
+    /// プロバイダー名
+    pub provider: String,
+    /// 抽出結果の充足率（0.0〜1.0、失敗時は `None`）
+    pub completeness: Option<f64>,
+    /// エラーメッセージ（失敗時のみ）
+    pub error: Option<String>,
+}
+
+/// エスカレーションの結果
+pub struct EscalationOutcome {
+    /// 採用した抽出結果（全段失敗時は最後のエラー）
+    pub result: Result<ReceiptData, ProviderError>,
+    /// 採用した結果を返したプロバイダー名（全段失敗時は `None`）
+    pub provider_name: Option<String>,
+    /// 試行した各段の記録
+    pub steps: Vec<EscalationStep>,
+    /// 停止要求により抽出に着手せずに終えたか
+    pub stopped: bool,
+}
+
+/// 主要項目（店舗名・日付・金額）の充足率
+pub fn completeness(data: &ReceiptData) -> f64 {
+    let filled = [
+        data.merchant.is_some(),
+        data.date.is_some(),
+        data.amount.is_some(),
+    ]
+    .iter()
+    .filter(|filled| **filled)
+    .count();
+
+    filled as f64 / 3.0
+}
+
+/// チェーンの先頭から順に抽出し、充足率が閾値を満たした時点の結果を採用する
+///
+/// `adoption` が [`Adoption::FirstSuccess`] なら閾値を見ずに最初に成功した結果を採用する。
+/// どの段も閾値に届かなかった場合は、成功した中で最も充足率の高い結果を採用する
+/// （同率なら先の段）。全段が失敗した場合は最後のエラーを返す。
+/// 各段にはプロバイダー別のタイムアウト・リトライを適用し、`limits` があれば
+/// プロバイダーごとの同時実行数も制限する（実行枠の確保後に停止要求があれば着手しない）。
+/// `clock` があれば最初の段の実行枠を確保した時点を記録する。
+/// `timing` があれば全段の所要時間を加算する。
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_with_escalation(
+    chain: &[Arc<dyn OcrProvider>],
+    file_path: &str,
+    file_content: &str,
+    mime_type: &str,
+    settings: &OcrSettings,
+    adoption: Adoption,
+    limits: Option<&ProviderLimits>,
+    clock: Option<&FileClock>,
+    mut timing: Option<&mut OcrTiming>,
+) -> EscalationOutcome {
+    let min_completeness = match adoption {
+        Adoption::Completeness => settings
+            .escalation_min_completeness
+            .unwrap_or(DEFAULT_MIN_COMPLETENESS),
+        Adoption::FirstSuccess => 0.0,
+    };
+
+    let mut steps = Vec::new();
+    let mut best: Option<(f64, ReceiptData, &str)> = None;
+    let mut last_error = ProviderError::permanent("OCRプロバイダーが見つかりません");
+
+    for provider in chain {
+        let extracted = {
+            let _permit = match limits {
+                Some(limits) => limits.acquire(provider.name()).await,
+                None => None,
+            };
+            // 実行枠を待つ間に停止が要求されていたら着手しない
+            if limits.is_some_and(ProviderLimits::is_stopped) {
+                return EscalationOutcome {
+                    result: Err(AppError::Cancelled.into()),
+                    provider_name: None,
+                    steps,
+                    stopped: true,
+                };
+            }
+            if let Some(clock) = clock {
+                clock.start();
+            }
+            extract_with_tuning(
+                provider.as_ref(),
+                file_path,
+                file_content,
+                mime_type,
+                settings,
+                timing.as_deref_mut(),
+            )
+            .await
+        };
+
+        match extracted {
+            Ok(data) => {
+                let score = completeness(&data);
+                steps.push(EscalationStep {
+                    provider: provider.name().to_string(),
+                    completeness: Some(score),
+                    error: None,
+                });
+
+                if score >= min_completeness {
+                    return EscalationOutcome {
+                        result: Ok(data),
+                        provider_name: Some(provider.name().to_string()),
+                        steps,
+                        stopped: false,
+                    };
+                }
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_score, _, _)| score > *best_score)
+                {
+                    best = Some((score, data, provider.name()));
+                }
+            }
+            Err(e) => {
+                steps.push(EscalationStep {
+                    provider: provider.name().to_string(),
+                    completeness: None,
+                    error: Some(e.message.clone()),
+                });
+                last_error = e;
+            }
+        }
+    }
+
+    let provider_name = best.as_ref().map(|(_, _, name)| name.to_string());
+    EscalationOutcome {
+        result: best.map(|(_, data, _)| data).ok_or(last_error),
+        provider_name,
+        steps,
+        stopped: false,
+    }
+}
+
+/// プロバイダーの切り替え（`provider-failover` イベント）
+///
+/// バッチでは同じ切り替えをまとめ、`count` に件数、`files` に対象ファイルを持つ。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderFailover {
+    /// 切り替え元（不調だった）プロバイダー
+    pub from: String,
+    /// 切り替え先のプロバイダー
+    pub to: String,
+    /// 切り替えた理由（最後に発生したもの）
+    pub reason: String,
+    pub count: usize,
+    pub files: Vec<String>,
+}
+
+/// エスカレーションの記録から、次の段へ切り替えた箇所を取り出す
+pub fn detect_failovers(file_name: &str, steps: &[EscalationStep]) -> Vec<ProviderFailover> {
+    steps
+        .windows(2)
+        .map(|pair| {
+            let reason = match (&pair[0].error, pair[0].completeness) {
+                (Some(error), _) => error.clone(),
+                (None, Some(score)) => format!("充足率が閾値未満（{:.0}%）", score * 100.0),
+                (None, None) => String::new(),
+            };
+            ProviderFailover {
+                from: pair[0].provider.clone(),
+                to: pair[1].provider.clone(),
+                reason,
+                count: 1,
+                files: vec![file_name.to_string()],
+            }
+        })
+        .collect()
+}
+
+/// 同じ切り替え元・先の組をまとめ、件数の多い順に並べる
+pub fn merge_failovers(failovers: Vec<ProviderFailover>) -> Vec<ProviderFailover> {
+    let mut merged: Vec<ProviderFailover> = Vec::new();
+    for failover in failovers {
+        match merged
+            .iter_mut()
+            .find(|m| m.from == failover.from && m.to == failover.to)
+        {
+            Some(existing) => {
+                existing.count += failover.count;
+                existing.files.extend(failover.files);
+                existing.reason = failover.reason;
+            }
+            None => merged.push(failover),
+        }
+    }
+    merged.sort_by_key(|failover| std::cmp::Reverse(failover.count));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(provider: &str, completeness: Option<f64>, error: Option<&str>) -> EscalationStep {
+        EscalationStep {
+            provider: provider.to_string(),
+            completeness,
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn failovers_are_detected_per_step_and_merged_by_provider_pair() {
+        let timeout = [
+            step("cheap", None, Some("タイムアウト")),
+            step("google", Some(1.0), None),
+        ];
+        let partial = [
+            step("cheap", Some(2.0 / 3.0), None),
+            step("google", Some(1.0), None),
+        ];
+        assert_eq!(
+            detect_failovers("b.jpg", &partial)[0].reason,
+            "充足率が閾値未満（67%）"
+        );
+        assert!(detect_failovers("c.jpg", &partial[1..]).is_empty());
+
+        let merged = merge_failovers(
+            [
+                detect_failovers("a.jpg", &timeout),
+                detect_failovers("b.jpg", &partial),
+            ]
+            .concat(),
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].count, 2);
+        assert_eq!(merged[0].files, vec!["a.jpg", "b.jpg"]);
+    }
+}
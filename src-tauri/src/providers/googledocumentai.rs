@@ -2,22 +2,159 @@
 //!
 //! Google Cloud Document AI を使用してレシート画像からデータを抽出する。
 
-use super::{OcrProvider, OcrSettings, ReceiptData};
+use super::single_flight::SingleFlight;
+use super::timing::{OcrPhase, OcrTiming, PhaseRecorder};
+use super::{request_error, LineItem, OcrProvider, OcrSettings, ReceiptConfidence, ReceiptData};
+use crate::error::{AppError, ProviderError};
+use crate::money::Currency;
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use futures::TryFutureExt;
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+/// トークンエンドポイントの既定値
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+/// メタデータサーバーのアクセストークン取得先（GCE・Cloud Run など GCP 上で実行している場合）
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// メタデータサーバーの応答待ち（GCP の外では繋がらないので短くする）
+const METADATA_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// ADC で取得したトークンのキャッシュのキー（サービスアカウントのメールアドレスの代わり）
+const ADC_CACHE_KEY: &str = "adc";
+
+/// 有効期限のこの秒数前からはキャッシュしたトークンを使わず取り直す
+const TOKEN_EXPIRY_MARGIN_SECS: i64 = 60;
+
+/// 一時的なエラーとして再送するステータス
+const RETRYABLE_STATUSES: &[u16] = &[429, 500, 503];
+
+/// 一時的なエラーで再送する最大回数
+const MAX_BACKOFF_RETRIES: u32 = 3;
+
+/// これより長い `Retry-After` は待たずにエラーとして返す（日次クォータの超過など）
+const MAX_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 項目ごとに探すエンティティ名の既定の候補（`GoogleSettings.entity_mapping` で上書きできる）
+pub const DEFAULT_ENTITY_TYPES: &[(&str, &[&str])] = &[
+    (
+        "merchant",
+        &[
+            "merchant_name",
+            "supplier_name",
+            "vendor_name",
+            "receipt_merchant_name",
+        ],
+    ),
+    (
+        "date",
+        &[
+            "receipt_date",
+            "purchase_date",
+            "transaction_date",
+            "invoice_date",
+            "date",
+        ],
+    ),
+    (
+        "time",
+        &["purchase_time", "receipt_time", "transaction_time"],
+    ),
+    (
+        "amount",
+        &["total_amount", "invoice_total", "receipt_total"],
+    ),
+    ("currency", &["currency"]),
+    ("taxAmount", &["total_tax_amount", "tax_amount"]),
+    ("netAmount", &["net_amount"]),
+    ("taxRate", &["tax_rate"]),
+    (
+        "receiverName",
+        &[
+            "receiver_name",
+            "ship_to_name",
+            "bill_to_name",
+            "customer_name",
+        ],
+    ),
+    (
+        "documentNumber",
+        &[
+            "receipt_id",
+            "invoice_id",
+            "receipt_number",
+            "document_number",
+        ],
+    ),
+];
 
 /// サービスアカウントキー
-#[derive(Debug, Clone, Deserialize)]
+///
+/// 秘密鍵は破棄時にゼロ化する。トークン取得の間だけ保持し、使い終えたら破棄する。
+#[derive(Deserialize)]
 struct ServiceAccountKey {
     client_email: String,
-    private_key: String,
+    private_key: Zeroizing<String>,
     #[serde(default)]
     token_uri: Option<String>,
 }
 
+/// ADC の認証情報ファイル（`GOOGLE_APPLICATION_CREDENTIALS` または gcloud の既定の場所）
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AdcCredentials {
+    /// サービスアカウントの鍵
+    ServiceAccount(ServiceAccountKey),
+    /// `gcloud auth application-default login` で作ったユーザーの認証情報
+    AuthorizedUser(AuthorizedUserKey),
+}
+
+/// ユーザーの認証情報（リフレッシュトークン）
+#[derive(Deserialize)]
+struct AuthorizedUserKey {
+    client_id: String,
+    client_secret: Zeroizing<String>,
+    refresh_token: Zeroizing<String>,
+    /// API の利用料・クォータを割り当てるプロジェクト（`x-goog-user-project` で送る）
+    #[serde(default)]
+    quota_project_id: Option<String>,
+}
+
+/// 取得したアクセストークン
+#[derive(Debug, Clone, PartialEq)]
+struct AccessToken {
+    token: String,
+    /// ユーザーの認証情報で指定されたクォータのプロジェクト
+    quota_project_id: Option<String>,
+}
+
+impl AccessToken {
+    fn new(token: String) -> Self {
+        Self {
+            token,
+            quota_project_id: None,
+        }
+    }
+
+    /// 認証ヘッダー（クォータのプロジェクトがあれば `x-goog-user-project` も）を付ける
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = request.header("Authorization", format!("Bearer {}", self.token));
+        match &self.quota_project_id {
+            Some(project) => request.header("x-goog-user-project", project),
+            None => request,
+        }
+    }
+}
+
 /// JWTクレーム
 #[derive(Debug, Serialize)]
 struct Claims {
@@ -33,6 +170,18 @@ struct Claims {
 #[derive(Debug, Deserialize)]
 struct TokenResponse {
     access_token: String,
+    /// 有効期間（秒）
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+/// キャッシュしたアクセストークン
+struct CachedToken {
+    /// 取得したサービスアカウント（設定で差し替えられたら使わない）
+    client_email: String,
+    access_token: AccessToken,
+    /// 有効期限（UNIX 秒）
+    expires_at: i64,
 }
 
 /// Document AI レスポンス
@@ -43,7 +192,17 @@ struct DocumentAiResponse {
 
 #[derive(Debug, Deserialize)]
 struct DocumentAiDocument {
+    /// OCR全文（言語判定に使う）
+    text: Option<String>,
     entities: Option<Vec<DocumentAiEntity>>,
+    revisions: Option<Vec<DocumentAiRevision>>,
+}
+
+/// ドキュメントのリビジョン（どのプロセッサで処理されたか）
+#[derive(Debug, Deserialize)]
+struct DocumentAiRevision {
+    /// 例: projects/{p}/locations/{l}/processors/{id}/processorVersions/{version}
+    processor: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,6 +211,8 @@ struct DocumentAiEntity {
     #[serde(rename = "type")]
     entity_type: Option<String>,
     mention_text: Option<String>,
+    /// 抽出の信頼度（0.0〜1.0）
+    confidence: Option<f32>,
     normalized_value: Option<DocumentAiNormalizedValue>,
     properties: Option<Vec<DocumentAiEntity>>,
 }
@@ -62,6 +223,7 @@ struct DocumentAiNormalizedValue {
     text: Option<String>,
     money_value: Option<DocumentAiMoneyValue>,
     date_value: Option<DocumentAiDateValue>,
+    datetime_value: Option<DocumentAiDateTimeValue>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -79,15 +241,130 @@ struct DocumentAiDateValue {
     day: Option<i32>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentAiDateTimeValue {
+    hours: Option<u32>,
+    minutes: Option<u32>,
+    seconds: Option<u32>,
+    /// UTC からのオフセット（`"32400s"` 形式）
+    utc_offset: Option<String>,
+}
+
+/// Google API のエラーレスポンス
+#[derive(Debug, Deserialize)]
+struct GoogleApiErrorResponse {
+    error: GoogleApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleApiError {
+    status: Option<String>,
+    message: Option<String>,
+    #[serde(default)]
+    details: Vec<serde_json::Value>,
+}
+
+/// ロケーション推定の根拠
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LocationSource {
+    /// OSのロケールの国
+    Locale,
+    /// OSのタイムゾーン（UTCオフセット）
+    Timezone,
+}
+
 /// Google Document AI プロバイダー
 pub struct GoogleDocumentAiProvider {
     client: Client,
+    /// サービスアカウント（`client_email`）ごとのトークン取得（並列タスクの同時取得を1回にまとめる）
+    token_refresh: SingleFlight<String, Result<(AccessToken, i64), ProviderError>>,
+    /// 最後に取得したアクセストークン（有効期限の60秒前まで使い回す）
+    token_cache: Mutex<Option<CachedToken>>,
 }
 
 impl GoogleDocumentAiProvider {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            token_refresh: SingleFlight::default(),
+            token_cache: Mutex::new(None),
+        }
+    }
+
+    /// 国コードに近い Document AI のロケーション
+    fn location_for_country(country: &str) -> &'static str {
+        match country {
+            "JP" => "asia-northeast1",
+            "KR" => "asia-northeast3",
+            "TW" => "asia-east1",
+            "HK" => "asia-east2",
+            "SG" | "MY" | "ID" | "TH" | "VN" | "PH" => "asia-southeast1",
+            "IN" => "asia-south1",
+            "AU" | "NZ" => "australia-southeast1",
+            "GB" => "europe-west2",
+            "DE" | "AT" | "CH" => "europe-west3",
+            "FR" | "IT" | "ES" | "NL" | "BE" | "IE" | "PT" | "SE" | "DK" | "FI" | "NO" | "PL"
+            | "CZ" => "eu",
+            "CA" => "northamerica-northeast1",
+            _ => "us",
+        }
+    }
+
+    /// UTCオフセット（秒）に近い Document AI のロケーション
+    fn location_for_utc_offset(offset_secs: i32) -> &'static str {
+        match offset_secs {
+            32_400 => "asia-northeast1",
+            19_800 => "asia-south1",
+            25_200..=28_800 => "asia-southeast1",
+            36_000..=39_600 => "australia-southeast1",
+            -3_600..=10_800 => "eu",
+            _ => "us",
+        }
+    }
+
+    /// OSのロケール（`ja-JP` など）とUTCオフセットからロケーションを推定
+    ///
+    /// ロケールに国が含まれていればそれを優先し、無ければタイムゾーンから推定する。
+    /// 戻り値は（ロケーション, 推定の根拠）。
+    pub fn suggest_location(
+        locale: Option<&str>,
+        utc_offset_secs: i32,
+    ) -> (&'static str, LocationSource) {
+        let country = locale.and_then(|locale| {
+            locale
+                .split(['-', '_', '.'])
+                .skip(1)
+                .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+                .map(|part| part.to_ascii_uppercase())
+        });
+
+        match country {
+            Some(country) => (Self::location_for_country(&country), LocationSource::Locale),
+            None => (
+                Self::location_for_utc_offset(utc_offset_secs),
+                LocationSource::Timezone,
+            ),
+        }
+    }
+
+    /// process エンドポイントのURL
+    ///
+    /// バージョン指定時はそのバージョンに固定し、未指定時はプロセッサの既定バージョンを使う。
+    fn process_url(
+        project_id: &str,
+        location: &str,
+        processor_id: &str,
+        processor_version: Option<&str>,
+    ) -> String {
+        let processor = format!(
+            "https://{}-documentai.googleapis.com/v1/projects/{}/locations/{}/processors/{}",
+            location, project_id, location, processor_id
+        );
+        match processor_version {
+            Some(version) => format!("{}/processorVersions/{}:process", processor, version),
+            None => format!("{}:process", processor),
         }
     }
 
@@ -97,15 +374,82 @@ impl GoogleDocumentAiProvider {
             .map_err(|e| format!("サービスアカウントJSONのパースに失敗しました: {}", e))
     }
 
-    /// アクセストークンを取得
-    async fn fetch_access_token(
+    /// サービスアカウントJSON（`use_adc` なら ADC）からアクセストークンを取得
+    ///
+    /// 同じサービスアカウントの取得が進行中なら新たに取りに行かず、その結果を待って受け取る。
+    /// 秘密鍵は取得処理の中だけで保持し、取得の完了時点でゼロ化される。
+    async fn access_token_for(&self, settings: &OcrSettings) -> Result<AccessToken, ProviderError> {
+        let google = settings.google.as_ref();
+        let client = self.client.clone();
+        if google.is_some_and(|google| google.use_adc) {
+            return self
+                .cached_token(
+                    ADC_CACHE_KEY.to_string(),
+                    Utc::now().timestamp(),
+                    move || async move { Self::fetch_access_token_adc(&client).await },
+                )
+                .await;
+        }
+
+        let service_account = Self::parse_service_account(
+            google
+                .and_then(|google| google.service_account_json.as_ref())
+                .ok_or("サービスアカウントJSONが設定されていません")?,
+        )?;
+
+        self.cached_token(
+            service_account.client_email.clone(),
+            Utc::now().timestamp(),
+            move || async move { Self::fetch_access_token(&client, &service_account).await },
+        )
+        .await
+    }
+
+    /// キャッシュが有効ならそのトークンを返し、無ければ `fetch` で取得してキャッシュする
+    async fn cached_token<F>(
         &self,
+        client_email: String,
+        now: i64,
+        fetch: impl FnOnce() -> F,
+    ) -> Result<AccessToken, ProviderError>
+    where
+        F: Future<Output = Result<(AccessToken, i64), ProviderError>> + Send + 'static,
+    {
+        let cached = self
+            .token_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|cached| {
+                cached.client_email == client_email
+                    && now < cached.expires_at - TOKEN_EXPIRY_MARGIN_SECS
+            })
+            .map(|cached| cached.access_token.clone());
+        if let Some(access_token) = cached {
+            return Ok(access_token);
+        }
+
+        let (access_token, expires_at) =
+            self.token_refresh.run(client_email.clone(), fetch).await?;
+        *self.token_cache.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedToken {
+            client_email,
+            access_token: access_token.clone(),
+            expires_at,
+        });
+        Ok(access_token)
+    }
+
+    /// アクセストークンを取得（戻り値は（トークン, 有効期限の UNIX 秒））
+    ///
+    /// 通信の失敗・429・5xx は一時的、鍵の誤りや認証の拒否は再試行しても無駄なエラーとして返す。
+    async fn fetch_access_token(
+        client: &Client,
         service_account: &ServiceAccountKey,
-    ) -> Result<String, String> {
+    ) -> Result<(AccessToken, i64), ProviderError> {
         let token_uri = service_account
             .token_uri
             .as_deref()
-            .unwrap_or("https://oauth2.googleapis.com/token");
+            .unwrap_or(DEFAULT_TOKEN_URI);
 
         let now = Utc::now().timestamp();
         let claims = Claims {
@@ -123,35 +467,337 @@ impl GoogleDocumentAiProvider {
 
         let assertion = encode(&header, &claims, &key)
             .map_err(|e| format!("JWTの生成に失敗しました: {}", e))?;
+        // 署名後は鍵を使わないので、通信の完了を待たずに破棄する
+        drop(key);
 
         let params = [
             ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
             ("assertion", &assertion),
         ];
+        let token_response = Self::send_token_request(client.post(token_uri).form(&params)).await?;
 
-        let response = self
-            .client
-            .post(token_uri)
-            .form(&params)
+        let expires_at = token_response
+            .expires_in
+            .map_or(claims.exp, |expires_in| now + expires_in);
+        Ok((AccessToken::new(token_response.access_token), expires_at))
+    }
+
+    /// トークンのリクエストを送り、応答を読む
+    async fn send_token_request(
+        request: reqwest::RequestBuilder,
+    ) -> Result<TokenResponse, ProviderError> {
+        let response = request
             .send()
             .await
-            .map_err(|e| format!("トークンリクエストに失敗しました: {}", e))?;
+            .map_err(|e| request_error("トークンリクエストに失敗しました", e))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            return Err(format!(
-                "トークン取得に失敗しました: HTTP {} - {}",
-                status, text
+            return Err(ProviderError::from_status(
+                status.as_u16(),
+                format!("トークン取得に失敗しました: HTTP {} - {}", status, text),
             ));
         }
 
-        let token_response: TokenResponse = response
+        response
             .json()
             .await
-            .map_err(|e| format!("トークンレスポンスのパースに失敗しました: {}", e))?;
+            .map_err(|e| format!("トークンレスポンスのパースに失敗しました: {}", e).into())
+    }
+
+    /// ADC の認証情報ファイルの場所
+    ///
+    /// `GOOGLE_APPLICATION_CREDENTIALS` があればそれだけを使い、無ければ gcloud の既定の場所
+    /// （Windows は `%APPDATA%\gcloud`、それ以外は `~/.config/gcloud`）を探す。
+    fn adc_credentials_path(env: impl Fn(&str) -> Option<String>) -> Option<(PathBuf, bool)> {
+        if let Some(path) = env("GOOGLE_APPLICATION_CREDENTIALS").filter(|p| !p.is_empty()) {
+            return Some((PathBuf::from(path), true));
+        }
+        let config_dir = match env("APPDATA") {
+            Some(app_data) if cfg!(windows) => PathBuf::from(app_data),
+            _ => PathBuf::from(env("HOME")?).join(".config"),
+        };
+        Some((
+            config_dir
+                .join("gcloud")
+                .join("application_default_credentials.json"),
+            false,
+        ))
+    }
+
+    /// ADC（Application Default Credentials）でアクセストークンを取得
+    ///
+    /// 認証情報ファイル（`adc_credentials_path`）→ メタデータサーバーの順に探す。
+    /// `GOOGLE_APPLICATION_CREDENTIALS` を指定していてファイルが読めない場合はメタデータサーバーに
+    /// 進まずにエラーにする。どれも使えなければ ADC が利用できない旨のエラーを返す。
+    async fn fetch_access_token_adc(client: &Client) -> Result<(AccessToken, i64), ProviderError> {
+        let now = Utc::now().timestamp();
+        let credentials_path = Self::adc_credentials_path(|key| std::env::var(key).ok());
+        let credentials = match &credentials_path {
+            Some((path, explicit)) => match std::fs::read_to_string(path) {
+                Ok(json) => Some(serde_json::from_str::<AdcCredentials>(&json).map_err(|e| {
+                    format!(
+                        "ADCの認証情報ファイルを読み込めません（{}）: {}",
+                        path.display(),
+                        e
+                    )
+                })?),
+                Err(e) if *explicit => {
+                    return Err(format!(
+                        "GOOGLE_APPLICATION_CREDENTIALS のファイルを読み込めません（{}）: {}",
+                        path.display(),
+                        e
+                    )
+                    .into())
+                }
+                Err(_) => None,
+            },
+            None => None,
+        };
+
+        match credentials {
+            Some(AdcCredentials::ServiceAccount(service_account)) => {
+                Self::fetch_access_token(client, &service_account).await
+            }
+            Some(AdcCredentials::AuthorizedUser(user)) => {
+                let params = [
+                    ("grant_type", "refresh_token"),
+                    ("client_id", &user.client_id),
+                    ("client_secret", &user.client_secret),
+                    ("refresh_token", &user.refresh_token),
+                ];
+                let token_response =
+                    Self::send_token_request(client.post(DEFAULT_TOKEN_URI).form(&params)).await?;
+                let expires_at = now + token_response.expires_in.unwrap_or(3600);
+                let access_token = AccessToken {
+                    token: token_response.access_token,
+                    quota_project_id: user
+                        .quota_project_id
+                        .filter(|project| !project.trim().is_empty()),
+                };
+                Ok((access_token, expires_at))
+            }
+            None => {
+                let request = client
+                    .get(METADATA_TOKEN_URL)
+                    .header("Metadata-Flavor", "Google")
+                    .timeout(METADATA_TIMEOUT);
+                let token_response = match request.send().await {
+                    Ok(response) if response.status().is_success() => response
+                        .json::<TokenResponse>()
+                        .await
+                        .map_err(|e| format!("トークンレスポンスのパースに失敗しました: {}", e))?,
+                    _ => {
+                        return Err(ProviderError::permanent(
+                            "ADCが利用できません。GOOGLE_APPLICATION_CREDENTIALS の設定、\
+                             `gcloud auth application-default login` の実行、\
+                             または GCP 上での実行のいずれかが必要です",
+                        ))
+                    }
+                };
+                let expires_at = now + token_response.expires_in.unwrap_or(3600);
+                Ok((AccessToken::new(token_response.access_token), expires_at))
+            }
+        }
+    }
+
+    /// 権限確認用の空リクエストへの応答を解釈する
+    ///
+    /// IAM の確認はリクエスト内容の検証より先に行われるため、空の文書に対する
+    /// 400（INVALID_ARGUMENT）は権限があることを意味する。
+    fn interpret_permission_probe(status: u16, body: &str) -> Result<(), String> {
+        let api_error = serde_json::from_str::<GoogleApiErrorResponse>(body)
+            .ok()
+            .map(|r| r.error);
+        let message = api_error
+            .as_ref()
+            .and_then(|e| e.message.as_deref())
+            .unwrap_or(body);
+        let reason = api_error.as_ref().and_then(|e| {
+            e.details
+                .iter()
+                .find_map(|detail| detail.get("reason").and_then(|v| v.as_str()))
+        });
+
+        match status {
+            200..=299 | 400 => Ok(()),
+            401 => Err(format!(
+                "認証に失敗しました。サービスアカウントキーが無効か失効しています: {}",
+                message
+            )),
+            403 if reason == Some("SERVICE_DISABLED") => Err(format!(
+                "プロジェクトで Document AI API が有効になっていません: {}",
+                message
+            )),
+            403 => Err(format!(
+                "サービスアカウントに documentai.processors.processOnline 権限が必要です（ロール「Document AI API ユーザー」を付与してください）: {}",
+                message
+            )),
+            404 => Err(format!(
+                "プロセッサが見つかりません。プロジェクトID・ロケーション・プロセッサID（バージョン）を確認してください: {}",
+                message
+            )),
+            _ => Err(format!(
+                "Document AIへの接続確認に失敗しました: HTTP {} - {}",
+                status, message
+            )),
+        }
+    }
+
+    /// 再送までの待ち時間（再送しないなら `None`）
+    ///
+    /// `retry` は何回目の再送か（0 始まり）。429 は `Retry-After` があればそれを優先し、
+    /// 無ければ 1s・2s・4s と倍々に待つ。
+    fn backoff_delay(
+        status: u16,
+        retry_after: Option<&str>,
+        retry: u32,
+    ) -> Option<std::time::Duration> {
+        if retry >= MAX_BACKOFF_RETRIES || !RETRYABLE_STATUSES.contains(&status) {
+            return None;
+        }
+        let retry_after = retry_after
+            .filter(|_| status == 429)
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(std::time::Duration::from_secs);
+        match retry_after {
+            Some(delay) if delay > MAX_RETRY_AFTER => None,
+            Some(delay) => Some(delay),
+            None => Some(super::tuning::retry_backoff(retry)),
+        }
+    }
+
+    /// 429・500・503 の応答と一時的な通信の失敗を指数バックオフで最大3回まで再送する
+    ///
+    /// 再送しても解消しなければ最後の応答（またはエラー）をそのまま返す（応答の解釈は呼び出し側）。
+    async fn retry_with_backoff<F, Fut>(mut send: F) -> Result<reqwest::Response, ProviderError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<reqwest::Response, ProviderError>>,
+    {
+        let mut retry = 0;
+        loop {
+            let response = match send().await {
+                Ok(response) => response,
+                Err(e) if e.retryable && retry < MAX_BACKOFF_RETRIES => {
+                    tokio::time::sleep(super::tuning::retry_backoff(retry)).await;
+                    retry += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok());
+            let Some(delay) = Self::backoff_delay(response.status().as_u16(), retry_after, retry)
+            else {
+                return Ok(response);
+            };
+            retry += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// エラーレスポンスがクォータ超過（RESOURCE_EXHAUSTED）かを判定
+    fn detect_quota_exceeded(
+        status: u16,
+        retry_after: Option<&str>,
+        body: &str,
+        now: DateTime<Utc>,
+    ) -> Option<AppError> {
+        let api_error = serde_json::from_str::<GoogleApiErrorResponse>(body)
+            .ok()
+            .map(|r| r.error);
+
+        let is_resource_exhausted = api_error
+            .as_ref()
+            .and_then(|e| e.status.as_deref())
+            .is_some_and(|s| s == "RESOURCE_EXHAUSTED");
+
+        if status != 429 && !is_resource_exhausted {
+            return None;
+        }
+
+        Some(AppError::QuotaExceeded {
+            retry_after_secs: Self::estimate_quota_reset_secs(retry_after, api_error.as_ref(), now),
+        })
+    }
+
+    /// クォータリセットまでの秒数を推定
+    ///
+    /// `Retry-After` ヘッダ、`RetryInfo` の `retryDelay`、メッセージ中のクォータ単位
+    /// （分次/日次）の順に手がかりを探す。
+    fn estimate_quota_reset_secs(
+        retry_after: Option<&str>,
+        api_error: Option<&GoogleApiError>,
+        now: DateTime<Utc>,
+    ) -> Option<u64> {
+        if let Some(secs) = retry_after.and_then(|v| v.trim().parse::<u64>().ok()) {
+            return Some(secs);
+        }
+
+        let api_error = api_error?;
+
+        // 例: {"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "30s"}
+        let retry_delay = api_error.details.iter().find_map(|detail| {
+            detail
+                .get("retryDelay")
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.trim_end_matches('s').parse::<f64>().ok())
+        });
+        if let Some(delay) = retry_delay {
+            return Some(delay.ceil() as u64);
+        }
+
+        let message = api_error.message.as_deref().unwrap_or("").to_lowercase();
+        if message.contains("per minute") {
+            return Some(60);
+        }
+        if message.contains("per day") {
+            // 日次クォータは太平洋時間の0時にリセットされる（夏時間は考慮しない概算）
+            let pacific = FixedOffset::west_opt(8 * 3600)?;
+            let local_now = now.with_timezone(&pacific).naive_local();
+            let next_midnight = (local_now.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            return Some((next_midnight - local_now).num_seconds().max(0) as u64);
+        }
 
-        Ok(token_response.access_token)
+        None
+    }
+
+    /// リビジョン情報からプロセッサバージョンを解決
+    fn resolve_model_version(revisions: &[DocumentAiRevision]) -> Option<String> {
+        revisions.iter().rev().find_map(|revision| {
+            let processor = revision.processor.as_deref()?;
+            let version = processor
+                .split_once("/processorVersions/")
+                .map_or(processor, |(_, version)| version);
+            Some(version.to_string())
+        })
+    }
+
+    /// 項目のエンティティを検索（マッピングに無い項目は既定の候補で探す）
+    fn find_field<'a>(
+        entities: &'a [DocumentAiEntity],
+        mapping: Option<&HashMap<String, Vec<String>>>,
+        field: &str,
+    ) -> Option<&'a DocumentAiEntity> {
+        match mapping
+            .and_then(|mapping| mapping.get(field))
+            .filter(|types| !types.is_empty())
+        {
+            Some(types) => {
+                let types: Vec<&str> = types.iter().map(String::as_str).collect();
+                Self::find_entity(entities, &types)
+            }
+            None => {
+                let (_, types) = DEFAULT_ENTITY_TYPES
+                    .iter()
+                    .find(|(known, _)| *known == field)?;
+                Self::find_entity(entities, types)
+            }
+        }
     }
 
     /// エンティティを検索
@@ -183,36 +829,91 @@ impl GoogleDocumentAiProvider {
                     return Some(format!("{:04}-{:02}-{:02}", year, month, day));
                 }
             }
+            if let Some(ref datetime_value) = normalized.datetime_value {
+                if let Some(hours) = datetime_value.hours {
+                    let offset = datetime_value
+                        .utc_offset
+                        .as_deref()
+                        .and_then(|offset| offset.trim_end_matches('s').parse::<i32>().ok())
+                        .map(|secs| {
+                            let sign = if secs < 0 { '-' } else { '+' };
+                            let secs = secs.abs();
+                            format!("{}{:02}:{:02}", sign, secs / 3600, secs % 3600 / 60)
+                        })
+                        .unwrap_or_default();
+                    return Some(format!(
+                        "{:02}:{:02}:{:02}{}",
+                        hours,
+                        datetime_value.minutes.unwrap_or(0),
+                        datetime_value.seconds.unwrap_or(0),
+                        offset
+                    ));
+                }
+            }
         }
         entity.mention_text.clone()
     }
 
-    /// エンティティから通貨コードを解決（正規化値を優先）
-    fn resolve_currency(entity: &DocumentAiEntity) -> Option<String> {
+    /// エンティティから通貨コードを解決（正規化値を優先し、無ければ通貨記号から推定）
+    fn resolve_currency(entity: &DocumentAiEntity) -> Option<Currency> {
         if let Some(ref normalized) = entity.normalized_value {
             // まず text フィールドを確認（"USD", "JPY" など）
             if let Some(ref text) = normalized.text {
-                return Some(text.clone());
+                return Currency::parse(text);
             }
             // money_value.currency_code を確認
             if let Some(ref money) = normalized.money_value {
                 if let Some(ref code) = money.currency_code {
-                    return Some(code.clone());
+                    return Currency::parse(code);
                 }
             }
         }
-        // フォールバック：mention_text を通貨コードに変換
-        entity.mention_text.as_ref().map(|s| match s.trim() {
-            "¥" | "\\" | "￥" => "JPY".to_string(),
-            "$" => "USD".to_string(),
-            "€" => "EUR".to_string(),
-            "£" => "GBP".to_string(),
-            other => other.to_string(),
-        })
+        // フォールバック：mention_text（"¥" など）を通貨コードに変換
+        entity.mention_text.as_deref().and_then(Currency::parse)
     }
 
-    /// エンティティから金額と通貨コードを解決
-    fn resolve_amount(entity: &DocumentAiEntity) -> (Option<f64>, Option<String>) {
+    /// 明細行のエンティティから品名・金額・数量を解決（入れ子の properties も走査する）
+    fn resolve_line_item(entity: &DocumentAiEntity, language: Option<&str>) -> LineItem {
+        let mut item = LineItem::default();
+        Self::collect_line_item_fields(entity, language, &mut item);
+        if item.description.is_none() && entity.properties.is_none() {
+            item.description = entity.mention_text.as_deref().and_then(Self::trimmed);
+        }
+        item
+    }
+
+    fn collect_line_item_fields(
+        entity: &DocumentAiEntity,
+        language: Option<&str>,
+        item: &mut LineItem,
+    ) {
+        for prop in entity.properties.iter().flatten() {
+            match prop.entity_type.as_deref() {
+                Some("line_item/description") if item.description.is_none() => {
+                    item.description = Self::resolve_text(prop).as_deref().and_then(Self::trimmed);
+                }
+                Some("line_item/amount") if item.amount.is_none() => {
+                    item.amount = Self::resolve_amount(prop, language).0;
+                }
+                Some("line_item/quantity") if item.quantity.is_none() => {
+                    item.quantity = Self::resolve_text(prop)
+                        .and_then(|text| crate::language::parse_amount(&text, language));
+                }
+                _ => Self::collect_line_item_fields(prop, language, item),
+            }
+        }
+    }
+
+    fn trimmed(text: &str) -> Option<String> {
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// エンティティから金額と通貨コードを解決（正規化値が無ければ言語の小数点記号で読む）
+    fn resolve_amount(
+        entity: &DocumentAiEntity,
+        language: Option<&str>,
+    ) -> (Option<f64>, Option<Currency>) {
         if let Some(ref normalized) = entity.normalized_value {
             if let Some(ref money) = normalized.money_value {
                 let units: f64 = money
@@ -221,7 +922,7 @@ impl GoogleDocumentAiProvider {
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(0.0);
                 let nanos: f64 = money.nanos.unwrap_or(0) as f64 / 1_000_000_000.0;
-                let currency = money.currency_code.clone();
+                let currency = money.currency_code.as_deref().and_then(Currency::parse);
                 return (Some(units + nanos), currency);
             }
             if let Some(ref text) = normalized.text {
@@ -232,11 +933,7 @@ impl GoogleDocumentAiProvider {
         }
 
         if let Some(ref mention) = entity.mention_text {
-            let digits: String = mention
-                .chars()
-                .filter(|c| c.is_ascii_digit() || *c == '.')
-                .collect();
-            if let Ok(value) = digits.parse::<f64>() {
+            if let Some(value) = crate::language::parse_amount(mention, language) {
                 return (Some(value), None);
             }
         }
@@ -244,7 +941,7 @@ impl GoogleDocumentAiProvider {
         // プロパティ内を再帰的に検索
         if let Some(ref properties) = entity.properties {
             for prop in properties {
-                let (amount, currency) = Self::resolve_amount(prop);
+                let (amount, currency) = Self::resolve_amount(prop, language);
                 if amount.is_some() {
                     return (amount, currency);
                 }
@@ -268,9 +965,11 @@ impl OcrProvider for GoogleDocumentAiProvider {
     }
 
     fn is_configured(&self, settings: &OcrSettings) -> bool {
-        settings.project_id.is_some()
-            && settings.processor_id.is_some()
-            && settings.service_account_json.is_some()
+        settings.google.as_ref().is_some_and(|google| {
+            google.project_id.is_some()
+                && google.processor_id.is_some()
+                && (google.use_adc || google.service_account_json.is_some())
+        })
     }
 
     async fn test_connection(&self, settings: &OcrSettings) -> Result<(), String> {
@@ -278,13 +977,33 @@ impl OcrProvider for GoogleDocumentAiProvider {
             return Err("設定が不完全です".to_string());
         }
 
-        let service_account =
-            Self::parse_service_account(settings.service_account_json.as_ref().unwrap())?;
-
         // アクセストークンを取得してテスト
-        let _token = self.fetch_access_token(&service_account).await?;
+        let access_token = self.access_token_for(settings).await?;
 
-        Ok(())
+        // 空の文書で process を呼び、プロセッサへの権限を確認する（課金対象にならない）
+        let google = settings.google.as_ref().unwrap();
+        let project_id = google.project_id.as_ref().unwrap();
+        let location = google.location.as_deref().unwrap_or("us");
+        let processor_id = google.processor_id.as_ref().unwrap();
+        let processor_version = google
+            .processor_version
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let url = Self::process_url(project_id, location, processor_id, processor_version);
+
+        let response = access_token
+            .authorize(self.client.post(&url))
+            .json(&serde_json::json!({
+                "rawDocument": { "content": "", "mimeType": "application/pdf" },
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Document AI APIリクエストに失敗しました: {}", e))?;
+
+        let status = response.status().as_u16();
+        let text = response.text().await.unwrap_or_default();
+        Self::interpret_permission_probe(status, &text)
     }
 
     async fn extract_receipt(
@@ -293,56 +1012,92 @@ impl OcrProvider for GoogleDocumentAiProvider {
         file_content: &str,
         mime_type: &str,
         settings: &OcrSettings,
-    ) -> Result<ReceiptData, String> {
+    ) -> Result<ReceiptData, ProviderError> {
+        self.extract_receipt_timed(file_path, file_content, mime_type, settings, None)
+            .await
+    }
+
+    async fn extract_receipt_timed(
+        &self,
+        file_path: &str,
+        file_content: &str,
+        mime_type: &str,
+        settings: &OcrSettings,
+        timing: Option<&mut OcrTiming>,
+    ) -> Result<ReceiptData, ProviderError> {
+        let mut recorder = PhaseRecorder::new(timing);
+
         if !self.is_configured(settings) {
-            return Err("OCR設定が不完全です".to_string());
+            return Err("OCR設定が不完全です".into());
         }
 
-        let project_id = settings.project_id.as_ref().unwrap();
-        let location = settings.location.as_deref().unwrap_or("us");
-        let processor_id = settings.processor_id.as_ref().unwrap();
+        let google = settings.google.as_ref().unwrap();
+        let project_id = google.project_id.as_ref().unwrap();
+        let location = google.location.as_deref().unwrap_or("us");
+        let processor_id = google.processor_id.as_ref().unwrap();
 
-        let service_account =
-            Self::parse_service_account(settings.service_account_json.as_ref().unwrap())?;
+        let access_token = self.access_token_for(settings).await?;
+        recorder.record(OcrPhase::Token);
 
-        let access_token = self.fetch_access_token(&service_account).await?;
-
-        let endpoint = format!("{}-documentai.googleapis.com", location);
-        let url = format!(
-            "https://{}/v1/projects/{}/locations/{}/processors/{}:process",
-            endpoint, project_id, location, processor_id
-        );
+        let processor_version = google
+            .processor_version
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let url = Self::process_url(project_id, location, processor_id, processor_version);
 
         let request_body = serde_json::json!({
             "rawDocument": {
                 "content": file_content,
                 "mimeType": mime_type,
             },
-            "fieldMask": "entities",
+            "fieldMask": "text,entities,revisions",
         });
+        recorder.record(OcrPhase::Preprocess);
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| format!("Document AI APIリクエストに失敗しました: {}", e))?;
+        // 一時的な 429・500・503 は待ってから送り直す
+        let response = Self::retry_with_backoff(|| {
+            access_token
+                .authorize(self.client.post(&url))
+                .header("Content-Type", "application/json")
+                .json(&request_body)
+                .send()
+                .map_err(|e| request_error("Document AI APIリクエストに失敗しました", e))
+        })
+        .await?;
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
             let text = response.text().await.unwrap_or_default();
-            return Err(format!(
-                "Document AI処理に失敗しました: HTTP {} - {}",
-                status, text
+
+            // クォータ超過はバグと切り分けられるよう専用のエラーにする
+            if let Some(quota_error) = Self::detect_quota_exceeded(
+                status.as_u16(),
+                retry_after.as_deref(),
+                &text,
+                Utc::now(),
+            ) {
+                return Err(quota_error.into());
+            }
+
+            return Err(ProviderError::from_status(
+                status.as_u16(),
+                format!("Document AI処理に失敗しました: HTTP {} - {}", status, text),
             ));
         }
 
-        let api_response: DocumentAiResponse = response
-            .json()
+        let body = response
+            .bytes()
             .await
+            .map_err(|e| request_error("Document AIレスポンスの受信に失敗しました", e))?;
+        recorder.record(OcrPhase::Api);
+
+        let api_response: DocumentAiResponse = serde_json::from_slice(&body)
             .map_err(|e| format!("Document AIレスポンスのパースに失敗しました: {}", e))?;
 
         let file_name = std::path::Path::new(file_path)
@@ -352,68 +1107,476 @@ impl OcrProvider for GoogleDocumentAiProvider {
             .to_string();
 
         let mut receipt_data = ReceiptData::new(file_name);
+        receipt_data.source_provider = Some(self.name().to_string());
 
         if let Some(document) = api_response.document {
+            if let Some(ref revisions) = document.revisions {
+                receipt_data.model_version = Self::resolve_model_version(revisions);
+            }
+
             if let Some(entities) = document.entities {
+                let mut confidence = ReceiptConfidence::default();
+                let mapping = settings
+                    .google
+                    .as_ref()
+                    .map(|google| &google.entity_mapping);
+
                 // 店舗名を検索
-                if let Some(merchant_entity) = Self::find_entity(
-                    &entities,
-                    &[
-                        "merchant_name",
-                        "supplier_name",
-                        "vendor_name",
-                        "receipt_merchant_name",
-                    ],
-                ) {
+                if let Some(merchant_entity) = Self::find_field(&entities, mapping, "merchant") {
                     receipt_data.merchant = Self::resolve_text(merchant_entity);
+                    confidence.merchant = merchant_entity.confidence.map(|c| c.clamp(0.0, 1.0));
                 }
 
+                // 全文（無ければ店舗名）から言語を推定し、日付・金額の解釈に使う
+                let language = document
+                    .text
+                    .as_deref()
+                    .and_then(crate::language::detect_language)
+                    .or_else(|| {
+                        receipt_data
+                            .merchant
+                            .as_deref()
+                            .and_then(crate::language::detect_language)
+                    });
+                receipt_data.detected_language = language.map(String::from);
+
                 // 日付を検索
-                if let Some(date_entity) = Self::find_entity(
-                    &entities,
-                    &[
-                        "receipt_date",
-                        "purchase_date",
-                        "transaction_date",
-                        "invoice_date",
-                        "date",
-                    ],
-                ) {
-                    receipt_data.date = Self::resolve_text(date_entity);
+                if let Some(date_entity) = Self::find_field(&entities, mapping, "date") {
+                    confidence.date = date_entity.confidence.map(|c| c.clamp(0.0, 1.0));
+                    receipt_data.date = Self::resolve_text(date_entity).map(|date| {
+                        crate::language::normalize_date(&date, language).unwrap_or(date)
+                    });
+                }
+
+                // 時刻を検索（正規化値・読み取った文字列のどちらも HH:MM:SS にする）
+                if let Some(time_entity) = Self::find_field(&entities, mapping, "time") {
+                    let time = Self::resolve_text(time_entity);
+                    receipt_data.time = time.as_deref().and_then(crate::language::normalize_time);
+                    // UTC オフセット付きの時刻はローカルの日付・時刻に直す（日またぎの計上日ズレを防ぐ）
+                    if let (Some(date), Some(time)) = (&receipt_data.date, &time) {
+                        let (local_date, local_time) = crate::language::normalize_date_with_tz(
+                            date,
+                            Some(time),
+                            settings.local_offset(),
+                        );
+                        receipt_data.date = Some(local_date);
+                        receipt_data.time = local_time;
+                    }
                 }
 
                 // 合計金額を検索
-                if let Some(total_entity) = Self::find_entity(
-                    &entities,
-                    &["total_amount", "invoice_total", "receipt_total"],
-                ) {
-                    let (amount, currency) = Self::resolve_amount(total_entity);
+                if let Some(total_entity) = Self::find_field(&entities, mapping, "amount") {
+                    let (amount, currency) = Self::resolve_amount(total_entity, language);
+                    // 合計金額は amountへ格納（`ReceiptData` に total は無い。読めなければ None のまま）
                     receipt_data.amount = amount;
+                    confidence.amount = total_entity.confidence.map(|c| c.clamp(0.0, 1.0));
                     receipt_data.currency = currency;
                 }
 
                 // 通貨が取得できなかった場合、独立したcurrencyエンティティを検索
                 if receipt_data.currency.is_none() {
-                    if let Some(currency_entity) = Self::find_entity(&entities, &["currency"]) {
+                    if let Some(currency_entity) = Self::find_field(&entities, mapping, "currency")
+                    {
                         receipt_data.currency = Self::resolve_currency(currency_entity);
                     }
                 }
 
+                // 通貨が確定してから最小単位の整数に換算する
+                receipt_data.amount_minor = receipt_data.amount.and_then(|amount| {
+                    crate::money::to_minor_units(amount, receipt_data.currency.as_deref())
+                });
+
+                // 消費税額を検索（無ければ税抜金額との差から求める。複数税率は合計税額のみ）
+                receipt_data.tax_amount = Self::find_field(&entities, mapping, "taxAmount")
+                    .and_then(|tax_entity| Self::resolve_amount(tax_entity, language).0)
+                    .or_else(|| {
+                        let net_entity = Self::find_field(&entities, mapping, "netAmount")?;
+                        let net = Self::resolve_amount(net_entity, language).0?;
+                        receipt_data
+                            .amount
+                            .map(|total| total - net)
+                            .filter(|tax| *tax >= 0.0)
+                    });
+
+                // 税率は明示されていればそれを、無ければ合計と税額から推定する
+                receipt_data.tax_rate = Self::find_field(&entities, mapping, "taxRate")
+                    .and_then(Self::resolve_text)
+                    .and_then(|text| crate::language::parse_amount(&text, None))
+                    .map(|rate| if rate > 1.0 { rate / 100.0 } else { rate })
+                    .or_else(|| {
+                        crate::money::estimate_tax_rate(
+                            receipt_data.amount?,
+                            receipt_data.tax_amount?,
+                            receipt_data.currency.as_deref(),
+                        )
+                    });
+
                 // 宛名を検索
-                if let Some(receiver_entity) = Self::find_entity(
-                    &entities,
-                    &[
-                        "receiver_name",
-                        "ship_to_name",
-                        "bill_to_name",
-                        "customer_name",
-                    ],
-                ) {
+                if let Some(receiver_entity) = Self::find_field(&entities, mapping, "receiverName")
+                {
                     receipt_data.receiver_name = Self::resolve_text(receiver_entity);
                 }
+
+                // 証憑番号を検索
+                receipt_data.document_number =
+                    Self::find_field(&entities, mapping, "documentNumber")
+                        .and_then(Self::resolve_text);
+
+                // 明細行を組み立てる（何も読めなかった行は捨てる）
+                receipt_data.line_items = entities
+                    .iter()
+                    .filter(|entity| entity.entity_type.as_deref() == Some("line_item"))
+                    .map(|entity| Self::resolve_line_item(entity, language))
+                    .filter(|item| *item != LineItem::default())
+                    .collect();
+
+                receipt_data.confidence = Some(confidence);
             }
         }
 
+        recorder.record(OcrPhase::Parse);
+
         Ok(receipt_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use futures::FutureExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn cached_token_is_fetched_once_for_concurrent_tasks() {
+        let provider = GoogleDocumentAiProvider::new();
+        let fetches = Arc::new(AtomicUsize::new(0));
+        // バッチの3並列と同じく、3件とも呼び出してから取得を完了させる
+        let (release, gate) = futures::channel::oneshot::channel::<()>();
+        let gate = gate.shared();
+        let fetch = |token: &'static str| {
+            let fetches = Arc::clone(&fetches);
+            let gate = gate.clone();
+            move || async move {
+                let _ = gate.await;
+                fetches.fetch_add(1, Ordering::SeqCst);
+                Ok((AccessToken::new(token.to_string()), 1_000 + 3_600))
+            }
+        };
+        let sa = || "sa@example.com".to_string();
+
+        let (a, b, c, _) = futures::executor::block_on(futures::future::join4(
+            provider.cached_token(sa(), 1_000, fetch("t1")),
+            provider.cached_token(sa(), 1_000, fetch("t1")),
+            provider.cached_token(sa(), 1_000, fetch("t1")),
+            async move {
+                let _ = release.send(());
+            },
+        ));
+        assert_eq!(
+            (a.unwrap().token, b.unwrap().token, c.unwrap().token),
+            ("t1".into(), "t1".into(), "t1".into())
+        );
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // 期限の60秒前までは使い回し、それ以降・別のサービスアカウントは取り直す
+        let run = |email: String, now: i64, token: &'static str| {
+            futures::executor::block_on(provider.cached_token(email, now, fetch(token)))
+                .unwrap()
+                .token
+        };
+        assert_eq!(run(sa(), 4_539, "t2"), "t1");
+        assert_eq!(run(sa(), 4_540, "t2"), "t2");
+        assert_eq!(run("other@example.com".to_string(), 4_540, "t3"), "t3");
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn adc_credentials_path_prefers_environment_variable() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                vars.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            GoogleDocumentAiProvider::adc_credentials_path(env(&[
+                ("GOOGLE_APPLICATION_CREDENTIALS", "/keys/sa.json"),
+                ("HOME", "/home/me"),
+            ])),
+            Some((PathBuf::from("/keys/sa.json"), true))
+        );
+        if !cfg!(windows) {
+            assert_eq!(
+                GoogleDocumentAiProvider::adc_credentials_path(env(&[("HOME", "/home/me")])),
+                Some((
+                    PathBuf::from("/home/me/.config/gcloud/application_default_credentials.json"),
+                    false
+                ))
+            );
+        }
+        assert_eq!(
+            GoogleDocumentAiProvider::adc_credentials_path(env(&[])),
+            None
+        );
+
+        let credentials: AdcCredentials = serde_json::from_str(
+            r#"{"type":"authorized_user","client_id":"id","client_secret":"s","refresh_token":"r","quota_project_id":"billing-project"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            credentials,
+            AdcCredentials::AuthorizedUser(AuthorizedUserKey { quota_project_id: Some(ref project), .. })
+                if project == "billing-project"
+        ));
+    }
+
+    #[test]
+    fn access_token_sends_quota_project_header() {
+        let client = Client::new();
+        let headers = |token: AccessToken| {
+            token
+                .authorize(client.post("https://example.com"))
+                .build()
+                .unwrap()
+                .headers()
+                .clone()
+        };
+
+        let with_project = headers(AccessToken {
+            token: "t".to_string(),
+            quota_project_id: Some("billing-project".to_string()),
+        });
+        assert_eq!(with_project["Authorization"], "Bearer t");
+        assert_eq!(with_project["x-goog-user-project"], "billing-project");
+
+        let without = headers(AccessToken::new("t".to_string()));
+        assert!(without.get("x-goog-user-project").is_none());
+    }
+
+    fn quota_body(message: &str, details: serde_json::Value) -> String {
+        serde_json::json!({
+            "error": {
+                "code": 429,
+                "message": message,
+                "status": "RESOURCE_EXHAUSTED",
+                "details": details,
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn resolve_amount_leaves_unreadable_totals_as_none() {
+        let entity: DocumentAiEntity = serde_json::from_value(serde_json::json!({
+            "type": "total_amount",
+            "mentionText": "合計",
+            "normalizedValue": { "text": "不明" }
+        }))
+        .unwrap();
+        assert_eq!(
+            GoogleDocumentAiProvider::resolve_amount(&entity, Some("ja")),
+            (None, None)
+        );
+
+        let entity: DocumentAiEntity = serde_json::from_value(serde_json::json!({
+            "type": "total_amount",
+            "mentionText": "¥1,188"
+        }))
+        .unwrap();
+        assert_eq!(
+            GoogleDocumentAiProvider::resolve_amount(&entity, Some("ja")).0,
+            Some(1188.0)
+        );
+    }
+
+    #[test]
+    fn find_field_uses_entity_mapping_then_defaults() {
+        let entities: Vec<DocumentAiEntity> = serde_json::from_value(serde_json::json!([
+            { "type": "supplier_name", "mentionText": "ローソン" },
+            { "type": "shop_label", "mentionText": "ローソン渋谷店" },
+            { "type": "total_amount", "mentionText": "¥500" }
+        ]))
+        .unwrap();
+        let mention = |entity: Option<&DocumentAiEntity>| entity?.mention_text.clone();
+
+        let mapping = HashMap::from([
+            ("merchant".to_string(), vec!["shop_label".to_string()]),
+            ("amount".to_string(), Vec::new()),
+        ]);
+        assert_eq!(
+            mention(GoogleDocumentAiProvider::find_field(
+                &entities,
+                Some(&mapping),
+                "merchant"
+            )),
+            Some("ローソン渋谷店".to_string())
+        );
+        // 空のマッピング・未設定の項目は既定の候補で探す
+        assert_eq!(
+            mention(GoogleDocumentAiProvider::find_field(
+                &entities,
+                Some(&mapping),
+                "amount"
+            )),
+            Some("¥500".to_string())
+        );
+        assert_eq!(
+            mention(GoogleDocumentAiProvider::find_field(
+                &entities, None, "merchant"
+            )),
+            Some("ローソン".to_string())
+        );
+        assert!(GoogleDocumentAiProvider::find_field(&entities, None, "date").is_none());
+    }
+
+    #[test]
+    fn resolve_line_item_walks_nested_properties() {
+        let entity: DocumentAiEntity = serde_json::from_value(serde_json::json!({
+            "type": "line_item",
+            "mentionText": "コーヒー 2 ¥400",
+            "properties": [
+                { "type": "line_item/description", "mentionText": " コーヒー " },
+                { "type": "line_item/quantity", "mentionText": "2" },
+                {
+                    "type": "line_item/group",
+                    "properties": [{
+                        "type": "line_item/amount",
+                        "mentionText": "¥400",
+                        "normalizedValue": {
+                            "moneyValue": { "currencyCode": "JPY", "units": "400" }
+                        }
+                    }]
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            GoogleDocumentAiProvider::resolve_line_item(&entity, Some("ja")),
+            LineItem {
+                description: Some("コーヒー".to_string()),
+                amount: Some(400.0),
+                quantity: Some(2.0),
+            }
+        );
+    }
+
+    #[test]
+    fn process_url_pins_processor_version_when_given() {
+        assert_eq!(
+            GoogleDocumentAiProvider::process_url("proj", "us", "abc", None),
+            "https://us-documentai.googleapis.com/v1/projects/proj/locations/us/processors/abc:process"
+        );
+        assert_eq!(
+            GoogleDocumentAiProvider::process_url("proj", "eu", "abc", Some("stable")),
+            "https://eu-documentai.googleapis.com/v1/projects/proj/locations/eu/processors/abc/processorVersions/stable:process"
+        );
+    }
+
+    #[test]
+    fn suggest_location_prefers_locale_country_then_timezone() {
+        assert_eq!(
+            GoogleDocumentAiProvider::suggest_location(Some("ja-JP"), 0),
+            ("asia-northeast1", LocationSource::Locale)
+        );
+        assert_eq!(
+            GoogleDocumentAiProvider::suggest_location(Some("en_GB.UTF-8"), 32_400),
+            ("europe-west2", LocationSource::Locale)
+        );
+        assert_eq!(
+            GoogleDocumentAiProvider::suggest_location(Some("ja"), 32_400),
+            ("asia-northeast1", LocationSource::Timezone)
+        );
+        assert_eq!(
+            GoogleDocumentAiProvider::suggest_location(None, -18_000),
+            ("us", LocationSource::Timezone)
+        );
+    }
+
+    #[test]
+    fn interpret_permission_probe_explains_iam_errors() {
+        let invalid = r#"{"error": {"code": 400, "status": "INVALID_ARGUMENT", "message": "Document is empty"}}"#;
+        assert!(GoogleDocumentAiProvider::interpret_permission_probe(400, invalid).is_ok());
+
+        let denied = r#"{"error": {"code": 403, "status": "PERMISSION_DENIED", "message": "Permission denied"}}"#;
+        let error = GoogleDocumentAiProvider::interpret_permission_probe(403, denied).unwrap_err();
+        assert!(error.contains("documentai.processors.processOnline"));
+
+        let disabled = r#"{"error": {"code": 403, "status": "PERMISSION_DENIED", "message": "API disabled",
+            "details": [{"@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "SERVICE_DISABLED"}]}}"#;
+        let error =
+            GoogleDocumentAiProvider::interpret_permission_probe(403, disabled).unwrap_err();
+        assert!(error.contains("有効になっていません"));
+    }
+
+    #[test]
+    fn detect_quota_exceeded_ignores_other_errors() {
+        let body = r#"{"error": {"code": 404, "message": "not found", "status": "NOT_FOUND"}}"#;
+        assert_eq!(
+            GoogleDocumentAiProvider::detect_quota_exceeded(404, None, body, Utc::now()),
+            None
+        );
+    }
+
+    #[test]
+    fn backoff_delay_doubles_and_prefers_retry_after_for_429() {
+        let delay = |status, retry_after, retry| {
+            GoogleDocumentAiProvider::backoff_delay(status, retry_after, retry).map(|d| d.as_secs())
+        };
+        assert_eq!(delay(503, None, 0), Some(1));
+        assert_eq!(delay(500, None, 1), Some(2));
+        assert_eq!(delay(429, None, 2), Some(4));
+        assert_eq!(delay(503, None, 3), None);
+        assert_eq!(delay(400, None, 0), None);
+
+        assert_eq!(delay(429, Some("5"), 0), Some(5));
+        assert_eq!(delay(503, Some("5"), 0), Some(1));
+        // 日次クォータのように長く待つ必要があるものは再送しない
+        assert_eq!(delay(429, Some("3600"), 0), None);
+    }
+
+    #[test]
+    fn detect_quota_exceeded_prefers_retry_after_then_retry_info() {
+        let body = quota_body(
+            "Quota exceeded",
+            serde_json::json!([{
+                "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                "retryDelay": "30s",
+            }]),
+        );
+
+        assert_eq!(
+            GoogleDocumentAiProvider::detect_quota_exceeded(429, Some("120"), &body, Utc::now()),
+            Some(AppError::QuotaExceeded {
+                retry_after_secs: Some(120)
+            })
+        );
+        assert_eq!(
+            GoogleDocumentAiProvider::detect_quota_exceeded(429, None, &body, Utc::now()),
+            Some(AppError::QuotaExceeded {
+                retry_after_secs: Some(30)
+            })
+        );
+    }
+
+    #[test]
+    fn detect_quota_exceeded_estimates_daily_reset_in_pacific_time() {
+        let body = quota_body(
+            "Quota exceeded for quota metric 'Online processing requests' per day",
+            serde_json::json!([]),
+        );
+        // 太平洋時間（UTC-8）で 23:00 → 翌0時まで1時間
+        let now = Utc.with_ymd_and_hms(2025, 1, 16, 7, 0, 0).unwrap();
+
+        assert_eq!(
+            GoogleDocumentAiProvider::detect_quota_exceeded(400, None, &body, now),
+            Some(AppError::QuotaExceeded {
+                retry_after_secs: Some(3600)
+            })
+        );
+    }
+}
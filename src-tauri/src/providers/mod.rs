@@ -3,24 +3,402 @@
 //! レシート画像からテキストを抽出するOCRプロバイダーを抽象化し、
 //! プラグイン的に追加可能なアーキテクチャを提供する。
 
+pub mod escalation;
 pub mod googledocumentai;
+pub mod prompt;
+pub mod refine;
+pub mod single_flight;
+pub mod tesseract;
+pub mod textract;
+pub mod timing;
+pub mod tuning;
+pub mod veryfi;
 
+use crate::error::{AppError, ProviderError};
+use crate::money::Currency;
+use crate::summary::ReviewStatus;
 use async_trait::async_trait;
+use escalation::Adoption;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
 
 /// OCR設定
+///
+/// プロバイダー固有の項目はプロバイダーごとのサブ設定に持つ
+/// （旧形式のフラットな設定は [`crate::settings_migration`] で移行する）。
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct OcrSettings {
-    /// Google Document AI プロジェクトID
+    /// 既定で使うプロバイダー名（未指定なら登録順の先頭）
+    #[serde(default)]
+    pub active_provider: Option<String>,
+    /// Google Document AI の設定
+    #[serde(default)]
+    pub google: Option<GoogleSettings>,
+    /// Veryfi の設定
+    #[serde(default)]
+    pub veryfi: Option<VeryfiSettings>,
+    /// AWS Textract の設定
+    #[serde(default)]
+    pub textract: Option<TextractSettings>,
+    /// Tesseract の設定
+    #[serde(default)]
+    pub tesseract: Option<TesseractSettings>,
+    /// エスカレーションチェーン（プロバイダー名、安い順）。空なら既定プロバイダーのみ
+    #[serde(default)]
+    pub escalation_chain: Vec<String>,
+    /// 次のプロバイダーへ上げない充足率の閾値（0.0〜1.0、既定 1.0）
+    pub escalation_min_completeness: Option<f64>,
+    /// バッチ処理の同時実行数（既定 4、1〜10 に丸める）。プロバイダー別の上書きが無ければこれを使う
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+    /// プロバイダー名 → タイムアウト・リトライ・同時実行数の上書き
+    #[serde(default)]
+    pub provider_overrides: HashMap<String, tuning::ProviderTuning>,
+    /// バッチ完了時に結果を POST する Webhook URL
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Webhook 署名（HMAC-SHA256）の共有シークレット
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+    /// LLM 系プロバイダーの抽出プロンプトのテンプレート（未指定なら既定のプロンプト）
+    #[serde(default)]
+    pub llm_prompt_template: Option<String>,
+    /// OCR前に縮小する画像の長辺の上限（px）。未指定なら既定値、0 なら縮小しない
+    #[serde(default)]
+    pub max_image_dimension: Option<u32>,
+    /// 抽出結果の各フィールドに定義順で当てる正規表現の整形ルール
+    #[serde(default)]
+    pub postprocess_rules: Vec<crate::postprocess::PostprocessRule>,
+    /// 一覧に画像として追加する拡張子（`tiff`・`bmp` など。既定の拡張子に加える）
+    #[serde(default)]
+    pub extra_image_extensions: Vec<String>,
+    /// 信頼度が低ければ別プロバイダーで読み直す項目（`merchant`・`date`・`amount`）
+    #[serde(default)]
+    pub refine_fields: Vec<String>,
+    /// 読み直す信頼度の閾値（0.0〜1.0、既定 0.7）
+    #[serde(default)]
+    pub refine_min_confidence: Option<f32>,
+    /// 読み直しに使うプロバイダー名
+    #[serde(default)]
+    pub refine_provider: Option<String>,
+    /// 計上日を決めるタイムゾーン（UTC オフセット、`+09:00` など。未指定なら OS のタイムゾーン）
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// 共有プロファイル（OCR設定の JSON）のパス。変更を監視して設定に反映する
+    #[serde(default)]
+    pub profile_path: Option<String>,
+}
+
+/// Google Document AI の設定
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleSettings {
+    /// プロジェクトID
+    #[serde(default)]
     pub project_id: Option<String>,
-    /// Google Document AI ロケーション
+    /// ロケーション
+    #[serde(default)]
     pub location: Option<String>,
-    /// Google Document AI プロセッサID
+    /// プロセッサID
+    #[serde(default)]
     pub processor_id: Option<String>,
+    /// プロセッサバージョン（未指定ならプロセッサの既定バージョン）
+    #[serde(default)]
+    pub processor_version: Option<String>,
     /// サービスアカウントJSON（文字列として保存）
+    #[serde(default)]
     pub service_account_json: Option<String>,
+    /// サービスアカウントJSONの代わりに ADC（Application Default Credentials）を使う
+    #[serde(default)]
+    pub use_adc: bool,
+    /// 項目（`merchant`・`date` など）→ 探すエンティティ名（先にあるものを優先）
+    ///
+    /// カスタムプロセッサのエンティティを拾うのに使う。無い項目は既定の候補で探す。
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub entity_mapping: HashMap<String, Vec<String>>,
+}
+
+/// Veryfi の設定
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VeryfiSettings {
+    /// クライアントID
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// APIキー
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// ユーザー名
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+/// AWS Textract の設定
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextractSettings {
+    /// リージョン
+    #[serde(default)]
+    pub region: Option<String>,
+    /// アクセスキーID
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// シークレットアクセスキー
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+}
+
+/// Tesseract の設定
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TesseractSettings {
+    /// 言語データ（`jpn`・`jpn+eng` など。未指定なら `jpn+eng`）
+    #[serde(default)]
+    pub lang: Option<String>,
+}
+
+/// プロジェクトIDの形式（英小文字始まり、英小文字・数字・ハイフン、6〜30文字）
+static PROJECT_ID_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z][a-z0-9-]{4,28}[a-z0-9]$").unwrap());
+
+/// プロセッサIDの形式（16進文字列）
+static PROCESSOR_ID_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[0-9a-f]+$").unwrap());
+
+/// プロセッサバージョンの形式（`pretrained-expense-v1.3-2022-09-12`・`stable` など）
+///
+/// エンドポイントのパスに埋め込まれるため、`/` や `:` を含まないことを保証する。
+static PROCESSOR_VERSION_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]*$").unwrap());
+
+/// ロケーションの形式（`us` / `eu` またはリージョン名）
+///
+/// エンドポイント `{location}-documentai.googleapis.com` のホスト名に使われる。
+static LOCATION_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z]+(-[a-z]+[0-9]+)?$").unwrap());
+
+/// 空文字列を未設定として扱う
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+impl GoogleSettings {
+    /// 設定値の形式を検証する（未設定の項目は検証しない）
+    fn validate(&self) -> Result<(), String> {
+        if let Some(project_id) = non_empty(&self.project_id) {
+            if !PROJECT_ID_PATTERN.is_match(project_id) {
+                return Err(format!(
+                    "プロジェクトIDの形式が正しくありません（英小文字・数字・ハイフンの6〜30文字）: {}",
+                    project_id
+                ));
+            }
+        }
+
+        if let Some(processor_id) = non_empty(&self.processor_id) {
+            if !PROCESSOR_ID_PATTERN.is_match(processor_id) {
+                return Err(format!(
+                    "プロセッサIDの形式が正しくありません（16進文字列）: {}",
+                    processor_id
+                ));
+            }
+        }
+
+        if let Some(processor_version) = non_empty(&self.processor_version) {
+            if !PROCESSOR_VERSION_PATTERN.is_match(processor_version) {
+                return Err(format!(
+                    "プロセッサバージョンの形式が正しくありません: {}",
+                    processor_version
+                ));
+            }
+        }
+
+        if let Some(location) = non_empty(&self.location) {
+            if !LOCATION_PATTERN.is_match(location) {
+                return Err(format!(
+                    "ロケーションからエンドポイントを構築できません（例: us, eu, asia-northeast1）: {}",
+                    location
+                ));
+            }
+        }
+
+        if let Some(field) = self.entity_mapping.keys().find(|field| {
+            !googledocumentai::DEFAULT_ENTITY_TYPES
+                .iter()
+                .any(|(known, _)| known == field)
+        }) {
+            return Err(format!(
+                "エンティティのマッピングに対応していない項目があります: {}",
+                field
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl OcrSettings {
+    /// 計上日を決めるローカルのタイムゾーン（未指定・不正なら OS のタイムゾーン）
+    pub fn local_offset(&self) -> chrono::FixedOffset {
+        non_empty(&self.timezone)
+            .and_then(crate::language::parse_offset)
+            .unwrap_or_else(|| *chrono::Local::now().offset())
+    }
+
+    /// OCR前に縮小する画像の長辺の上限（縮小しない設定なら `None`）
+    pub fn max_image_dimension(&self) -> Option<u32> {
+        match self.max_image_dimension {
+            None => Some(crate::downscale::DEFAULT_MAX_IMAGE_DIMENSION),
+            Some(0) => None,
+            Some(max) => Some(max),
+        }
+    }
+
+    /// 設定値の形式を検証する（未設定の項目は検証しない）
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(google) = &self.google {
+            google.validate()?;
+        }
+
+        if let Some(max) = self.max_image_dimension {
+            if max != 0 && max < crate::downscale::MIN_MAX_IMAGE_DIMENSION {
+                return Err(format!(
+                    "画像の最大解像度は{}px以上にしてください（0 で縮小しない）: {}",
+                    crate::downscale::MIN_MAX_IMAGE_DIMENSION,
+                    max
+                ));
+            }
+        }
+
+        if let Some(region) = self
+            .textract
+            .as_ref()
+            .and_then(|textract| non_empty(&textract.region))
+        {
+            if !textract::AWS_REGION_PATTERN.is_match(region) {
+                return Err(format!(
+                    "AWSリージョンの形式が正しくありません（例: ap-northeast-1）: {}",
+                    region
+                ));
+            }
+        }
+
+        if let Some(timezone) = non_empty(&self.timezone) {
+            if crate::language::parse_offset(timezone).is_none() {
+                return Err(format!(
+                    "タイムゾーンの形式が正しくありません（例: +09:00）: {}",
+                    timezone
+                ));
+            }
+        }
+
+        if let Some(lang) = self
+            .tesseract
+            .as_ref()
+            .and_then(|tesseract| non_empty(&tesseract.lang))
+        {
+            if !tesseract::TESSERACT_LANG_PATTERN.is_match(lang) {
+                return Err(format!(
+                    "Tesseract の言語の形式が正しくありません（例: jpn, jpn+eng）: {}",
+                    lang
+                ));
+            }
+        }
+
+        for extension in &self.extra_image_extensions {
+            let name = extension.trim().trim_start_matches('.');
+            if name.is_empty()
+                || name.len() > 10
+                || !name.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(format!(
+                    "追加する拡張子の形式が正しくありません（英数字のみ）: {}",
+                    extension
+                ));
+            }
+        }
+
+        for field in &self.refine_fields {
+            if !refine::REFINABLE_FIELDS.contains(&field.as_str()) {
+                return Err(format!(
+                    "再抽出する項目が正しくありません（{}）: {}",
+                    refine::REFINABLE_FIELDS.join(", "),
+                    field
+                ));
+            }
+        }
+
+        if let Some(min) = self.refine_min_confidence {
+            if !(0.0..=1.0).contains(&min) {
+                return Err(format!(
+                    "再抽出の信頼度の閾値は0.0〜1.0で指定してください: {}",
+                    min
+                ));
+            }
+        }
+
+        for rule in &self.postprocess_rules {
+            rule.validate()?;
+        }
+
+        if let Some(webhook_url) = non_empty(&self.webhook_url) {
+            let is_http = reqwest::Url::parse(webhook_url)
+                .map(|url| matches!(url.scheme(), "http" | "https"))
+                .unwrap_or(false);
+            if !is_http {
+                return Err(format!(
+                    "Webhook URLの形式が正しくありません（http または https）: {}",
+                    webhook_url
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 明細行（品目ごとに勘定科目を振るために使う）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineItem {
+    /// 品名
+    pub description: Option<String>,
+    /// 金額
+    pub amount: Option<f64>,
+    /// 数量
+    pub quantity: Option<f64>,
+}
+
+/// 主要項目の読み取りの信頼度（0.0〜1.0。プロバイダーが返さない項目は `None`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptConfidence {
+    pub merchant: Option<f32>,
+    pub date: Option<f32>,
+    pub amount: Option<f32>,
+}
+
+impl ReceiptConfidence {
+    /// 項目名（`merchant`・`date`・`amount`）の信頼度
+    pub fn get(&self, field: &str) -> Option<f32> {
+        match field {
+            "merchant" => self.merchant,
+            "date" => self.date,
+            "amount" => self.amount,
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, field: &str, confidence: Option<f32>) {
+        match field {
+            "merchant" => self.merchant = confidence,
+            "date" => self.date = confidence,
+            "amount" => self.amount = confidence,
+            _ => {}
+        }
+    }
 }
 
 /// レシートデータ
@@ -31,14 +409,61 @@ pub struct ReceiptData {
     pub file: String,
     /// 店舗名
     pub merchant: Option<String>,
-    /// 日付（YYYY-MM-DD形式）
+    /// 店舗名の末尾の支店名（`渋谷店` など。店舗名はそのまま残す）
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// 日付（YYYY-MM-DD形式。読み取れた表記のまま入ることもある）
+    ///
+    /// OCRキャッシュにもこの型のまま保存するため、検証はサマリーの読み込み（`SummaryReceipt`）で行う。
+    #[serde(default)]
     pub date: Option<String>,
-    /// 合計金額
+    /// 購入時刻（HH:MM:SS形式。同じレシートの二重スキャンの検出に使う）
+    #[serde(default)]
+    pub time: Option<String>,
+    /// 合計金額（表示用。集計には `amount_minor` を使う）
+    #[serde(default)]
     pub amount: Option<f64>,
-    /// 通貨コード（JPY, USD など）
-    pub currency: Option<String>,
+    /// 合計金額（通貨の最小単位の整数。円なら円、ドルならセント）
+    pub amount_minor: Option<i64>,
+    /// 消費税額（複数税率が混在する場合は合計税額）
+    #[serde(default)]
+    pub tax_amount: Option<f64>,
+    /// 消費税率（0.10 = 10%。明示されていなければ合計と税額から推定）
+    #[serde(default)]
+    pub tax_rate: Option<f64>,
+    /// 通貨コード（ISO 4217。JPY, USD など）
+    #[serde(default, deserialize_with = "crate::sanitize::deserialize_currency")]
+    pub currency: Option<Currency>,
     /// 宛名
     pub receiver_name: Option<String>,
+    /// 証憑番号（領収書番号・レシート番号。スキャン漏れの検知に使う）
+    #[serde(default)]
+    pub document_number: Option<String>,
+    /// 明細行（明細を返さないプロバイダーでは空）
+    #[serde(default)]
+    pub line_items: Vec<LineItem>,
+    /// 勘定科目ルールから推定した勘定科目
+    pub category: Option<String>,
+    /// 勘定科目推定の確信度（0.0〜1.0）
+    pub category_confidence: Option<f32>,
+    /// 店舗名から推定した大まかな業種（コンビニ・カフェ・交通など）
+    pub merchant_category: Option<String>,
+    /// 読み取りに使ったOCRプロバイダー名
+    pub source_provider: Option<String>,
+    /// 読み取りに使ったモデル（プロセッサ）のバージョン
+    pub model_version: Option<String>,
+    /// OCR全文から推定した言語（ISO 639-1、`ja`・`en` など）
+    #[serde(default)]
+    pub detected_language: Option<String>,
+    /// 主要項目の読み取りの信頼度（プロバイダーが返す場合のみ）
+    #[serde(default)]
+    pub confidence: Option<ReceiptConfidence>,
+    /// 手動で確定した項目名（再OCRでも上書きしない）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub manually_edited: Vec<String>,
+    /// レビューの状態（再OCRでもサマリーの状態を引き継ぐ）
+    #[serde(default)]
+    pub review_status: ReviewStatus,
 }
 
 impl ReceiptData {
@@ -46,12 +471,38 @@ impl ReceiptData {
         Self {
             file,
             merchant: None,
+            branch: None,
             date: None,
+            time: None,
             amount: None,
+            amount_minor: None,
+            tax_amount: None,
+            tax_rate: None,
             currency: None,
             receiver_name: None,
+            document_number: None,
+            line_items: Vec::new(),
+            category: None,
+            category_confidence: None,
+            merchant_category: None,
+            source_provider: None,
+            model_version: None,
+            detected_language: None,
+            confidence: None,
+            manually_edited: Vec::new(),
+            review_status: ReviewStatus::default(),
         }
     }
+
+    /// 合計金額を通貨の最小単位の整数で返す
+    ///
+    /// `amount_minor` が無い従来形式のデータは `amount` から換算する。
+    pub fn amount_in_minor_units(&self) -> Option<i64> {
+        self.amount_minor.or_else(|| {
+            self.amount
+                .and_then(|amount| crate::money::to_minor_units(amount, self.currency.as_deref()))
+        })
+    }
 }
 
 /// OCR処理結果
@@ -62,8 +513,37 @@ pub struct OcrResult {
     pub success: bool,
     /// レシートデータ
     pub data: Option<ReceiptData>,
-    /// エラーメッセージ
+    /// エラーメッセージ（日本語）
     pub error: Option<String>,
+    /// エラーの識別子とパラメータ（フロントで `localize_error` により翻訳する）
+    ///
+    /// 識別できないエラー（プロバイダーが返したメッセージなど）は `None` で、`error` をそのまま表示する。
+    #[serde(default)]
+    pub error_detail: Option<AppError>,
+    /// 処理を行わずにスキップしたかどうか
+    pub skipped: bool,
+    /// 結果を返したプロバイダー名（フォールバックで切り替えた場合は切り替え先）
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    /// エスカレーションの各段の記録（チェーン設定時・フォールバック時のみ）
+    pub escalation_steps: Vec<escalation::EscalationStep>,
+    /// フェーズ別の所要時間（`collect_timings` 指定時のみ）
+    pub timing: Option<timing::OcrTiming>,
+    /// 1ファイルの処理時間（ミリ秒、実行枠を確保してから数える。バッチで処理した場合のみ）
+    #[serde(default)]
+    pub elapsed_ms: Option<u64>,
+    /// 処理は続けたが注意が必要な事柄（画像を縮小した など）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 連続何回目の試行か（バッチで処理した場合のみ、成功すると数え直す。0 は未記録）
+    #[serde(default)]
+    pub attempt: u32,
+    /// 画像が不鮮明で再撮影したほうがよいか（`check_image_quality` 指定時のみ判定）
+    #[serde(default)]
+    pub needs_retake: bool,
+    /// 失敗が一時的なもので、再試行すれば成功しうるか（フロントの再試行ボタンの表示に使う）
+    #[serde(default)]
+    pub retryable: bool,
 }
 
 impl OcrResult {
@@ -72,6 +552,16 @@ impl OcrResult {
             success: true,
             data: Some(data),
             error: None,
+            error_detail: None,
+            skipped: false,
+            provider_name: None,
+            escalation_steps: Vec::new(),
+            timing: None,
+            elapsed_ms: None,
+            warnings: Vec::new(),
+            attempt: 0,
+            needs_retake: false,
+            retryable: false,
         }
     }
 
@@ -80,6 +570,49 @@ impl OcrResult {
             success: false,
             data: None,
             error: Some(error),
+            error_detail: None,
+            skipped: false,
+            provider_name: None,
+            escalation_steps: Vec::new(),
+            timing: None,
+            elapsed_ms: None,
+            warnings: Vec::new(),
+            attempt: 0,
+            needs_retake: false,
+            retryable: false,
+        }
+    }
+
+    /// 識別できるエラーで失敗した結果
+    pub fn failure_with(error: AppError) -> Self {
+        Self::provider_failure(error.into())
+    }
+
+    /// プロバイダーのエラーで失敗した結果（再試行できるかと識別子を引き継ぐ）
+    pub fn provider_failure(error: ProviderError) -> Self {
+        Self {
+            retryable: error.retryable,
+            error_detail: error.detail,
+            ..Self::failure(error.message)
+        }
+    }
+
+    /// OCRを実行せずにスキップした結果（理由をエラーメッセージとして持つ）
+    pub fn skipped(reason: AppError) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(reason.to_string()),
+            error_detail: Some(reason),
+            skipped: true,
+            provider_name: None,
+            escalation_steps: Vec::new(),
+            timing: None,
+            elapsed_ms: None,
+            warnings: Vec::new(),
+            attempt: 0,
+            needs_retake: false,
+            retryable: false,
         }
     }
 }
@@ -96,6 +629,22 @@ pub struct OcrProgressEvent {
     pub file_name: String,
     /// 処理結果（処理完了時のみ）
     pub result: Option<OcrResult>,
+    /// 成功したレシートの金額の通貨別の累計（通貨不明は `UNKNOWN`）
+    #[serde(default)]
+    pub running_total: HashMap<String, f64>,
+    /// バッチが中止されたか（以降の未処理分はスキップされる）
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// 送受信の失敗をプロバイダーのエラーにする（リクエストの組み立ての誤り以外は一時的とみなす）
+pub fn request_error(context: &str, error: reqwest::Error) -> ProviderError {
+    let message = format!("{}: {}", context, error);
+    if error.is_builder() {
+        ProviderError::permanent(message)
+    } else {
+        ProviderError::transient(message)
+    }
 }
 
 /// OCRプロバイダー trait
@@ -104,7 +653,6 @@ pub struct OcrProgressEvent {
 #[async_trait]
 pub trait OcrProvider: Send + Sync {
     /// プロバイダー名
-    #[allow(dead_code)]
     fn name(&self) -> &str;
 
     /// 設定が有効かどうか
@@ -126,7 +674,30 @@ pub trait OcrProvider: Send + Sync {
         file_content: &str,
         mime_type: &str,
         settings: &OcrSettings,
-    ) -> Result<ReceiptData, String>;
+    ) -> Result<ReceiptData, ProviderError>;
+
+    /// フェーズ別の所要時間を `timing` に加算しながらデータを抽出
+    ///
+    /// `timing` が `None` のときは計測しない。計測に対応しないプロバイダーは
+    /// 既定実装のまま `extract_receipt` を呼ぶ（所要時間は全体にのみ計上される）。
+    async fn extract_receipt_timed(
+        &self,
+        file_path: &str,
+        file_content: &str,
+        mime_type: &str,
+        settings: &OcrSettings,
+        timing: Option<&mut timing::OcrTiming>,
+    ) -> Result<ReceiptData, ProviderError> {
+        let _ = timing;
+        self.extract_receipt(file_path, file_content, mime_type, settings)
+            .await
+    }
+}
+
+/// 抽出に使うプロバイダーの試行順と、結果の採用基準
+pub struct OcrChain {
+    pub providers: Vec<Arc<dyn OcrProvider>>,
+    pub adoption: Adoption,
 }
 
 /// OCRプロバイダーレジストリ
@@ -147,6 +718,9 @@ impl OcrProviderRegistry {
     pub fn new() -> Self {
         let mut registry = Self { providers: vec![] };
         registry.register(Arc::new(googledocumentai::GoogleDocumentAiProvider::new()));
+        registry.register(Arc::new(veryfi::VeryfiProvider::new()));
+        registry.register(Arc::new(tesseract::TesseractProvider::new()));
+        registry.register(Arc::new(textract::TextractProvider::new()));
         registry
     }
 
@@ -160,12 +734,74 @@ impl OcrProviderRegistry {
         self.providers.first().cloned()
     }
 
+    /// 設定で選んだプロバイダー（未指定・未登録ならデフォルトプロバイダー）
+    pub fn get_active_provider(&self, settings: &OcrSettings) -> Option<Arc<dyn OcrProvider>> {
+        non_empty(&settings.active_provider)
+            .and_then(|name| self.get_provider(name))
+            .or_else(|| self.get_default_provider())
+    }
+
+    /// 失敗時に切り替える試行順（設定で選んだプロバイダーを先頭に、残りは登録順）
+    ///
+    /// 未設定のプロバイダーを除くかどうかは設定を持つ呼び出し側で判断する。
+    pub fn get_fallback_chain(&self, settings: &OcrSettings) -> Vec<Arc<dyn OcrProvider>> {
+        let active = self.get_active_provider(settings);
+        active
+            .iter()
+            .cloned()
+            .chain(
+                self.providers
+                    .iter()
+                    .filter(|provider| active.as_ref().is_none_or(|a| !Arc::ptr_eq(a, provider)))
+                    .cloned(),
+            )
+            .collect()
+    }
+
     /// 名前でプロバイダーを取得
-    #[allow(dead_code)]
     pub fn get_provider(&self, name: &str) -> Option<Arc<dyn OcrProvider>> {
         self.providers.iter().find(|p| p.name() == name).cloned()
     }
 
+    /// 設定に応じて、抽出に使うプロバイダーを試行順に解決
+    ///
+    /// エスカレーションチェーンがあればその順（未登録のプロバイダー名は無視する）で、充足率で採用する。
+    /// 未設定なら設定で選んだプロバイダーを先頭に、設定済みの別プロバイダーへ失敗時だけ切り替える。
+    /// 単発・バッチ・取り込みのどの経路もこの解決を使う。
+    pub fn resolve_chain(&self, settings: &OcrSettings) -> OcrChain {
+        let escalation: Vec<_> = settings
+            .escalation_chain
+            .iter()
+            .filter_map(|name| self.get_provider(name))
+            .collect();
+        if !escalation.is_empty() {
+            return OcrChain {
+                providers: escalation,
+                adoption: Adoption::Completeness,
+            };
+        }
+
+        OcrChain {
+            providers: self
+                .get_fallback_chain(settings)
+                .into_iter()
+                .enumerate()
+                .filter(|(i, provider)| *i == 0 || provider.is_configured(settings))
+                .map(|(_, provider)| provider)
+                .collect(),
+            adoption: Adoption::FirstSuccess,
+        }
+    }
+
+    /// 信頼度の低い項目を読み直すプロバイダー（再抽出する項目が無い・未登録・未設定なら `None`）
+    pub fn resolve_refiner(&self, settings: &OcrSettings) -> Option<Arc<dyn OcrProvider>> {
+        if settings.refine_fields.is_empty() {
+            return None;
+        }
+        let provider = self.get_provider(non_empty(&settings.refine_provider)?)?;
+        provider.is_configured(settings).then_some(provider)
+    }
+
     /// 利用可能なプロバイダー名一覧を取得
     #[allow(dead_code)]
     pub fn list_providers(&self) -> Vec<String> {
@@ -175,3 +811,116 @@ impl OcrProviderRegistry {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(project_id: &str, location: &str, processor_id: &str) -> OcrSettings {
+        OcrSettings {
+            google: Some(GoogleSettings {
+                project_id: Some(project_id.to_string()),
+                location: Some(location.to_string()),
+                processor_id: Some(processor_id.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_settings_and_skips_empty_values() {
+        assert!(
+            settings("my-project-123", "asia-northeast1", "1a2b3c4d5e6f7a8b")
+                .validate()
+                .is_ok()
+        );
+        assert!(settings("", "", "").validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_malformed_ids_and_locations() {
+        assert!(settings("My_Project", "us", "1a2b3c").validate().is_err());
+        assert!(settings("proj", "us", "1a2b3c").validate().is_err());
+        assert!(settings("my-project", "us", "projects/1a2b3c")
+            .validate()
+            .is_err());
+        assert!(settings("my-project", "us.evil.com/", "1a2b3c")
+            .validate()
+            .is_err());
+
+        let mut versioned = settings("my-project", "us", "1a2b3c");
+        let google = versioned.google.as_mut().unwrap();
+        google.processor_version = Some("pretrained-expense-v1.3-2022-09-12".to_string());
+        assert!(versioned.validate().is_ok());
+        versioned.google.as_mut().unwrap().processor_version = Some("v1:process?x=".to_string());
+        assert!(versioned.validate().is_err());
+
+        let mut webhook = settings("my-project", "us", "1a2b3c");
+        webhook.webhook_url = Some("https://example.com/hooks/torifune".to_string());
+        assert!(webhook.validate().is_ok());
+        webhook.webhook_url = Some("file:///etc/passwd".to_string());
+        assert!(webhook.validate().is_err());
+    }
+
+    #[test]
+    fn fallback_chain_starts_with_active_provider() {
+        let registry = OcrProviderRegistry::new();
+        let names = |settings: &OcrSettings| -> Vec<String> {
+            registry
+                .get_fallback_chain(settings)
+                .iter()
+                .map(|provider| provider.name().to_string())
+                .collect()
+        };
+
+        let defaults = names(&OcrSettings::default());
+        assert_eq!(
+            defaults.first().map(String::as_str),
+            registry.get_default_provider().as_ref().map(|p| p.name())
+        );
+        assert_eq!(defaults, registry.list_providers());
+
+        let veryfi = names(&OcrSettings {
+            active_provider: Some("veryfi".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(veryfi[0], "veryfi");
+        assert_eq!(veryfi.len(), defaults.len());
+    }
+
+    #[test]
+    fn resolve_chain_falls_back_to_configured_providers_unless_escalating() {
+        let registry = OcrProviderRegistry::new();
+        let resolve = |settings: &OcrSettings| {
+            let chain = registry.resolve_chain(settings);
+            let names: Vec<String> = chain
+                .providers
+                .iter()
+                .map(|provider| provider.name().to_string())
+                .collect();
+            (names, chain.adoption)
+        };
+
+        // 選んだプロバイダーは未設定でも先頭に置き、残りは設定済みのものだけ
+        let mut fallback = settings("my-project", "us", "1a2b3c");
+        fallback.google.as_mut().unwrap().use_adc = true;
+        fallback.active_provider = Some("veryfi".to_string());
+        assert_eq!(
+            resolve(&fallback),
+            (
+                vec!["veryfi".to_string(), "googledocumentai".to_string()],
+                Adoption::FirstSuccess
+            )
+        );
+
+        let escalation = OcrSettings {
+            escalation_chain: vec!["textract".to_string(), "unknown".to_string()],
+            ..fallback
+        };
+        assert_eq!(
+            resolve(&escalation),
+            (vec!["textract".to_string()], Adoption::Completeness)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! LLM 系プロバイダー向けの抽出プロンプト
+//!
+//! `OcrSettings.llm_prompt_template` でレシートの抽出指示を差し替えられるようにする。
+//! テンプレートには `{fields}`（抽出項目の一覧）と `{file_name}` を埋め込める。
+//! 未指定時は既定のレシート抽出プロンプトを使う。
+
+/// 既定のレシート抽出プロンプト
+pub const DEFAULT_RECEIPT_PROMPT: &str = "\
+画像はレシートまたは領収書です（ファイル名: {file_name}）。
+次の項目を読み取り、JSON オブジェクトのみを返してください。
+読み取れない項目は null にしてください。
+
+{fields}";
+
+/// 抽出項目（JSON のキーと説明）
+pub const RECEIPT_FIELDS: &[(&str, &str)] = &[
+    ("merchant", "店舗名"),
+    ("date", "日付（YYYY-MM-DD）"),
+    ("amount", "合計金額（数値）"),
+    ("currency", "通貨コード（JPY, USD など）"),
+    ("receiverName", "宛名"),
+];
+
+/// テンプレートに含まれていないと結果を取り込めない項目
+pub const REQUIRED_FIELDS: &[&str] = &["merchant", "date", "amount"];
+
+/// 使えるプレースホルダ
+const PLACEHOLDERS: &[&str] = &["fields", "file_name"];
+
+/// `{fields}` に埋め込む抽出項目の一覧
+fn fields_list() -> String {
+    RECEIPT_FIELDS
+        .iter()
+        .map(|(key, description)| format!("- {}: {}", key, description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 空文字列を未指定として扱い、使うテンプレートを返す
+fn template_or_default(template: Option<&str>) -> &str {
+    template
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(DEFAULT_RECEIPT_PROMPT)
+}
+
+/// `{name}` 形式のプレースホルダ名を列挙する
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let name = &after[..end];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            names.push(name);
+        }
+        rest = &after[end + 1..];
+    }
+    names
+}
+
+/// テンプレートにプレースホルダを埋め込んでプロンプトを作る
+pub fn render_prompt(template: Option<&str>, file_name: &str) -> String {
+    template_or_default(template)
+        .replace("{fields}", &fields_list())
+        .replace("{file_name}", file_name)
+}
+
+/// テンプレートの問題点を警告として返す（空なら問題なし）
+///
+/// `{fields}` を含まず、必須項目のキーも書かれていない場合や、未知のプレースホルダがある場合に警告する。
+pub fn check_prompt_template(template: Option<&str>) -> Vec<String> {
+    let template = template_or_default(template);
+    let mut warnings = Vec::new();
+
+    for name in placeholders(template) {
+        if !PLACEHOLDERS.contains(&name) {
+            warnings.push(format!(
+                "未知のプレースホルダです（使えるのは {}）: {{{}}}",
+                PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{}}}", p))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                name
+            ));
+        }
+    }
+
+    if !template.contains("{fields}") {
+        let missing: Vec<&str> = REQUIRED_FIELDS
+            .iter()
+            .copied()
+            .filter(|field| !template.contains(field))
+            .collect();
+        if !missing.is_empty() {
+            warnings.push(format!(
+                "必須項目の指示がありません（{{fields}} を含めるか項目名を記載してください）: {}",
+                missing.join(", ")
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_renders_placeholders_and_warns_on_missing_fields() {
+        let prompt = render_prompt(None, "a.jpg");
+        assert!(prompt.contains("ファイル名: a.jpg"));
+        assert!(prompt.contains("- amount: 合計金額（数値）"));
+        assert!(check_prompt_template(Some("  ")).is_empty());
+
+        let custom = "自社の納品書です。{fields}\n{file_name}";
+        assert!(render_prompt(Some(custom), "b.pdf").ends_with("- receiverName: 宛名\nb.pdf"));
+        assert!(check_prompt_template(Some(custom)).is_empty());
+
+        assert_eq!(
+            check_prompt_template(Some("merchant と amount を {json} で返して")),
+            vec![
+                "未知のプレースホルダです（使えるのは {fields}, {file_name}）: {json}",
+                "必須項目の指示がありません（{fields} を含めるか項目名を記載してください）: date",
+            ]
+        );
+    }
+}
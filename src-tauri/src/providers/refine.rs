@@ -0,0 +1,181 @@
+//! 信頼度の低い項目の再抽出
+//!
+//! 採用した抽出結果のうち `OcrSettings.refine_fields` に挙げた項目の信頼度が
+//! `refine_min_confidence` 未満なら、`refine_provider` で読み直してその項目だけ差し替える。
+//! エスカレーション（結果全体の取り直し）と違い、他の項目は元の結果のまま残す。
+
+use super::tuning::{extract_with_tuning, ProviderLimits};
+use super::{OcrProvider, OcrSettings, ReceiptData};
+
+/// 信頼度の既定の閾値
+pub const DEFAULT_REFINE_MIN_CONFIDENCE: f32 = 0.7;
+
+/// 再抽出できる項目
+pub const REFINABLE_FIELDS: &[&str] = &["merchant", "date", "amount"];
+
+impl OcrSettings {
+    /// 再抽出する信頼度の閾値
+    pub fn refine_min_confidence(&self) -> f32 {
+        self.refine_min_confidence
+            .unwrap_or(DEFAULT_REFINE_MIN_CONFIDENCE)
+    }
+}
+
+/// `fields` のうち信頼度が `min_confidence` 未満の項目（信頼度の無い項目は対象外）
+pub fn low_confidence_fields(
+    data: &ReceiptData,
+    fields: &[String],
+    min_confidence: f32,
+) -> Vec<String> {
+    let Some(confidence) = &data.confidence else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|field| {
+            confidence
+                .get(field)
+                .is_some_and(|value| value < min_confidence)
+        })
+        .cloned()
+        .collect()
+}
+
+/// 再抽出の結果から `fields` の項目だけを差し替え、差し替えた項目を返す
+///
+/// 再抽出で値が取れなかった項目は元の値を残す。金額は通貨・最小単位の金額もあわせて差し替える。
+pub fn merge_refined_fields(
+    data: &mut ReceiptData,
+    refined: &ReceiptData,
+    fields: &[String],
+) -> Vec<String> {
+    let mut replaced = Vec::new();
+    for field in fields {
+        let taken = match field.as_str() {
+            "merchant" if refined.merchant.is_some() => {
+                data.merchant = refined.merchant.clone();
+                true
+            }
+            "date" if refined.date.is_some() => {
+                data.date = refined.date.clone();
+                true
+            }
+            "amount" if refined.amount.is_some() => {
+                data.amount = refined.amount;
+                data.amount_minor = refined.amount_minor;
+                if refined.currency.is_some() {
+                    data.currency = refined.currency.clone();
+                }
+                true
+            }
+            _ => false,
+        };
+        if taken {
+            let value = refined.confidence.as_ref().and_then(|c| c.get(field));
+            data.confidence
+                .get_or_insert_with(Default::default)
+                .set(field, value);
+            replaced.push(field.clone());
+        }
+    }
+    replaced
+}
+
+/// 信頼度の低い項目を `refiner` で読み直して差し替える
+///
+/// 結果の warnings に残す文言を返す（対象の項目が無ければ `None`）。
+/// 再抽出に失敗した場合は元の結果のまま、失敗を warnings に残す。
+pub async fn refine_low_confidence_fields(
+    data: &mut ReceiptData,
+    refiner: &dyn OcrProvider,
+    file_path: &str,
+    file_content: &str,
+    mime_type: &str,
+    settings: &OcrSettings,
+    limits: Option<&ProviderLimits>,
+) -> Option<String> {
+    let fields = low_confidence_fields(
+        data,
+        &settings.refine_fields,
+        settings.refine_min_confidence(),
+    );
+    if fields.is_empty() {
+        return None;
+    }
+
+    let refined = {
+        let _permit = match limits {
+            Some(limits) => limits.acquire(refiner.name()).await,
+            None => None,
+        };
+        extract_with_tuning(refiner, file_path, file_content, mime_type, settings, None).await
+    };
+
+    Some(match refined {
+        Ok(refined) => {
+            let replaced = merge_refined_fields(data, &refined, &fields);
+            if replaced.is_empty() {
+                format!(
+                    "信頼度の低い項目を{}で読み直しましたが値を取得できませんでした: {}",
+                    refiner.name(),
+                    fields.join(", ")
+                )
+            } else {
+                format!(
+                    "信頼度の低い項目を{}で再抽出しました: {}",
+                    refiner.name(),
+                    replaced.join(", ")
+                )
+            }
+        }
+        Err(e) => format!(
+            "信頼度の低い項目の再抽出に失敗しました（{}）: {}",
+            refiner.name(),
+            e
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ReceiptConfidence;
+
+    #[test]
+    fn only_low_confidence_fields_are_replaced() {
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.merchant = Some("ロ-ソン".to_string());
+        data.date = Some("2025-01-05".to_string());
+        data.amount = Some(108.0);
+        data.confidence = Some(ReceiptConfidence {
+            merchant: Some(0.4),
+            date: Some(0.95),
+            amount: Some(0.5),
+        });
+
+        let fields: Vec<String> = ["merchant", "date", "amount"].map(String::from).to_vec();
+        let low = low_confidence_fields(&data, &fields, DEFAULT_REFINE_MIN_CONFIDENCE);
+        assert_eq!(low, vec!["merchant", "amount"]);
+
+        let mut refined = ReceiptData::new("a.jpg".to_string());
+        refined.merchant = Some("ローソン".to_string());
+        refined.date = Some("2025-01-06".to_string());
+        refined.confidence = Some(ReceiptConfidence {
+            merchant: Some(0.9),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            merge_refined_fields(&mut data, &refined, &low),
+            vec!["merchant"]
+        );
+        assert_eq!(data.merchant.as_deref(), Some("ローソン"));
+        assert_eq!(data.date.as_deref(), Some("2025-01-05"));
+        assert_eq!(data.amount, Some(108.0));
+        let confidence = data.confidence.unwrap();
+        assert_eq!(
+            (confidence.merchant, confidence.amount),
+            (Some(0.9), Some(0.5))
+        );
+    }
+}
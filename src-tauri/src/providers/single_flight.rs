@@ -0,0 +1,95 @@
+//! 同じ処理の同時実行をまとめる（single-flight）
+//!
+//! バッチの複数タスクが同時にアクセストークンを取りに行くと、同じリクエストが並列数ぶん
+//! 飛んで 429 の原因になる。実行中の処理があればそれに相乗りし、1回分の結果を全員で受け取る。
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::future::Future;
+use std::sync::Mutex;
+
+/// キーが同じ処理の同時実行を1回にまとめる
+///
+/// 完了した結果は保持しない（次の呼び出しは新しく実行する）。
+pub struct SingleFlight<K, T: Clone> {
+    in_flight: Mutex<Option<(K, Shared<BoxFuture<'static, T>>)>>,
+}
+
+impl<K, T: Clone> Default for SingleFlight<K, T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(None),
+        }
+    }
+}
+
+impl<K: PartialEq, T: Clone + Send + Sync + 'static> SingleFlight<K, T> {
+    /// `key` の処理が実行中ならその結果を待ち、無ければ `start` で始めた処理を共有する
+    pub async fn run<F>(&self, key: K, start: impl FnOnce() -> F) -> T
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.as_ref() {
+                Some((running, shared)) if *running == key => shared.clone(),
+                _ => {
+                    let shared = start().boxed().shared();
+                    *in_flight = Some((key, shared.clone()));
+                    shared
+                }
+            }
+        };
+
+        let output = shared.clone().await;
+
+        // 完了した処理は外す（別の処理に入れ替わっていれば残す）
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if in_flight
+            .as_ref()
+            .is_some_and(|(_, running)| running.ptr_eq(&shared))
+        {
+            *in_flight = None;
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn concurrent_calls_share_one_run_per_key() {
+        let flight = SingleFlight::<&str, usize>::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        // 3件とも呼び出してから完了させる
+        let (release, gate) = futures::channel::oneshot::channel::<()>();
+        let gate = gate.shared();
+        let start = || {
+            let runs = Arc::clone(&runs);
+            let gate = gate.clone();
+            move || async move {
+                let _ = gate.await;
+                runs.fetch_add(1, Ordering::SeqCst) + 1
+            }
+        };
+
+        let (a, b, c, _) = futures::executor::block_on(futures::future::join4(
+            flight.run("sa@example.com", start()),
+            flight.run("sa@example.com", start()),
+            flight.run("sa@example.com", start()),
+            async move {
+                let _ = release.send(());
+            },
+        ));
+        assert_eq!((a, b, c), (1, 1, 1));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // 完了後は新しく実行する
+        let d = futures::executor::block_on(flight.run("sa@example.com", || async { 2 }));
+        assert_eq!(d, 2);
+    }
+}
@@ -0,0 +1,254 @@
+//! Tesseract OCRプロバイダー（ローカル）
+//!
+//! 画像を外部に送らずに PATH 上の `tesseract` コマンドで全文を読み取り、
+//! 行ごとの正規表現で日付・合計金額・店舗名を推定する。外部の認証情報は不要だが、
+//! 精度が低いため設定で選んだとき（既定・エスカレーション・再抽出）だけ使う。
+//! 読み取る言語は `OcrSettings.tesseract.lang`（既定 `jpn+eng`）で切り替える。
+
+use super::{non_empty, OcrProvider, OcrSettings, ReceiptData};
+use crate::error::ProviderError;
+use crate::money::Currency;
+use async_trait::async_trait;
+use regex::Regex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+
+/// 既定の言語データ
+pub const DEFAULT_TESSERACT_LANG: &str = "jpn+eng";
+
+/// 言語指定の形式（`jpn`・`jpn+eng` など）
+pub static TESSERACT_LANG_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z_]+(\+[A-Za-z_]+)*$").unwrap());
+
+/// 合計金額の行（小計・税の内訳・点数は `EXCLUDED_TOTAL_PATTERN` で除く）
+static TOTAL_LINE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(合\s*計|総\s*額|お買上|ご請求|total|amount\s+due)").unwrap()
+});
+
+static EXCLUDED_TOTAL_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(小\s*計|subtotal|対象|内税|税額|点\s*数|個\s*数)").unwrap());
+
+/// 行内の金額（記号付きの数字。最後のものを合計とみなす）
+static AMOUNT_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[¥￥$€£]?\s*\d[\d,.]*").unwrap());
+
+/// 店舗名とみなさない見出し
+const NON_MERCHANT_WORDS: &[&str] = &["領収書", "領収証", "レシート", "receipt", "invoice"];
+
+/// 一時ファイル名の連番（同時実行での衝突を避ける）
+static TEMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl OcrSettings {
+    /// Tesseract に渡す言語データ
+    pub fn tesseract_lang(&self) -> &str {
+        self.tesseract
+            .as_ref()
+            .and_then(|tesseract| non_empty(&tesseract.lang))
+            .unwrap_or(DEFAULT_TESSERACT_LANG)
+    }
+}
+
+/// 全文から推定した項目
+#[derive(Debug, Default, PartialEq)]
+struct ParsedReceipt {
+    merchant: Option<String>,
+    date: Option<String>,
+    amount: Option<f64>,
+    currency: Option<Currency>,
+}
+
+/// 全文から日付・合計金額・店舗名を推定する
+fn parse_receipt_text(text: &str) -> ParsedReceipt {
+    let language = crate::language::detect_language(text);
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let date = lines
+        .iter()
+        .find_map(|line| crate::language::normalize_date(line, language));
+
+    let total_line = lines
+        .iter()
+        .find(|line| TOTAL_LINE_PATTERN.is_match(line) && !EXCLUDED_TOTAL_PATTERN.is_match(line));
+    let amount = total_line.and_then(|line| {
+        AMOUNT_PATTERN
+            .find_iter(line)
+            .last()
+            .and_then(|m| crate::language::parse_amount(m.as_str(), language))
+    });
+
+    let merchant = lines
+        .iter()
+        .find(|line| {
+            let lower = line.to_lowercase();
+            line.chars().filter(|c| c.is_alphabetic()).count() >= 2
+                && !NON_MERCHANT_WORDS.iter().any(|word| lower.contains(word))
+                && crate::language::normalize_date(line, language).is_none()
+        })
+        .map(|line| line.to_string());
+
+    let currency = ["¥", "￥", "円", "€", "£", "$"]
+        .into_iter()
+        .find(|symbol| text.contains(symbol))
+        .and_then(Currency::parse);
+
+    ParsedReceipt {
+        merchant,
+        date,
+        amount,
+        currency,
+    }
+}
+
+/// 画像を一時ファイルに書き、`tesseract` で全文を読み取る
+fn recognize(content: &[u8], extension: &str, lang: &str) -> Result<String, String> {
+    let path = std::env::temp_dir().join(format!(
+        "torifune-tesseract-{}-{}.{}",
+        std::process::id(),
+        TEMP_FILE_SEQ.fetch_add(1, Ordering::Relaxed),
+        extension
+    ));
+    std::fs::write(&path, content)
+        .map_err(|e| format!("一時ファイルの作成に失敗しました: {}", e))?;
+
+    let output = path
+        .to_str()
+        .ok_or_else(|| "パスの変換に失敗しました".to_string())
+        .and_then(|input| crate::video::run("tesseract", &[input, "stdout", "-l", lang]));
+    let _ = std::fs::remove_file(&path);
+
+    Ok(String::from_utf8_lossy(&output?).into_owned())
+}
+
+/// Tesseract プロバイダー
+#[derive(Default)]
+pub struct TesseractProvider;
+
+impl TesseractProvider {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl OcrProvider for TesseractProvider {
+    fn name(&self) -> &str {
+        "tesseract"
+    }
+
+    /// 設定で選んだときだけ使う（他のプロバイダーが失敗したときに勝手に切り替えない）
+    fn is_configured(&self, settings: &OcrSettings) -> bool {
+        let name = self.name();
+        non_empty(&settings.active_provider) == Some(name)
+            || non_empty(&settings.refine_provider) == Some(name)
+            || settings
+                .escalation_chain
+                .iter()
+                .any(|provider| provider == name)
+    }
+
+    async fn test_connection(&self, _settings: &OcrSettings) -> Result<(), String> {
+        tauri::async_runtime::spawn_blocking(|| crate::video::run("tesseract", &["--version"]))
+            .await
+            .map_err(|e| format!("tesseract の起動に失敗しました: {}", e))?
+            .map(|_| ())
+    }
+
+    async fn extract_receipt(
+        &self,
+        file_path: &str,
+        file_content: &str,
+        mime_type: &str,
+        settings: &OcrSettings,
+    ) -> Result<ReceiptData, ProviderError> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let extension = match mime_type {
+            "image/jpeg" => "jpg",
+            "image/png" => "png",
+            "image/tiff" => "tif",
+            "image/bmp" => "bmp",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            other => return Err(format!("Tesseract で読み取れない形式です: {}", other).into()),
+        };
+        let content = STANDARD
+            .decode(file_content)
+            .map_err(|e| format!("Base64デコードに失敗しました: {}", e))?;
+        let lang = settings.tesseract_lang().to_string();
+
+        let text =
+            tauri::async_runtime::spawn_blocking(move || recognize(&content, extension, &lang))
+                .await
+                .map_err(|e| format!("tesseract の起動に失敗しました: {}", e))??;
+
+        let file_name = std::path::Path::new(file_path)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or(file_path)
+            .to_string();
+
+        let parsed = parse_receipt_text(&text);
+        let mut receipt_data = ReceiptData::new(file_name);
+        receipt_data.merchant = parsed.merchant;
+        receipt_data.date = parsed.date;
+        receipt_data.amount = parsed.amount;
+        receipt_data.currency = parsed.currency;
+        receipt_data.amount_minor = receipt_data.amount.and_then(|amount| {
+            crate::money::to_minor_units(amount, receipt_data.currency.as_deref())
+        });
+        receipt_data.detected_language = crate::language::detect_language(&text).map(String::from);
+        receipt_data.source_provider = Some(self.name().to_string());
+        Ok(receipt_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_receipt_text_finds_merchant_date_and_total() {
+        let text = "領収書\nファミリーマート 渋谷店\n2025年1月5日 12:34\nおにぎり ¥150\n\
+                    小計 ¥1,080\n合計 ¥1,188\nお預り ¥2,000\n";
+        assert_eq!(
+            parse_receipt_text(text),
+            ParsedReceipt {
+                merchant: Some("ファミリーマート 渋谷店".to_string()),
+                date: Some("2025-01-05".to_string()),
+                amount: Some(1188.0),
+                currency: Currency::parse("JPY"),
+            }
+        );
+
+        let english = "BLUE BOTTLE COFFEE\n01/05/2025\nSubtotal 11.00\nTOTAL $12.50\n";
+        let parsed = parse_receipt_text(english);
+        assert_eq!(parsed.merchant.as_deref(), Some("BLUE BOTTLE COFFEE"));
+        assert_eq!(parsed.amount, Some(12.5));
+        assert_eq!(parsed.currency.as_deref(), Some("USD"));
+
+        // 「合計点数」の行は点数であって合計金額ではない
+        let counted = "ローソン\n合計点数 3点\n合計 ¥540\n";
+        assert_eq!(parse_receipt_text(counted).amount, Some(540.0));
+
+        assert!(TESSERACT_LANG_PATTERN.is_match("jpn+eng"));
+        assert!(!TESSERACT_LANG_PATTERN.is_match("jpn; rm"));
+    }
+
+    #[test]
+    fn is_configured_only_when_selected() {
+        let provider = TesseractProvider::new();
+        let mut settings = OcrSettings::default();
+        assert!(!provider.is_configured(&settings));
+
+        settings.escalation_chain = vec!["tesseract".to_string()];
+        assert!(provider.is_configured(&settings));
+
+        settings.escalation_chain.clear();
+        settings.active_provider = Some("tesseract".to_string());
+        assert!(provider.is_configured(&settings));
+    }
+}
@@ -0,0 +1,381 @@
+//! AWS Textract OCRプロバイダー
+//!
+//! Textract の `AnalyzeExpense` API にレシート画像（Base64）を SigV4 署名付きで送り、
+//! `SummaryFields` から店舗名・日付・合計金額を抽出する。複数ページの場合は最初のページを採用する。
+
+use super::{non_empty, request_error, OcrProvider, OcrSettings, ReceiptConfidence, ReceiptData};
+use crate::error::ProviderError;
+use crate::money::Currency;
+use async_trait::async_trait;
+use aws_credential_types::Credentials;
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningSettings};
+use aws_sigv4::sign::v4;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+/// リージョンの形式（`ap-northeast-1` など）
+pub static AWS_REGION_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z]{2}(-[a-z]+)+-\d+$").unwrap());
+
+/// `AnalyzeExpense` の `X-Amz-Target`
+const ANALYZE_EXPENSE_TARGET: &str = "Textract.AnalyzeExpense";
+
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+/// 時間をおけば通るエラー種別（スロットリングは HTTP 400 で返るためステータスでは判別できない）
+const TRANSIENT_ERROR_TYPES: &[&str] = &[
+    "ThrottlingException",
+    "ProvisionedThroughputExceededException",
+    "LimitExceededException",
+    "InternalServerError",
+];
+
+/// 認証は通っていて入力だけが不正なときのエラー種別（接続テストでは成功とみなす）
+const INPUT_ERROR_TYPES: &[&str] = &[
+    "InvalidParameterException",
+    "UnsupportedDocumentException",
+    "BadDocumentException",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AnalyzeExpenseResponse {
+    #[serde(default)]
+    expense_documents: Vec<ExpenseDocument>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExpenseDocument {
+    #[serde(default)]
+    summary_fields: Vec<ExpenseField>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExpenseField {
+    #[serde(rename = "Type")]
+    field_type: Option<ExpenseDetection>,
+    value_detection: Option<ExpenseDetection>,
+    currency: Option<ExpenseCurrency>,
+    page_number: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExpenseDetection {
+    text: Option<String>,
+    /// 信頼度（0〜100）
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExpenseCurrency {
+    code: Option<String>,
+}
+
+/// AWS のエラーレスポンス（`__type` は `com.amazonaws...#InvalidParameterException` の形式もある）
+#[derive(Debug, Deserialize)]
+struct AwsErrorResponse {
+    #[serde(rename = "__type")]
+    error_type: Option<String>,
+}
+
+/// Textract の認証情報
+struct TextractCredentials<'a> {
+    region: &'a str,
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+}
+
+/// AWS Textract プロバイダー
+pub struct TextractProvider {
+    client: Client,
+}
+
+impl TextractProvider {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    fn credentials(settings: &OcrSettings) -> Option<TextractCredentials<'_>> {
+        let textract = settings.textract.as_ref()?;
+        Some(TextractCredentials {
+            region: non_empty(&textract.region)?,
+            access_key_id: non_empty(&textract.access_key_id)?,
+            secret_access_key: non_empty(&textract.secret_access_key)?,
+        })
+    }
+
+    /// `AnalyzeExpense` に SigV4 署名付きで POST する
+    async fn post_analyze_expense(
+        &self,
+        credentials: &TextractCredentials<'_>,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, ProviderError> {
+        let url = format!("https://textract.{}.amazonaws.com/", credentials.region);
+
+        let identity = Credentials::new(
+            credentials.access_key_id,
+            credentials.secret_access_key,
+            None,
+            None,
+            "torifune-settings",
+        )
+        .into();
+        let signing_params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(credentials.region)
+            .name("textract")
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| format!("Textractリクエストの署名に失敗しました: {}", e))?
+            .into();
+
+        let headers = [
+            ("content-type", CONTENT_TYPE),
+            ("x-amz-target", ANALYZE_EXPENSE_TARGET),
+        ];
+        let signable = SignableRequest::new(
+            "POST",
+            url.as_str(),
+            headers.iter().copied(),
+            SignableBody::Bytes(&body),
+        )
+        .map_err(|e| format!("Textractリクエストの署名に失敗しました: {}", e))?;
+        let (instructions, _signature) = sign(signable, &signing_params)
+            .map_err(|e| format!("Textractリクエストの署名に失敗しました: {}", e))?
+            .into_parts();
+
+        let mut request = self.client.post(&url);
+        for (name, value) in headers.iter().copied().chain(instructions.headers()) {
+            request = request.header(name, value);
+        }
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| request_error("Textract APIリクエストに失敗しました", e))
+    }
+
+    /// エラーレスポンスの種別（`InvalidParameterException` など）
+    fn error_type(body: &str) -> Option<String> {
+        let error: AwsErrorResponse = serde_json::from_str(body).ok()?;
+        let error_type = error.error_type?;
+        Some(
+            error_type
+                .rsplit('#')
+                .next()
+                .unwrap_or(&error_type)
+                .to_string(),
+        )
+    }
+
+    /// レスポンスを `ReceiptData` に変換する（最初のページのサマリーを採用）
+    fn parse_response(file_name: String, body: &[u8]) -> Result<ReceiptData, String> {
+        let response: AnalyzeExpenseResponse = serde_json::from_slice(body)
+            .map_err(|e| format!("Textractレスポンスのパースに失敗しました: {}", e))?;
+
+        let fields: Vec<&ExpenseField> = response
+            .expense_documents
+            .iter()
+            .flat_map(|document| &document.summary_fields)
+            .collect();
+        let first_page = fields
+            .iter()
+            .filter_map(|field| field.page_number)
+            .min()
+            .unwrap_or(1);
+        let find = |field_type: &str| {
+            fields.iter().copied().find(|field| {
+                field.page_number.unwrap_or(1) == first_page
+                    && field.field_type.as_ref().and_then(|t| t.text.as_deref()) == Some(field_type)
+            })
+        };
+        let value = |field: &ExpenseField| {
+            field
+                .value_detection
+                .as_ref()
+                .and_then(|v| v.text.as_deref())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(str::to_string)
+        };
+        let confidence = |field: &ExpenseField| {
+            field
+                .value_detection
+                .as_ref()
+                .and_then(|v| v.confidence)
+                .map(|c| (c / 100.0).clamp(0.0, 1.0))
+        };
+
+        let mut receipt_data = ReceiptData::new(file_name);
+        let mut receipt_confidence = ReceiptConfidence::default();
+
+        if let Some(vendor) = find("VENDOR_NAME") {
+            receipt_data.merchant = value(vendor);
+            receipt_confidence.merchant = confidence(vendor);
+        }
+
+        let language = receipt_data
+            .merchant
+            .as_deref()
+            .and_then(crate::language::detect_language);
+        receipt_data.detected_language = language.map(String::from);
+
+        if let Some(date) = find("INVOICE_RECEIPT_DATE") {
+            receipt_data.date = value(date)
+                .map(|text| crate::language::normalize_date(&text, language).unwrap_or(text));
+            receipt_confidence.date = confidence(date);
+        }
+
+        if let Some(total) = find("TOTAL") {
+            receipt_data.amount =
+                value(total).and_then(|text| crate::language::parse_amount(&text, language));
+            receipt_data.currency = total
+                .currency
+                .as_ref()
+                .and_then(|currency| currency.code.as_deref())
+                .and_then(Currency::parse);
+            receipt_confidence.amount = confidence(total);
+        }
+
+        receipt_data.amount_minor = receipt_data.amount.and_then(|amount| {
+            crate::money::to_minor_units(amount, receipt_data.currency.as_deref())
+        });
+        receipt_data.document_number = find("INVOICE_RECEIPT_ID").and_then(value);
+        receipt_data.confidence = Some(receipt_confidence);
+        Ok(receipt_data)
+    }
+}
+
+impl Default for TextractProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl OcrProvider for TextractProvider {
+    fn name(&self) -> &str {
+        "textract"
+    }
+
+    fn is_configured(&self, settings: &OcrSettings) -> bool {
+        Self::credentials(settings).is_some()
+    }
+
+    async fn test_connection(&self, settings: &OcrSettings) -> Result<(), String> {
+        let credentials = Self::credentials(settings).ok_or("設定が不完全です")?;
+
+        // 空の文書を送り、入力エラーが返れば認証は通っている（課金対象にならない）
+        let body = serde_json::to_vec(&serde_json::json!({ "Document": { "Bytes": "" } }))
+            .map_err(|e| format!("リクエストの作成に失敗しました: {}", e))?;
+        let response = self.post_analyze_expense(&credentials, body).await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let text = response.text().await.unwrap_or_default();
+        match Self::error_type(&text) {
+            Some(error_type) if INPUT_ERROR_TYPES.contains(&error_type.as_str()) => Ok(()),
+            _ => Err(format!(
+                "Textractへの接続に失敗しました: HTTP {} - {}",
+                status, text
+            )),
+        }
+    }
+
+    async fn extract_receipt(
+        &self,
+        file_path: &str,
+        file_content: &str,
+        _mime_type: &str,
+        settings: &OcrSettings,
+    ) -> Result<ReceiptData, ProviderError> {
+        let credentials = Self::credentials(settings).ok_or("OCR設定が不完全です")?;
+
+        let body = serde_json::to_vec(&serde_json::json!({
+            "Document": { "Bytes": file_content },
+        }))
+        .map_err(|e| format!("リクエストの作成に失敗しました: {}", e))?;
+        let response = self.post_analyze_expense(&credentials, body).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            let message = format!("Textract処理に失敗しました: HTTP {} - {}", status, text);
+            let throttled = Self::error_type(&text)
+                .is_some_and(|error_type| TRANSIENT_ERROR_TYPES.contains(&error_type.as_str()));
+            return Err(if throttled {
+                ProviderError::transient(message)
+            } else {
+                ProviderError::from_status(status.as_u16(), message)
+            });
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| request_error("Textractレスポンスの受信に失敗しました", e))?;
+
+        let file_name = std::path::Path::new(file_path)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or(file_path)
+            .to_string();
+
+        let mut receipt_data = Self::parse_response(file_name, &body)?;
+        receipt_data.source_provider = Some(self.name().to_string());
+        Ok(receipt_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_response_takes_first_page_summary_fields() {
+        let body = br#"{
+            "ExpenseDocuments": [{
+                "SummaryFields": [
+                    { "Type": { "Text": "VENDOR_NAME" }, "PageNumber": 2,
+                      "ValueDetection": { "Text": "Second Page Store", "Confidence": 99.0 } },
+                    { "Type": { "Text": "VENDOR_NAME" }, "PageNumber": 1,
+                      "ValueDetection": { "Text": "Blue Bottle Coffee", "Confidence": 95.5 } },
+                    { "Type": { "Text": "INVOICE_RECEIPT_DATE" }, "PageNumber": 1,
+                      "ValueDetection": { "Text": "01/05/2025", "Confidence": 80.0 } },
+                    { "Type": { "Text": "TOTAL" }, "PageNumber": 1,
+                      "ValueDetection": { "Text": "$12.50", "Confidence": 60.0 },
+                      "Currency": { "Code": "usd" } }
+                ]
+            }]
+        }"#;
+        let data = TextractProvider::parse_response("a.jpg".to_string(), body).unwrap();
+        assert_eq!(data.merchant.as_deref(), Some("Blue Bottle Coffee"));
+        assert_eq!(data.date.as_deref(), Some("2025-01-05"));
+        assert_eq!(data.amount, Some(12.5));
+        assert_eq!(data.currency.as_deref(), Some("USD"));
+        assert_eq!(data.amount_minor, Some(1250));
+        assert_eq!(data.confidence.unwrap().amount, Some(0.6));
+
+        assert_eq!(
+            TextractProvider::error_type(
+                r#"{"__type":"com.amazonaws.textract#InvalidParameterException"}"#
+            )
+            .as_deref(),
+            Some("InvalidParameterException")
+        );
+        assert!(AWS_REGION_PATTERN.is_match("ap-northeast-1"));
+        assert!(!AWS_REGION_PATTERN.is_match("tokyo"));
+    }
+}
@@ -0,0 +1,95 @@
+//! OCR処理のフェーズ別計測
+//!
+//! `collect_timings` が有効なときだけ時刻を取得する。無効時は計測コストを払わない。
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// フェーズ別の所要時間（ミリ秒）
+///
+/// リトライやエスカレーションで複数回抽出した場合は合算する。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrTiming {
+    /// アクセストークン取得
+    pub token_ms: u64,
+    /// 画像の前処理・リクエストの組み立て
+    pub preprocess_ms: u64,
+    /// API の往復（レスポンス本文の受信まで）
+    pub api_ms: u64,
+    /// レスポンスのパース・項目の抽出
+    pub parse_ms: u64,
+    /// 全体（待ち時間・リトライを含む）
+    pub total_ms: u64,
+}
+
+/// 計測対象のフェーズ
+#[derive(Debug, Clone, Copy)]
+pub enum OcrPhase {
+    Token,
+    Preprocess,
+    Api,
+    Parse,
+}
+
+impl OcrTiming {
+    fn phase_mut(&mut self, phase: OcrPhase) -> &mut u64 {
+        match phase {
+            OcrPhase::Token => &mut self.token_ms,
+            OcrPhase::Preprocess => &mut self.preprocess_ms,
+            OcrPhase::Api => &mut self.api_ms,
+            OcrPhase::Parse => &mut self.parse_ms,
+        }
+    }
+}
+
+/// フェーズの区切りごとに前回からの経過時間を加算する記録器
+///
+/// `timing` が `None` の場合は何もしない。
+pub struct PhaseRecorder<'a> {
+    timing: Option<&'a mut OcrTiming>,
+    last: Option<Instant>,
+}
+
+impl<'a> PhaseRecorder<'a> {
+    pub fn new(timing: Option<&'a mut OcrTiming>) -> Self {
+        let last = timing.is_some().then(Instant::now);
+        Self { timing, last }
+    }
+
+    /// 直前の区切りからここまでを `phase` の所要時間として加算する
+    pub fn record(&mut self, phase: OcrPhase) {
+        let (Some(timing), Some(last)) = (self.timing.as_deref_mut(), self.last) else {
+            return;
+        };
+
+        let now = Instant::now();
+        *timing.phase_mut(phase) += now.duration_since(last).as_millis() as u64;
+        self.last = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn phase_recorder_accumulates_only_when_enabled() {
+        let mut disabled = PhaseRecorder::new(None);
+        disabled.record(OcrPhase::Token);
+        assert!(disabled.last.is_none());
+
+        let mut timing = OcrTiming {
+            api_ms: 5,
+            ..Default::default()
+        };
+        let mut recorder = PhaseRecorder::new(Some(&mut timing));
+        std::thread::sleep(Duration::from_millis(10));
+        recorder.record(OcrPhase::Api);
+        recorder.record(OcrPhase::Parse);
+
+        assert!(timing.api_ms >= 15);
+        assert_eq!(timing.token_ms, 0);
+    }
+}
@@ -0,0 +1,340 @@
+//! プロバイダー別のチューニング
+//!
+//! タイムアウト・リトライ回数・同時実行数をプロバイダーごとに上書きできるようにする。
+//! 上書きされていない項目はグローバルの既定値を使う。
+
+use super::timing::OcrTiming;
+use super::{OcrProvider, OcrSettings, ReceiptData};
+use crate::error::ProviderError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// 1回の抽出のタイムアウト（秒）の既定値
+///
+/// 複数ページの PDF でも通常は数十秒以内に返るため、それを超えたら応答が無いものとみなす。
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// 失敗時のリトライ回数の既定値
+///
+/// Document AI は一時的なエラー（429・5xx）をリクエストごとに再送しているため、既定では
+/// 抽出全体を重ねて再試行しない。
+pub const DEFAULT_MAX_RETRIES: u32 = 0;
+
+/// 最初の再試行までの待ち時間（以降は倍々に延ばす）
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 待ち時間を倍にする回数の上限（16 秒で頭打ち）
+const MAX_BACKOFF_DOUBLINGS: u32 = 4;
+
+/// バッチ処理での同時実行数の既定値
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// 同時実行数の上限（グローバル・プロバイダー別とも設定値はこの範囲に丸める）
+pub const MAX_CONCURRENT_LIMIT: usize = 10;
+
+/// プロバイダー別の上書き設定（未指定の項目はグローバル値）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderTuning {
+    /// 1回の抽出のタイムアウト（秒）
+    pub timeout_secs: Option<u64>,
+    /// 失敗時のリトライ回数
+    pub max_retries: Option<u32>,
+    /// バッチ処理での同時実行数（1〜10 に丸める）
+    pub max_concurrent: Option<usize>,
+}
+
+/// グローバル値で補完した実効設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedTuning {
+    pub timeout: Duration,
+    pub max_retries: u32,
+    pub max_concurrent: usize,
+}
+
+impl OcrSettings {
+    /// バッチ処理でのグローバルの同時実行数（1〜10 に丸める）
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+            .unwrap_or(DEFAULT_MAX_CONCURRENT)
+            .clamp(1, MAX_CONCURRENT_LIMIT)
+    }
+
+    /// 指定プロバイダーの実効設定を解決する
+    pub fn tuning_for(&self, provider_name: &str) -> ResolvedTuning {
+        let tuning = self.provider_overrides.get(provider_name);
+
+        ResolvedTuning {
+            timeout: Duration::from_secs(
+                tuning
+                    .and_then(|t| t.timeout_secs)
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS),
+            ),
+            max_retries: tuning
+                .and_then(|t| t.max_retries)
+                .unwrap_or(DEFAULT_MAX_RETRIES),
+            max_concurrent: tuning
+                .and_then(|t| t.max_concurrent)
+                .unwrap_or_else(|| self.max_concurrent())
+                .clamp(1, MAX_CONCURRENT_LIMIT),
+        }
+    }
+}
+
+/// プロバイダーごとの同時実行数の制限
+pub struct ProviderLimits {
+    semaphores: HashMap<String, Arc<Semaphore>>,
+    /// チェーンの先頭（どのファイルも最初に使う）プロバイダー名
+    primary: Option<String>,
+    /// いずれかが立っていれば実行枠を確保しても抽出に着手しない
+    stops: Vec<Arc<AtomicBool>>,
+}
+
+impl ProviderLimits {
+    pub fn new(chain: &[Arc<dyn OcrProvider>], settings: &OcrSettings) -> Self {
+        Self::for_providers(chain.iter().map(|provider| provider.name()), settings)
+    }
+
+    /// プロバイダー名（チェーン順）から作る
+    fn for_providers<'a>(names: impl IntoIterator<Item = &'a str>, settings: &OcrSettings) -> Self {
+        let names: Vec<&str> = names.into_iter().collect();
+        let semaphores = names
+            .iter()
+            .map(|name| {
+                let max_concurrent = settings.tuning_for(name).max_concurrent;
+                (name.to_string(), Arc::new(Semaphore::new(max_concurrent)))
+            })
+            .collect();
+
+        Self {
+            semaphores,
+            primary: names.first().map(|name| name.to_string()),
+            stops: Vec::new(),
+        }
+    }
+
+    /// 停止要求のフラグを追加する（アプリの終了・ユーザーの中止など）
+    pub fn with_stop_flag(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stops.push(stop);
+        self
+    }
+
+    /// 停止が要求されているか
+    pub fn is_stopped(&self) -> bool {
+        self.stops.iter().any(|stop| stop.load(Ordering::SeqCst))
+    }
+
+    /// 先頭プロバイダーの実行枠（同時に進めるファイル数の上限に使う）
+    ///
+    /// どのファイルも先頭プロバイダーから始めるため、エスカレーション先の枠を足しても
+    /// 同時に抽出できるファイル数は増えない。
+    pub fn primary_capacity(&self) -> usize {
+        self.primary
+            .as_ref()
+            .and_then(|name| self.semaphores.get(name))
+            .map_or(1, |semaphore| semaphore.available_permits())
+            .max(1)
+    }
+
+    /// 指定プロバイダーの実行枠を確保する（制限対象外なら `None`）
+    pub async fn acquire(&self, provider_name: &str) -> Option<SemaphorePermit<'_>> {
+        let semaphore = self.semaphores.get(provider_name)?;
+        semaphore.acquire().await.ok()
+    }
+}
+
+/// `retry` 回目（0 始まり）の再試行までの待ち時間（1s・2s・4s と倍々に延ばす）
+pub fn retry_backoff(retry: u32) -> Duration {
+    INITIAL_BACKOFF * 2u32.pow(retry.min(MAX_BACKOFF_DOUBLINGS))
+}
+
+/// 1ファイルの処理の起点（最初のプロバイダーの実行枠を確保した時点）
+///
+/// 実行枠を待つ間を1ファイルの制限時間に含めないために使う。
+#[derive(Default)]
+pub struct FileClock {
+    started: OnceLock<Instant>,
+    notify: Notify,
+}
+
+impl FileClock {
+    /// 起点を記録する（2回目以降は何もしない）
+    pub fn start(&self) {
+        if self.started.set(Instant::now()).is_ok() {
+            self.notify.notify_one();
+        }
+    }
+
+    /// 記録した起点（実行枠をまだ確保していなければ `None`）
+    pub fn started(&self) -> Option<Instant> {
+        self.started.get().copied()
+    }
+
+    /// 起点から `limit` が経過するまで待つ（起点が記録されるまでは数え始めない）
+    pub async fn expired(&self, limit: Duration) {
+        let started = loop {
+            if let Some(started) = self.started() {
+                break started;
+            }
+            self.notify.notified().await;
+        };
+        tokio::time::sleep_until(started + limit).await;
+    }
+}
+
+/// タイムアウトとリトライを適用して1プロバイダーで抽出する
+///
+/// 再試行するのは一時的なエラー（`retryable`）とタイムアウトのみで、認証の誤りや
+/// 非対応の形式などはその場で返す。再試行の前には [`retry_backoff`] だけ待つ。`timing` があれば各試行のフェーズ別所要時間を加算する。
+pub async fn extract_with_tuning(
+    provider: &dyn OcrProvider,
+    file_path: &str,
+    file_content: &str,
+    mime_type: &str,
+    settings: &OcrSettings,
+    mut timing: Option<&mut OcrTiming>,
+) -> Result<ReceiptData, ProviderError> {
+    let tuning = settings.tuning_for(provider.name());
+    let mut last_error = ProviderError::permanent(String::new());
+
+    for retry in 0..=tuning.max_retries {
+        if retry > 0 {
+            tokio::time::sleep(retry_backoff(retry - 1)).await;
+        }
+        let attempt = tokio::time::timeout(
+            tuning.timeout,
+            provider.extract_receipt_timed(
+                file_path,
+                file_content,
+                mime_type,
+                settings,
+                timing.as_deref_mut(),
+            ),
+        )
+        .await;
+
+        match attempt {
+            Ok(Ok(data)) => return Ok(data),
+            Ok(Err(e)) if !e.retryable => return Err(e),
+            Ok(Err(e)) => last_error = e,
+            Err(_) => {
+                last_error = ProviderError::transient(format!(
+                    "{} の処理がタイムアウトしました（{}秒）",
+                    provider.name(),
+                    tuning.timeout.as_secs()
+                ))
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuning_for_prefers_override_and_falls_back_per_field() {
+        let mut settings = OcrSettings::default();
+        settings.provider_overrides.insert(
+            "googledocumentai".to_string(),
+            ProviderTuning {
+                timeout_secs: Some(120),
+                max_retries: None,
+                max_concurrent: Some(0),
+            },
+        );
+
+        assert_eq!(
+            settings.tuning_for("googledocumentai"),
+            ResolvedTuning {
+                timeout: Duration::from_secs(120),
+                max_retries: DEFAULT_MAX_RETRIES,
+                max_concurrent: 1,
+            }
+        );
+        assert_eq!(
+            settings.tuning_for("other").timeout,
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+        assert_eq!(settings.tuning_for("other").max_concurrent, 4);
+
+        settings.provider_overrides.insert(
+            "veryfi".to_string(),
+            ProviderTuning {
+                max_concurrent: Some(usize::MAX),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            settings.tuning_for("veryfi").max_concurrent,
+            MAX_CONCURRENT_LIMIT
+        );
+    }
+
+    #[test]
+    fn primary_capacity_uses_the_first_provider_only() {
+        let mut settings = OcrSettings::default();
+        for (name, max_concurrent) in [("tesseract", 2), ("googledocumentai", 8)] {
+            settings.provider_overrides.insert(
+                name.to_string(),
+                ProviderTuning {
+                    max_concurrent: Some(max_concurrent),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let limits = ProviderLimits::for_providers(["tesseract", "googledocumentai"], &settings);
+        assert_eq!(limits.primary_capacity(), 2);
+        assert_eq!(
+            ProviderLimits::for_providers([], &settings).primary_capacity(),
+            1
+        );
+    }
+
+    #[test]
+    fn retry_backoff_doubles_and_caps() {
+        let secs = |retry| retry_backoff(retry).as_secs();
+        assert_eq!([secs(0), secs(1), secs(2)], [1, 2, 4]);
+        assert_eq!(secs(4), 16);
+        assert_eq!(secs(u32::MAX), 16);
+    }
+
+    #[test]
+    fn global_max_concurrent_is_clamped() {
+        let with = |max_concurrent| OcrSettings {
+            max_concurrent,
+            ..Default::default()
+        };
+        assert_eq!(with(None).max_concurrent(), 4);
+        assert_eq!(with(Some(0)).max_concurrent(), 1);
+        assert_eq!(with(Some(6)).max_concurrent(), 6);
+        assert_eq!(
+            with(Some(usize::MAX)).max_concurrent(),
+            MAX_CONCURRENT_LIMIT
+        );
+        assert_eq!(with(Some(6)).tuning_for("veryfi").max_concurrent, 6);
+    }
+
+    #[test]
+    fn file_clock_does_not_expire_before_start() {
+        use futures::FutureExt;
+
+        let clock = FileClock::default();
+        // 実行枠を確保するまでは制限時間を数え始めない
+        assert!(clock.expired(Duration::ZERO).now_or_never().is_none());
+        assert!(clock.started().is_none());
+
+        clock.start();
+        let started = clock.started().unwrap();
+        clock.start();
+        assert_eq!(clock.started(), Some(started));
+    }
+}
@@ -0,0 +1,228 @@
+//! Veryfi OCRプロバイダー
+//!
+//! Veryfi の `/partner/documents` API にレシート画像（Base64）を送り、データを抽出する。
+
+use super::{non_empty, request_error, OcrProvider, OcrSettings, ReceiptData};
+use crate::error::ProviderError;
+use crate::money::Currency;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+/// Veryfi API のベースURL
+const API_BASE_URL: &str = "https://api.veryfi.com/api/v8/partner";
+
+/// `/partner/documents` のレスポンス（使う項目のみ）
+#[derive(Debug, Deserialize)]
+struct VeryfiDocument {
+    vendor: Option<VeryfiVendor>,
+    /// 取引日時（`YYYY-MM-DD HH:MM:SS`）
+    date: Option<String>,
+    total: Option<f64>,
+    currency_code: Option<String>,
+    /// 領収書番号・請求書番号
+    invoice_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VeryfiVendor {
+    name: Option<String>,
+}
+
+/// Veryfi の認証情報
+struct VeryfiCredentials<'a> {
+    client_id: &'a str,
+    username: &'a str,
+    api_key: &'a str,
+}
+
+/// Veryfi プロバイダー
+pub struct VeryfiProvider {
+    client: Client,
+}
+
+impl VeryfiProvider {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    fn credentials(settings: &OcrSettings) -> Option<VeryfiCredentials<'_>> {
+        let veryfi = settings.veryfi.as_ref()?;
+        Some(VeryfiCredentials {
+            client_id: non_empty(&veryfi.client_id)?,
+            username: non_empty(&veryfi.username)?,
+            api_key: non_empty(&veryfi.api_key)?,
+        })
+    }
+
+    /// 認証ヘッダーを付けたリクエスト
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        credentials: &VeryfiCredentials<'_>,
+    ) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", API_BASE_URL, path))
+            .header("CLIENT-ID", credentials.client_id)
+            .header(
+                "AUTHORIZATION",
+                format!("apikey {}:{}", credentials.username, credentials.api_key),
+            )
+            .header("Accept", "application/json")
+    }
+
+    /// レスポンスを `ReceiptData` に変換する
+    fn parse_document(file_name: String, body: &[u8]) -> Result<ReceiptData, String> {
+        let document: VeryfiDocument = serde_json::from_slice(body)
+            .map_err(|e| format!("Veryfiレスポンスのパースに失敗しました: {}", e))?;
+
+        let mut receipt_data = ReceiptData::new(file_name);
+        receipt_data.merchant = document
+            .vendor
+            .and_then(|vendor| vendor.name)
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        // 時刻を除いた YYYY-MM-DD
+        receipt_data.date = document
+            .date
+            .and_then(|date| date.get(..10).map(str::to_string));
+        receipt_data.amount = document.total;
+        receipt_data.currency = document.currency_code.as_deref().and_then(Currency::parse);
+        receipt_data.document_number = document
+            .invoice_number
+            .map(|number| number.trim().to_string())
+            .filter(|number| !number.is_empty());
+        receipt_data.amount_minor = receipt_data.amount.and_then(|amount| {
+            crate::money::to_minor_units(amount, receipt_data.currency.as_deref())
+        });
+
+        Ok(receipt_data)
+    }
+}
+
+impl Default for VeryfiProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl OcrProvider for VeryfiProvider {
+    fn name(&self) -> &str {
+        "veryfi"
+    }
+
+    fn is_configured(&self, settings: &OcrSettings) -> bool {
+        Self::credentials(settings).is_some()
+    }
+
+    async fn test_connection(&self, settings: &OcrSettings) -> Result<(), String> {
+        let credentials = Self::credentials(settings).ok_or("設定が不完全です")?;
+
+        // 文書一覧を1件だけ取得して認証情報を確認する
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                "/documents?page=1&page_size=1",
+                &credentials,
+            )
+            .send()
+            .await
+            .map_err(|e| format!("Veryfi APIリクエストに失敗しました: {}", e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let text = response.text().await.unwrap_or_default();
+        Err(format!(
+            "Veryfiへの接続に失敗しました: HTTP {} - {}",
+            status, text
+        ))
+    }
+
+    async fn extract_receipt(
+        &self,
+        file_path: &str,
+        file_content: &str,
+        _mime_type: &str,
+        settings: &OcrSettings,
+    ) -> Result<ReceiptData, ProviderError> {
+        let credentials = Self::credentials(settings).ok_or("OCR設定が不完全です")?;
+
+        let file_name = std::path::Path::new(file_path)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or(file_path)
+            .to_string();
+
+        let response = self
+            .request(reqwest::Method::POST, "/documents", &credentials)
+            .json(&serde_json::json!({
+                "file_name": file_name,
+                "file_data": file_content,
+            }))
+            .send()
+            .await
+            .map_err(|e| request_error("Veryfi APIリクエストに失敗しました", e))?;
+
+        // 201 Created などの成功以外はエラーボディをそのまま返す
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(ProviderError::from_status(
+                status.as_u16(),
+                format!("Veryfi処理に失敗しました: HTTP {} - {}", status, text),
+            ));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| request_error("Veryfiレスポンスの受信に失敗しました", e))?;
+
+        let mut receipt_data = Self::parse_document(file_name, &body)?;
+        receipt_data.source_provider = Some(self.name().to_string());
+        Ok(receipt_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::VeryfiSettings;
+
+    #[test]
+    fn parse_document_maps_vendor_date_total_and_currency() {
+        let body = br#"{
+            "id": 1,
+            "vendor": { "name": " Starbucks " },
+            "date": "2025-01-05 12:34:00",
+            "total": 12.5,
+            "currency_code": "usd"
+        }"#;
+        let data = VeryfiProvider::parse_document("a.jpg".to_string(), body).unwrap();
+        assert_eq!(data.merchant.as_deref(), Some("Starbucks"));
+        assert_eq!(data.date.as_deref(), Some("2025-01-05"));
+        assert_eq!(data.amount, Some(12.5));
+        assert_eq!(data.currency.as_deref(), Some("USD"));
+        assert_eq!(data.amount_minor, Some(1250));
+
+        let empty = VeryfiProvider::parse_document("b.jpg".to_string(), b"{}").unwrap();
+        assert_eq!(empty.merchant, None);
+        assert!(VeryfiProvider::parse_document("c.jpg".to_string(), b"<html>").is_err());
+
+        let settings = OcrSettings {
+            veryfi: Some(VeryfiSettings {
+                client_id: Some("id".to_string()),
+                username: Some("user".to_string()),
+                api_key: Some(" ".to_string()),
+            }),
+            ..Default::default()
+        };
+        assert!(!VeryfiProvider::new().is_configured(&settings));
+    }
+}
@@ -0,0 +1,359 @@
+//! ルートディレクトリのインデックス
+//!
+//! ルートディレクトリ以下の `YYYY/MM` ディレクトリを走査し、月ごとのレシートファイル・
+//! サマリーの有無・未処理件数をまとめる。初回起動時の全体把握に使い、結果はメモリに
+//! キャッシュする。
+
+use crate::{summary, summary_excel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// ディレクトリ内のファイル情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    pub name: String,
+    pub path: String,
+    pub is_image: bool,
+    pub is_pdf: bool,
+    pub size: u64,
+}
+
+/// 年月ディレクトリ
+#[derive(Debug, Clone)]
+pub struct MonthDirectory {
+    pub year: String,
+    pub month: String,
+    pub year_month: String,
+    pub path: PathBuf,
+}
+
+impl MonthDirectory {
+    /// Excelサマリー（`{YYYYMM}-summary.xlsx`）があるか
+    pub fn has_excel(&self) -> bool {
+        self.path
+            .join(format!("{}-summary.xlsx", self.year_month))
+            .exists()
+    }
+}
+
+/// 月ごとのインデックス
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthIndex {
+    pub year_month: String,
+    pub path: String,
+    pub has_excel: bool,
+    /// サマリーJSON（`{YYYYMM}-summary.json`）があるか
+    pub has_summary: bool,
+    pub files: Vec<FileInfo>,
+    /// 未OCRのファイル数（Excelサマリーが読めない月は `None`）
+    pub unprocessed_count: Option<usize>,
+}
+
+/// ルートディレクトリ全体のインデックス
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootIndex {
+    pub root_directory: String,
+    /// 作成日時（RFC 3339）
+    pub indexed_at: String,
+    /// 年月の降順
+    pub months: Vec<MonthIndex>,
+    pub total_files: usize,
+    /// 判定できた月の未OCRファイル数の合計
+    pub total_unprocessed: usize,
+}
+
+/// インデックス作成の進捗イベント（`index-progress`）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexProgressEvent {
+    pub current: usize,
+    pub total: usize,
+    pub year_month: String,
+}
+
+/// 既定で画像として扱う拡張子
+pub const DEFAULT_IMAGE_EXTENSIONS: &[&str] =
+    &["jpg", "jpeg", "png", "gif", "webp", "heic", "heif"];
+
+/// レシートとして扱うファイルなら（画像か, PDFか）を返す
+///
+/// `extra_image_extensions` は設定で追加した画像の拡張子（`OcrSettings.extra_image_extensions`）。
+/// サマリーファイルと画像・PDF以外は `None`。
+pub fn receipt_file_kind(
+    file_name: &str,
+    extra_image_extensions: &[String],
+) -> Option<(bool, bool)> {
+    if file_name.ends_with("-summary.json") || file_name.ends_with("-summary.xlsx") {
+        return None;
+    }
+
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let is_image = DEFAULT_IMAGE_EXTENSIONS.contains(&extension.as_str())
+        || extra_image_extensions.iter().any(|extra| {
+            extra
+                .trim()
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(&extension)
+        });
+    let is_pdf = extension == "pdf";
+
+    (is_image || is_pdf).then_some((is_image, is_pdf))
+}
+
+/// ディレクトリ内のレシートファイル（画像・PDF）をファイル名順に列挙する
+pub fn list_receipt_files(
+    dir: &Path,
+    extra_image_extensions: &[String],
+) -> io::Result<Vec<FileInfo>> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir)?.flatten() {
+        let file_path = entry.path();
+        if !file_path.is_file() {
+            continue;
+        }
+
+        let file_name = file_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        // サマリーファイル・画像・PDF以外はスキップ
+        let Some((is_image, is_pdf)) = receipt_file_kind(&file_name, extra_image_extensions) else {
+            continue;
+        };
+
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+
+        files.push(FileInfo {
+            name: file_name,
+            path: file_path.to_str().unwrap_or("").to_string(),
+            is_image,
+            is_pdf,
+            size,
+        });
+    }
+
+    // ファイル名でソート
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(files)
+}
+
+/// ルートディレクトリ以下の年月ディレクトリを年月の降順で列挙する
+pub fn find_month_directories(root: &Path) -> io::Result<Vec<MonthDirectory>> {
+    let mut results = Vec::new();
+
+    // 年ディレクトリを走査
+    for year_entry in fs::read_dir(root)?.flatten() {
+        let year_path = year_entry.path();
+        if !year_path.is_dir() {
+            continue;
+        }
+
+        let year_name = year_path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+
+        // 4桁の数字（年）かチェック
+        if year_name.len() != 4 || year_name.parse::<u32>().is_err() {
+            continue;
+        }
+
+        // 月ディレクトリを走査
+        let month_entries = match fs::read_dir(&year_path) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for month_entry in month_entries.flatten() {
+            let month_path = month_entry.path();
+            if !month_path.is_dir() {
+                continue;
+            }
+
+            let month_name = month_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("");
+
+            // 2桁の数字（月）かチェック
+            if month_name.len() != 2 {
+                continue;
+            }
+            let month_num = match month_name.parse::<u32>() {
+                Ok(n) if (1..=12).contains(&n) => n,
+                _ => continue,
+            };
+
+            results.push(MonthDirectory {
+                year: year_name.to_string(),
+                month: month_name.to_string(),
+                year_month: format!("{}{:02}", year_name, month_num),
+                path: month_path,
+            });
+        }
+    }
+
+    // 降順ソート（新しい年月が先）
+    results.sort_by(|a, b| b.year_month.cmp(&a.year_month));
+
+    Ok(results)
+}
+
+/// 未OCRのファイル数を数える
+///
+/// Excelサマリーで処理済みのファイルを除いた画像・PDFの数。
+/// Excelサマリーが無い月（`processed` が `None`）はすべて未OCR。
+pub fn count_unprocessed(files: &[FileInfo], processed: Option<&HashSet<String>>) -> usize {
+    files
+        .iter()
+        .filter(|file| processed.is_none_or(|processed| !processed.contains(&file.name)))
+        .count()
+}
+
+/// 月ディレクトリの未OCRファイル数を数える（Excelサマリーが読めなければ `None`）
+pub fn count_unprocessed_in_month(
+    files: &[FileInfo],
+    month_dir: &Path,
+    year_month: &str,
+) -> Option<usize> {
+    let processed = summary_excel::read_processed_files(month_dir, year_month).ok()?;
+    Some(count_unprocessed(files, processed.as_ref()))
+}
+
+/// 1か月分のインデックスを作成する
+pub fn index_month(dir: &MonthDirectory, extra_image_extensions: &[String]) -> MonthIndex {
+    let files = list_receipt_files(&dir.path, extra_image_extensions).unwrap_or_default();
+    let has_excel = dir.has_excel();
+    let has_summary = matches!(
+        summary::read_summary(&dir.path, &dir.year_month),
+        Ok(Some(_))
+    );
+    // 壊れたExcelサマリーは処理状況を判定できない
+    let unprocessed_count = count_unprocessed_in_month(&files, &dir.path, &dir.year_month);
+
+    MonthIndex {
+        year_month: dir.year_month.clone(),
+        path: dir.path.to_str().unwrap_or("").to_string(),
+        has_excel,
+        has_summary,
+        files,
+        unprocessed_count,
+    }
+}
+
+/// ルートディレクトリ全体のインデックスを作成する
+///
+/// 1か月走査するごとに `on_progress` を呼ぶ。
+pub fn build_index(
+    root: &Path,
+    extra_image_extensions: &[String],
+    mut on_progress: impl FnMut(IndexProgressEvent),
+) -> io::Result<RootIndex> {
+    let directories = if root.exists() {
+        find_month_directories(root)?
+    } else {
+        Vec::new()
+    };
+
+    let total = directories.len();
+    let mut months = Vec::with_capacity(total);
+    for (i, dir) in directories.iter().enumerate() {
+        months.push(index_month(dir, extra_image_extensions));
+        on_progress(IndexProgressEvent {
+            current: i + 1,
+            total,
+            year_month: dir.year_month.clone(),
+        });
+    }
+
+    Ok(RootIndex {
+        root_directory: root.to_str().unwrap_or("").to_string(),
+        indexed_at: chrono::Local::now().to_rfc3339(),
+        total_files: months.iter().map(|m| m.files.len()).sum(),
+        total_unprocessed: months.iter().filter_map(|m| m.unprocessed_count).sum(),
+        months,
+    })
+}
+
+/// 最後に作成したインデックスのキャッシュ
+#[derive(Default)]
+pub struct RootIndexCache {
+    index: Mutex<Option<RootIndex>>,
+}
+
+impl RootIndexCache {
+    /// 同じルートディレクトリのインデックスがあれば返す
+    pub fn get(&self, root_directory: &str) -> Option<RootIndex> {
+        self.index
+            .lock()
+            .ok()?
+            .as_ref()
+            .filter(|index| index.root_directory == root_directory)
+            .cloned()
+    }
+
+    pub fn set(&self, index: RootIndex) {
+        if let Ok(mut cached) = self.index.lock() {
+            *cached = Some(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_index_scans_month_directories() {
+        let root = std::env::temp_dir().join(format!("torifune-index-{}", std::process::id()));
+        let month = root.join("2025").join("01");
+        fs::create_dir_all(&month).unwrap();
+        fs::create_dir_all(root.join("2025").join("13")).unwrap();
+        fs::create_dir_all(root.join("misc")).unwrap();
+        for name in ["a.jpg", "b.pdf", "c.tiff", "notes.txt"] {
+            fs::write(month.join(name), b"x").unwrap();
+        }
+        fs::write(
+            month.join("202501-summary.json"),
+            r#"{ "yearMonth": "202501", "receipts": [] }"#,
+        )
+        .unwrap();
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("Summary").unwrap();
+        sheet.write_string(0, 0, "ファイル名").unwrap();
+        sheet.write_string(1, 0, "a.jpg").unwrap();
+        sheet.write_string(1, 4, "ローソン").unwrap();
+        workbook.save(month.join("202501-summary.xlsx")).unwrap();
+
+        let mut events = Vec::new();
+        let index = build_index(&root, &[".TIFF".to_string()], |event| {
+            events.push(event.year_month)
+        })
+        .unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(events, vec!["202501"]);
+        assert_eq!(index.months.len(), 1);
+        assert!(index.months[0].has_summary);
+        assert_eq!(index.total_files, 3);
+        assert_eq!(index.total_unprocessed, 2);
+        assert!(index.months[0]
+            .files
+            .iter()
+            .any(|f| f.name == "c.tiff" && f.is_image));
+    }
+}
@@ -0,0 +1,165 @@
+//! デシリアライズ時の値検証
+//!
+//! サマリー JSON など外部由来のデータを読み込む際に、壊れた日付や不正な金額・通貨を
+//! `None` に落として取り込む。落とした項目は [`collect_invalid_fields`] の実行中だけ
+//! 収集できる。
+
+use crate::money::Currency;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+
+/// デシリアライズ時に `None` に落とした項目
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidField {
+    /// 項目名（`date` / `amount` / `currency`）
+    pub field: String,
+    /// 元の値（JSON 表現）
+    pub value: String,
+    /// 落とした理由
+    pub reason: String,
+}
+
+thread_local! {
+    static COLLECTOR: RefCell<Option<Vec<InvalidField>>> = const { RefCell::new(None) };
+}
+
+/// `f` の実行中に落とした項目を収集する
+pub fn collect_invalid_fields<T>(f: impl FnOnce() -> T) -> (T, Vec<InvalidField>) {
+    let previous = COLLECTOR.with(|c| c.borrow_mut().replace(Vec::new()));
+    let result = f();
+    let collected = COLLECTOR.with(|c| std::mem::replace(&mut *c.borrow_mut(), previous));
+    (result, collected.unwrap_or_default())
+}
+
+fn report_invalid(field: &str, value: &Value, reason: &str) {
+    COLLECTOR.with(|c| {
+        if let Some(collected) = c.borrow_mut().as_mut() {
+            collected.push(InvalidField {
+                field: field.to_string(),
+                value: value.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    });
+}
+
+/// 日付（`YYYY-MM-DD` 形式の実在する日付）のみを受け付ける
+///
+/// 空文字は未設定とみなし、それ以外の不正な値は `None` に落とす。
+pub fn deserialize_date<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(match value {
+        None | Some(Value::Null) => None,
+        Some(Value::String(s)) if s.trim().is_empty() => None,
+        Some(Value::String(s)) => {
+            let date = s.trim();
+            if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() && date.len() == 10 {
+                Some(date.to_string())
+            } else {
+                report_invalid(
+                    "date",
+                    &Value::String(s),
+                    "YYYY-MM-DD 形式の日付ではありません",
+                );
+                None
+            }
+        }
+        Some(other) => {
+            report_invalid("date", &other, "日付が文字列ではありません");
+            None
+        }
+    })
+}
+
+/// 金額（有限の数値）のみを受け付ける
+///
+/// 数値の文字列（`"1,234"` など）は数値として読み、NaN・無限大や数値以外は `None` に落とす。
+pub fn deserialize_amount<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Value>::deserialize(deserializer)?;
+    let parsed = match &value {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().replace(',', "").parse::<f64>().ok(),
+        Some(_) => None,
+    };
+
+    match parsed {
+        Some(amount) if amount.is_finite() => Ok(Some(amount)),
+        _ => {
+            if let Some(value) = &value {
+                report_invalid("amount", value, "有限の数値ではありません");
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// 通貨（ISO 4217 の通貨コード）のみを受け付ける
+///
+/// 小文字や通貨記号（`"jpy"`・`"￥"` など）はコードに正規化し、推定できない値は `None` に落とす。
+pub fn deserialize_currency<'de, D>(deserializer: D) -> Result<Option<Currency>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(match value {
+        None | Some(Value::Null) => None,
+        Some(Value::String(s)) if s.trim().is_empty() => None,
+        Some(Value::String(s)) => Currency::parse(&s).or_else(|| {
+            report_invalid(
+                "currency",
+                &Value::String(s),
+                "ISO 4217 の通貨コードではありません",
+            );
+            None
+        }),
+        Some(other) => {
+            report_invalid("currency", &other, "通貨が文字列ではありません");
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Sample {
+        #[serde(default, deserialize_with = "deserialize_date")]
+        date: Option<String>,
+        #[serde(default, deserialize_with = "deserialize_amount")]
+        amount: Option<f64>,
+    }
+
+    #[test]
+    fn invalid_values_become_none_and_are_collected() {
+        let (sample, invalid) = collect_invalid_fields(|| {
+            serde_json::from_str::<Sample>(r#"{ "date": "2025-02-30", "amount": "1,234" }"#)
+                .unwrap()
+        });
+        assert_eq!(sample.date, None);
+        assert_eq!(sample.amount, Some(1234.0));
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].field, "date");
+
+        let (sample, invalid) = collect_invalid_fields(|| {
+            serde_json::from_str::<Sample>(r#"{ "date": "2025-01-05", "amount": "NaN" }"#).unwrap()
+        });
+        assert_eq!(sample.date.as_deref(), Some("2025-01-05"));
+        assert_eq!(sample.amount, None);
+        assert_eq!(invalid[0].field, "amount");
+
+        // 収集していない場合も落とすだけでエラーにしない
+        let sample = serde_json::from_str::<Sample>(r#"{ "amount": true }"#).unwrap();
+        assert_eq!(sample.amount, None);
+    }
+}
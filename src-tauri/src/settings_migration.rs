@@ -0,0 +1,115 @@
+//! OCR設定の形式の移行
+//!
+//! 保存する設定に `version` を付け、読み込み時に古い形式を現在の形式へ書き換える。
+//! - v1（`version` なし）: プロバイダー固有の項目がトップレベルに並ぶフラットな形式
+//! - v2: プロバイダーごとのサブ設定（`google`・`veryfi`・`textract`・`tesseract`）に入れ子にした形式
+
+use crate::providers::OcrSettings;
+use serde_json::{Map, Value};
+
+/// 現在の設定の形式のバージョン
+pub const OCR_SETTINGS_VERSION: u64 = 2;
+
+/// v1 のフラットな項目 →（サブ設定, サブ設定での項目名）
+const FLAT_FIELDS: &[(&str, &str, &str)] = &[
+    ("projectId", "google", "projectId"),
+    ("location", "google", "location"),
+    ("processorId", "google", "processorId"),
+    ("processorVersion", "google", "processorVersion"),
+    ("serviceAccountJson", "google", "serviceAccountJson"),
+    ("veryfiClientId", "veryfi", "clientId"),
+    ("veryfiApiKey", "veryfi", "apiKey"),
+    ("veryfiUsername", "veryfi", "username"),
+    ("awsRegion", "textract", "region"),
+    ("awsAccessKeyId", "textract", "accessKeyId"),
+    ("awsSecretAccessKey", "textract", "secretAccessKey"),
+    ("tesseractLang", "tesseract", "lang"),
+];
+
+/// 古い形式の設定を現在の形式に書き換える（書き換えたら `true`）
+///
+/// トップレベルに残るフラットな項目はサブ設定へ移し、サブ設定の同名の項目より優先する。
+/// 新しいバージョンの設定には手を付けない。
+pub fn migrate_ocr_settings(value: &mut Value) -> bool {
+    let Value::Object(settings) = value else {
+        return false;
+    };
+    let version = settings.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version > OCR_SETTINGS_VERSION {
+        return false;
+    }
+
+    let mut migrated = version < OCR_SETTINGS_VERSION;
+    for (flat, section, field) in FLAT_FIELDS {
+        let Some(flat_value) = settings.remove(*flat) else {
+            continue;
+        };
+        migrated = true;
+        if flat_value.is_null() {
+            continue;
+        }
+        let section = settings
+            .entry(*section)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !section.is_object() {
+            *section = Value::Object(Map::new());
+        }
+        if let Value::Object(section) = section {
+            section.insert(field.to_string(), flat_value);
+        }
+    }
+
+    settings.insert("version".to_string(), OCR_SETTINGS_VERSION.into());
+    migrated
+}
+
+/// ストアに保存する形（現在のバージョンを付ける）
+pub fn to_store_value(settings: &OcrSettings) -> Result<Value, String> {
+    let mut value = serde_json::to_value(settings)
+        .map_err(|e| format!("OCR設定をシリアライズできませんでした: {}", e))?;
+    if let Value::Object(map) = &mut value {
+        map.insert("version".to_string(), OCR_SETTINGS_VERSION.into());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn migrate_moves_flat_fields_into_provider_sections() {
+        let mut value = json!({
+            "projectId": "my-project",
+            "serviceAccountJson": "{}",
+            "processorVersion": null,
+            "veryfiClientId": "id",
+            "tesseractLang": "jpn",
+            "escalationChain": ["googledocumentai"]
+        });
+        assert!(migrate_ocr_settings(&mut value));
+        assert_eq!(
+            value,
+            json!({
+                "version": 2,
+                "google": { "projectId": "my-project", "serviceAccountJson": "{}" },
+                "veryfi": { "clientId": "id" },
+                "tesseract": { "lang": "jpn" },
+                "escalationChain": ["googledocumentai"]
+            })
+        );
+
+        let settings: OcrSettings = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            settings.google.unwrap().project_id.as_deref(),
+            Some("my-project")
+        );
+
+        // 移行済み・新しいバージョンの設定はそのまま
+        assert!(!migrate_ocr_settings(&mut value));
+        let mut future = json!({ "version": 3, "projectId": "x" });
+        assert!(!migrate_ocr_settings(&mut future));
+        assert_eq!(future["projectId"], "x");
+    }
+}
@@ -0,0 +1,158 @@
+//! 壊れた設定からの復旧
+//!
+//! ストアのファイルが JSON として読めない、または保存された値が設定の型に合わない場合に、
+//! 元の内容を `.corrupt` として退避し、読める項目だけ救済して既定値で続ける。
+//! 復旧したことは `settings-recovered` イベントでフロントエンドに知らせる。
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// `settings-recovered` イベントの内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsRecoveredEvent {
+    /// 復旧した設定のキー（ストアのファイルごと退避した場合は `None`）
+    pub key: Option<String>,
+    /// 読み込めなかった理由
+    pub reason: String,
+    /// 元の内容の退避先
+    pub backup_path: Option<String>,
+    /// 救済できた項目
+    pub recovered_fields: Vec<String>,
+    /// 読めずに既定値に戻した項目
+    pub dropped_fields: Vec<String>,
+}
+
+/// 項目ごとに救済した設定
+#[derive(Debug)]
+pub struct Salvaged<T> {
+    pub value: T,
+    pub recovered_fields: Vec<String>,
+    pub dropped_fields: Vec<String>,
+}
+
+/// 型に合う項目だけを残して読み込む（オブジェクトでなければ既定値）
+///
+/// 項目を1つずつ加えて読み込めるかを確かめ、読めなくなる項目は捨てる。
+pub fn salvage<T: DeserializeOwned + Default>(value: &Value) -> Salvaged<T> {
+    let Some(object) = value.as_object() else {
+        return Salvaged {
+            value: T::default(),
+            recovered_fields: Vec::new(),
+            dropped_fields: Vec::new(),
+        };
+    };
+
+    let mut kept = Map::new();
+    let mut recovered_fields = Vec::new();
+    let mut dropped_fields = Vec::new();
+    for (key, field) in object {
+        kept.insert(key.clone(), field.clone());
+        if serde_json::from_value::<T>(Value::Object(kept.clone())).is_ok() {
+            recovered_fields.push(key.clone());
+        } else {
+            kept.remove(key);
+            dropped_fields.push(key.clone());
+        }
+    }
+
+    Salvaged {
+        value: serde_json::from_value(Value::Object(kept)).unwrap_or_default(),
+        recovered_fields,
+        dropped_fields,
+    }
+}
+
+/// 既存のファイルと重ならない退避先（`{name}.corrupt`、あれば `{name}.1.corrupt` …）
+fn corrupt_path(dir: &Path, name: &str) -> PathBuf {
+    let first = dir.join(format!("{}.corrupt", name));
+    if !first.exists() {
+        return first;
+    }
+    (1..)
+        .map(|n| dir.join(format!("{}.{}.corrupt", name, n)))
+        .find(|path| !path.exists())
+        .expect("連番は尽きない")
+}
+
+/// JSON として読めないファイルを `.corrupt` にリネームする（読めるか、ファイルが無ければ `None`）
+pub fn quarantine_if_corrupt(path: &Path) -> io::Result<Option<PathBuf>> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if serde_json::from_slice::<Value>(&content).is_ok() {
+        return Ok(None);
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("store");
+    let backup = corrupt_path(dir, name);
+    fs::rename(path, &backup)?;
+    Ok(Some(backup))
+}
+
+/// 型に合わない設定値を `{key}.json.corrupt` に書き出す
+pub fn backup_value(app: &AppHandle, key: &str, value: &Value) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("データディレクトリを取得できませんでした: {}", e))?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("データディレクトリの作成に失敗しました: {}", e))?;
+    let backup = corrupt_path(&dir, &format!("{}.json", key));
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("設定のシリアライズに失敗しました: {}", e))?;
+    fs::write(&backup, json).map_err(|e| format!("設定の退避に失敗しました: {}", e))?;
+    Ok(backup)
+}
+
+/// 復旧をフロントエンドに知らせる（`settings-recovered`）
+pub fn notify(app: &AppHandle, event: SettingsRecoveredEvent) {
+    let _ = app.emit("settings-recovered", event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Deserialize)]
+    struct Sample {
+        name: Option<String>,
+        #[serde(default)]
+        retries: u32,
+    }
+
+    #[test]
+    fn salvage_keeps_readable_fields_and_quarantines_broken_files() {
+        let salvaged: Salvaged<Sample> =
+            salvage(&serde_json::json!({ "name": "a", "retries": "three" }));
+        assert_eq!(salvaged.value.name.as_deref(), Some("a"));
+        assert_eq!(salvaged.value.retries, 0);
+        assert_eq!(salvaged.recovered_fields, vec!["name"]);
+        assert_eq!(salvaged.dropped_fields, vec!["retries"]);
+
+        let dir = std::env::temp_dir().join(format!("torifune-recovery-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = dir.join("store.json");
+        fs::write(&store, br#"{"ok": true}"#).unwrap();
+        assert!(quarantine_if_corrupt(&store).unwrap().is_none());
+
+        fs::write(&store, br#"{"ok": tr"#).unwrap();
+        let backup = quarantine_if_corrupt(&store).unwrap().unwrap();
+        assert_eq!(backup, dir.join("store.json.corrupt"));
+        assert!(!store.exists());
+
+        fs::write(&store, b"\0").unwrap();
+        let backup = quarantine_if_corrupt(&store).unwrap().unwrap();
+        assert_eq!(backup, dir.join("store.json.1.corrupt"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
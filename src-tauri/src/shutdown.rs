@@ -0,0 +1,197 @@
+//! アプリ終了時のバッチの停止
+//!
+//! ウィンドウを閉じるときに実行中のバッチがあれば終了を保留し、バッチに停止を伝える。
+//! 停止したバッチは未着手のファイルをスキップし、処理済みの結果をサマリーへ書き出してから終わる。
+//! 全バッチの終了を待つのは [`GRACEFUL_SHUTDOWN_TIMEOUT`] までで、超えたらそのまま終了する。
+//!
+//! ユーザーによるバッチの中止（[`BatchCancellation`]）も同じ仕組みで未着手のファイルを止めるほか、
+//! 処理中のファイルもプロバイダーの応答を待たずに打ち切る。
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// 実行中のバッチの終了を待つ上限
+pub const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 実行中のバッチ数と終了要求
+#[derive(Default)]
+pub struct BatchShutdown {
+    shutting_down: Arc<AtomicBool>,
+    running: AtomicUsize,
+    idle: Notify,
+}
+
+impl BatchShutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// バッチの開始を登録する（ガードのドロップで終了扱いになる）
+    pub fn register(self: &Arc<Self>) -> RunningBatchGuard {
+        self.running.fetch_add(1, Ordering::SeqCst);
+        RunningBatchGuard {
+            shutdown: Arc::clone(self),
+        }
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// 終了が要求されているか（バッチは未着手のファイルに手を付けない）
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// 終了要求のフラグ（プロバイダーの実行枠を確保した直後に確認させる）
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.shutting_down)
+    }
+
+    /// 終了を要求する（既に要求済みなら `false`）
+    pub fn begin(&self) -> bool {
+        !self.shutting_down.swap(true, Ordering::SeqCst)
+    }
+
+    /// 実行中のバッチがすべて終わるまで待つ（`timeout` を超えたら `false`）
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.running() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// 1バッチの中止要求
+///
+/// フラグは実行枠の待ちを止めるのに、通知は処理中のファイルの打ち切りに使う。
+#[derive(Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    notify: Notify,
+}
+
+impl CancelToken {
+    /// 中止が要求されているか
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// 中止要求のフラグ（プロバイダーの実行枠を確保した直後に確認させる）
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.flag)
+    }
+
+    /// 中止を要求し、待っているタスクを起こす（既に要求済みなら `false`）
+    pub fn cancel(&self) -> bool {
+        let first = !self.flag.swap(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+        first
+    }
+
+    /// 中止が要求されるまで待つ（`tokio::select!` で処理と競わせる）
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// ユーザーによるバッチの中止
+///
+/// バッチごとに中止要求を発行し、`cancel_all` で実行中のバッチすべてを中止する。
+/// 中止後に始めたバッチには影響しない。
+#[derive(Default)]
+pub struct BatchCancellation {
+    tokens: std::sync::Mutex<Vec<Weak<CancelToken>>>,
+}
+
+impl BatchCancellation {
+    /// バッチの中止要求を発行する（バッチの終了で手放せば登録も外れる）
+    pub fn register(&self) -> Arc<CancelToken> {
+        let token = Arc::new(CancelToken::default());
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.retain(|token| token.strong_count() > 0);
+        tokens.push(Arc::downgrade(&token));
+        token
+    }
+
+    /// 実行中のバッチをすべて中止する（中止したバッチ数を返す）
+    pub fn cancel_all(&self) -> usize {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.retain(|token| token.strong_count() > 0);
+        tokens
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|token| token.cancel())
+            .count()
+    }
+}
+
+/// 実行中バッチの登録ガード
+pub struct RunningBatchGuard {
+    shutdown: Arc<BatchShutdown>,
+}
+
+impl Drop for RunningBatchGuard {
+    fn drop(&mut self) {
+        if self.shutdown.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn guards_track_running_batches_and_begin_is_idempotent() {
+        let shutdown = Arc::new(BatchShutdown::new());
+        let first = shutdown.register();
+        let second = shutdown.register();
+        assert_eq!(shutdown.running(), 2);
+
+        assert!(!shutdown.is_shutting_down());
+        assert!(shutdown.begin());
+        assert!(!shutdown.begin());
+        assert!(shutdown.is_shutting_down());
+
+        drop(first);
+        drop(second);
+        assert_eq!(shutdown.running(), 0);
+    }
+
+    #[test]
+    fn cancel_all_stops_only_running_batches() {
+        let cancellation = BatchCancellation::default();
+        let running = cancellation.register();
+        let finished = cancellation.register();
+        drop(finished);
+
+        // 処理中のタスクは中止の時点で起こされる
+        let waiting = running.cancelled();
+        assert_eq!(cancellation.cancel_all(), 1);
+        assert!(running.is_cancelled());
+        futures::executor::block_on(waiting);
+        // 中止済みのバッチは数えず、後から始めたバッチは中止されていない
+        assert_eq!(cancellation.cancel_all(), 0);
+        let later = cancellation.register();
+        assert!(!later.is_cancelled());
+        assert!(later.cancelled().now_or_never().is_none());
+    }
+}
@@ -0,0 +1,295 @@
+//! 設定ストアのファイル名・キー
+//!
+//! キー文字列は必ずここの定数を使う（読み書きでキーが食い違うと設定が保存されないように見える）。
+//! 書き込みは必ず [`with_store_transaction`]（1キーなら [`set_value`]）を通す。
+//!
+//! ストアはアプリ識別子ごとのデータディレクトリに置かれる。同じ識別子の開発版と本番版で
+//! 認証トークンや設定が混ざらないよう、ファイル名も環境（スコープ）ごとに分ける。
+//! スコープ付きのストアが無い初回は本番のストアを複製して始める（以降は独立）。
+
+use crate::settings_recovery::{self, SettingsRecoveredEvent};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex, Once};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+
+/// 本番のストアのファイル名（スコープなし）
+pub const STORE_FILE: &str = "torifune.store.json";
+
+/// ストアのスコープを指定する環境変数
+pub const STORE_SCOPE_ENV: &str = "TORIFUNE_STORE_SCOPE";
+
+/// デバッグビルドの既定のスコープ
+const DEV_STORE_SCOPE: &str = "dev";
+
+/// スコープ付きのストアのファイル名（スコープなしは本番の `torifune.store.json`）
+///
+/// スコープは英数字・`-`・`_` のみ使い、それ以外の文字は `_` に置き換える。
+pub fn scoped_store_file(scope: Option<&str>) -> String {
+    let scope: String = scope
+        .unwrap_or("")
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if scope.is_empty() {
+        STORE_FILE.to_string()
+    } else {
+        format!("torifune.{}.store.json", scope)
+    }
+}
+
+static STORE_FILE_NAME: LazyLock<String> = LazyLock::new(|| {
+    let scope = std::env::var(STORE_SCOPE_ENV)
+        .ok()
+        .or_else(|| cfg!(debug_assertions).then(|| DEV_STORE_SCOPE.to_string()));
+    scoped_store_file(scope.as_deref())
+});
+
+/// このプロセスのストアのファイル名
+///
+/// `TORIFUNE_STORE_SCOPE` があればそのスコープ、無ければデバッグビルドは `dev`、
+/// リリースビルドはスコープなし。
+pub fn store_file() -> &'static str {
+    &STORE_FILE_NAME
+}
+
+/// OCR設定
+pub const OCR_SETTINGS: &str = "ocr_settings";
+/// ルートディレクトリ
+pub const ROOT_DIRECTORY: &str = "root_directory";
+/// 勘定科目ルール
+pub const ACCOUNT_CATEGORY_RULES: &str = "account_category_rules";
+/// 業種ユーザー辞書
+pub const MERCHANT_CATEGORY_DICTIONARY: &str = "merchant_category_dictionary";
+/// バリデーションルール
+pub const VALIDATION_RULES: &str = "validation_rules";
+/// 宛名履歴
+pub const RECEIVER_NAME_HISTORY: &str = "receiver_name_history";
+/// 認証トークン
+pub const AUTH_TOKENS: &str = "auth_tokens";
+/// ディープリンクスキーム
+pub const DEEP_LINK_SCHEME: &str = "deep_link_scheme";
+/// このデバイスの識別子（サマリーの競合解決に使う）
+pub const DEVICE_ID: &str = "device_id";
+/// バッチの進捗の接頭辞（`batch_progress.{batch_id}`）
+pub const BATCH_PROGRESS_PREFIX: &str = "batch_progress.";
+
+/// スコープ付きのストアが無ければ本番のストアを複製する（複製したら `true`）
+///
+/// スコープを分けたことで既存の設定が見えなくならないよう、初回だけ引き継ぐ。
+fn seed_scoped_store(data_dir: &Path, file_name: &str) -> io::Result<bool> {
+    let scoped = data_dir.join(file_name);
+    let unscoped = data_dir.join(STORE_FILE);
+    if file_name == STORE_FILE || scoped.exists() || !unscoped.exists() {
+        return Ok(false);
+    }
+
+    fs::copy(&unscoped, &scoped)?;
+    Ok(true)
+}
+
+/// 設定ストアを開く
+///
+/// ファイルが JSON として読めない場合は `.corrupt` に退避して空のストアで続け、
+/// `settings-recovered` イベントで知らせる。スコープ付きのストアを初めて開くときは
+/// 本番のストアの内容を引き継ぐ。
+pub fn open_store(app: &AppHandle) -> Result<Arc<Store<Wry>>, String> {
+    static SEED_SCOPED_STORE: Once = Once::new();
+    SEED_SCOPED_STORE.call_once(|| {
+        if let Ok(data_dir) = app.path().app_data_dir() {
+            if let Err(e) = seed_scoped_store(&data_dir, store_file()) {
+                eprintln!("Failed to copy the settings store: {}", e);
+            }
+        }
+    });
+
+    let error = match app.store(store_file()) {
+        Ok(store) => return Ok(store),
+        Err(e) => format!("ストアの読み込みに失敗しました: {}", e),
+    };
+
+    let path = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| error.clone())?
+        .join(store_file());
+    let Ok(Some(backup)) = settings_recovery::quarantine_if_corrupt(&path) else {
+        return Err(error);
+    };
+    settings_recovery::notify(
+        app,
+        SettingsRecoveredEvent {
+            key: None,
+            reason: error,
+            backup_path: Some(backup.to_string_lossy().into_owned()),
+            recovered_fields: Vec::new(),
+            dropped_fields: Vec::new(),
+        },
+    );
+
+    app.store(store_file())
+        .map_err(|e| format!("ストアの読み込みに失敗しました: {}", e))
+}
+
+/// 設定ストアをファイルに書き出す
+fn save_store(store: &Store<Wry>) -> Result<(), String> {
+    store
+        .save()
+        .map_err(|e| format!("設定の保存に失敗しました: {}", e))
+}
+
+/// トランザクション中の変更（コミットまでストアには反映しない）
+pub struct StoreTransaction<'a> {
+    store: &'a Store<Wry>,
+    /// キー → 変更後の値（`None` は削除）
+    changes: BTreeMap<String, Option<Value>>,
+}
+
+impl StoreTransaction<'_> {
+    /// 値を読む（このトランザクションで変更したキーは変更後の値）
+    pub fn get(&self, key: &str) -> Option<Value> {
+        match self.changes.get(key) {
+            Some(value) => value.clone(),
+            None => self.store.get(key),
+        }
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: Value) {
+        self.changes.insert(key.into(), Some(value));
+    }
+
+    /// キーを消す（無いキーなら何もせず `false`）
+    pub fn delete(&mut self, key: impl Into<String>) -> bool {
+        let key = key.into();
+        if self.get(&key).is_none() {
+            return false;
+        }
+        self.changes.insert(key, None);
+        true
+    }
+}
+
+/// 書き込みを直列化するロック（同時に書くと互いの変更を巻き戻しうる）
+static TRANSACTION_LOCK: Mutex<()> = Mutex::new(());
+
+/// 複数キーの変更をまとめて保存する
+///
+/// `f` の中の変更はメモリ上に溜め、`f` が成功したときだけストアに適用して一度に書き出す。
+/// `f` が失敗した場合は何も適用せず、書き出しに失敗した場合は適用前の値に戻す。
+pub fn with_store_transaction<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut StoreTransaction) -> Result<T, String>,
+) -> Result<T, String> {
+    let _lock = TRANSACTION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let store = open_store(app)?;
+    let mut transaction = StoreTransaction {
+        store: &store,
+        changes: BTreeMap::new(),
+    };
+    let output = f(&mut transaction)?;
+    let changes = transaction.changes;
+    if changes.is_empty() {
+        return Ok(output);
+    }
+
+    let originals: Vec<(String, Option<Value>)> = changes
+        .keys()
+        .map(|key| (key.clone(), store.get(key)))
+        .collect();
+    apply_changes(&store, changes);
+
+    if let Err(e) = save_store(&store) {
+        apply_changes(&store, originals);
+        return Err(e);
+    }
+    Ok(output)
+}
+
+/// 1つのキーを保存する
+pub fn set_value(app: &AppHandle, key: &str, value: Value) -> Result<(), String> {
+    with_store_transaction(app, |store| {
+        store.set(key, value);
+        Ok(())
+    })
+}
+
+/// 値を書き込む（`None` はキーを消す）
+fn apply_changes(store: &Store<Wry>, changes: impl IntoIterator<Item = (String, Option<Value>)>) {
+    for (key, value) in changes {
+        match value {
+            Some(value) => store.set(key, value),
+            None => {
+                store.delete(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn keys_are_unique_and_do_not_collide_with_batch_progress() {
+        let keys = [
+            OCR_SETTINGS,
+            ROOT_DIRECTORY,
+            ACCOUNT_CATEGORY_RULES,
+            MERCHANT_CATEGORY_DICTIONARY,
+            VALIDATION_RULES,
+            RECEIVER_NAME_HISTORY,
+            AUTH_TOKENS,
+            DEEP_LINK_SCHEME,
+            DEVICE_ID,
+        ];
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), keys.len());
+        assert!(keys
+            .iter()
+            .all(|key| !key.starts_with(BATCH_PROGRESS_PREFIX)));
+    }
+
+    #[test]
+    fn store_file_is_scoped_per_environment() {
+        assert_eq!(scoped_store_file(None), STORE_FILE);
+        assert_eq!(scoped_store_file(Some(" ")), STORE_FILE);
+        assert_eq!(scoped_store_file(Some("dev")), "torifune.dev.store.json");
+        assert_eq!(
+            scoped_store_file(Some("../staging")),
+            "torifune.___staging.store.json"
+        );
+    }
+
+    #[test]
+    fn seed_scoped_store_copies_the_unscoped_store_once() {
+        let dir = std::env::temp_dir().join(format!("torifune-store-seed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dev = scoped_store_file(Some("dev"));
+
+        assert!(!seed_scoped_store(&dir, &dev).unwrap());
+        fs::write(dir.join(STORE_FILE), r#"{"root_directory":"/receipts"}"#).unwrap();
+        assert!(!seed_scoped_store(&dir, STORE_FILE).unwrap());
+        assert!(seed_scoped_store(&dir, &dev).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join(&dev)).unwrap(),
+            r#"{"root_directory":"/receipts"}"#
+        );
+
+        // 以降は本番のストアと独立
+        fs::write(dir.join(&dev), "{}").unwrap();
+        assert!(!seed_scoped_store(&dir, &dev).unwrap());
+        assert_eq!(fs::read_to_string(dir.join(&dev)).unwrap(), "{}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,894 @@
+//! 月別サマリー
+//!
+//! 月ディレクトリ直下の `{YYYYMM}-summary.json` に、その月のレシートの解析結果を保存する。
+//! フロントエンドが保存する項目のうち Rust 側で使わないものも `extra` に保持し、
+//! 読み書きで失われないようにする。
+
+use crate::money::Currency;
+use crate::providers::{OcrResult, ReceiptData};
+use crate::sanitize::{collect_invalid_fields, InvalidField};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// レシートの処理状態
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReceiptStatus {
+    #[default]
+    Pending,
+    Processing,
+    Success,
+    Error,
+}
+
+/// 経費レビューの状態
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReviewStatus {
+    /// 下書き（OCR直後）
+    #[default]
+    Draft,
+    /// レビュー待ち
+    NeedsReview,
+    /// 承認済み
+    Approved,
+    /// 差し戻し
+    Rejected,
+}
+
+impl ReviewStatus {
+    /// `next` への遷移を許可するか（同じ状態への遷移は許可する）
+    ///
+    /// 承認済みはレビュー待ちに戻す（承認の取り消し）以外には遷移できない。
+    pub fn can_transition_to(self, next: ReviewStatus) -> bool {
+        use ReviewStatus::*;
+        self == next
+            || matches!(
+                (self, next),
+                (Draft, NeedsReview)
+                    | (NeedsReview, Draft | Approved | Rejected)
+                    | (Rejected, Draft | NeedsReview)
+                    | (Approved, NeedsReview)
+            )
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ReviewStatus::Draft => "下書き",
+            ReviewStatus::NeedsReview => "レビュー待ち",
+            ReviewStatus::Approved => "承認済み",
+            ReviewStatus::Rejected => "差し戻し",
+        }
+    }
+}
+
+/// 検証で見つかった問題（フロントエンドの `ValidationIssue`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ValidationIssue {
+    /// 対象項目（`date`・`amount`・`merchant`・`file`・`duplicate`・`note`）
+    pub field: String,
+    /// 種類（`format`・`range`・`outlier`・`duplicate-data` など）
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub severity: String,
+    pub message: String,
+    /// 対象のファイル名（バッチの検証結果のみ。レシートに付ける場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// サマリーに保存する1レシート
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryReceipt {
+    /// ファイル名
+    pub file: String,
+    #[serde(default)]
+    pub status: ReceiptStatus,
+    /// レビューの状態
+    #[serde(default)]
+    pub review_status: ReviewStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant: Option<String>,
+    /// 店舗名の末尾の支店名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::sanitize::deserialize_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date: Option<String>,
+    /// 購入時刻（HH:MM:SS）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::sanitize::deserialize_amount",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub amount: Option<f64>,
+    /// 消費税額（複数税率の場合は合計税額）
+    #[serde(
+        default,
+        deserialize_with = "crate::sanitize::deserialize_amount",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tax_amount: Option<f64>,
+    /// 消費税率（0.10 = 10%）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_rate: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "crate::sanitize::deserialize_currency",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub currency: Option<Currency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receiver_name: Option<String>,
+    /// 証憑番号（領収書番号など）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// タグ
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 手動で確定した項目名（`receiverName` など）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub manually_edited: Vec<String>,
+    /// 検証結果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issues: Option<Vec<ValidationIssue>>,
+    /// このエントリの最終更新日時（RFC 3339、競合解決に使う）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// このエントリを最後に更新したデバイス
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    /// 上記以外の項目（フロントエンド側の項目をそのまま保持する）
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// 月別サマリー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthSummary {
+    pub year_month: String,
+    /// 最終更新日時（RFC 3339）
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub receipts: Vec<SummaryReceipt>,
+    /// Excelサマリー（フロントエンドが読み書きする正本）に未反映の変更があるか
+    ///
+    /// 終了時の書き出しなどバックエンドだけで更新したときに立て、フロントエンドは次の読み込みで
+    /// サマリーの内容をExcelに取り込んで保存する（保存すると下ろす）。
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pending_excel_sync: bool,
+}
+
+/// サマリーファイルのパス
+pub fn summary_path(month_dir: &Path, year_month: &str) -> PathBuf {
+    month_dir.join(format!("{}-summary.json", year_month))
+}
+
+/// 読み込み時に `None` に落としたレシートの項目
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedField {
+    pub file: String,
+    #[serde(flatten)]
+    pub invalid: InvalidField,
+}
+
+/// サマリーを読み込む（ファイルが無い場合は `None`）
+pub fn read_summary(month_dir: &Path, year_month: &str) -> io::Result<Option<MonthSummary>> {
+    Ok(read_summary_checked(month_dir, year_month)?.map(|(summary, _)| summary))
+}
+
+/// サマリーを読み込み、不正な日付・金額として落とした項目も返す（ファイルが無い場合は `None`）
+pub fn read_summary_checked(
+    month_dir: &Path,
+    year_month: &str,
+) -> io::Result<Option<(MonthSummary, Vec<DroppedField>)>> {
+    let content = match fs::read_to_string(summary_path(month_dir, year_month)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let (summary, invalid) =
+        collect_invalid_fields(|| serde_json::from_str::<MonthSummary>(&content));
+    let summary = summary.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    // どのレシートで落ちたかを特定するため、落とした項目があればレシート単位で読み直す
+    let dropped = if invalid.is_empty() {
+        Vec::new()
+    } else {
+        let raw: Value = serde_json::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        raw["receipts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|receipt| {
+                let (parsed, invalid) = collect_invalid_fields(|| {
+                    serde_json::from_value::<SummaryReceipt>(receipt.clone())
+                });
+                let file = parsed.map(|r| r.file).unwrap_or_default();
+                invalid.into_iter().map(move |invalid| DroppedField {
+                    file: file.clone(),
+                    invalid,
+                })
+            })
+            .collect()
+    };
+
+    Ok(Some((summary, dropped)))
+}
+
+/// サマリーを保存する
+///
+/// 一時ファイルに書いてから置き換えるため、書き込み途中で終了しても壊れない。
+pub fn write_summary(month_dir: &Path, summary: &MonthSummary) -> io::Result<()> {
+    let path = summary_path(month_dir, &summary.year_month);
+    let temp_path = path.with_extension("json.tmp");
+
+    let content = serde_json::to_string_pretty(summary).map_err(io::Error::other)?;
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, &path)
+}
+
+/// 一括更新の対象を選ぶ条件（指定した条件をすべて満たすレシートが対象）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptFilter {
+    /// 店舗名の完全一致（前後の空白と大文字・小文字を無視）
+    #[serde(default)]
+    pub merchant: Option<String>,
+    /// 店舗名の部分一致（大文字・小文字を無視）
+    #[serde(default)]
+    pub merchant_contains: Option<String>,
+    #[serde(default)]
+    pub status: Option<ReceiptStatus>,
+    /// ファイル名のいずれかに一致
+    #[serde(default)]
+    pub files: Option<Vec<String>>,
+}
+
+impl ReceiptFilter {
+    fn matches(&self, receipt: &SummaryReceipt) -> bool {
+        let merchant = receipt
+            .merchant
+            .as_deref()
+            .map(|m| m.trim().to_lowercase())
+            .unwrap_or_default();
+
+        self.merchant
+            .as_ref()
+            .is_none_or(|expected| merchant == expected.trim().to_lowercase())
+            && self
+                .merchant_contains
+                .as_ref()
+                .is_none_or(|part| merchant.contains(&part.trim().to_lowercase()))
+            && self.status.is_none_or(|status| receipt.status == status)
+            && self
+                .files
+                .as_ref()
+                .is_none_or(|files| files.contains(&receipt.file))
+    }
+}
+
+/// 一括更新で適用する部分更新（指定した項目だけを更新する）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptPatch {
+    #[serde(default)]
+    pub merchant: Option<String>,
+    #[serde(default, deserialize_with = "crate::sanitize::deserialize_date")]
+    pub date: Option<String>,
+    #[serde(default, deserialize_with = "crate::sanitize::deserialize_amount")]
+    pub amount: Option<f64>,
+    #[serde(default)]
+    pub receiver_name: Option<String>,
+    #[serde(default)]
+    pub account_category: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// 追加するタグ（既存のタグは残す）
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    /// 取り除くタグ
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+}
+
+/// 手動確定済みの項目の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ManualEditMode {
+    /// 手動確定済みの項目は更新しない
+    #[default]
+    Preserve,
+    /// 手動確定済みの項目も上書きする
+    Overwrite,
+}
+
+/// 一括更新の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateResult {
+    /// 条件に合致したレシート数
+    pub matched: usize,
+    /// 1項目以上を更新したレシート数
+    pub updated: usize,
+}
+
+/// 1項目を更新する（値が変わった場合は手動確定済みとして記録する）
+fn patch_field<T: Clone + PartialEq>(
+    receipt_field: &mut Option<T>,
+    value: &Option<T>,
+    name: &str,
+    manually_edited: &mut Vec<String>,
+    mode: ManualEditMode,
+) -> bool {
+    let Some(value) = value else {
+        return false;
+    };
+    let is_manual = manually_edited.iter().any(|field| field == name);
+    if (is_manual && mode == ManualEditMode::Preserve) || receipt_field.as_ref() == Some(value) {
+        return false;
+    }
+
+    *receipt_field = Some(value.clone());
+    if !is_manual {
+        manually_edited.push(name.to_string());
+    }
+    true
+}
+
+/// 項目の手動確定で古くなる検証結果の対象項目
+fn issue_fields_affected_by(edited: &str) -> &'static [&'static str] {
+    match edited {
+        "date" => &["date"],
+        "amount" => &["amount"],
+        "merchant" => &["merchant"],
+        // 交際費の備考必須チェックは勘定科目と備考の両方に依存する
+        "accountCategory" | "note" => &["note"],
+        _ => &[],
+    }
+}
+
+/// 重複判定（`duplicate-data`）に使われる項目
+const DUPLICATE_KEY_FIELDS: &[&str] = &["date", "merchant", "amount"];
+
+/// 検証結果の一部を取り除く（残りが無ければ `None` にする）
+fn remove_issues(receipt: &mut SummaryReceipt, remove: impl Fn(&ValidationIssue) -> bool) {
+    if let Some(issues) = receipt.issues.as_mut() {
+        let count = issues.len();
+        issues.retain(|issue| !remove(issue));
+        if issues.is_empty() && count > 0 {
+            receipt.issues = None;
+        }
+    }
+}
+
+/// 手動確定後に検証結果を手動値基準で見直す
+///
+/// 手動確定した項目への警告は解除する。重複の警告は、日付と金額が一致する
+/// 他のレシートが無くなったものだけ解除する（店舗名の類似判定はフロントエンドに任せる）。
+pub fn revalidate_after_edit(summary: &mut MonthSummary, edited: &HashMap<usize, Vec<String>>) {
+    for (&index, fields) in edited {
+        let affected: Vec<&str> = fields
+            .iter()
+            .flat_map(|field| issue_fields_affected_by(field))
+            .copied()
+            .collect();
+        remove_issues(&mut summary.receipts[index], |issue| {
+            affected.contains(&issue.field.as_str())
+        });
+    }
+
+    let duplicate_keys_changed = edited
+        .values()
+        .flatten()
+        .any(|field| DUPLICATE_KEY_FIELDS.contains(&field.as_str()));
+    if !duplicate_keys_changed {
+        return;
+    }
+
+    let keys: Vec<_> = summary
+        .receipts
+        .iter()
+        .map(|receipt| (receipt.date.clone(), receipt.amount))
+        .collect();
+    for (index, receipt) in summary.receipts.iter_mut().enumerate() {
+        let still_duplicated = keys[index].0.is_some()
+            && keys[index].1.is_some()
+            && keys
+                .iter()
+                .enumerate()
+                .any(|(other, key)| other != index && *key == keys[index]);
+        if !still_duplicated {
+            remove_issues(receipt, |issue| issue.kind == "duplicate-data");
+        }
+    }
+}
+
+/// 条件に合致するレシートに部分更新を適用する
+///
+/// 一括更新は手動の確定とみなし、更新した項目を `manually_edited` に記録して
+/// 検証結果を手動値基準で見直す。更新があればExcelサマリーへの取り込み待ち
+/// （`pending_excel_sync`）にする。
+pub fn bulk_update(
+    summary: &mut MonthSummary,
+    filter: &ReceiptFilter,
+    patch: &ReceiptPatch,
+    mode: ManualEditMode,
+) -> BulkUpdateResult {
+    let mut result = BulkUpdateResult::default();
+    let mut edited: HashMap<usize, Vec<String>> = HashMap::new();
+
+    for (index, receipt) in summary.receipts.iter_mut().enumerate() {
+        if !filter.matches(receipt) {
+            continue;
+        }
+        result.matched += 1;
+
+        let manually_edited = &mut receipt.manually_edited;
+        let mut fields = Vec::new();
+        let mut patch_string = |field: &mut Option<String>, value: &Option<String>, name: &str| {
+            if patch_field(field, value, name, manually_edited, mode) {
+                fields.push(name.to_string());
+            }
+        };
+        patch_string(&mut receipt.merchant, &patch.merchant, "merchant");
+        patch_string(&mut receipt.date, &patch.date, "date");
+        patch_string(
+            &mut receipt.receiver_name,
+            &patch.receiver_name,
+            "receiverName",
+        );
+        patch_string(
+            &mut receipt.account_category,
+            &patch.account_category,
+            "accountCategory",
+        );
+        patch_string(&mut receipt.note, &patch.note, "note");
+
+        if patch_field(
+            &mut receipt.amount,
+            &patch.amount,
+            "amount",
+            &mut receipt.manually_edited,
+            mode,
+        ) {
+            fields.push("amount".to_string());
+            // 集計用の整数値も手動値に合わせる
+            if let Some(minor) = receipt.amount.and_then(|amount| {
+                crate::money::to_minor_units(amount, receipt.currency.as_deref())
+            }) {
+                receipt
+                    .extra
+                    .insert("amountMinor".to_string(), minor.into());
+            }
+        }
+
+        let tag_count = receipt.tags.len();
+        receipt.tags.retain(|tag| !patch.remove_tags.contains(tag));
+        let mut tags_changed = receipt.tags.len() != tag_count;
+        for tag in &patch.add_tags {
+            if !receipt.tags.contains(tag) {
+                receipt.tags.push(tag.clone());
+                tags_changed = true;
+            }
+        }
+
+        if !fields.is_empty() || tags_changed {
+            result.updated += 1;
+        }
+        if !fields.is_empty() {
+            edited.insert(index, fields);
+        }
+    }
+
+    revalidate_after_edit(summary, &edited);
+    if result.updated > 0 {
+        summary.pending_excel_sync = true;
+    }
+    result
+}
+
+/// レシートのレビュー状態を変更する（許可しない遷移は拒否する）
+///
+/// 変更はExcelサマリーへの取り込み待ち（`pending_excel_sync`）にする。
+pub fn set_review_status(
+    summary: &mut MonthSummary,
+    file: &str,
+    status: ReviewStatus,
+) -> Result<(), String> {
+    let receipt = summary
+        .receipts
+        .iter_mut()
+        .find(|r| r.file == file)
+        .ok_or_else(|| format!("サマリーにレシートがありません: {}", file))?;
+
+    if !receipt.review_status.can_transition_to(status) {
+        return Err(format!(
+            "{}から{}には変更できません: {}",
+            receipt.review_status.label(),
+            status.label(),
+            file
+        ));
+    }
+    receipt.review_status = status;
+    summary.pending_excel_sync = true;
+    Ok(())
+}
+
+/// 月ディレクトリの年月（`{root}/YYYY/MM` → `YYYYMM`）
+pub fn year_month_of_directory(month_dir: &Path) -> Option<String> {
+    let month = month_dir.file_name()?.to_str()?;
+    let year = month_dir.parent()?.file_name()?.to_str()?;
+    let is_digits = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    (is_digits(year, 4) && is_digits(month, 2)).then(|| format!("{}{}", year, month))
+}
+
+/// ファイルが置かれた月ディレクトリと年月（`{root}/YYYY/MM/{file}` → `YYYYMM`）
+pub fn month_of_file(file_path: &Path) -> Option<(PathBuf, String)> {
+    let month_dir = file_path.parent()?;
+    year_month_of_directory(month_dir).map(|year_month| (month_dir.to_path_buf(), year_month))
+}
+
+/// ファイルが置かれた月のサマリーをまとめて読み込む（月ディレクトリ → サマリー）
+pub fn load_summaries_for<'a>(
+    file_paths: impl IntoIterator<Item = &'a str>,
+) -> HashMap<PathBuf, MonthSummary> {
+    let mut summaries = HashMap::new();
+    for file_path in file_paths {
+        let Some((month_dir, year_month)) = month_of_file(Path::new(file_path)) else {
+            continue;
+        };
+        if summaries.contains_key(&month_dir) {
+            continue;
+        }
+        if let Ok(Some(summary)) = read_summary(&month_dir, &year_month) {
+            summaries.insert(month_dir, summary);
+        }
+    }
+    summaries
+}
+
+/// 再OCRの結果に手動確定済みの値を引き継ぐ（手動値をOCR結果で上書きしない）
+pub fn preserve_manual_edits(
+    data: &mut ReceiptData,
+    file_path: &str,
+    summaries: &HashMap<PathBuf, MonthSummary>,
+) {
+    let Some(receipt) = month_of_file(Path::new(file_path))
+        .and_then(|(month_dir, _)| summaries.get(&month_dir))
+        .and_then(|summary| summary.receipts.iter().find(|r| r.file == data.file))
+    else {
+        return;
+    };
+
+    for field in &receipt.manually_edited {
+        match field.as_str() {
+            "merchant" => {
+                data.merchant = receipt.merchant.clone();
+                data.branch = receipt.branch.clone();
+            }
+            "date" => data.date = receipt.date.clone(),
+            "amount" => data.amount = receipt.amount,
+            "currency" => data.currency = receipt.currency.clone(),
+            "receiverName" => data.receiver_name = receipt.receiver_name.clone(),
+            "documentNumber" => data.document_number = receipt.document_number.clone(),
+            _ => {}
+        }
+    }
+    if receipt
+        .manually_edited
+        .iter()
+        .any(|field| field == "amount" || field == "currency")
+    {
+        data.amount_minor = data
+            .amount
+            .and_then(|amount| crate::money::to_minor_units(amount, data.currency.as_deref()));
+    }
+    data.manually_edited = receipt.manually_edited.clone();
+    data.review_status = receipt.review_status;
+}
+
+/// OCRの結果をサマリーのエントリに反映する（スキップした結果は反映しない）
+///
+/// 結果には [`preserve_manual_edits`] で手動確定済みの値が引き継がれている前提で、読み取った項目をそのまま書き込む。
+pub fn apply_ocr_result(summary: &mut MonthSummary, file: &str, result: &OcrResult) {
+    if result.skipped {
+        return;
+    }
+
+    let index = match summary.receipts.iter().position(|r| r.file == file) {
+        Some(index) => index,
+        None => {
+            let receipt = serde_json::from_value(serde_json::json!({ "file": file }))
+                .expect("ファイル名のみのエントリは常に読み込める");
+            summary.receipts.push(receipt);
+            summary.receipts.len() - 1
+        }
+    };
+    let receipt = &mut summary.receipts[index];
+
+    match &result.data {
+        Some(data) => {
+            receipt.status = ReceiptStatus::Success;
+            receipt.merchant = data.merchant.clone();
+            receipt.branch = data.branch.clone();
+            receipt.date = data.date.clone();
+            receipt.time = data.time.clone();
+            receipt.amount = data.amount;
+            receipt.tax_amount = data.tax_amount;
+            receipt.tax_rate = data.tax_rate;
+            receipt.currency = data.currency.clone();
+            receipt.receiver_name = data.receiver_name.clone();
+            receipt.document_number = data.document_number.clone();
+            if receipt.account_category.is_none() {
+                receipt.account_category = data.category.clone();
+            }
+            match data.amount_minor {
+                Some(minor) => receipt
+                    .extra
+                    .insert("amountMinor".to_string(), minor.into()),
+                None => receipt.extra.remove("amountMinor"),
+            };
+            // どのエンジンで読んだか（エンジンの切り替え前後の比較に使う）
+            for (key, value) in [
+                ("sourceProvider", &data.source_provider),
+                ("modelVersion", &data.model_version),
+            ] {
+                match value {
+                    Some(value) => receipt.extra.insert(key.to_string(), value.clone().into()),
+                    None => receipt.extra.remove(key),
+                };
+            }
+            receipt.extra.remove("errorMessage");
+        }
+        None => {
+            receipt.status = ReceiptStatus::Error;
+            if let Some(error) = &result.error {
+                receipt
+                    .extra
+                    .insert("errorMessage".to_string(), error.clone().into());
+            }
+        }
+    }
+}
+
+/// バッチの結果をファイルが置かれた月のサマリーへ書き出す
+///
+/// アプリ終了時など、フロントエンドが結果を保存できない場合に使う。Excelサマリーへは次に月を
+/// 読み込んだときに取り込まれる（`pending_excel_sync`）。書き出したエントリ数を返す。
+pub fn flush_results(file_paths: &[String], results: &[OcrResult]) -> io::Result<usize> {
+    let mut by_month: HashMap<(PathBuf, String), Vec<(String, &OcrResult)>> = HashMap::new();
+    for (file_path, result) in file_paths.iter().zip(results) {
+        let path = Path::new(file_path);
+        let (Some(month), Some(file)) = (
+            month_of_file(path),
+            path.file_name().and_then(|s| s.to_str()),
+        ) else {
+            continue;
+        };
+        if !result.skipped {
+            by_month
+                .entry(month)
+                .or_default()
+                .push((file.to_string(), result));
+        }
+    }
+
+    let mut written = 0;
+    for ((month_dir, year_month), entries) in by_month {
+        let mut summary = read_summary(&month_dir, &year_month)?.unwrap_or(MonthSummary {
+            year_month,
+            updated_at: String::new(),
+            receipts: Vec::new(),
+            pending_excel_sync: false,
+        });
+        for (file, result) in &entries {
+            apply_ocr_result(&mut summary, file, result);
+        }
+        summary.updated_at = chrono::Local::now().to_rfc3339();
+        summary.pending_excel_sync = true;
+        write_summary(&month_dir, &summary)?;
+        written += entries.len();
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_round_trip_keeps_unknown_fields() {
+        let json = serde_json::json!({
+            "yearMonth": "202501",
+            "receipts": [
+                { "file": "a.jpg", "status": "success", "merchant": "ローソン", "issues": [] },
+                { "file": "b.jpg", "status": "pending" },
+                { "file": "c.pdf", "status": "error", "errorMessage": "失敗" },
+            ],
+        });
+
+        let summary: MonthSummary = serde_json::from_value(json).unwrap();
+        assert_eq!(summary.receipts[2].status, ReceiptStatus::Error);
+
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(value["receipts"][0]["issues"], serde_json::json!([]));
+        assert_eq!(value["receipts"][2]["errorMessage"], "失敗");
+        assert!(value["receipts"][1].get("merchant").is_none());
+    }
+
+    #[test]
+    fn bulk_update_respects_manual_edits() {
+        let json = serde_json::json!({
+            "yearMonth": "202501",
+            "receipts": [
+                { "file": "a.jpg", "merchant": "ローソン 新宿店", "tags": ["出張"] },
+                { "file": "b.jpg", "merchant": "ローソン新宿店", "accountCategory": "会議費",
+                  "manuallyEdited": ["accountCategory"] },
+                { "file": "c.jpg", "merchant": "セブンイレブン" },
+            ],
+        });
+        let mut summary: MonthSummary = serde_json::from_value(json).unwrap();
+
+        let filter = ReceiptFilter {
+            merchant_contains: Some("ローソン".to_string()),
+            ..Default::default()
+        };
+        let patch = ReceiptPatch {
+            account_category: Some("消耗品費".to_string()),
+            add_tags: vec!["出張".to_string()],
+            ..Default::default()
+        };
+
+        let result = bulk_update(&mut summary, &filter, &patch, ManualEditMode::Preserve);
+        assert_eq!(
+            result,
+            BulkUpdateResult {
+                matched: 2,
+                updated: 2
+            }
+        );
+        assert_eq!(summary.receipts[0].tags, vec!["出張"]);
+        assert_eq!(summary.receipts[0].manually_edited, vec!["accountCategory"]);
+        assert!(summary.pending_excel_sync);
+        assert_eq!(
+            summary.receipts[1].account_category.as_deref(),
+            Some("会議費")
+        );
+
+        let result = bulk_update(&mut summary, &filter, &patch, ManualEditMode::Overwrite);
+        assert_eq!(
+            result,
+            BulkUpdateResult {
+                matched: 2,
+                updated: 1
+            }
+        );
+        assert_eq!(
+            summary.receipts[1].account_category.as_deref(),
+            Some("消耗品費")
+        );
+        assert!(summary.receipts[2].account_category.is_none());
+    }
+
+    #[test]
+    fn manual_amount_edit_clears_stale_issues() {
+        let duplicate = serde_json::json!({ "field": "duplicate", "type": "duplicate-data",
+            "severity": "warning", "message": "類似のレシートが存在します" });
+        let outlier = serde_json::json!({ "field": "amount", "type": "outlier",
+            "severity": "warning", "message": "金額が極端に高いです" });
+        let json = serde_json::json!({
+            "yearMonth": "202501",
+            "receipts": [
+                { "file": "a.jpg", "date": "2025-01-05", "amount": 10800, "amountMinor": 10800,
+                  "issues": [duplicate, outlier] },
+                { "file": "b.jpg", "date": "2025-01-05", "amount": 10800, "issues": [duplicate] },
+            ],
+        });
+        let mut summary: MonthSummary = serde_json::from_value(json).unwrap();
+
+        let filter = ReceiptFilter {
+            files: Some(vec!["a.jpg".to_string()]),
+            ..Default::default()
+        };
+        let patch = ReceiptPatch {
+            amount: Some(1080.0),
+            ..Default::default()
+        };
+        bulk_update(&mut summary, &filter, &patch, ManualEditMode::Preserve);
+
+        assert_eq!(summary.receipts[0].manually_edited, vec!["amount"]);
+        assert_eq!(summary.receipts[0].extra["amountMinor"], 1080);
+        assert!(summary.receipts[0].issues.is_none());
+        assert!(summary.receipts[1].issues.is_none());
+
+        // 再OCRの結果には手動値を引き継ぐ
+        let dir = Path::new("/receipts/2025/01");
+        let summaries = HashMap::from([(dir.to_path_buf(), summary)]);
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.amount = Some(10800.0);
+        preserve_manual_edits(&mut data, "/receipts/2025/01/a.jpg", &summaries);
+        assert_eq!(data.amount, Some(1080.0));
+        assert_eq!(data.manually_edited, vec!["amount"]);
+    }
+
+    #[test]
+    fn set_review_status_rejects_disallowed_transitions() {
+        let json = serde_json::json!({
+            "yearMonth": "202501",
+            "receipts": [{ "file": "a.jpg", "status": "success" }],
+        });
+        let mut summary: MonthSummary = serde_json::from_value(json).unwrap();
+        assert_eq!(summary.receipts[0].review_status, ReviewStatus::Draft);
+
+        set_review_status(&mut summary, "a.jpg", ReviewStatus::NeedsReview).unwrap();
+        set_review_status(&mut summary, "a.jpg", ReviewStatus::Approved).unwrap();
+        assert_eq!(
+            set_review_status(&mut summary, "a.jpg", ReviewStatus::Draft).unwrap_err(),
+            "承認済みから下書きには変更できません: a.jpg"
+        );
+        assert!(set_review_status(&mut summary, "b.jpg", ReviewStatus::Approved).is_err());
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["receipts"][0]["reviewStatus"], "approved");
+        assert_eq!(json["pendingExcelSync"], true);
+    }
+
+    #[test]
+    fn apply_ocr_result_upserts_entries_and_ignores_skips() {
+        let json = serde_json::json!({
+            "yearMonth": "202501",
+            "receipts": [{ "file": "a.jpg", "status": "pending", "accountCategory": "会議費" }],
+        });
+        let mut summary: MonthSummary = serde_json::from_value(json).unwrap();
+
+        let mut data = ReceiptData::new("a.jpg".to_string());
+        data.merchant = Some("スターバックス".to_string());
+        data.amount = Some(550.0);
+        data.amount_minor = Some(550);
+        data.category = Some("交際費".to_string());
+        data.source_provider = Some("googledocumentai".to_string());
+        apply_ocr_result(&mut summary, "a.jpg", &OcrResult::success(data));
+        apply_ocr_result(
+            &mut summary,
+            "b.jpg",
+            &OcrResult::failure("失敗".to_string()),
+        );
+        apply_ocr_result(
+            &mut summary,
+            "c.jpg",
+            &OcrResult::skipped(crate::error::AppError::Cancelled),
+        );
+
+        assert_eq!(summary.receipts.len(), 2);
+        let a = &summary.receipts[0];
+        assert_eq!(a.status, ReceiptStatus::Success);
+        assert_eq!(a.merchant.as_deref(), Some("スターバックス"));
+        assert_eq!(a.account_category.as_deref(), Some("会議費"));
+        assert_eq!(a.extra["amountMinor"], 550);
+        assert_eq!(a.extra["sourceProvider"], "googledocumentai");
+        assert!(!a.extra.contains_key("modelVersion"));
+        assert_eq!(summary.receipts[1].status, ReceiptStatus::Error);
+        assert_eq!(summary.receipts[1].extra["errorMessage"], "失敗");
+    }
+}
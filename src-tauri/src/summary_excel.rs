@@ -0,0 +1,450 @@
+//! Excelサマリー（`{YYYYMM}-summary.xlsx`）の読み取り
+//!
+//! フロントエンド（`services/excel/exporter.ts`）が書く「Summary」シートを読み、
+//! OCR済みのファイルを判定する。書き込みはフロントエンドが行うため、ここでは読むだけ。
+//! セルの値は文字列として扱い、書式・数式・画像は無視する。
+
+use quick_xml::events::{BytesRef, Event};
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// サマリーのシート名
+const SUMMARY_SHEET_NAME: &str = "Summary";
+
+/// 新フォーマットの判定に使うヘッダー（`exporter.ts` と同じ）
+const NEW_FORMAT_MARKER: &str = "宛名";
+
+/// OCR結果の有無を判定する列（0始まり）
+struct Columns {
+    file: usize,
+    date: usize,
+    merchant: usize,
+    amount: usize,
+}
+
+/// 新フォーマット: FileName(1), …, Date(4), Merchant(5), ReceiverName(6), Amount(7)
+const NEW_FORMAT_COLUMNS: Columns = Columns {
+    file: 0,
+    date: 3,
+    merchant: 4,
+    amount: 6,
+};
+
+/// 旧フォーマット: FileName(1), …, Date(4), Merchant(5), Amount(6)
+const OLD_FORMAT_COLUMNS: Columns = Columns {
+    file: 0,
+    date: 3,
+    merchant: 4,
+    amount: 5,
+};
+
+/// 1行分のセル（列番号 → 値）
+type Row = HashMap<usize, String>;
+
+/// Excelサマリーのパス
+pub fn summary_excel_path(month_dir: &Path, year_month: &str) -> PathBuf {
+    month_dir.join(format!("{}-summary.xlsx", year_month))
+}
+
+/// Excelサマリー上でOCR済みのファイル名を読む
+///
+/// 日付・店名・金額のいずれかがある行をOCR済みとする（フロントエンドが読み込み時に
+/// `success` とみなす条件と同じ）。ファイルが無ければ `Ok(None)`。
+pub fn read_processed_files(
+    month_dir: &Path,
+    year_month: &str,
+) -> io::Result<Option<HashSet<String>>> {
+    let path = summary_excel_path(month_dir, year_month);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let rows = read_summary_rows(file)?;
+    Ok(Some(processed_files(&rows)))
+}
+
+/// 「Summary」シートの行を（行番号, セル）の組で読む
+fn read_summary_rows(file: File) -> io::Result<Vec<(usize, Row)>> {
+    let mut archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+
+    let sheet_path = find_sheet_path(&mut archive, SUMMARY_SHEET_NAME)?;
+    let shared_strings = match read_entry(&mut archive, "xl/sharedStrings.xml")? {
+        Some(xml) => parse_shared_strings(&xml)?,
+        None => Vec::new(),
+    };
+    let sheet = read_entry(&mut archive, &sheet_path)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "シートが見つかりません"))?;
+
+    parse_rows(&sheet, &shared_strings)
+}
+
+/// 見出し行からフォーマットを判定し、OCR済みのファイル名を集める
+fn processed_files(rows: &[(usize, Row)]) -> HashSet<String> {
+    let is_new_format = rows
+        .iter()
+        .filter(|(number, _)| *number == 1)
+        .any(|(_, row)| row.values().any(|value| value == NEW_FORMAT_MARKER));
+    let columns = if is_new_format {
+        &NEW_FORMAT_COLUMNS
+    } else {
+        &OLD_FORMAT_COLUMNS
+    };
+
+    let has_value = |row: &Row, column: usize| row.get(&column).is_some_and(|v| !v.is_empty());
+    // 金額0はフロントエンドでも未入力扱い
+    let has_amount = |row: &Row| {
+        row.get(&columns.amount)
+            .is_some_and(|v| !v.is_empty() && v.trim().parse::<f64>() != Ok(0.0))
+    };
+
+    rows.iter()
+        .filter(|(number, _)| *number > 1)
+        .filter(|(_, row)| {
+            has_value(row, columns.date) || has_value(row, columns.merchant) || has_amount(row)
+        })
+        .filter_map(|(_, row)| row.get(&columns.file).filter(|file| !file.is_empty()))
+        .cloned()
+        .collect()
+}
+
+/// アーカイブ内のファイルを文字列で読む（無ければ `None`）
+fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> io::Result<Option<String>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(io::Error::other(e)),
+    };
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+/// シート名からシートXMLのパスを引く（workbook.xml → workbook.xml.rels）
+fn find_sheet_path(archive: &mut zip::ZipArchive<File>, sheet_name: &str) -> io::Result<String> {
+    let not_found = || io::Error::new(io::ErrorKind::InvalidData, "シートが見つかりません");
+
+    let workbook = read_entry(archive, "xl/workbook.xml")?.ok_or_else(not_found)?;
+    let relationship_id = find_attribute(&workbook, b"sheet", |attrs| {
+        (attrs.get("name").map(String::as_str) == Some(sheet_name))
+            .then(|| attrs.get("id").cloned())
+            .flatten()
+    })?
+    .ok_or_else(not_found)?;
+
+    let rels = read_entry(archive, "xl/_rels/workbook.xml.rels")?.ok_or_else(not_found)?;
+    let target = find_attribute(&rels, b"Relationship", |attrs| {
+        (attrs.get("Id") == Some(&relationship_id))
+            .then(|| attrs.get("Target").cloned())
+            .flatten()
+    })?
+    .ok_or_else(not_found)?;
+
+    // Target は xl/ からの相対パス（"/" 始まりならアーカイブのルートから）
+    Ok(match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("xl/{}", target),
+    })
+}
+
+/// 指定した要素の属性（名前空間の接頭辞は除く）を順に調べ、最初に値を返したものを返す
+fn find_attribute(
+    xml: &str,
+    element: &[u8],
+    mut pick: impl FnMut(&HashMap<String, String>) -> Option<String>,
+) -> io::Result<Option<String>> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event().map_err(invalid_data)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == element => {
+                let mut attrs = HashMap::new();
+                for attr in e.attributes() {
+                    let attr = attr.map_err(invalid_data)?;
+                    let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+                    let value = attr.unescape_value().map_err(invalid_data)?.into_owned();
+                    attrs.insert(key, value);
+                }
+                if let Some(value) = pick(&attrs) {
+                    return Ok(Some(value));
+                }
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// sharedStrings.xml の文字列を順に読む
+///
+/// リッチテキストは各ランを連結し、ふりがな（`rPh`）は除く。
+fn parse_shared_strings(xml: &str) -> io::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+    let mut in_phonetic = false;
+
+    loop {
+        match reader.read_event().map_err(invalid_data)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"si" => current.clear(),
+                b"t" => in_text = true,
+                b"rPh" => in_phonetic = true,
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"si" => strings.push(std::mem::take(&mut current)),
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"si" => strings.push(String::new()),
+            Event::Text(e) if in_text && !in_phonetic => {
+                current.push_str(&e.decode().map_err(invalid_data)?)
+            }
+            Event::GeneralRef(e) if in_text && !in_phonetic => push_reference(&mut current, &e)?,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(strings)
+}
+
+/// シートXMLのセルを行ごとに読む
+///
+/// 共有文字列（`t="s"`）は `shared_strings` から引き、それ以外は `<v>` か
+/// インライン文字列（`<is><t>`）の内容をそのまま使う。空のセルは含めない。
+fn parse_rows(xml: &str, shared_strings: &[String]) -> io::Result<Vec<(usize, Row)>> {
+    let mut reader = Reader::from_str(xml);
+    let mut rows = Vec::new();
+    let mut row_number = 0;
+    let mut row = Row::new();
+    let mut next_column = 0;
+    let mut cell: Option<(usize, bool)> = None;
+    let mut value = String::new();
+    let mut in_value = false;
+    let mut in_phonetic = false;
+
+    loop {
+        match reader.read_event().map_err(invalid_data)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"row" => {
+                    row_number = attribute(&e, b"r")?
+                        .and_then(|r| r.parse().ok())
+                        .unwrap_or(row_number + 1);
+                    row.clear();
+                    next_column = 0;
+                }
+                b"c" => {
+                    let column = attribute(&e, b"r")?
+                        .and_then(|r| column_index(&r))
+                        .unwrap_or(next_column);
+                    next_column = column + 1;
+                    let is_shared = attribute(&e, b"t")?.as_deref() == Some("s");
+                    cell = Some((column, is_shared));
+                    value.clear();
+                }
+                b"v" | b"t" => in_value = true,
+                b"rPh" => in_phonetic = true,
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"row" => {
+                    row_number = attribute(&e, b"r")?
+                        .and_then(|r| r.parse().ok())
+                        .unwrap_or(row_number + 1);
+                }
+                b"c" => {
+                    next_column = attribute(&e, b"r")?
+                        .and_then(|r| column_index(&r))
+                        .unwrap_or(next_column)
+                        + 1;
+                }
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"row" => rows.push((row_number, std::mem::take(&mut row))),
+                b"c" => {
+                    if let Some((column, is_shared)) = cell.take() {
+                        let resolved = if is_shared {
+                            value
+                                .trim()
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|index| shared_strings.get(index))
+                                .cloned()
+                                .unwrap_or_default()
+                        } else {
+                            std::mem::take(&mut value)
+                        };
+                        if !resolved.is_empty() {
+                            row.insert(column, resolved);
+                        }
+                    }
+                }
+                b"v" | b"t" => in_value = false,
+                b"rPh" => in_phonetic = false,
+                _ => {}
+            },
+            Event::Text(e) if in_value && !in_phonetic && cell.is_some() => {
+                value.push_str(&e.decode().map_err(invalid_data)?)
+            }
+            Event::GeneralRef(e) if in_value && !in_phonetic && cell.is_some() => {
+                push_reference(&mut value, &e)?
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(rows)
+}
+
+/// 属性の値（名前空間の接頭辞は除いて照合）
+fn attribute(e: &quick_xml::events::BytesStart, name: &[u8]) -> io::Result<Option<String>> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(invalid_data)?;
+        if attr.key.local_name().as_ref() == name {
+            return Ok(Some(
+                attr.unescape_value().map_err(invalid_data)?.into_owned(),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+/// 文字参照・定義済み実体参照（`&amp;` など）を展開して追加する
+fn push_reference(out: &mut String, reference: &BytesRef) -> io::Result<()> {
+    if let Some(ch) = reference.resolve_char_ref().map_err(invalid_data)? {
+        out.push(ch);
+        return Ok(());
+    }
+    let name = reference.decode().map_err(invalid_data)?;
+    let resolved = quick_xml::escape::resolve_predefined_entity(&name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("不明な実体参照: &{};", name),
+        )
+    })?;
+    out.push_str(resolved);
+    Ok(())
+}
+
+/// セル参照（"D5" など）の列番号（0始まり）
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: String = reference
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let column = letters.chars().fold(0usize, |acc, c| {
+        acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+    });
+    Some(column - 1)
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_xlsxwriter::Workbook;
+
+    fn write_summary(month_dir: &Path, header: &[&str], rows: &[&[(u16, &str)]]) {
+        let mut workbook = Workbook::new();
+        workbook.add_worksheet().set_name("Sheet1").unwrap();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(SUMMARY_SHEET_NAME).unwrap();
+        for (col, title) in header.iter().enumerate() {
+            sheet.write_string(0, col as u16, *title).unwrap();
+        }
+        for (i, cells) in rows.iter().enumerate() {
+            for (col, value) in cells.iter() {
+                let row = i as u32 + 1;
+                match value.parse::<f64>() {
+                    Ok(number) => sheet.write_number(row, *col, number).unwrap(),
+                    Err(_) => sheet.write_string(row, *col, *value).unwrap(),
+                };
+            }
+        }
+        workbook
+            .save(summary_excel_path(month_dir, "202501"))
+            .unwrap();
+    }
+
+    fn fs_reset(dir: &Path) {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_processed_files_from_summary_sheet() {
+        let dir =
+            std::env::temp_dir().join(format!("torifune-summary-excel-{}", std::process::id()));
+        fs_reset(&dir);
+        assert!(read_processed_files(&dir, "202501").unwrap().is_none());
+
+        let header = [
+            "ファイル名",
+            "プレビュー",
+            "元ファイル",
+            "日付",
+            "店名",
+            "宛名",
+            "金額",
+        ];
+        write_summary(
+            &dir,
+            &header,
+            &[
+                &[(0, "a.jpg"), (4, "ローソン")],
+                &[(0, "b.jpg")],
+                &[(0, "A&B <1>.jpg"), (6, "1200")],
+                &[(0, "c.pdf"), (6, "0")],
+                &[(0, "d.jpg"), (5, "山田")],
+            ],
+        );
+        let processed = read_processed_files(&dir, "202501").unwrap().unwrap();
+        assert_eq!(
+            processed,
+            HashSet::from(["a.jpg".to_string(), "A&B <1>.jpg".to_string()])
+        );
+
+        // 旧フォーマットは金額が6列目
+        write_summary(
+            &dir,
+            &[
+                "ファイル名",
+                "プレビュー",
+                "元ファイル",
+                "日付",
+                "店名",
+                "金額",
+            ],
+            &[&[(0, "a.jpg"), (5, "500")], &[(0, "b.jpg"), (6, "JPY")]],
+        );
+        let processed = read_processed_files(&dir, "202501").unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(processed, HashSet::from(["a.jpg".to_string()]));
+    }
+
+    #[test]
+    fn shared_strings_skip_phonetic_runs() {
+        let xml = r#"<sst><si><t>東京</t><rPh sb="0" eb="2"><t>トウキョウ</t></rPh></si><si><r><t>A&amp;</t></r><r><t xml:space="preserve"> B&#65;</t></r></si><si/></sst>"#;
+        assert_eq!(
+            parse_shared_strings(xml).unwrap(),
+            vec!["東京".to_string(), "A& BA".to_string(), String::new()]
+        );
+        assert_eq!(column_index("D5"), Some(3));
+        assert_eq!(column_index("AB12"), Some(27));
+    }
+}
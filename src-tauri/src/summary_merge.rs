@@ -0,0 +1,381 @@
+//! 月別サマリーの競合解決
+//!
+//! 共有フォルダを複数のデバイスで使うと、同じ月のサマリーを別々に更新して上書きしてしまう。
+//! 保存時にディスク上の現在の内容を読み直し、読み込み時点の内容（ベース）との 3-way マージを行う。
+//! 双方が同じ項目を別の値に変えた場合はエントリの `updatedAt` が新しい方を採用し、
+//! 競合として返して手動で解決できるようにする。
+//! レビュー状態はマージ後もディスク側からの遷移として許可されるもの（`can_transition_to`）に限る。
+
+use crate::summary::{MonthSummary, ReviewStatus, SummaryReceipt};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 比較しない項目（エントリの更新情報と、読み込みごとに振り直すフロントエンドのID）
+const METADATA_FIELDS: &[&str] = &["updatedAt", "deviceId", "id"];
+
+/// 競合時に採用した側
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSide {
+    /// 保存しようとした内容
+    Ours,
+    /// ディスク上の内容（他のデバイスの更新）
+    Theirs,
+}
+
+/// 競合した項目
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryConflict {
+    /// ファイル名
+    pub file: String,
+    /// 項目名（一方が削除し他方が更新したエントリは `None`）
+    pub field: Option<String>,
+    pub ours: Value,
+    pub theirs: Value,
+    /// 自動で採用した側
+    pub resolved: ConflictSide,
+    /// ディスク側を更新したデバイス
+    pub their_device_id: Option<String>,
+}
+
+/// マージ結果
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub summary: MonthSummary,
+    pub conflicts: Vec<SummaryConflict>,
+}
+
+/// 保存時のベース（読み込み・保存した時点のサマリー）をサマリーファイルごとに保持する
+#[derive(Default)]
+pub struct SummaryBaseCache {
+    bases: Mutex<HashMap<PathBuf, MonthSummary>>,
+}
+
+impl SummaryBaseCache {
+    pub fn get(&self, summary_path: &Path) -> Option<MonthSummary> {
+        self.bases.lock().ok()?.get(summary_path).cloned()
+    }
+
+    pub fn set(&self, summary_path: PathBuf, summary: MonthSummary) {
+        if let Ok(mut bases) = self.bases.lock() {
+            bases.insert(summary_path, summary);
+        }
+    }
+}
+
+fn to_map(receipt: &SummaryReceipt) -> Map<String, Value> {
+    match serde_json::to_value(receipt) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn by_file(summary: Option<&MonthSummary>) -> HashMap<String, Map<String, Value>> {
+    summary
+        .map(|s| {
+            s.receipts
+                .iter()
+                .map(|r| (r.file.clone(), to_map(r)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn field(map: &Map<String, Value>, key: &str) -> Value {
+    map.get(key).cloned().unwrap_or(Value::Null)
+}
+
+/// 更新情報を除いた内容が同じか
+fn same_content(a: Option<&Map<String, Value>>, b: Option<&Map<String, Value>>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a
+            .keys()
+            .chain(b.keys())
+            .filter(|key| !METADATA_FIELDS.contains(&key.as_str()))
+            .all(|key| field(a, key) == field(b, key)),
+        _ => false,
+    }
+}
+
+fn updated_at(map: &Map<String, Value>) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    map.get("updatedAt")
+        .and_then(Value::as_str)
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+}
+
+fn device_id(map: &Map<String, Value>) -> Option<String> {
+    map.get("deviceId")
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+/// マージ後のレビュー状態がディスク側から許可されない遷移なら、ディスク側の状態に戻して競合として返す
+///
+/// 承認済みのレシートが、レビュー状態を持たない古い内容の保存で下書きに戻るのを防ぐ。
+fn keep_allowed_review_status(
+    file: &str,
+    merged: &mut Map<String, Value>,
+    theirs: Option<&Map<String, Value>>,
+    conflicts: &mut Vec<SummaryConflict>,
+) {
+    let Some(theirs) = theirs else {
+        return;
+    };
+    let status = |map: &Map<String, Value>| {
+        serde_json::from_value::<ReviewStatus>(field(map, "reviewStatus")).unwrap_or_default()
+    };
+    let (current, next) = (status(theirs), status(merged));
+    if current.can_transition_to(next) {
+        return;
+    }
+    conflicts.push(SummaryConflict {
+        file: file.to_string(),
+        field: Some("reviewStatus".to_string()),
+        ours: field(merged, "reviewStatus"),
+        theirs: field(theirs, "reviewStatus"),
+        resolved: ConflictSide::Theirs,
+        their_device_id: device_id(theirs),
+    });
+    merged.insert("reviewStatus".to_string(), field(theirs, "reviewStatus"));
+}
+
+/// 1エントリをマージする（`None` はエントリが無い・削除された）
+fn merge_entry(
+    file: &str,
+    base: Option<&Map<String, Value>>,
+    ours: Option<&Map<String, Value>>,
+    theirs: Option<&Map<String, Value>>,
+    conflicts: &mut Vec<SummaryConflict>,
+) -> Option<Map<String, Value>> {
+    if same_content(ours, base) {
+        return theirs.cloned();
+    }
+    if same_content(theirs, base) || same_content(ours, theirs) {
+        return ours.cloned();
+    }
+
+    let (ours, theirs) = match (ours, theirs) {
+        (Some(ours), Some(theirs)) => (ours, theirs),
+        // 削除と更新の競合は、データを失わないよう更新した側を残す
+        (ours, theirs) => {
+            let resolved = if ours.is_some() {
+                ConflictSide::Ours
+            } else {
+                ConflictSide::Theirs
+            };
+            conflicts.push(SummaryConflict {
+                file: file.to_string(),
+                field: None,
+                ours: ours.cloned().map(Value::Object).unwrap_or(Value::Null),
+                theirs: theirs.cloned().map(Value::Object).unwrap_or(Value::Null),
+                resolved,
+                their_device_id: theirs.and_then(device_id),
+            });
+            return ours.or(theirs).cloned();
+        }
+    };
+
+    // 項目単位でマージし、双方が変更した項目は更新日時が新しい方を採用する
+    let empty = Map::new();
+    let base = base.unwrap_or(&empty);
+    let ours_newer = updated_at(ours) >= updated_at(theirs);
+    let mut keys: Vec<&String> = ours.keys().collect();
+    keys.extend(theirs.keys().filter(|key| !ours.contains_key(*key)));
+
+    let mut merged = Map::new();
+    for key in keys {
+        let (base_value, our_value, their_value) =
+            (field(base, key), field(ours, key), field(theirs, key));
+        let value = if METADATA_FIELDS.contains(&key.as_str()) {
+            if ours_newer {
+                our_value
+            } else {
+                their_value
+            }
+        } else if our_value == base_value {
+            their_value
+        } else if their_value == base_value || our_value == their_value {
+            our_value
+        } else {
+            let resolved = if ours_newer {
+                ConflictSide::Ours
+            } else {
+                ConflictSide::Theirs
+            };
+            conflicts.push(SummaryConflict {
+                file: file.to_string(),
+                field: Some(key.clone()),
+                ours: our_value.clone(),
+                theirs: their_value.clone(),
+                resolved,
+                their_device_id: device_id(theirs),
+            });
+            if ours_newer {
+                our_value
+            } else {
+                their_value
+            }
+        };
+        if !value.is_null() {
+            merged.insert(key.clone(), value);
+        }
+    }
+    Some(merged)
+}
+
+/// 保存する内容をディスク上の内容と 3-way マージする
+///
+/// フロントエンドは保存する内容を同時にExcelサマリーへ書くため、マージでディスク側の内容を
+/// 取り込んだ場合だけ結果の `pending_excel_sync` を立てる（次の読み込みでExcelに取り込ませる）。
+/// `base` は読み込んだ時点のサマリー。ベースから変更したエントリには `now` と `device_id` を記録する。
+/// エントリの順序は保存する内容に従い、ディスク側にだけあるエントリは末尾に追加する。
+pub fn merge_summary_with_disk(
+    base: Option<&MonthSummary>,
+    mut ours: MonthSummary,
+    disk: Option<&MonthSummary>,
+    device_id: &str,
+    now: &str,
+) -> Result<MergeResult, String> {
+    let base_entries = by_file(base);
+    for receipt in &mut ours.receipts {
+        if !same_content(Some(&to_map(receipt)), base_entries.get(&receipt.file)) {
+            receipt.updated_at = Some(now.to_string());
+            receipt.device_id = Some(device_id.to_string());
+        }
+    }
+
+    let our_entries = by_file(Some(&ours));
+    let their_entries = by_file(disk);
+    let mut files: Vec<&String> = ours.receipts.iter().map(|r| &r.file).collect();
+    if let Some(disk) = disk {
+        files.extend(
+            disk.receipts
+                .iter()
+                .map(|r| &r.file)
+                .filter(|file| !our_entries.contains_key(*file)),
+        );
+    }
+    // 同じファイル名のエントリは1つにまとめる（ベースにだけあるエントリは双方が削除したもの）
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(*file));
+
+    let mut conflicts = Vec::new();
+    let mut receipts = Vec::new();
+    for file in files {
+        let merged = merge_entry(
+            file,
+            base_entries.get(file),
+            our_entries.get(file),
+            their_entries.get(file),
+            &mut conflicts,
+        );
+        if let Some(mut merged) = merged {
+            keep_allowed_review_status(file, &mut merged, their_entries.get(file), &mut conflicts);
+            let receipt = serde_json::from_value(Value::Object(merged))
+                .map_err(|e| format!("サマリーのマージに失敗しました: {}", e))?;
+            receipts.push(receipt);
+        }
+    }
+
+    Ok(MergeResult {
+        summary: MonthSummary {
+            year_month: ours.year_month,
+            updated_at: now.to_string(),
+            pending_excel_sync: receipts.len() != our_entries.len()
+                || receipts
+                    .iter()
+                    .any(|r| !same_content(Some(&to_map(r)), our_entries.get(&r.file))),
+            receipts,
+        },
+        conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(receipts: Value) -> MonthSummary {
+        serde_json::from_value(serde_json::json!({ "yearMonth": "202501", "receipts": receipts }))
+            .unwrap()
+    }
+
+    #[test]
+    fn merge_keeps_both_sides_and_reports_conflicts() {
+        let base = summary(serde_json::json!([
+            { "file": "a.jpg", "merchant": "ローソン", "amount": 500 },
+            { "file": "b.jpg", "merchant": "スタバ", "amount": 600 },
+        ]));
+        // このデバイス: a の金額と b の店舗名を変更、c を追加
+        let ours = summary(serde_json::json!([
+            { "file": "a.jpg", "merchant": "ローソン", "amount": 550 },
+            { "file": "b.jpg", "merchant": "スターバックス", "amount": 600 },
+            { "file": "c.jpg", "merchant": "ドトール" },
+        ]));
+        // 他のデバイス（後から保存）: a の店舗名と b の店舗名を変更、d を追加
+        let disk = summary(serde_json::json!([
+            { "file": "a.jpg", "merchant": "LAWSON", "amount": 500,
+              "updatedAt": "2025-01-10T12:00:00+09:00", "deviceId": "other" },
+            { "file": "b.jpg", "merchant": "Starbucks", "amount": 600,
+              "updatedAt": "2099-01-01T00:00:00+09:00", "deviceId": "other" },
+            { "file": "d.jpg", "merchant": "セブン" },
+        ]));
+
+        let result = merge_summary_with_disk(
+            Some(&base),
+            ours,
+            Some(&disk),
+            "mine",
+            "2025-01-10T13:00:00+09:00",
+        )
+        .unwrap();
+        let receipts = &result.summary.receipts;
+
+        let files: Vec<_> = receipts.iter().map(|r| r.file.as_str()).collect();
+        assert_eq!(files, ["a.jpg", "b.jpg", "c.jpg", "d.jpg"]);
+        assert_eq!(receipts[0].merchant.as_deref(), Some("LAWSON"));
+        assert_eq!(receipts[0].amount, Some(550.0));
+        assert_eq!(receipts[0].device_id.as_deref(), Some("mine"));
+        assert_eq!(receipts[1].merchant.as_deref(), Some("Starbucks"));
+        // ディスク側の内容を取り込んだので、Excelへの反映待ちにする
+        assert!(result.summary.pending_excel_sync);
+
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.file, "b.jpg");
+        assert_eq!(conflict.field.as_deref(), Some("merchant"));
+        assert_eq!(conflict.ours, "スターバックス");
+        assert_eq!(conflict.resolved, ConflictSide::Theirs);
+        assert_eq!(conflict.their_device_id.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn merge_does_not_revert_approved_receipts() {
+        let approved = serde_json::json!([
+            { "file": "a.jpg", "reviewStatus": "approved", "tags": ["出張"] },
+        ]);
+        // レビュー状態を持たない内容（既定の下書き）で保存しても承認済みのまま
+        let ours = summary(serde_json::json!([{ "file": "a.jpg", "tags": ["出張"] }]));
+        let result = merge_summary_with_disk(
+            Some(&summary(approved.clone())),
+            ours,
+            Some(&summary(approved)),
+            "mine",
+            "2025-01-10T13:00:00+09:00",
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.summary.receipts[0].review_status,
+            ReviewStatus::Approved
+        );
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].field.as_deref(), Some("reviewStatus"));
+        assert_eq!(result.conflicts[0].resolved, ConflictSide::Theirs);
+    }
+}
@@ -0,0 +1,423 @@
+//! サムネイルファイル管理
+//!
+//! サムネイルは `{YYYY}/{MM}/thumbnails/{file_name}.{content_hash}.thumbnail.png` に保存する。
+//! ハッシュは元ファイルの内容から計算するため、同名のファイルが差し替えられても
+//! 古いサムネイルが表示されることはない。
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// ハッシュ計算に使う先頭バイト数
+const HASH_SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// サムネイルファイル名の接尾辞
+const THUMBNAIL_SUFFIX: &str = ".thumbnail.png";
+
+/// 受け付けるサムネイルのサイズの上限（バイト）
+pub const MAX_THUMBNAIL_BYTES: usize = 5 * 1024 * 1024;
+
+/// 受け付けるDataURLの長さの上限（`MAX_THUMBNAIL_BYTES` の Base64 長に接頭辞の分を加える）
+const MAX_THUMBNAIL_DATA_URL_LEN: usize = MAX_THUMBNAIL_BYTES.div_ceil(3) * 4 + 32;
+
+/// 受け付けるサムネイルの幅・高さの上限（ピクセル）
+pub const MAX_THUMBNAIL_DIMENSION: u32 = 2048;
+
+/// バックエンドで生成するサムネイルの既定の長辺（ピクセル）
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+/// 受け付けるDataURLの接頭辞
+const DATA_URL_PREFIXES: &[&str] = &[
+    "data:image/png;base64,",
+    "data:image/jpeg;base64,",
+    "data:image/webp;base64,",
+];
+
+/// 元ファイルの内容ハッシュを計算する
+///
+/// 先頭 `HASH_SAMPLE_BYTES` バイトとファイルサイズに対する FNV-1a（64bit）。
+/// 差し替え検知が目的なので暗号学的な強度は不要。
+pub fn content_hash(path: &Path) -> io::Result<String> {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut sample = Vec::new();
+    file.take(HASH_SAMPLE_BYTES).read_to_end(&mut sample)?;
+
+    let hash = size
+        .to_le_bytes()
+        .iter()
+        .chain(sample.iter())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        });
+
+    Ok(format!("{:016x}", hash))
+}
+
+/// 内容ハッシュ付きのサムネイルファイル名
+pub fn thumbnail_file_name(file_name: &str, hash: &str) -> String {
+    format!("{}.{}{}", file_name, hash, THUMBNAIL_SUFFIX)
+}
+
+/// ハッシュを含まない旧形式のサムネイルファイル名
+pub fn legacy_thumbnail_file_name(file_name: &str) -> String {
+    format!("{}{}", file_name, THUMBNAIL_SUFFIX)
+}
+
+/// サムネイルファイル名が指定ファイルのもの（旧形式を含む）かどうか
+fn is_thumbnail_of(thumbnail_name: &str, file_name: &str) -> bool {
+    if thumbnail_name == legacy_thumbnail_file_name(file_name) {
+        return true;
+    }
+
+    thumbnail_name
+        .strip_prefix(file_name)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|rest| rest.strip_suffix(THUMBNAIL_SUFFIX))
+        .is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// 指定ファイルのサムネイル（旧形式・古いハッシュを含む）をすべて列挙する
+pub fn list_thumbnails(thumbnails_dir: &Path, file_name: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(thumbnails_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|s| s.to_str())
+                .is_some_and(|name| is_thumbnail_of(name, file_name))
+        })
+        .collect()
+}
+
+/// サムネイルのDataURLを検証してデコードする
+///
+/// 異常に大きな入力でメモリを使い切らないよう、Base64 をデコードする前に文字列長を、
+/// デコード後は画像全体を展開せずにヘッダから寸法を確認する。
+pub fn decode_thumbnail_data_url(data_url: &str) -> Result<Vec<u8>, String> {
+    if data_url.len() > MAX_THUMBNAIL_DATA_URL_LEN {
+        return Err(format!(
+            "サムネイルが大きすぎます（上限 {}MB）",
+            MAX_THUMBNAIL_BYTES / 1024 / 1024
+        ));
+    }
+
+    // 形式: data:image/png;base64,XXXXXX
+    let base64_data = DATA_URL_PREFIXES
+        .iter()
+        .find_map(|prefix| data_url.strip_prefix(prefix))
+        .ok_or("無効なDataURL形式です")?;
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    let image_data = STANDARD
+        .decode(base64_data)
+        .map_err(|e| format!("Base64デコードに失敗しました: {}", e))?;
+
+    let (width, height) = image::ImageReader::new(io::Cursor::new(&image_data))
+        .with_guessed_format()
+        .map_err(|e| format!("サムネイル画像の読み込みに失敗しました: {}", e))?
+        .into_dimensions()
+        .map_err(|e| format!("サムネイル画像の読み込みに失敗しました: {}", e))?;
+    if width > MAX_THUMBNAIL_DIMENSION || height > MAX_THUMBNAIL_DIMENSION {
+        return Err(format!(
+            "サムネイルが大きすぎます（{}x{}、上限 {}px）",
+            width, height, MAX_THUMBNAIL_DIMENSION
+        ));
+    }
+
+    Ok(image_data)
+}
+
+/// 画像をデコードし、EXIF の向きを画素に適用する
+pub fn decode_oriented(content: &[u8]) -> image::ImageResult<image::DynamicImage> {
+    use image::{DynamicImage, ImageDecoder};
+
+    let mut decoder = image::ImageReader::new(io::Cursor::new(content))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// 画像から長辺 `max_dimension` の PNG サムネイルを作る（EXIF の向きは画素に適用する）
+pub fn render_thumbnail(content: &[u8], max_dimension: u32) -> image::ImageResult<Vec<u8>> {
+    let image = decode_oriented(content)?;
+
+    let max_dimension = max_dimension.clamp(1, MAX_THUMBNAIL_DIMENSION);
+    let mut png = io::Cursor::new(Vec::new());
+    image
+        .thumbnail(max_dimension, max_dimension)
+        .write_to(&mut png, image::ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// サムネイルを `{month_dir}/thumbnails/` に保存し、差し替え前の内容の古いサムネイルを削除する
+pub fn write_thumbnail(
+    month_dir: &Path,
+    file_name: &str,
+    image_data: &[u8],
+) -> Result<PathBuf, String> {
+    let thumbnails_path = month_dir.join("thumbnails");
+
+    // 元ファイルの内容ハッシュをサムネイル名に含める
+    let content_hash = content_hash(&month_dir.join(file_name))
+        .map_err(|e| format!("元ファイルの読み込みに失敗しました: {}", e))?;
+
+    fs::create_dir_all(&thumbnails_path)
+        .map_err(|e| format!("thumbnailsディレクトリの作成に失敗しました: {}", e))?;
+
+    let file_path = thumbnails_path.join(thumbnail_file_name(file_name, &content_hash));
+    fs::write(&file_path, image_data)
+        .map_err(|e| format!("サムネイルの保存に失敗しました: {}", e))?;
+
+    remove_stale_thumbnails(&thumbnails_path, file_name, &file_path);
+
+    Ok(file_path)
+}
+
+/// 月ディレクトリの元画像 `file_name` から長辺 `max_dimension` のサムネイルを作り、`thumbnails/` に保存する
+///
+/// `file_name` はディレクトリを含まない名前のみ受け付ける（月ディレクトリの外は読まない）。
+/// 保存名は `write_thumbnail` と同じ（`{file_name}.{hash}.thumbnail.png`）。PDF は未対応。
+pub fn generate_thumbnail(
+    month_dir: &Path,
+    file_name: &str,
+    max_dimension: u32,
+) -> Result<PathBuf, String> {
+    if Path::new(file_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        != Some(file_name)
+    {
+        return Err(format!("ファイル名が正しくありません: {}", file_name));
+    }
+    let content = fs::read(month_dir.join(file_name))
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    if content.starts_with(b"%PDF") {
+        return Err("PDFのサムネイル生成には対応していません".to_string());
+    }
+    let png = render_thumbnail(&content, max_dimension)
+        .map_err(|e| format!("サムネイルの生成に失敗しました: {}", e))?;
+
+    write_thumbnail(month_dir, file_name, &png)
+}
+
+/// `keep` 以外の古いサムネイルを削除する
+pub fn remove_stale_thumbnails(thumbnails_dir: &Path, file_name: &str, keep: &Path) {
+    for path in list_thumbnails(thumbnails_dir, file_name) {
+        if path != keep {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+/// 元ファイルの現在の内容に対応するサムネイルのパスを解決する
+///
+/// 旧形式（ハッシュなし）や古いハッシュのサムネイルは現在の内容のものか分からないため削除する
+/// （呼び出し側で作り直す）。元ファイルが読めない場合やサムネイルが無い場合は `None`。
+pub fn resolve_current_thumbnail(month_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let hash = content_hash(&month_dir.join(file_name)).ok()?;
+    let thumbnails_dir = month_dir.join("thumbnails");
+    let current = thumbnails_dir.join(thumbnail_file_name(file_name, &hash));
+
+    remove_stale_thumbnails(&thumbnails_dir, file_name, &current);
+    current.exists().then_some(current)
+}
+
+/// 元ファイルの現在の内容に対応するサムネイルをDataURL形式で読み込む
+///
+/// サムネイルが無い場合は `None`。
+pub fn read_thumbnail_data_url(month_dir: &Path, file_name: &str) -> io::Result<Option<String>> {
+    let Some(file_path) = resolve_current_thumbnail(month_dir, file_name) else {
+        return Ok(None);
+    };
+
+    let image_data = fs::read(&file_path)?;
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    Ok(Some(format!(
+        "data:image/png;base64,{}",
+        STANDARD.encode(&image_data)
+    )))
+}
+
+/// 一括読み込み時の最大並列数
+pub const MAX_PARALLEL_THUMBNAIL_READS: usize = 8;
+
+/// 複数のサムネイルを順に読み込む（ブロッキング）
+///
+/// 存在しないものや読み込みに失敗したものは `None` とする。並列に読む場合は呼び出し側で
+/// ファイル名を分けて `spawn_blocking` から呼ぶ。
+pub fn read_thumbnails(month_dir: &Path, file_names: &[String]) -> Vec<(String, Option<String>)> {
+    file_names
+        .iter()
+        .map(|file_name| {
+            let data_url = read_thumbnail_data_url(month_dir, file_name).ok().flatten();
+            (file_name.clone(), data_url)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_thumbnail_of_matches_hashed_and_legacy_names_only() {
+        assert!(is_thumbnail_of(
+            "receipt.jpg.0123456789abcdef.thumbnail.png",
+            "receipt.jpg"
+        ));
+        assert!(is_thumbnail_of("receipt.jpg.thumbnail.png", "receipt.jpg"));
+        assert!(!is_thumbnail_of(
+            "receipt.jpg.copy.jpg.0123456789abcdef.thumbnail.png",
+            "receipt.jpg"
+        ));
+        assert!(!is_thumbnail_of(
+            "receipt_1.jpg.0123456789abcdef.thumbnail.png",
+            "receipt.jpg"
+        ));
+    }
+
+    #[test]
+    fn decode_thumbnail_data_url_rejects_oversized_input() {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        use image::{DynamicImage, ImageFormat, RgbImage};
+
+        let encode = |width, height| {
+            let mut png = io::Cursor::new(Vec::new());
+            DynamicImage::ImageRgb8(RgbImage::new(width, height))
+                .write_to(&mut png, ImageFormat::Png)
+                .unwrap();
+            format!("data:image/png;base64,{}", STANDARD.encode(png.get_ref()))
+        };
+
+        assert!(decode_thumbnail_data_url(&encode(32, 32)).is_ok());
+        assert!(decode_thumbnail_data_url(&encode(MAX_THUMBNAIL_DIMENSION + 1, 1)).is_err());
+        assert!(decode_thumbnail_data_url("data:text/plain;base64,AAAA").is_err());
+
+        let huge = format!(
+            "data:image/png;base64,{}",
+            "A".repeat(MAX_THUMBNAIL_DATA_URL_LEN)
+        );
+        assert_eq!(
+            decode_thumbnail_data_url(&huge).unwrap_err(),
+            "サムネイルが大きすぎます（上限 5MB）"
+        );
+    }
+
+    #[test]
+    fn render_thumbnail_fits_longest_side() {
+        use image::{DynamicImage, ImageFormat, RgbImage};
+
+        let mut jpeg = io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(1000, 500))
+            .write_to(&mut jpeg, ImageFormat::Jpeg)
+            .unwrap();
+
+        let png = render_thumbnail(jpeg.get_ref(), DEFAULT_THUMBNAIL_SIZE).unwrap();
+        let thumbnail = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+        assert!(render_thumbnail(b"%PDF-1.7", DEFAULT_THUMBNAIL_SIZE).is_err());
+    }
+
+    #[test]
+    fn generate_thumbnail_writes_next_to_the_source_and_rejects_pdf() {
+        use image::{DynamicImage, ImageFormat, RgbImage};
+
+        let month_dir = std::env::temp_dir().join(format!(
+            "torifune-generate-thumbnail-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&month_dir).unwrap();
+        let mut png = io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(40, 20))
+            .write_to(&mut png, ImageFormat::Png)
+            .unwrap();
+        fs::write(month_dir.join("a.png"), png.get_ref()).unwrap();
+        fs::write(month_dir.join("b.pdf"), b"%PDF-1.7\n").unwrap();
+
+        let path = generate_thumbnail(&month_dir, "a.png", DEFAULT_THUMBNAIL_SIZE).unwrap();
+        let hash = content_hash(&month_dir.join("a.png")).unwrap();
+        assert_eq!(
+            path,
+            month_dir
+                .join("thumbnails")
+                .join(thumbnail_file_name("a.png", &hash))
+        );
+        assert!(path.exists());
+
+        assert_eq!(
+            generate_thumbnail(&month_dir, "b.pdf", DEFAULT_THUMBNAIL_SIZE).unwrap_err(),
+            "PDFのサムネイル生成には対応していません"
+        );
+        assert!(generate_thumbnail(&month_dir, "../a.png", DEFAULT_THUMBNAIL_SIZE).is_err());
+        let _ = fs::remove_dir_all(&month_dir);
+    }
+
+    #[test]
+    fn resolve_current_thumbnail_removes_legacy_thumbnails() {
+        let month_dir =
+            std::env::temp_dir().join(format!("torifune-resolve-thumbnail-{}", std::process::id()));
+        let thumbnails_dir = month_dir.join("thumbnails");
+        fs::create_dir_all(&thumbnails_dir).unwrap();
+        fs::write(month_dir.join("a.jpg"), b"new content").unwrap();
+        let legacy = thumbnails_dir.join(legacy_thumbnail_file_name("a.jpg"));
+        fs::write(&legacy, b"old thumbnail").unwrap();
+
+        // 旧形式は差し替え前の内容のものかもしれないので使わない
+        assert_eq!(resolve_current_thumbnail(&month_dir, "a.jpg"), None);
+        assert!(!legacy.exists());
+
+        let hash = content_hash(&month_dir.join("a.jpg")).unwrap();
+        let current = thumbnails_dir.join(thumbnail_file_name("a.jpg", &hash));
+        fs::write(&current, b"thumbnail").unwrap();
+        assert_eq!(
+            resolve_current_thumbnail(&month_dir, "a.jpg"),
+            Some(current)
+        );
+        let _ = fs::remove_dir_all(&month_dir);
+    }
+
+    #[test]
+    fn read_thumbnails_returns_none_for_missing_thumbnails() {
+        let month_dir =
+            std::env::temp_dir().join(format!("torifune-read-thumbnails-{}", std::process::id()));
+        let thumbnails_dir = month_dir.join("thumbnails");
+        fs::create_dir_all(&thumbnails_dir).unwrap();
+        fs::write(month_dir.join("a.jpg"), b"a").unwrap();
+        fs::write(month_dir.join("b.jpg"), b"b").unwrap();
+        let hash = content_hash(&month_dir.join("a.jpg")).unwrap();
+        fs::write(
+            thumbnails_dir.join(thumbnail_file_name("a.jpg", &hash)),
+            b"png",
+        )
+        .unwrap();
+
+        let names = ["a.jpg", "b.jpg", "missing.jpg"].map(String::from);
+        let thumbnails = read_thumbnails(&month_dir, &names);
+        let _ = fs::remove_dir_all(&month_dir);
+
+        assert_eq!(
+            thumbnails,
+            vec![
+                (
+                    "a.jpg".to_string(),
+                    Some("data:image/png;base64,cG5n".to_string())
+                ),
+                ("b.jpg".to_string(), None),
+                ("missing.jpg".to_string(), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! URL からのレシートの取り込み
+//!
+//! クラウドストレージの共有リンクやメールのリンク先の画像・PDF をダウンロードし、
+//! 一時ファイルに書き出して取り込みパイプラインに渡す。https のみ許可し、
+//! サイズの上限を超えるもの・OCRに対応していない形式（ログイン画面の HTML など）は拒否する。
+
+use crate::preflight::MAX_FILE_SIZE_BYTES;
+use reqwest::Url;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// ダウンロードのタイムアウト
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// たどるリダイレクトの上限（reqwest の既定と同じ）
+const MAX_REDIRECTS: usize = 10;
+
+/// 一時ファイル名の連番（同時に取り込んでも衝突させない）
+static TEMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// ダウンロードしたファイル
+#[derive(Debug)]
+pub struct DownloadedFile {
+    /// 保存に使うファイル名（URL の末尾から作り、形式に合った拡張子を付ける）
+    pub file_name: String,
+    pub content: Vec<u8>,
+}
+
+/// 取り込める URL か確かめる（https のみ）
+pub fn parse_download_url(url: &str) -> Result<Url, String> {
+    let parsed =
+        Url::parse(url.trim()).map_err(|_| format!("URLの形式が正しくありません: {}", url))?;
+    if parsed.scheme() != "https" {
+        return Err(format!(
+            "https 以外のURLからは取り込めません: {}",
+            parsed.scheme()
+        ));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("URLにホスト名がありません: {}", url));
+    }
+    Ok(parsed)
+}
+
+/// リダイレクト先をたどってよいか確かめる（https 以外への転送は拒否する）
+///
+/// `previous` はこれまでにたどった URL の数。
+fn check_redirect(url: &Url, previous: usize) -> Result<(), String> {
+    if previous >= MAX_REDIRECTS {
+        return Err("リダイレクトが多すぎます".to_string());
+    }
+    if url.scheme() != "https" {
+        return Err(format!(
+            "https 以外のURLへのリダイレクトは拒否しました: {}",
+            url.scheme()
+        ));
+    }
+    Ok(())
+}
+
+/// MIME タイプに対応する拡張子（取り込めない形式はエラー）
+fn extension_for(mime_type: &str) -> Result<&'static str, String> {
+    Ok(match mime_type {
+        "application/pdf" => "pdf",
+        "image/jpeg" => "jpg",
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        "image/tiff" => "tiff",
+        "image/webp" => "webp",
+        "image/heic" => "heic",
+        other => return Err(format!("取り込みに対応していない形式です: {}", other)),
+    })
+}
+
+/// `%XX` をデコードする（UTF-8 として読めないバイトは置き換え文字にする）
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// URL の末尾からファイル名を作る
+///
+/// ファイル名に使えない文字は `_` に置き換え、拡張子が形式と合わなければ付け足す。
+/// 末尾が空なら `receipt` とする。拡張子の決まらない形式はエラー。
+pub fn file_name_from_url(url: &Url, mime_type: &str) -> Result<String, String> {
+    let stem: String = percent_decode(
+        url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default(),
+    )
+    .chars()
+    .map(|c| {
+        if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+            c
+        } else {
+            '_'
+        }
+    })
+    .collect();
+    let stem = stem.trim_matches('.');
+    let stem = if stem.is_empty() { "receipt" } else { stem };
+
+    let extension = extension_for(mime_type)?;
+    let has_extension = Path::new(stem)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| {
+            e.eq_ignore_ascii_case(extension)
+                || (extension == "jpg" && e.eq_ignore_ascii_case("jpeg"))
+                || (extension == "tiff" && e.eq_ignore_ascii_case("tif"))
+                || (extension == "heic" && e.eq_ignore_ascii_case("heif"))
+        });
+    Ok(if has_extension {
+        stem.to_string()
+    } else {
+        format!("{}.{}", stem, extension)
+    })
+}
+
+/// URL の画像・PDF をダウンロードする
+///
+/// リダイレクトは https の転送先だけたどる。
+/// 上限（`MAX_FILE_SIZE_BYTES`）は `Content-Length` と受信済みの量の両方で確かめる。
+/// 形式は内容の先頭バイトを優先して判定し、OCRに対応していなければエラーにする。
+pub async fn download(url: &Url) -> Result<DownloadedFile, String> {
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::custom(
+            |attempt| match check_redirect(attempt.url(), attempt.previous().len()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            },
+        ))
+        .build()
+        .map_err(|e| format!("HTTPクライアントを作成できませんでした: {}", e))?;
+    let too_large = || {
+        format!(
+            "ファイルが大きすぎます（上限 {}MB）",
+            MAX_FILE_SIZE_BYTES / 1024 / 1024
+        )
+    };
+
+    let mut response = client.get(url.clone()).send().await.map_err(|e| {
+        if e.is_timeout() {
+            "ダウンロードがタイムアウトしました".to_string()
+        } else {
+            format!("ダウンロードに失敗しました: {}", e)
+        }
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(match status.as_u16() {
+            401 | 403 => format!(
+                "ダウンロードが拒否されました（HTTP {}）。リンクの共有設定を確認してください",
+                status.as_u16()
+            ),
+            404 => "ダウンロード先が見つかりません（HTTP 404）".to_string(),
+            code => format!("ダウンロードに失敗しました（HTTP {}）", code),
+        });
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_FILE_SIZE_BYTES)
+    {
+        return Err(too_large());
+    }
+    let declared = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut content = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("ダウンロード中に接続が切れました: {}", e))?
+    {
+        content.extend_from_slice(&chunk);
+        if content.len() as u64 > MAX_FILE_SIZE_BYTES {
+            return Err(too_large());
+        }
+    }
+    if content.is_empty() {
+        return Err("ダウンロードしたファイルが空です".to_string());
+    }
+
+    let mime_type = crate::mime::normalize_mime_type(&declared, &content)?;
+    Ok(DownloadedFile {
+        file_name: file_name_from_url(url, &mime_type)?,
+        content,
+    })
+}
+
+/// ダウンロードした内容を一時ディレクトリに書き出す（呼び出し側が使い終えたら消す）
+pub fn write_temp_file(file: &DownloadedFile) -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join(format!(
+        "torifune-url-import-{}-{}",
+        std::process::id(),
+        TEMP_FILE_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("一時ディレクトリの作成に失敗しました: {}", e))?;
+    let path = dir.join(&file.file_name);
+    std::fs::write(&path, &file.content)
+        .map_err(|e| format!("一時ファイルの作成に失敗しました: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_https_urls_are_accepted_and_named_after_the_path() {
+        assert!(parse_download_url("http://example.com/a.jpg").is_err());
+        assert!(parse_download_url("file:///etc/passwd").is_err());
+        assert!(parse_download_url("not a url").is_err());
+
+        let url = parse_download_url(" https://example.com/files/レシート 1.JPEG?dl=1 ").unwrap();
+        assert_eq!(
+            file_name_from_url(&url, "image/jpeg").unwrap(),
+            "レシート_1.JPEG"
+        );
+
+        let url = parse_download_url("https://example.com/download?id=42").unwrap();
+        assert_eq!(
+            file_name_from_url(&url, "application/pdf").unwrap(),
+            "download.pdf"
+        );
+
+        let url = parse_download_url("https://example.com/").unwrap();
+        assert_eq!(
+            file_name_from_url(&url, "image/png").unwrap(),
+            "receipt.png"
+        );
+
+        let url = parse_download_url("https://example.com/photos/photo").unwrap();
+        assert_eq!(
+            file_name_from_url(&url, "image/heic").unwrap(),
+            "photo.heic"
+        );
+        let url = parse_download_url("https://example.com/photos/IMG_1.HEIF").unwrap();
+        assert_eq!(
+            file_name_from_url(&url, "image/heic").unwrap(),
+            "IMG_1.HEIF"
+        );
+        assert!(file_name_from_url(&url, "text/html").is_err());
+    }
+
+    #[test]
+    fn redirects_to_non_https_are_rejected() {
+        let https = Url::parse("https://cdn.example.com/a.jpg").unwrap();
+        assert!(check_redirect(&https, 0).is_ok());
+        assert!(check_redirect(&https, MAX_REDIRECTS).is_err());
+
+        let http = Url::parse("http://cdn.example.com/a.jpg").unwrap();
+        assert_eq!(
+            check_redirect(&http, 1).unwrap_err(),
+            "https 以外のURLへのリダイレクトは拒否しました: http"
+        );
+        let file = Url::parse("file:///etc/passwd").unwrap();
+        assert!(check_redirect(&file, 1).is_err());
+    }
+}
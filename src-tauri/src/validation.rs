@@ -0,0 +1,416 @@
+//! OCR結果の検証（フロントエンドの `validateAllReceipts` の移植）
+//!
+//! バッチOCRの完了時に結果をまとめて検証し、保存済みのバリデーションルールに従って
+//! 問題を `ValidationIssue` として返す。対象月が決まらないバッチでは日付範囲のチェックは行わず、
+//! OCR結果に備考が無いため交際費の備考チェックも対象外とする。
+//! 類似のレシートの判定は `utils/stringSimilarity.ts` の `isMerchantSimilar` と同じで、
+//! テストも同じケース（`merchantSimilarity.cases.json`）を使う。
+//! 宛名が登録済みの宛名のどれとも似ていない場合の警告はバッチの検証だけで行う。
+
+use crate::providers::ReceiptData;
+use crate::summary::ValidationIssue;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// 保存済みのバリデーションルール（フロントエンドの `ValidationRule`）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationRule {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub enabled: bool,
+    pub severity: String,
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+/// バリデーションルール設定（フロントエンドの `ValidationRulesSettings`）
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidationRulesSettings {
+    pub rules: Vec<ValidationRule>,
+}
+
+/// 組み込みルールの既定値（種類, 有効か, 重要度）
+const DEFAULT_RULES: &[(&str, bool, &str)] = &[
+    ("date-format", true, "error"),
+    ("amount-decimal", true, "warning"),
+    ("amount-outlier", true, "warning"),
+    ("duplicate-file", true, "warning"),
+    ("duplicate-data", true, "warning"),
+    ("denchou-required-fields", false, "error"),
+];
+
+/// 保存済みのルールに不足している組み込みルールを既定値で補う
+pub fn merge_with_default_rules(mut rules: Vec<ValidationRule>) -> Vec<ValidationRule> {
+    for &(kind, enabled, severity) in DEFAULT_RULES {
+        if !rules.iter().any(|rule| rule.kind == kind) {
+            rules.push(ValidationRule {
+                kind: kind.to_string(),
+                enabled,
+                severity: severity.to_string(),
+                params: Map::new(),
+            });
+        }
+    }
+    rules
+}
+
+/// 有効なルールを種類で探す
+fn enabled_rule<'a>(rules: &'a [ValidationRule], kind: &str) -> Option<&'a ValidationRule> {
+    rules.iter().find(|rule| rule.kind == kind && rule.enabled)
+}
+
+/// ルールの数値パラメータ（未指定なら既定値）
+fn param(rule: &ValidationRule, name: &str, default: f64) -> f64 {
+    rule.params
+        .get(name)
+        .and_then(Value::as_f64)
+        .unwrap_or(default)
+}
+
+/// YYYY-MM-DD 形式か
+fn is_iso_date(date: &str) -> bool {
+    let bytes = date.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// 店舗名が類似しているとみなす類似度（`isMerchantSimilar` の既定値）
+const MERCHANT_SIMILARITY_THRESHOLD: f64 = 0.85;
+
+/// 類似度を測る前の正規化（空白の除去・小文字化・長音符やダッシュの統一）
+fn normalize_for_similarity(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'ー' | '—' | '−' | '‐' => '-',
+            c => c,
+        })
+        .collect()
+}
+
+/// 2つの文字列の Levenshtein 距離
+fn levenshtein_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j + 1] + 1)
+                .min(current[j] + 1)
+                .min(previous[j] + cost);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 類似度（0〜1、1 が完全一致。`calculateSimilarity` の移植）
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalize_for_similarity(a).chars().collect();
+    let b: Vec<char> = normalize_for_similarity(b).chars().collect();
+    let max_len = a.len().max(b.len());
+    if a == b || max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein_distance(&a, &b) as f64 / max_len as f64
+}
+
+/// 店舗名・宛名が類似しているか
+fn is_similar(a: &str, b: &str) -> bool {
+    similarity(a, b) >= MERCHANT_SIMILARITY_THRESHOLD
+}
+
+/// 日付と金額が一致し、店舗名が類似した他のレシートがあるか（`detectDataDuplicates` の移植）
+fn has_similar_receipt(receipts: &[ReceiptData], index: usize) -> bool {
+    let receipt = &receipts[index];
+    let (Some(date), Some(merchant), Some(amount)) =
+        (&receipt.date, &receipt.merchant, receipt.amount)
+    else {
+        return false;
+    };
+    receipts.iter().enumerate().any(|(other, r)| {
+        other != index
+            && r.date.as_ref() == Some(date)
+            && r.amount == Some(amount)
+            && r.merchant
+                .as_deref()
+                .is_some_and(|m| is_similar(m, merchant))
+    })
+}
+
+/// IQR法による外れ値の下限・上限（件数が足りなければ `None`）
+fn outlier_bounds(amounts: &[f64], rule: &ValidationRule) -> Option<(f64, f64)> {
+    if (amounts.len() as f64) < param(rule, "minSampleSize", 4.0) {
+        return None;
+    }
+    let mut sorted = amounts.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = sorted[sorted.len() / 4];
+    let q3 = sorted[sorted.len() * 3 / 4];
+    let iqr = q3 - q1;
+    Some((
+        q1 - iqr * param(rule, "lowerMultiplier", 1.5),
+        q3 + iqr * param(rule, "upperMultiplier", 3.0),
+    ))
+}
+
+/// レシートをまとめて検証し、見つかった問題を `file` 付きでレシート順に返す
+///
+/// `registered_names` は登録済みの宛名で、空でなければ宛名がどれにも似ていないレシートを警告する。
+pub fn validate_receipts(
+    receipts: &[ReceiptData],
+    rules: &[ValidationRule],
+    registered_names: &[String],
+) -> Vec<ValidationIssue> {
+    let amounts: Vec<f64> = receipts.iter().filter_map(|r| r.amount).collect();
+    let bounds = enabled_rule(rules, "amount-outlier")
+        .and_then(|rule| Some((rule, outlier_bounds(&amounts, rule)?)));
+    let duplicate_rule = enabled_rule(rules, "duplicate-data");
+
+    let mut issues = Vec::new();
+    for (index, receipt) in receipts.iter().enumerate() {
+        let mut push = |severity: &str, field: &str, kind: &str, message: String| {
+            issues.push(ValidationIssue {
+                field: field.to_string(),
+                kind: kind.to_string(),
+                severity: severity.to_string(),
+                message,
+                file: Some(receipt.file.clone()),
+            });
+        };
+
+        if let (Some(rule), Some(date)) = (enabled_rule(rules, "date-format"), &receipt.date) {
+            if !is_iso_date(date) {
+                push(
+                    &rule.severity,
+                    "date",
+                    "format",
+                    format!(
+                        "日付フォーマットが不正です: \"{}\" (期待: YYYY-MM-DD)",
+                        date
+                    ),
+                );
+            }
+        }
+
+        if let (Some(rule), Some(amount)) = (enabled_rule(rules, "amount-decimal"), receipt.amount)
+        {
+            if amount.fract() != 0.0 {
+                push(
+                    &rule.severity,
+                    "amount",
+                    "format",
+                    format!("金額に小数点が含まれています: {} (外貨の可能性)", amount),
+                );
+            }
+        }
+
+        if let (Some((rule, (lower, upper))), Some(amount)) = (bounds, receipt.amount) {
+            if amount < lower {
+                push(
+                    &rule.severity,
+                    "amount",
+                    "outlier",
+                    format!("金額が極端に低いです: ¥{}", amount),
+                );
+            } else if amount > upper {
+                push(
+                    &rule.severity,
+                    "amount",
+                    "outlier",
+                    format!("金額が極端に高いです: ¥{}", amount),
+                );
+            }
+        }
+
+        if let Some(rule) = enabled_rule(rules, "duplicate-file") {
+            let same_file = receipts
+                .iter()
+                .enumerate()
+                .any(|(other, r)| other != index && r.file == receipt.file);
+            if same_file {
+                push(
+                    &rule.severity,
+                    "file",
+                    "duplicate-file",
+                    format!("同じファイル名のレシートが存在します: {}", receipt.file),
+                );
+            }
+        }
+
+        if let Some(rule) = duplicate_rule {
+            if has_similar_receipt(receipts, index) {
+                push(
+                    &rule.severity,
+                    "duplicate",
+                    "duplicate-data",
+                    format!(
+                        "類似のレシートが存在します: {} / {} / ¥{}",
+                        receipt.date.as_deref().unwrap_or_default(),
+                        receipt.merchant.as_deref().unwrap_or_default(),
+                        receipt.amount.unwrap_or_default()
+                    ),
+                );
+            }
+        }
+
+        if let Some(receiver_name) = receipt
+            .receiver_name
+            .as_deref()
+            .filter(|name| !name.trim().is_empty() && !registered_names.is_empty())
+        {
+            if !registered_names
+                .iter()
+                .any(|registered| is_similar(registered, receiver_name))
+            {
+                push(
+                    "warning",
+                    "receiverName",
+                    "receiver-mismatch",
+                    format!("宛名が登録済みの宛名と一致しません: {}", receiver_name),
+                );
+            }
+        }
+
+        if let Some(rule) = enabled_rule(rules, "denchou-required-fields") {
+            let missing = [
+                ("date", "取引年月日", receipt.date.is_none()),
+                ("amount", "取引金額", receipt.amount.is_none()),
+                (
+                    "merchant",
+                    "取引先",
+                    receipt
+                        .merchant
+                        .as_deref()
+                        .is_none_or(|m| m.trim().is_empty()),
+                ),
+            ];
+            for (field, label, _) in missing.into_iter().filter(|(_, _, missing)| *missing) {
+                push(
+                    &rule.severity,
+                    field,
+                    "missing-field",
+                    format!("電子帳簿保存法の検索項目「{}」が入力されていません", label),
+                );
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issues_follow_enabled_rules_and_name_the_file() {
+        let receipt = |file: &str, date: &str, amount: f64| {
+            let mut data = ReceiptData::new(file.to_string());
+            data.merchant = Some("ローソン".to_string());
+            data.date = Some(date.to_string());
+            data.amount = Some(amount);
+            data
+        };
+        let receipts = [
+            receipt("a.jpg", "2025/01/06", 500.0),
+            receipt("b.jpg", "2025-01-07", 480.5),
+            receipt("c.jpg", "2025-01-08", 520.0),
+            receipt("d.jpg", "2025-01-08", 520.0),
+            receipt("e.jpg", "2025-01-09", 98000.0),
+        ];
+        let rules = merge_with_default_rules(Vec::new());
+
+        let issues = validate_receipts(&receipts, &rules, &[]);
+        let found: Vec<(&str, &str)> = issues
+            .iter()
+            .map(|issue| (issue.file.as_deref().unwrap(), issue.kind.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("a.jpg", "format"),
+                ("b.jpg", "format"),
+                ("c.jpg", "duplicate-data"),
+                ("d.jpg", "duplicate-data"),
+                ("e.jpg", "outlier"),
+            ]
+        );
+        assert_eq!(issues[0].severity, "error");
+
+        // 無効にしたルールは適用しない
+        let rules = merge_with_default_rules(vec![ValidationRule {
+            kind: "duplicate-data".to_string(),
+            enabled: false,
+            severity: "warning".to_string(),
+            params: Map::new(),
+        }]);
+        assert!(!validate_receipts(&receipts, &rules, &[])
+            .iter()
+            .any(|issue| issue.kind == "duplicate-data"));
+    }
+
+    #[test]
+    fn merchant_similarity_matches_the_frontend_cases() {
+        #[derive(Deserialize)]
+        struct Case {
+            a: String,
+            b: String,
+            similar: bool,
+        }
+        let cases: Vec<Case> = serde_json::from_str(include_str!(
+            "../../src-app/utils/merchantSimilarity.cases.json"
+        ))
+        .unwrap();
+        for case in cases {
+            assert_eq!(
+                is_similar(&case.a, &case.b),
+                case.similar,
+                "{} / {}",
+                case.a,
+                case.b
+            );
+        }
+    }
+
+    #[test]
+    fn similar_merchants_and_unregistered_receivers_are_reported() {
+        let receipt = |file: &str, merchant: &str, receiver_name: &str| {
+            let mut data = ReceiptData::new(file.to_string());
+            data.merchant = Some(merchant.to_string());
+            data.date = Some("2025-01-06".to_string());
+            data.amount = Some(1080.0);
+            data.receiver_name = Some(receiver_name.to_string());
+            data
+        };
+        let receipts = [
+            receipt("a.jpg", "ドトールコーヒー", "株式会社トリフネ"),
+            receipt("b.jpg", "ドトールコーヒ", "株式会社トリフネ"),
+            receipt("c.jpg", "タリーズ", "山田商事"),
+        ];
+        let rules = merge_with_default_rules(Vec::new());
+        let registered = ["株式会社 トリフネ".to_string()];
+
+        let found: Vec<(String, String)> = validate_receipts(&receipts, &rules, &registered)
+            .into_iter()
+            .map(|issue| (issue.file.unwrap(), issue.kind))
+            .collect();
+        let pair = |file: &str, kind: &str| (file.to_string(), kind.to_string());
+        assert_eq!(
+            found,
+            vec![
+                pair("a.jpg", "duplicate-data"),
+                pair("b.jpg", "duplicate-data"),
+                pair("c.jpg", "receiver-mismatch"),
+            ]
+        );
+        // 登録済みの宛名が無ければ宛名は見ない
+        assert!(!validate_receipts(&receipts, &rules, &[])
+            .iter()
+            .any(|issue| issue.kind == "receiver-mismatch"));
+    }
+}
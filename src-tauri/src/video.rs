@@ -0,0 +1,242 @@
+//! 動画からの静止画抽出
+//!
+//! 領収書を動画で撮影した場合に、指定時刻のフレームを JPEG として取り出して OCR の入力にする。
+//! デコードは `ffmpeg`・`ffprobe`（PATH 上のもの）に任せ、対応コーデックは限定する。
+//! 探索幅を指定すると前後のフレームから最も鮮明なもの（ラプラシアンの分散が最大）を選ぶ。
+
+use image::GrayImage;
+use std::path::Path;
+use std::process::Command;
+
+/// 対応する動画の拡張子
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v"];
+
+/// 対応する映像コーデック（`ffprobe` の `codec_name`）
+pub const SUPPORTED_CODECS: &[&str] = &["h264", "hevc"];
+
+/// 鮮明なフレームを探すときに調べるフレーム数
+const SHARPNESS_SAMPLES: usize = 7;
+
+/// 探索幅の上限（秒）
+const MAX_SEARCH_WINDOW_SECS: f64 = 5.0;
+
+/// ffmpeg の JPEG 品質（`-q:v`、2 が最高に近い）
+const JPEG_QSCALE: &str = "2";
+
+/// 取り出したフレーム
+#[derive(Debug, Clone)]
+pub struct ExtractedFrame {
+    /// JPEG データ
+    pub jpeg: Vec<u8>,
+    /// 実際に採用した時刻（秒）
+    pub timestamp: f64,
+}
+
+/// 外部コマンドを実行して標準出力を返す（見つからない・失敗した場合は日本語のエラー）
+pub fn run(program: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new(program).args(args).output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            format!(
+                "{} が見つかりません。インストールして PATH を通してください",
+                program
+            )
+        } else {
+            format!("{} の起動に失敗しました: {}", program, e)
+        }
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{} が失敗しました: {}",
+            program,
+            stderr.lines().last().unwrap_or("").trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// 動画の拡張子・コーデックが対応しているか確認する
+fn check_supported(video_path: &Path) -> Result<(), String> {
+    let extension = video_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    if !SUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "対応していない動画形式です（{}）: {}",
+            SUPPORTED_EXTENSIONS.join(", "),
+            video_path.display()
+        ));
+    }
+
+    let path = video_path.to_str().ok_or("パスの変換に失敗しました")?;
+    let codec = run(
+        "ffprobe",
+        &[
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            path,
+        ],
+    )?;
+    let codec = String::from_utf8_lossy(&codec).trim().to_string();
+    if codec.is_empty() {
+        return Err("動画に映像トラックがありません".to_string());
+    }
+    if !SUPPORTED_CODECS.contains(&codec.as_str()) {
+        return Err(format!(
+            "対応していないコーデックです（{}）: {}",
+            SUPPORTED_CODECS.join(", "),
+            codec
+        ));
+    }
+    Ok(())
+}
+
+/// 指定時刻の1フレームを JPEG で取り出す
+fn extract_jpeg(path: &str, timestamp: f64) -> Result<Vec<u8>, String> {
+    let timestamp = format!("{:.3}", timestamp);
+    let jpeg = run(
+        "ffmpeg",
+        &[
+            "-v",
+            "error",
+            "-ss",
+            &timestamp,
+            "-i",
+            path,
+            "-frames:v",
+            "1",
+            "-q:v",
+            JPEG_QSCALE,
+            "-f",
+            "image2pipe",
+            "-vcodec",
+            "mjpeg",
+            "-",
+        ],
+    )?;
+    if jpeg.is_empty() {
+        return Err(format!(
+            "指定時刻のフレームを取り出せませんでした（動画の長さを超えている可能性があります）: {}秒",
+            timestamp
+        ));
+    }
+    Ok(jpeg)
+}
+
+/// 画像の鮮明さ（4近傍ラプラシアンの分散）。ブレているほど小さい
+pub fn sharpness(image: &GrayImage) -> f64 {
+    let (width, height) = image.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let pixel = |x: u32, y: u32| f64::from(image.get_pixel(x, y)[0]);
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian = pixel(x - 1, y) + pixel(x + 1, y) + pixel(x, y - 1) + pixel(x, y + 1)
+                - 4.0 * pixel(x, y);
+            sum += laplacian;
+            sum_sq += laplacian * laplacian;
+        }
+    }
+
+    let count = f64::from((width - 2) * (height - 2));
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+/// 動画の指定時刻のフレームを JPEG として取り出す
+///
+/// `search_window_secs` を指定すると、`timestamp` の前後その秒数から最も鮮明なフレームを選ぶ。
+pub fn extract_frame(
+    video_path: &Path,
+    timestamp: f64,
+    search_window_secs: Option<f64>,
+) -> Result<ExtractedFrame, String> {
+    if !timestamp.is_finite() || timestamp < 0.0 {
+        return Err(format!(
+            "時刻は0以上の秒数で指定してください: {}",
+            timestamp
+        ));
+    }
+    if !video_path.is_file() {
+        return Err(format!(
+            "動画ファイルが見つかりません: {}",
+            video_path.display()
+        ));
+    }
+    check_supported(video_path)?;
+    let path = video_path.to_str().ok_or("パスの変換に失敗しました")?;
+
+    let window = search_window_secs
+        .filter(|window| window.is_finite() && *window > 0.0)
+        .map(|window| window.min(MAX_SEARCH_WINDOW_SECS));
+    let Some(window) = window else {
+        return Ok(ExtractedFrame {
+            jpeg: extract_jpeg(path, timestamp)?,
+            timestamp,
+        });
+    };
+
+    // 探索範囲から等間隔にフレームを取り出し、最も鮮明なものを採用する
+    let start = (timestamp - window).max(0.0);
+    let step = (timestamp + window - start) / (SHARPNESS_SAMPLES - 1) as f64;
+    let mut best: Option<(f64, ExtractedFrame)> = None;
+    let mut last_error = None;
+    for i in 0..SHARPNESS_SAMPLES {
+        let at = start + step * i as f64;
+        let jpeg = match extract_jpeg(path, at) {
+            Ok(jpeg) => jpeg,
+            Err(e) => {
+                last_error = Some(e);
+                continue;
+            }
+        };
+        let score = match image::load_from_memory(&jpeg) {
+            Ok(image) => sharpness(&image.to_luma8()),
+            Err(e) => {
+                last_error = Some(format!("フレームのデコードに失敗しました: {}", e));
+                continue;
+            }
+        };
+        if best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((
+                score,
+                ExtractedFrame {
+                    jpeg,
+                    timestamp: at,
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, frame)| frame)
+        .ok_or_else(|| last_error.unwrap_or_else(|| "フレームを取り出せませんでした".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn sharpness_prefers_crisp_edges_over_blur() {
+        let crisp = GrayImage::from_fn(16, 16, |x, _| Luma([if x < 8 { 0 } else { 255 }]));
+        let blurred = GrayImage::from_fn(16, 16, |x, _| Luma([(x * 16) as u8]));
+        let flat = GrayImage::from_pixel(16, 16, Luma([128]));
+
+        assert!(sharpness(&crisp) > sharpness(&blurred));
+        assert_eq!(sharpness(&flat), 0.0);
+    }
+}
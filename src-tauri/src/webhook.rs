@@ -0,0 +1,183 @@
+//! バッチ完了時の Webhook 連携
+//!
+//! OCRに成功したレシートを JSON で `webhook_url` に POST する。`webhook_secret` が
+//! 設定されていれば本文の HMAC-SHA256 を `X-Torifune-Signature` ヘッダに付ける。
+//! 一時的な失敗はリトライし、最終的な失敗はエラーログに残す。
+
+use crate::providers::{OcrResult, OcrSettings, ReceiptData};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// 署名ヘッダ名（値は `sha256=<16進>`）
+pub const SIGNATURE_HEADER: &str = "X-Torifune-Signature";
+
+/// 送信の最大試行回数
+const MAX_ATTEMPTS: u32 = 3;
+
+/// リトライ間隔の初期値（試行ごとに倍にする）
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 1回の送信のタイムアウト
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Webhook の本文
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload<'a> {
+    /// イベント種別（`batch.completed`）
+    pub event: &'static str,
+    /// 送信日時（RFC 3339）
+    pub sent_at: String,
+    /// OCRに成功したレシート
+    pub receipts: Vec<&'a ReceiptData>,
+}
+
+/// 本文の HMAC-SHA256 署名（`sha256=<16進>`）
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC は任意長の鍵を受け付ける");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+/// 1回送信する。リトライすべき失敗なら `Err((メッセージ, true))`
+async fn post_once(
+    client: &reqwest::Client,
+    url: &str,
+    body: &[u8],
+    signature: Option<&str>,
+) -> Result<(), (String, bool)> {
+    let mut request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(body.to_vec());
+    if let Some(signature) = signature {
+        request = request.header(SIGNATURE_HEADER, signature);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| (format!("Webhookの送信に失敗しました: {}", e), true))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    // サーバー側の一時的な失敗のみリトライする
+    let retryable = status.is_server_error() || status.as_u16() == 429;
+    Err((
+        format!("Webhookの送信に失敗しました: HTTP {}", status),
+        retryable,
+    ))
+}
+
+/// バッチの結果を Webhook に送る（`webhook_url` 未設定なら何もしない）
+///
+/// 失敗しても呼び出し元には返さず、エラーログに記録する。
+pub async fn send_batch_results(app: &AppHandle, settings: &OcrSettings, results: &[OcrResult]) {
+    let Some(url) = settings
+        .webhook_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+    else {
+        return;
+    };
+
+    let payload = WebhookPayload {
+        event: "batch.completed",
+        sent_at: chrono::Local::now().to_rfc3339(),
+        receipts: results.iter().filter_map(|r| r.data.as_ref()).collect(),
+    };
+    let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            log_failure(
+                app,
+                &format!("Webhookの本文を生成できませんでした: {}", e),
+                None,
+            );
+            return;
+        }
+    };
+    let signature = settings
+        .webhook_secret
+        .as_deref()
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| sign(secret, &body));
+
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            log_failure(
+                app,
+                &format!("HTTPクライアントを作成できませんでした: {}", e),
+                None,
+            );
+            return;
+        }
+    };
+
+    let context = format!("url={} receipts={}", url, payload.receipts.len());
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let (message, retryable) = match post_once(&client, url, &body, signature.as_deref()).await
+        {
+            Ok(()) => return,
+            Err(error) => error,
+        };
+
+        // 試行ごとの失敗を残す
+        log_failure(
+            app,
+            &format!("{}（{}/{}回目）", message, attempt, MAX_ATTEMPTS),
+            Some(&context),
+        );
+        if !retryable || attempt == MAX_ATTEMPTS {
+            return;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+/// バッチの結果の送信をバックグラウンドで開始する（バッチの応答は待たせない）
+pub fn spawn_batch_results(app: &AppHandle, results: &[OcrResult]) {
+    let app = app.clone();
+    let results = results.to_vec();
+    tauri::async_runtime::spawn(async move {
+        match crate::commands::get_ocr_settings(app.clone()).await {
+            Ok(settings) => send_batch_results(&app, &settings, &results).await,
+            Err(e) => log_failure(&app, &e, None),
+        }
+    });
+}
+
+fn log_failure(app: &AppHandle, message: &str, context: Option<&str>) {
+    let _ = crate::errorlog::write_log_entry(app, "rust-webhook", message, None, None, context);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_rfc4231_test_vector() {
+        // RFC 4231 Test Case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}